@@ -0,0 +1,152 @@
+//! `Decimal` variants of the hedging calculations.
+//!
+//! Enabled with the `decimal` feature. These functions mirror the `f64` API in
+//! the crate root but operate on `rust_decimal::Decimal`, so notional, margin
+//! and fee amounts can be computed without binary floating point rounding
+//! when they are used for settlement.
+
+use rust_decimal::Decimal;
+
+/// Calculates the total delta of the options position.
+///
+/// # Arguments
+///
+/// * `delta` - Delta of a single option.
+/// * `number_of_contracts` - Number of options contracts.
+///
+/// # Returns
+///
+/// The total delta of the options position.
+pub fn calculate_total_delta(delta: Decimal, number_of_contracts: Decimal) -> Decimal {
+    delta * number_of_contracts
+}
+
+/// Calculates the notional value of the futures contracts needed for hedging.
+///
+/// # Arguments
+///
+/// * `total_delta` - Total delta of the options position.
+/// * `underlying_price` - Current price of the underlying asset.
+///
+/// # Returns
+///
+/// The notional value of the futures contracts.
+pub fn calculate_notional_value(total_delta: Decimal, underlying_price: Decimal) -> Decimal {
+    total_delta * underlying_price
+}
+
+/// Calculates the required margin for the futures contracts.
+///
+/// # Arguments
+///
+/// * `notional_value` - Notional value of the futures contracts.
+/// * `leverage` - Leverage ratio (e.g., 10 for 10x leverage).
+///
+/// # Returns
+///
+/// The required margin for the futures contracts, or `None` if `leverage` is
+/// zero.
+pub fn calculate_required_margin(notional_value: Decimal, leverage: Decimal) -> Option<Decimal> {
+    notional_value.checked_div(leverage)
+}
+
+/// Calculates the transaction fees for the futures contracts.
+///
+/// # Arguments
+///
+/// * `notional_value` - Notional value of the futures contracts.
+/// * `transaction_fee_rate` - Transaction fee rate (e.g., 0.001 for 0.1%).
+///
+/// # Returns
+///
+/// The transaction fees for the futures contracts.
+pub fn calculate_fees(notional_value: Decimal, transaction_fee_rate: Decimal) -> Decimal {
+    notional_value * transaction_fee_rate
+}
+
+/// Determines the number of perpetual futures contracts needed to hedge the
+/// position.
+///
+/// # Arguments
+///
+/// * `current_total_delta` - Current total delta of the options position.
+/// * `target_total_delta` - Target total delta (typically zero for
+///   delta-neutral).
+///
+/// # Returns
+///
+/// The number of perpetual futures contracts to buy or sell to achieve the
+/// target delta.
+pub fn calculate_perps_needed(
+    current_total_delta: Decimal,
+    target_total_delta: Decimal,
+) -> Decimal {
+    target_total_delta - current_total_delta
+}
+
+/// Calculates the number of perpetual futures contracts needed to hedge the
+/// options position, along with the required margin and fees.
+///
+/// # Arguments
+///
+/// * `current_price` - Current price of the underlying asset.
+/// * `current_delta` - Current delta of the options.
+/// * `number_of_contracts` - Number of options contracts.
+/// * `target_total_delta` - Target total delta (typically zero for
+///   delta-neutral).
+/// * `leverage` - Leverage ratio (e.g., 10 for 10x leverage).
+/// * `transaction_fee_rate` - Transaction fee rate (e.g., 0.001 for 0.1%).
+///
+/// # Returns
+///
+/// A tuple containing the number of perpetual futures contracts needed,
+/// required margin, and transaction fees, or `None` if `leverage` is zero.
+pub fn get_perps_needed(
+    current_price: Decimal,
+    current_delta: Decimal,
+    number_of_contracts: Decimal,
+    target_total_delta: Decimal,
+    leverage: Decimal,
+    transaction_fee_rate: Decimal,
+) -> Option<(Decimal, Decimal, Decimal)> {
+    let current_total_delta = calculate_total_delta(current_delta, number_of_contracts);
+    let perps_needed = calculate_perps_needed(current_total_delta, target_total_delta);
+    let notional_value = calculate_notional_value(perps_needed.abs(), current_price);
+    let required_margin = calculate_required_margin(notional_value, leverage)?;
+    let fees = calculate_fees(notional_value, transaction_fee_rate);
+    Some((perps_needed, required_margin, fees))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_perps_needed() {
+        let (perps_needed, required_margin, fees) = get_perps_needed(
+            Decimal::new(100, 0),
+            Decimal::new(25, 2),
+            Decimal::new(10, 0),
+            Decimal::ZERO,
+            Decimal::new(10, 0),
+            Decimal::new(1, 3),
+        )
+        .unwrap();
+
+        assert_eq!(perps_needed, Decimal::new(-25, 1));
+        assert_eq!(required_margin, Decimal::new(25, 0));
+        assert_eq!(fees, Decimal::new(25, 2));
+    }
+
+    #[test]
+    fn test_fees_are_exact() {
+        // 0.1 + 0.2 style rounding must not leak into settlement amounts
+        let notional = Decimal::new(1, 1) + Decimal::new(2, 1);
+        assert_eq!(calculate_fees(notional, Decimal::ONE), Decimal::new(3, 1));
+    }
+
+    #[test]
+    fn test_zero_leverage() {
+        assert_eq!(calculate_required_margin(Decimal::ONE, Decimal::ZERO), None);
+    }
+}
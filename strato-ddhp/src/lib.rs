@@ -1,3 +1,8 @@
+#[cfg(feature = "decimal")]
+pub mod decimal;
+
+use strato_utils::vars::instrument::Instrument;
+
 /// Calculates the total delta of the options position.
 ///
 /// # Arguments
@@ -104,6 +109,34 @@ pub fn get_perps_needed(
     (perps_needed, required_margin, fees)
 }
 
+/// Calculates the hedge like `get_perps_needed`, expressed in contracts of
+/// `instrument` and rounded down to whole lots so the order can be placed as
+/// is. Margin and fees are computed on the rounded hedge.
+///
+/// # Returns
+///
+/// A tuple containing the number of contracts needed, required margin, and
+/// transaction fees.
+pub fn get_perps_needed_for_instrument(
+    instrument: &Instrument,
+    current_price: f64,
+    current_delta: f64,
+    number_of_contracts: f64,
+    target_total_delta: f64,
+    leverage: f64,
+    transaction_fee_rate: f64,
+) -> (f64, f64, f64) {
+    let current_total_delta = calculate_total_delta(current_delta, number_of_contracts);
+    let perps_needed = instrument.contracts(calculate_perps_needed(
+        current_total_delta,
+        target_total_delta,
+    ));
+    let notional_value = instrument.notional(current_price, perps_needed.abs());
+    let required_margin = calculate_required_margin(notional_value, leverage);
+    let fees = calculate_fees(notional_value, transaction_fee_rate);
+    (perps_needed, required_margin, fees)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,4 +212,21 @@ mod tests {
         assert_eq!(required_margin, expected_required_margin);
         assert_eq!(fees, expected_fees);
     }
+
+    #[test]
+    fn test_get_perps_needed_for_instrument() {
+        // Contracts of 0.1 underlying, traded in whole contracts
+        let instrument = Instrument::new("ETH-PERP", 0.01, 1.0).with_multiplier(0.1);
+
+        let (perps_needed, required_margin, fees) =
+            get_perps_needed_for_instrument(&instrument, 100.0, 0.25, 10.0, 0.0, 10.0, 0.001);
+
+        assert_eq!(perps_needed, -25.0);
+        assert_eq!(required_margin, 25.0);
+        assert_eq!(fees, 0.25);
+
+        let (perps_needed, _, _) =
+            get_perps_needed_for_instrument(&instrument, 100.0, 0.256, 10.0, 0.0, 10.0, 0.001);
+        assert_eq!(perps_needed, -25.0);
+    }
 }
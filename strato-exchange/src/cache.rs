@@ -0,0 +1,365 @@
+/*!
+Local cache of downloaded candles, enabled with the `download` feature.
+
+Candles are stored under the cache root as one file per downloaded range:
+
+```text
+<root>/<exchange>/<symbol>/<interval ms>/<start>-<end>.csv
+```
+
+A request is served from the files covering it, and only the ranges no file
+covers are downloaded and written as new files. Ranges are aligned to the
+candle interval, and candles that have not closed yet are returned but never
+cached. Files are written as CSV, or as Parquet with the `parquet` feature.
+
+```no_run
+# async fn run() -> Result<(), String> {
+use strato_exchange::cache::CandleCache;
+use strato_exchange::download::Downloader;
+use strato_exchange::download::Exchange;
+
+let mut cache = CandleCache::new("data", Downloader::new(Exchange::Binance));
+// Downloads the day on the first run and reads it from disk afterwards
+let candles = cache
+    .klines("BTCUSDT", 60_000, 1_704_067_200_000, 1_704_153_600_000)
+    .await?;
+# Ok(())
+# }
+```
+*/
+
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use strato_utils::vars::csv::CsvFormat;
+use strato_utils::vars::csv::TimestampFormat;
+use strato_utils::vars::ohlc::Ohlc;
+
+use crate::download::Downloader;
+use crate::download::Exchange;
+
+/// File format of the cached ranges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheFormat {
+    #[default]
+    Csv,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl CacheFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            CacheFormat::Csv => "csv",
+            #[cfg(feature = "parquet")]
+            CacheFormat::Parquet => "parquet",
+        }
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<Ohlc>, String> {
+        match self {
+            CacheFormat::Csv => Ohlc::from_csv_file(path, &csv_format()),
+            #[cfg(feature = "parquet")]
+            CacheFormat::Parquet => strato_utils::vars::columnar::read_candles_parquet(path),
+        }
+    }
+
+    fn write(&self, path: &Path, candles: &[Ohlc]) -> Result<(), String> {
+        match self {
+            CacheFormat::Csv => Ohlc::to_csv_file(candles, path, &csv_format()),
+            #[cfg(feature = "parquet")]
+            CacheFormat::Parquet => {
+                strato_utils::vars::columnar::write_candles_parquet(path, candles)
+            }
+        }
+    }
+}
+
+/// Serves candles from a local directory, downloading the ranges it does not
+/// hold yet.
+#[derive(Debug, Clone)]
+pub struct CandleCache {
+    root: PathBuf,
+    downloader: Downloader,
+    format: CacheFormat,
+}
+
+impl CandleCache {
+    /// Creates a cache stored under `root`, filled with `downloader`.
+    pub fn new<P: AsRef<Path>>(root: P, downloader: Downloader) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            downloader,
+            format: CacheFormat::default(),
+        }
+    }
+
+    /// Sets the format of newly cached files. Files of another format are
+    /// ignored.
+    pub fn with_format(mut self, format: CacheFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Returns the candles of `symbol` opening in `[start, end)`, downloading
+    /// and caching the parts of the range that are not cached yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - Exchange symbol, e.g. `BTCUSDT`.
+    /// * `interval` - Candle interval in milliseconds.
+    /// * `start` - Start of the range in Unix milliseconds (inclusive).
+    /// * `end` - End of the range in Unix milliseconds (exclusive).
+    ///
+    /// # Returns
+    ///
+    /// The candles sorted by timestamp, or an error if a download or a file
+    /// operation fails.
+    pub async fn klines(
+        &mut self,
+        symbol: &str,
+        interval: i64,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<Ohlc>, String> {
+        if interval <= 0 {
+            return Err("Interval must be positive.".to_string());
+        }
+        let (start, end) = align(start, end, interval);
+        // Candles of the current interval are still open
+        let closed = align_down(now(), interval).max(start);
+        let cacheable_end = end.min(closed);
+
+        for (from, to) in self.missing(symbol, interval, start, cacheable_end)? {
+            let candles = self.downloader.klines(symbol, interval, from, to).await?;
+            self.store(symbol, interval, from, to, &candles)?;
+        }
+
+        let mut candles = self.cached(symbol, interval, start, cacheable_end)?;
+        if cacheable_end < end {
+            candles.extend(
+                self.downloader
+                    .klines(symbol, interval, cacheable_end, end)
+                    .await?,
+            );
+        }
+        Ok(candles)
+    }
+
+    /// Returns the cached candles of `symbol` opening in `[start, end)`,
+    /// without downloading anything.
+    pub fn cached(
+        &self,
+        symbol: &str,
+        interval: i64,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<Ohlc>, String> {
+        let mut candles = Vec::new();
+        for (from, to, path) in self.files(symbol, interval)? {
+            if from < end && to > start {
+                candles.extend(
+                    self.format
+                        .read(&path)?
+                        .into_iter()
+                        .filter(|c| c.timestamp >= start && c.timestamp < end),
+                );
+            }
+        }
+        candles.sort_by_key(|c| c.timestamp);
+        candles.dedup_by_key(|c| c.timestamp);
+        Ok(candles)
+    }
+
+    /// Returns the parts of `[start, end)` that no cached file of `symbol`
+    /// covers, in increasing order.
+    pub fn missing(
+        &self,
+        symbol: &str,
+        interval: i64,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(i64, i64)>, String> {
+        let ranges: Vec<(i64, i64)> = self
+            .files(symbol, interval)?
+            .into_iter()
+            .map(|(from, to, _)| (from, to))
+            .collect();
+        Ok(gaps(ranges, start, end))
+    }
+
+    /// Writes downloaded candles as the cached range `[start, end)`. An empty
+    /// range is cached too, so that it is not downloaded again.
+    pub fn store(
+        &self,
+        symbol: &str,
+        interval: i64,
+        start: i64,
+        end: i64,
+        candles: &[Ohlc],
+    ) -> Result<(), String> {
+        let dir = self.dir(symbol, interval);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+        // Written under a temporary name first so that an interrupted write
+        // never leaves a partial range behind
+        let path = dir.join(format!("{}-{}.{}", start, end, self.format.extension()));
+        let partial = path.with_extension("part");
+        self.format.write(&partial, candles)?;
+        fs::rename(&partial, &path)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn dir(&self, symbol: &str, interval: i64) -> PathBuf {
+        self.root
+            .join(exchange_name(self.downloader.exchange()))
+            .join(symbol)
+            .join(interval.to_string())
+    }
+
+    /// Lists the cached files of `symbol` with the range they cover.
+    fn files(&self, symbol: &str, interval: i64) -> Result<Vec<(i64, i64, PathBuf)>, String> {
+        let dir = self.dir(symbol, interval);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        for entry in
+            fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(self.format.extension()) {
+                continue;
+            }
+            let range = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.split_once('-'))
+                .and_then(|(from, to)| Some((from.parse().ok()?, to.parse().ok()?)));
+            if let Some((from, to)) = range {
+                files.push((from, to, path));
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Unix milliseconds, written and read as such rather than detected.
+fn csv_format() -> CsvFormat {
+    CsvFormat {
+        timestamp_format: TimestampFormat::UnixMillis,
+        ..CsvFormat::default()
+    }
+}
+
+fn exchange_name(exchange: Exchange) -> &'static str {
+    match exchange {
+        Exchange::Binance => "binance",
+        Exchange::Bybit => "bybit",
+    }
+}
+
+/// Returns the parts of `[start, end)` not covered by any of `ranges`.
+fn gaps(mut ranges: Vec<(i64, i64)>, start: i64, end: i64) -> Vec<(i64, i64)> {
+    ranges.sort_unstable();
+
+    let mut gaps = Vec::new();
+    let mut cursor = start;
+    for (from, to) in ranges {
+        if cursor >= end {
+            break;
+        }
+        if from > cursor {
+            gaps.push((cursor, from.min(end)));
+        }
+        cursor = cursor.max(to);
+    }
+    if cursor < end {
+        gaps.push((cursor, end));
+    }
+    gaps
+}
+
+fn align_down(timestamp: i64, interval: i64) -> i64 {
+    timestamp.div_euclid(interval) * interval
+}
+
+/// Widens `[start, end)` to whole intervals.
+fn align(start: i64, end: i64, interval: i64) -> (i64, i64) {
+    let start = align_down(start, interval);
+    let end = align_down(end + interval - 1, interval).max(start);
+    (start, end)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaps() {
+        assert_eq!(gaps(vec![], 0, 10), vec![(0, 10)]);
+        assert_eq!(gaps(vec![(0, 10)], 0, 10), vec![]);
+        assert_eq!(
+            gaps(vec![(6, 8), (2, 4), (3, 5)], 0, 10),
+            vec![(0, 2), (5, 6), (8, 10)]
+        );
+        assert_eq!(gaps(vec![(-5, 3), (9, 20)], 0, 10), vec![(3, 9)]);
+        assert_eq!(align(61_000, 119_000, 60_000), (60_000, 120_000));
+    }
+
+    #[test]
+    fn test_serves_cached_ranges() {
+        let root = std::env::temp_dir().join(format!("strato-cache-{}", std::process::id()));
+        let cache = CandleCache::new(&root, Downloader::new(Exchange::Binance));
+        let candle = |timestamp| Ohlc {
+            timestamp,
+            open: 1.0,
+            high: 2.0,
+            low: 0.5,
+            close: 1.5,
+            volume: 10.0,
+        };
+
+        cache
+            .store(
+                "BTCUSDT",
+                60_000,
+                0,
+                180_000,
+                &[candle(0), candle(60_000), candle(120_000)],
+            )
+            .unwrap();
+        cache
+            .store("BTCUSDT", 60_000, 300_000, 360_000, &[candle(300_000)])
+            .unwrap();
+
+        assert_eq!(
+            cache.missing("BTCUSDT", 60_000, 0, 420_000).unwrap(),
+            vec![(180_000, 300_000), (360_000, 420_000)]
+        );
+        assert_eq!(
+            cache.missing("ETHUSDT", 60_000, 0, 60_000).unwrap(),
+            vec![(0, 60_000)]
+        );
+
+        let candles = cache.cached("BTCUSDT", 60_000, 60_000, 360_000).unwrap();
+        assert_eq!(
+            candles.iter().map(|c| c.timestamp).collect::<Vec<_>>(),
+            vec![60_000, 120_000, 300_000]
+        );
+        assert_eq!(candles[0], candle(60_000));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,413 @@
+/*!
+Historical market data downloader for Binance and Bybit USDⓈ-margined
+perpetuals, enabled with the `download` feature.
+
+Both exchanges cap the number of rows per request, so a range is fetched as
+consecutive pages, one request at a time and never faster than the
+configured rate limit. Requests rejected with HTTP 429 (or 418 on Binance)
+are retried after the delay the exchange asks for.
+
+```no_run
+# async fn run() -> Result<(), String> {
+use strato_exchange::download::Downloader;
+use strato_exchange::download::Exchange;
+
+let mut downloader = Downloader::new(Exchange::Binance);
+let candles = downloader
+    .klines("BTCUSDT", 60_000, 1_704_067_200_000, 1_704_153_600_000)
+    .await?;
+# Ok(())
+# }
+```
+*/
+
+use std::time::Duration;
+
+use serde_json::Value;
+pub use strato_utils::vars::funding::FundingRate;
+use strato_utils::vars::ohlc::Ohlc;
+use tokio::time::sleep;
+use tokio::time::Instant;
+
+/// The default minimum delay between two requests.
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The number of times a rate limited request is retried.
+const MAX_RETRIES: usize = 5;
+
+/// Funding intervals are at least an hour on both exchanges, so a window of
+/// `limit` hours never holds more rows than a page.
+const HOUR_MS: i64 = 60 * 60 * 1_000;
+
+/// An exchange to download from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exchange {
+    Binance,
+    Bybit,
+}
+
+impl Exchange {
+    fn base_url(&self) -> &'static str {
+        match self {
+            Exchange::Binance => "https://fapi.binance.com",
+            Exchange::Bybit => "https://api.bybit.com",
+        }
+    }
+
+    /// Maximum number of candles per kline request.
+    fn kline_limit(&self) -> i64 {
+        match self {
+            Exchange::Binance => 1_500,
+            Exchange::Bybit => 1_000,
+        }
+    }
+
+    /// Maximum number of rows per funding rate request.
+    fn funding_limit(&self) -> i64 {
+        match self {
+            Exchange::Binance => 1_000,
+            Exchange::Bybit => 200,
+        }
+    }
+
+    /// Name of a candle interval on the exchange.
+    fn interval_name(&self, interval: i64) -> Result<&'static str, String> {
+        const MINUTE: i64 = 60_000;
+        let names = match self {
+            Exchange::Binance => [
+                "1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "12h", "1d", "1w",
+            ],
+            Exchange::Bybit => [
+                "1", "3", "5", "15", "30", "60", "120", "240", "360", "720", "D", "W",
+            ],
+        };
+        let minutes = [1, 3, 5, 15, 30, 60, 120, 240, 360, 720, 1_440, 10_080];
+
+        minutes
+            .iter()
+            .position(|&m| m * MINUTE == interval)
+            .map(|i| names[i])
+            .ok_or_else(|| {
+                format!(
+                    "Interval of {} ms is not supported by {:?}.",
+                    interval, self
+                )
+            })
+    }
+}
+
+/// Downloads historical klines and funding rates of USDⓈ-margined perpetuals.
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    client: reqwest::Client,
+    exchange: Exchange,
+    min_interval: Duration,
+    last_request: Option<Instant>,
+}
+
+impl Downloader {
+    /// Creates a downloader sending at most one request per
+    /// `DEFAULT_MIN_INTERVAL`.
+    pub fn new(exchange: Exchange) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            exchange,
+            min_interval: DEFAULT_MIN_INTERVAL,
+            last_request: None,
+        }
+    }
+
+    /// Sets the minimum delay between two requests.
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    pub fn exchange(&self) -> Exchange {
+        self.exchange
+    }
+
+    /// Downloads the candles of `symbol` opening in `[start, end)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - Exchange symbol, e.g. `BTCUSDT`.
+    /// * `interval` - Candle interval in milliseconds, from one minute to one
+    ///   week.
+    /// * `start` - Start of the range in Unix milliseconds (inclusive).
+    /// * `end` - End of the range in Unix milliseconds (exclusive).
+    ///
+    /// # Returns
+    ///
+    /// The candles sorted by timestamp, or an error if a request fails.
+    pub async fn klines(
+        &mut self,
+        symbol: &str,
+        interval: i64,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<Ohlc>, String> {
+        let name = self.exchange.interval_name(interval)?;
+        let page = self.exchange.kline_limit() * interval;
+        let mut candles: Vec<Ohlc> = Vec::new();
+
+        let mut cursor = start;
+        while cursor < end {
+            let page_end = end.min(cursor + page);
+            let url = match self.exchange {
+                Exchange::Binance => format!(
+                    "{}/fapi/v1/klines?symbol={}&interval={}&startTime={}&endTime={}&limit={}",
+                    self.exchange.base_url(),
+                    symbol,
+                    name,
+                    cursor,
+                    page_end - 1,
+                    self.exchange.kline_limit()
+                ),
+                Exchange::Bybit => format!(
+                    "{}/v5/market/kline?category=linear&symbol={}&interval={}&start={}&end={}&limit={}",
+                    self.exchange.base_url(),
+                    symbol,
+                    name,
+                    cursor,
+                    page_end - 1,
+                    self.exchange.kline_limit()
+                ),
+            };
+            candles.extend(parse_klines(self.exchange, &self.get(&url).await?)?);
+            cursor = page_end;
+        }
+
+        candles.retain(|c| c.timestamp >= start && c.timestamp < end);
+        candles.sort_by_key(|c| c.timestamp);
+        candles.dedup_by_key(|c| c.timestamp);
+        Ok(candles)
+    }
+
+    /// Downloads the funding rates of `symbol` settled in `[start, end)`.
+    ///
+    /// # Returns
+    ///
+    /// The funding rates sorted by timestamp, or an error if a request
+    /// fails.
+    pub async fn funding_rates(
+        &mut self,
+        symbol: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<FundingRate>, String> {
+        let page = self.exchange.funding_limit() * HOUR_MS;
+        let mut rates: Vec<FundingRate> = Vec::new();
+
+        let mut cursor = start;
+        while cursor < end {
+            let page_end = end.min(cursor + page);
+            let url = match self.exchange {
+                Exchange::Binance => format!(
+                    "{}/fapi/v1/fundingRate?symbol={}&startTime={}&endTime={}&limit={}",
+                    self.exchange.base_url(),
+                    symbol,
+                    cursor,
+                    page_end - 1,
+                    self.exchange.funding_limit()
+                ),
+                Exchange::Bybit => format!(
+                    "{}/v5/market/funding/history?category=linear&symbol={}&startTime={}&endTime={}&limit={}",
+                    self.exchange.base_url(),
+                    symbol,
+                    cursor,
+                    page_end - 1,
+                    self.exchange.funding_limit()
+                ),
+            };
+            rates.extend(parse_funding_rates(self.exchange, &self.get(&url).await?)?);
+            cursor = page_end;
+        }
+
+        rates.retain(|r| r.timestamp >= start && r.timestamp < end);
+        rates.sort_by_key(|r| r.timestamp);
+        rates.dedup_by_key(|r| r.timestamp);
+        Ok(rates)
+    }
+
+    /// Sends a rate limited GET request, retrying when the exchange asks to
+    /// back off.
+    async fn get(&mut self, url: &str) -> Result<Value, String> {
+        for _ in 0..=MAX_RETRIES {
+            if let Some(last) = self.last_request {
+                let elapsed = last.elapsed();
+                if elapsed < self.min_interval {
+                    sleep(self.min_interval - elapsed).await;
+                }
+            }
+            self.last_request = Some(Instant::now());
+
+            let response = self
+                .client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+            let status = response.status().as_u16();
+            if status == 429 || status == 418 {
+                let retry_after = response
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .unwrap_or(60);
+                sleep(Duration::from_secs(retry_after)).await;
+                continue;
+            }
+
+            let body = response
+                .error_for_status()
+                .map_err(|e| format!("Failed to fetch {}: {}", url, e))?
+                .text()
+                .await
+                .map_err(|e| e.to_string())?;
+            return serde_json::from_str(&body).map_err(|e| format!("Invalid response: {}", e));
+        }
+
+        Err(format!("Rate limited too many times fetching {}", url))
+    }
+}
+
+/// Parses a kline response. Bybit returns the most recent candles first.
+fn parse_klines(exchange: Exchange, response: &Value) -> Result<Vec<Ohlc>, String> {
+    let rows = match exchange {
+        Exchange::Binance => response.as_array(),
+        Exchange::Bybit => bybit_result(response)?.as_array(),
+    }
+    .ok_or("Missing kline list")?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(Ohlc {
+                timestamp: number(&row[0])? as i64,
+                open: number(&row[1])?,
+                high: number(&row[2])?,
+                low: number(&row[3])?,
+                close: number(&row[4])?,
+                volume: number(&row[5])?,
+            })
+        })
+        .collect()
+}
+
+/// Parses a funding rate history response.
+fn parse_funding_rates(exchange: Exchange, response: &Value) -> Result<Vec<FundingRate>, String> {
+    let (rows, time_key) = match exchange {
+        Exchange::Binance => (response.as_array(), "fundingTime"),
+        Exchange::Bybit => (bybit_result(response)?.as_array(), "fundingRateTimestamp"),
+    };
+
+    rows.ok_or("Missing funding rate list")?
+        .iter()
+        .map(|row| {
+            Ok(FundingRate {
+                timestamp: number(&row[time_key])? as i64,
+                rate: number(&row["fundingRate"])?,
+            })
+        })
+        .collect()
+}
+
+/// Returns the `result.list` of a Bybit v5 response, or its error message.
+fn bybit_result(response: &Value) -> Result<&Value, String> {
+    match response["retCode"].as_i64() {
+        Some(0) => Ok(&response["result"]["list"]),
+        _ => Err(format!(
+            "Bybit error: {}",
+            response["retMsg"].as_str().unwrap_or("unknown")
+        )),
+    }
+}
+
+/// Reads a number sent either as a JSON number or as a string.
+fn number(value: &Value) -> Result<f64, String> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| format!("Invalid number {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_interval_names() {
+        assert_eq!(Exchange::Binance.interval_name(3_600_000), Ok("1h"));
+        assert_eq!(Exchange::Bybit.interval_name(86_400_000), Ok("D"));
+        assert!(Exchange::Binance.interval_name(42).is_err());
+    }
+
+    #[test]
+    fn test_parse_klines() {
+        let binance = json!([[
+            1_704_067_200_000_i64,
+            "42283.6",
+            "42554.6",
+            "42261.0",
+            "42475.2",
+            "1271.7",
+            1_704_067_259_999_i64,
+            "54000000.0",
+            1000,
+            "600.0",
+            "25000000.0",
+            "0"
+        ]]);
+        let candles = parse_klines(Exchange::Binance, &binance).unwrap();
+        assert_eq!(candles[0].timestamp, 1_704_067_200_000);
+        assert_eq!(candles[0].close, 42475.2);
+
+        let bybit = json!({
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": { "list": [
+                ["1704067260000", "2", "3", "1", "2.5", "10", "25"],
+                ["1704067200000", "1", "2", "0.5", "2", "5", "10"]
+            ]}
+        });
+        let candles = parse_klines(Exchange::Bybit, &bybit).unwrap();
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[1].volume, 5.0);
+
+        let error = json!({ "retCode": 10001, "retMsg": "params error" });
+        assert_eq!(
+            parse_klines(Exchange::Bybit, &error).unwrap_err(),
+            "Bybit error: params error"
+        );
+    }
+
+    #[test]
+    fn test_parse_funding_rates() {
+        let binance = json!([
+            { "symbol": "BTCUSDT", "fundingTime": 1_704_067_200_000_i64, "fundingRate": "0.00010000", "markPrice": "42000" }
+        ]);
+        assert_eq!(
+            parse_funding_rates(Exchange::Binance, &binance).unwrap(),
+            vec![FundingRate {
+                timestamp: 1_704_067_200_000,
+                rate: 0.0001
+            }]
+        );
+
+        let bybit = json!({
+            "retCode": 0,
+            "result": { "list": [
+                { "symbol": "BTCUSDT", "fundingRate": "-0.0002", "fundingRateTimestamp": "1704067200000" }
+            ]}
+        });
+        assert_eq!(
+            parse_funding_rates(Exchange::Bybit, &bybit).unwrap()[0].rate,
+            -0.0002
+        );
+    }
+}
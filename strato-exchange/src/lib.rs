@@ -1,3 +1,8 @@
+#[cfg(feature = "download")]
+pub mod cache;
+#[cfg(feature = "download")]
+pub mod download;
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
@@ -44,6 +44,7 @@ fn prepare_backtest() -> Backtest<HashMapMarketDepth> {
                     depth
                 })
                 .exchange(ExchangeKind::NoPartialFillExchange)
+                .last_trades_capacity(1_000)
                 .build()
                 .unwrap(),
         )
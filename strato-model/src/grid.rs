@@ -1 +1,8 @@
+pub mod costs;
 pub mod dynamic;
+pub mod engine;
+pub mod margin;
+pub mod neutral;
+pub mod optimize;
+pub mod report;
+pub mod static_grid;
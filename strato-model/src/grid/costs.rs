@@ -0,0 +1,108 @@
+/*!
+Trading costs of the grid executors.
+
+Fills are charged a fee of the exchange, in basis points of the notional for
+makers (resting limit orders) and takers (market orders) plus an optional
+fixed amount per fill, and market orders fill at a worse price than the
+reference price because of slippage. The default costs are zero, which is
+what the executors assumed before costs were modelled.
+*/
+
+use strato_utils::vars::trade::Side;
+
+/// Exchange fees of a fill.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeModel {
+    /// Fee of resting limit orders in basis points of the notional; negative
+    /// for a rebate.
+    pub maker_bps: f64,
+    /// Fee of market orders in basis points of the notional.
+    pub taker_bps: f64,
+    /// Fixed fee per fill in quote currency.
+    pub fixed: f64,
+}
+
+impl FeeModel {
+    /// Fee of a fill of `notional`.
+    pub fn fee(&self, notional: f64, maker: bool) -> f64 {
+        let bps = if maker {
+            self.maker_bps
+        } else {
+            self.taker_bps
+        };
+        notional.abs() * bps / 10_000.0 + self.fixed
+    }
+
+    /// Largest taker notional whose cost including fees fits in `budget`.
+    pub fn taker_notional_for(&self, budget: f64) -> f64 {
+        self.notional_for(budget, false)
+    }
+
+    /// Largest notional whose cost including the maker or taker fee fits in
+    /// `budget`.
+    pub fn notional_for(&self, budget: f64, maker: bool) -> f64 {
+        let bps = if maker {
+            self.maker_bps
+        } else {
+            self.taker_bps
+        };
+        ((budget - self.fixed) / (1.0 + bps / 10_000.0)).max(0.0)
+    }
+}
+
+/// Slippage of market orders.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Slippage {
+    /// Fills at the reference price.
+    #[default]
+    None,
+    /// Fills this many basis points through the reference price.
+    Bps(f64),
+    /// Fills half a tick of this size through the reference price, i.e. at
+    /// the touch when the reference is the mid price.
+    HalfTick(f64),
+}
+
+impl Slippage {
+    /// Price at which a market order on `side` fills for a reference `price`.
+    pub fn fill_price(&self, side: Side, price: f64) -> f64 {
+        let offset = match *self {
+            Slippage::None => 0.0,
+            Slippage::Bps(bps) => price * bps / 10_000.0,
+            Slippage::HalfTick(tick_size) => tick_size / 2.0,
+        };
+        price + side.sign() * offset
+    }
+}
+
+/// Fees and slippage applied by the executors.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionCosts {
+    pub fees: FeeModel,
+    pub slippage: Slippage,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_costs() {
+        let fees = FeeModel {
+            maker_bps: -1.0,
+            taker_bps: 5.0,
+            fixed: 0.5,
+        };
+        assert_eq!(fees.fee(10_000.0, false), 5.5);
+        assert_eq!(fees.fee(-10_000.0, true), -0.5);
+        let notional = fees.taker_notional_for(1_000.0);
+        assert!((notional + fees.fee(notional, false) - 1_000.0).abs() < 1e-9);
+
+        assert_eq!(Slippage::Bps(10.0).fill_price(Side::Buy, 100.0), 100.1);
+        assert_eq!(Slippage::HalfTick(0.5).fill_price(Side::Sell, 100.0), 99.75);
+        assert_eq!(Slippage::None.fill_price(Side::Sell, 100.0), 100.0);
+    }
+}
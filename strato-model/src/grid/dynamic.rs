@@ -7,31 +7,212 @@ The module relies on utility functions from the `strato_utils` crate for calcula
 RMA (Rolling Moving Average) and ATR (Average True Range).
 */
 
-use strato_utils::ta::atr::atr;
-use strato_utils::ta::rma::rma;
-use strato_utils::ta::sma::sma;
+use std::fmt;
+
+use strato_utils::ta::atr::atr_nan;
+use strato_utils::ta::dema::dema_nan;
+use strato_utils::ta::dema::tema_nan;
+use strato_utils::ta::ema::ema_nan;
+use strato_utils::ta::hma::hma_nan;
+use strato_utils::ta::rma::rma_nan;
+use strato_utils::ta::sma::sma_nan;
+use strato_utils::ta::vwma::vwma_nan;
+use strato_utils::ta::wma::wma_nan;
+use strato_utils::vars::funding::FundingRate;
 use strato_utils::vars::ohlc::Ohlc;
+use strato_utils::vars::trade::Side;
+
+use crate::grid::costs::ExecutionCosts;
+use crate::grid::costs::Slippage;
+use crate::grid::report::BacktestReport;
+use crate::grid::report::ClosedTrade;
 
 const DEFAULT_MA_LEN: usize = 100;
 const DEFAULT_ATR_LEN: usize = 14;
 const DEFAULT_BAND_MULT: f64 = 2.5;
+const DEFAULT_LEVELS: usize = 1;
+const DEFAULT_LEVEL_SPACING: f64 = 1.0;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MaType {
     Rma,
     Sma,
+    /// Exponential Moving Average, seeded with the SMA as in Pine Script.
+    Ema,
+    Wma,
+    /// Hull Moving Average, the lowest-lag option.
+    Hma,
+    /// Double Exponential Moving Average, its EMAs seeded like `Ema`.
+    Dema,
+    /// Triple Exponential Moving Average, its EMAs seeded like `Ema`.
+    Tema,
+    /// Volume-Weighted Moving Average.
+    Vwma,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GridLogic {
+    /// Bands `band_mult` ATRs away from the moving average.
     Atr,
+    /// Bands `band_mult` percent away from the moving average.
     Percent,
 }
 
+/// Which side of the grid is traded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    /// Buy at discount levels and sell at premium levels.
+    #[default]
+    LongOnly,
+    /// Short at premium levels and cover at discount levels.
+    ShortOnly,
+    /// Trade both sides, one position at a time.
+    Both,
+}
+
+/// How the orders of a backtest fill.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FillModel {
+    /// Market orders at the close of the bars reaching a level, like
+    /// `execute_trades`.
+    #[default]
+    Close,
+    /// Limit orders at the levels, filled during the bars reaching them.
+    Intrabar(IntrabarFill),
+}
+
+/// Fills of limit orders on bars trading through their price.
+///
+/// A bar whose low (high) only touches a buy (sell) level may fill part of
+/// the order, since orders queued before it take the liquidity there. The
+/// deeper the bar trades through the level, the more of the order fills, in
+/// full from `full_fill_bps` beyond it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntrabarFill {
+    /// Share of the order filled when the bar only touches the level.
+    pub touch_fill: f64,
+    /// Depth beyond the level, in basis points of its price, from which the
+    /// order fills in full. The share grows linearly from `touch_fill` in
+    /// between.
+    pub full_fill_bps: f64,
+}
+
+impl Default for IntrabarFill {
+    /// Orders fill in full as soon as the level is touched.
+    fn default() -> Self {
+        IntrabarFill {
+            touch_fill: 1.0,
+            full_fill_bps: 0.0,
+        }
+    }
+}
+
+impl IntrabarFill {
+    /// Share of a limit order on `side` at `price` filled during a bar,
+    /// between 0 and 1.
+    pub fn fill_ratio(&self, side: Side, price: f64, candle: &Ohlc) -> f64 {
+        let through = match side {
+            Side::Buy => price - candle.low,
+            Side::Sell => candle.high - price,
+        };
+        if through.is_nan() || through < 0.0 {
+            return 0.0;
+        }
+        let depth_bps = through / price * 10_000.0;
+        if depth_bps >= self.full_fill_bps {
+            return 1.0;
+        }
+        let touch_fill = self.touch_fill.clamp(0.0, 1.0);
+        touch_fill + (1.0 - touch_fill) * depth_bps / self.full_fill_bps
+    }
+}
+
+/// How the equity is split across the levels of a multi-level grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Allocation {
+    /// The same share at every level.
+    Equal,
+    /// Each level gets `ratio` times the share of the previous one, e.g.
+    /// `2.0` for a martingale doubling down on the deeper levels.
+    Geometric { ratio: f64 },
+    /// Equal shares scaled down by `target_vol / volatility` when the
+    /// volatility (ATR over price) exceeds `target_vol`, so that volatile
+    /// periods are traded smaller.
+    VolatilityWeighted { target_vol: f64 },
+}
+
+/// Sizing of the levels of a multi-level grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelSizing {
+    pub allocation: Allocation,
+    /// Maximum notional of the whole position as a multiple of the equity,
+    /// reached when every level is filled.
+    pub max_exposure: f64,
+}
+
+impl Default for LevelSizing {
+    fn default() -> Self {
+        LevelSizing {
+            allocation: Allocation::Equal,
+            max_exposure: 1.0,
+        }
+    }
+}
+
+impl LevelSizing {
+    /// Shares of the maximum exposure allocated to each of `levels` levels,
+    /// summing to one.
+    pub fn weights(&self, levels: usize) -> Vec<f64> {
+        let raw: Vec<f64> = match self.allocation {
+            Allocation::Geometric { ratio } => (0..levels).map(|k| ratio.powi(k as i32)).collect(),
+            Allocation::Equal | Allocation::VolatilityWeighted { .. } => vec![1.0; levels],
+        };
+        let total: f64 = raw.iter().sum();
+        raw.iter().map(|w| w / total).collect()
+    }
+
+    /// Notional to buy at a level.
+    ///
+    /// # Arguments
+    ///
+    /// * `weight` - Share of the level, from `weights`.
+    /// * `equity` - Current equity of the account.
+    /// * `volatility` - Current ATR over price, used by
+    ///   `Allocation::VolatilityWeighted`.
+    pub fn notional(&self, weight: f64, equity: f64, volatility: f64) -> f64 {
+        let scale = match self.allocation {
+            Allocation::VolatilityWeighted { target_vol } if volatility > target_vol => {
+                target_vol / volatility
+            }
+            _ => 1.0,
+        };
+        weight * scale * self.max_exposure * equity
+    }
+}
+
+/// Cash balance and position of the grid. A short position is negative and
+/// its sale proceeds are part of the balance.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TradingState {
     pub balance: f64,
     pub position: f64,
+    /// Fees paid so far, already deducted from the balance.
+    pub fees_paid: f64,
+    /// Funding paid so far on perpetuals, negative when received, already
+    /// deducted from the balance.
+    pub funding_paid: f64,
 }
 
 /// Parameters for configuring the grid trading strategy.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GridParams {
     /// Length of the Rolling Moving Average (RMA) period.
     pub ma_len: usize,
@@ -39,10 +220,17 @@ pub struct GridParams {
     pub ma_type: MaType,
     /// Grid Logic (e.g., ATR, Percent)
     pub grid_logic: GridLogic,
-    /// Multiplier for the ATR to determine grid levels.
+    /// Multiplier for the ATR to determine grid levels, or width of the
+    /// bands in percent of the moving average with `GridLogic::Percent`.
     pub band_mult: f64,
-    /// Length of the Average True Range (ATR) period.
+    /// Length of the Average True Range (ATR) period, unused with
+    /// `GridLogic::Percent`.
     pub atr_len: usize,
+    /// Number of premium and of discount levels per bar.
+    pub levels: usize,
+    /// Distance between consecutive levels, in the unit of `band_mult`
+    /// (ATRs or percent).
+    pub level_spacing: f64,
 }
 
 impl Default for GridParams {
@@ -53,16 +241,194 @@ impl Default for GridParams {
             grid_logic: GridLogic::Atr,
             band_mult: DEFAULT_BAND_MULT,
             atr_len: DEFAULT_ATR_LEN,
+            levels: DEFAULT_LEVELS,
+            level_spacing: DEFAULT_LEVEL_SPACING,
+        }
+    }
+}
+
+impl GridParams {
+    /// Returns a builder starting from the default parameters.
+    pub fn builder() -> GridParamsBuilder {
+        GridParamsBuilder::default()
+    }
+
+    /// Checks that the lengths and the number of levels are positive and that
+    /// the band multiplier and the level spacing are positive and finite.
+    pub fn validate(&self) -> Result<(), GridParamsError> {
+        let lengths = [
+            ("ma_len", self.ma_len),
+            ("atr_len", self.atr_len),
+            ("levels", self.levels),
+        ];
+        if let Some(&(field, _)) = lengths.iter().find(|(_, value)| *value == 0) {
+            return Err(GridParamsError::Zero { field });
+        }
+        let widths = [
+            ("band_mult", self.band_mult),
+            ("level_spacing", self.level_spacing),
+        ];
+        for (field, value) in widths {
+            if !(value > 0.0 && value.is_finite()) {
+                return Err(GridParamsError::NonPositive { field, value });
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates the parameters for `len` candles: on top of `validate`, the
+    /// moving average and, with `GridLogic::Atr`, the ATR must fit in the
+    /// data, otherwise they never warm up.
+    pub fn validate_for(&self, len: usize) -> Result<(), GridParamsError> {
+        self.validate()?;
+        if self.ma_len > len {
+            return Err(GridParamsError::InsufficientData {
+                field: "ma_len",
+                required: self.ma_len,
+                len,
+            });
+        }
+        if self.grid_logic == GridLogic::Atr && self.atr_len > len {
+            return Err(GridParamsError::InsufficientData {
+                field: "atr_len",
+                required: self.atr_len,
+                len,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Errors returned when validating `GridParams`, `StaticGridParams` and
+/// `NeutralGridParams`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridParamsError {
+    /// A length or the number of levels was zero.
+    Zero { field: &'static str },
+    /// The band multiplier or the level spacing was zero, negative or not
+    /// finite.
+    NonPositive { field: &'static str, value: f64 },
+    /// A parameter that may be zero was negative or not finite.
+    Negative { field: &'static str, value: f64 },
+    /// A length exceeded the number of candles.
+    InsufficientData {
+        field: &'static str,
+        required: usize,
+        len: usize,
+    },
+    /// The lower bound of a static grid was not below its upper bound.
+    InvertedBounds { lower: f64, upper: f64 },
+}
+
+impl fmt::Display for GridParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridParamsError::Zero { field } => write!(f, "{} must be positive", field),
+            GridParamsError::NonPositive { field, value } => {
+                write!(f, "{} must be positive and finite, got {}", field, value)
+            }
+            GridParamsError::Negative { field, value } => {
+                write!(
+                    f,
+                    "{} must be finite and not negative, got {}",
+                    field, value
+                )
+            }
+            GridParamsError::InsufficientData {
+                field,
+                required,
+                len,
+            } => write!(
+                f,
+                "{} of {} exceeds the {} candles of the data",
+                field, required, len
+            ),
+            GridParamsError::InvertedBounds { lower, upper } => write!(
+                f,
+                "lower bound {} must be below upper bound {}",
+                lower, upper
+            ),
         }
     }
 }
 
+impl std::error::Error for GridParamsError {}
+
+/// Builds validated `GridParams`, see `GridParams::builder`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GridParamsBuilder {
+    params: GridParams,
+}
+
+impl GridParamsBuilder {
+    pub fn ma_len(mut self, ma_len: usize) -> Self {
+        self.params.ma_len = ma_len;
+        self
+    }
+
+    pub fn ma_type(mut self, ma_type: MaType) -> Self {
+        self.params.ma_type = ma_type;
+        self
+    }
+
+    pub fn grid_logic(mut self, grid_logic: GridLogic) -> Self {
+        self.params.grid_logic = grid_logic;
+        self
+    }
+
+    pub fn band_mult(mut self, band_mult: f64) -> Self {
+        self.params.band_mult = band_mult;
+        self
+    }
+
+    pub fn atr_len(mut self, atr_len: usize) -> Self {
+        self.params.atr_len = atr_len;
+        self
+    }
+
+    pub fn levels(mut self, levels: usize) -> Self {
+        self.params.levels = levels;
+        self
+    }
+
+    pub fn level_spacing(mut self, level_spacing: f64) -> Self {
+        self.params.level_spacing = level_spacing;
+        self
+    }
+
+    /// Returns the parameters if they pass `GridParams::validate`.
+    pub fn build(self) -> Result<GridParams, GridParamsError> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+
+    /// Returns the parameters if they pass `GridParams::validate_for` with
+    /// `len` candles.
+    pub fn build_for(self, len: usize) -> Result<GridParams, GridParamsError> {
+        self.params.validate_for(len)?;
+        Ok(self.params)
+    }
+}
+
+/// Premium and discount levels of a multi-level grid, indexed by level and
+/// then by bar. Level 0 is the closest to the moving average.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridLevels {
+    pub premium: Vec<Vec<f64>>,
+    pub discount: Vec<Vec<f64>>,
+}
+
 /// Generates the premium and discount grid levels based on the provided ohlc
 /// and parameters.
 ///
-/// This function calculates the Rolling Moving Average (RMA) and Average True
-/// Range (ATR) of the market data, and then uses these values to generate the
-/// grid levels.
+/// This function calculates the moving average of the market data and, with
+/// `GridLogic::Atr`, its Average True Range (ATR), and then uses these values
+/// to generate the grid levels. With `GridLogic::Percent` the bands are
+/// `band_mult` percent above and below the moving average instead.
+///
+/// Levels are `f64::NAN` until the averages are warmed up, so no entry or
+/// exit condition is met on a short history.
 ///
 /// # Arguments
 ///
@@ -76,13 +442,201 @@ impl Default for GridParams {
 /// - `premium_levels`: The calculated premium levels.
 /// - `discount_levels`: The calculated discount levels.
 pub fn generate_grid_levels(ohlc: &[Ohlc], params: &GridParams) -> (Vec<f64>, Vec<f64>) {
+    let (ma_values, band_values) = calculate_bands(ohlc, params);
+    calculate_grid_levels(&ma_values, &band_values, params.band_mult)
+}
+
+/// Validates `params` against the data with `GridParams::validate_for` before
+/// generating the grid levels like `generate_grid_levels`.
+pub fn try_generate_grid_levels(
+    ohlc: &[Ohlc],
+    params: &GridParams,
+) -> Result<(Vec<f64>, Vec<f64>), GridParamsError> {
+    params.validate_for(ohlc.len())?;
+    Ok(generate_grid_levels(ohlc, params))
+}
+
+/// Generates `params.levels` premium and discount levels per bar, the first
+/// one as in `generate_grid_levels` and the following ones
+/// `params.level_spacing` further away each.
+///
+/// # Arguments
+///
+/// * `ohlc` - A slice of `Ohlc` structs representing market data.
+/// * `params` - A reference to `GridParams` struct containing the parameters
+///   for the grid.
+///
+/// # Returns
+///
+/// The grid levels, `f64::NAN` until the averages are warmed up.
+pub fn generate_multi_grid_levels(ohlc: &[Ohlc], params: &GridParams) -> GridLevels {
+    let (ma_values, band_values) = calculate_bands(ohlc, params);
+    calculate_multi_grid_levels(
+        &ma_values,
+        &band_values,
+        params.band_mult,
+        params.levels,
+        params.level_spacing,
+    )
+}
+
+/// A recentering of an anchored grid, see
+/// `generate_recentering_grid_levels`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Recenter {
+    /// Index of the bar whose close triggered the recentering. The new levels
+    /// apply from the next bar.
+    pub index: usize,
+    /// Center of the grid before the recentering.
+    pub from: f64,
+    /// Center of the grid after the recentering, the triggering close.
+    pub to: f64,
+}
+
+/// Generates the levels of a grid anchored at a fixed center instead of
+/// following the moving average, recentering it when the trend runs away.
+///
+/// The grid is first centered on the moving average once it is warmed up,
+/// with the band unit of that bar. It stays there until `bars` consecutive
+/// closes are beyond the outermost premium or discount level, and is then
+/// recentered on the last close with the current band unit. Inventory bought
+/// at the old levels is released at the new, closer premium levels instead
+/// of waiting for a reversion that may never come.
+///
+/// # Arguments
+///
+/// * `ohlc` - A slice of `Ohlc` structs representing market data.
+/// * `params` - The grid parameters, including the number of levels.
+/// * `bars` - Number of consecutive closes beyond the outer levels that trigger
+///   a recentering, at least 1.
+///
+/// # Returns
+///
+/// A tuple of the grid levels, `f64::NAN` until the first anchoring, and the
+/// recenterings in bar order.
+pub fn generate_recentering_grid_levels(
+    ohlc: &[Ohlc],
+    params: &GridParams,
+    bars: usize,
+) -> (GridLevels, Vec<Recenter>) {
+    let (ma_values, band_values) = calculate_bands(ohlc, params);
+    let distances = level_distances(params);
+
+    let mut levels = GridLevels {
+        premium: vec![vec![f64::NAN; ohlc.len()]; params.levels],
+        discount: vec![vec![f64::NAN; ohlc.len()]; params.levels],
+    };
+    let mut recenters = Vec::new();
+    let mut anchor = Anchor::new(bars);
+
+    for (i, candle) in ohlc.iter().enumerate() {
+        let Some((center, band)) = anchor.current(ma_values[i], band_values[i]) else {
+            continue;
+        };
+        for (k, distance) in distances.iter().enumerate() {
+            levels.premium[k][i] = center + band * distance;
+            levels.discount[k][i] = center - band * distance;
+        }
+        recenters.extend(anchor.update(i, candle.close, band_values[i], params));
+    }
+
+    (levels, recenters)
+}
+
+/// Distances of the levels from the center, in band units.
+pub(crate) fn level_distances(params: &GridParams) -> Vec<f64> {
+    (0..params.levels)
+        .map(|k| params.band_mult + k as f64 * params.level_spacing)
+        .collect()
+}
+
+/// Center and band unit of an anchored grid, recentered when `bars`
+/// consecutive closes are beyond its outer levels.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Anchor {
+    bars: usize,
+    grid: Option<(f64, f64)>,
+    /// Consecutive closes beyond the outer levels.
+    beyond: usize,
+}
+
+impl Anchor {
+    pub(crate) fn new(bars: usize) -> Self {
+        Self {
+            bars: bars.max(1),
+            grid: None,
+            beyond: 0,
+        }
+    }
+
+    /// Returns the center and band unit of the grid, anchoring it on the
+    /// moving average once both values are warmed up.
+    pub(crate) fn current(&mut self, ma: f64, band: f64) -> Option<(f64, f64)> {
+        if self.grid.is_none() && ma.is_finite() && band.is_finite() {
+            self.grid = Some((ma, band));
+        }
+        self.grid
+    }
+
+    /// Counts a close and recenters the grid on it if it is the `bars`-th
+    /// consecutive close beyond the outer levels.
+    pub(crate) fn update(
+        &mut self,
+        index: usize,
+        close: f64,
+        band: f64,
+        params: &GridParams,
+    ) -> Option<Recenter> {
+        let (center, unit) = self.grid?;
+        let outer = level_distances(params).last().copied().unwrap_or(0.0);
+        if (close - center).abs() > unit * outer {
+            self.beyond += 1;
+        } else {
+            self.beyond = 0;
+        }
+        if self.beyond < self.bars || !band.is_finite() {
+            return None;
+        }
+
+        let unit = match params.grid_logic {
+            GridLogic::Atr => band,
+            GridLogic::Percent => close / 100.0,
+        };
+        self.grid = Some((close, unit));
+        self.beyond = 0;
+        Some(Recenter {
+            index,
+            from: center,
+            to: close,
+        })
+    }
+}
+
+/// Calculates the moving average of the source prices and the band unit of
+/// the grid logic: the ATR, or one percent of the average.
+pub(crate) fn calculate_bands(ohlc: &[Ohlc], params: &GridParams) -> (Vec<f64>, Vec<f64>) {
     let src = calculate_src(ohlc);
     let ma_values = match params.ma_type {
-        MaType::Sma => sma(&src, params.ma_len),
-        MaType::Rma => rma(&src, params.ma_len),
+        MaType::Sma => sma_nan(&src, params.ma_len),
+        MaType::Rma => rma_nan(&src, params.ma_len),
+        MaType::Ema => ema_nan(&src, params.ma_len),
+        MaType::Wma => wma_nan(&src, params.ma_len),
+        MaType::Hma => hma_nan(&src, params.ma_len),
+        MaType::Dema => dema_nan(&src, params.ma_len),
+        MaType::Tema => tema_nan(&src, params.ma_len),
+        MaType::Vwma => {
+            let volume: Vec<f64> = ohlc.iter().map(|c| c.volume).collect();
+            vwma_nan(&src, &volume, params.ma_len)
+        }
+    };
+    let band_values = match params.grid_logic {
+        GridLogic::Atr => atr_nan(ohlc, params.atr_len),
+        // One percent of the average per unit of `band_mult`
+        GridLogic::Percent => ma_values.iter().map(|ma| ma / 100.0).collect(),
     };
-    let atr_values = atr(ohlc, params.atr_len);
-    calculate_grid_levels(&ma_values, &atr_values, params.band_mult)
+    (ma_values, band_values)
 }
 
 /// Calculates the source prices from the provided ohlc.
@@ -134,6 +688,34 @@ pub fn calculate_grid_levels(rma: &[f64], atr: &[f64], band_mult: f64) -> (Vec<f
     (premium_levels, discount_levels)
 }
 
+/// Calculates several premium and discount grid levels per bar.
+///
+/// # Arguments
+///
+/// * `ma` - A slice of moving average values.
+/// * `band` - A slice of band units, e.g. ATR values.
+/// * `band_mult` - Distance of the first level from the average, in band units.
+/// * `levels` - The number of premium and of discount levels.
+/// * `spacing` - Distance between consecutive levels, in band units.
+///
+/// # Returns
+///
+/// The grid levels, level `k` being `band_mult + k * spacing` band units
+/// away from the average.
+pub fn calculate_multi_grid_levels(
+    ma: &[f64],
+    band: &[f64],
+    band_mult: f64,
+    levels: usize,
+    spacing: f64,
+) -> GridLevels {
+    let (premium, discount) = (0..levels)
+        .map(|k| calculate_grid_levels(ma, band, band_mult + k as f64 * spacing))
+        .unzip();
+
+    GridLevels { premium, discount }
+}
+
 /// Checks entry conditions based on the discount levels.
 ///
 /// The entry condition is met when the low price of the ohlc is below the
@@ -176,9 +758,40 @@ pub fn check_exit_conditions(ohlc: &[Ohlc], premium_levels: &[f64]) -> Vec<bool>
         .collect()
 }
 
+/// Checks the entry conditions of every discount level, see
+/// `check_entry_conditions`.
+///
+/// # Returns
+///
+/// The entry conditions indexed by level and then by bar.
+pub fn check_level_entry_conditions(ohlc: &[Ohlc], levels: &GridLevels) -> Vec<Vec<bool>> {
+    levels
+        .discount
+        .iter()
+        .map(|discount_levels| check_entry_conditions(ohlc, discount_levels))
+        .collect()
+}
+
+/// Checks the exit conditions of every premium level, see
+/// `check_exit_conditions`.
+///
+/// # Returns
+///
+/// The exit conditions indexed by level and then by bar.
+pub fn check_level_exit_conditions(ohlc: &[Ohlc], levels: &GridLevels) -> Vec<Vec<bool>> {
+    levels
+        .premium
+        .iter()
+        .map(|premium_levels| check_exit_conditions(ohlc, premium_levels))
+        .collect()
+}
+
 /// Manages the grids based on the calculated grid levels and entry/exit
 /// conditions.
 ///
+/// The conditions are evaluated in hindsight over the whole history; use
+/// `GridEngine` to run the grid bar by bar with explicit orders.
+///
 /// # Arguments
 ///
 /// * `ohlc` - A slice of `Ohlc` structs representing market data.
@@ -197,7 +810,8 @@ pub fn manage_grids(ohlc: &[Ohlc], params: &GridParams) -> (Vec<bool>, Vec<bool>
     (entry_conditions, exit_conditions)
 }
 
-/// Executes trades based on the entry and exit conditions.
+/// Executes long trades based on the entry and exit conditions, see
+/// `execute_trades_with_direction`.
 ///
 /// # Arguments
 ///
@@ -217,22 +831,538 @@ pub fn execute_trades(
     exit_conditions: &[bool],
     initial_balance: f64,
 ) -> f64 {
+    execute_trades_with_direction(
+        ohlc,
+        entry_conditions,
+        exit_conditions,
+        initial_balance,
+        Direction::LongOnly,
+    )
+}
+
+/// Executes trades on the sides allowed by `direction`.
+///
+/// A bar meeting the entry condition (price at a discount level) covers an
+/// open short or, when flat, opens a long. A bar meeting the exit condition
+/// (price at a premium level) sells an open long or, when flat, opens a
+/// short. At most one of these actions is taken per bar.
+///
+/// # Arguments
+///
+/// * `ohlc` - A slice of `Ohlc` structs representing market data.
+/// * `entry_conditions` - Whether each bar reached a discount level.
+/// * `exit_conditions` - Whether each bar reached a premium level.
+/// * `initial_balance` - The initial balance for the trading account.
+/// * `direction` - The sides to trade.
+///
+/// # Returns
+///
+/// The final balance after closing any open position at the last close.
+pub fn execute_trades_with_direction(
+    ohlc: &[Ohlc],
+    entry_conditions: &[bool],
+    exit_conditions: &[bool],
+    initial_balance: f64,
+    direction: Direction,
+) -> f64 {
+    execute_trades_with_costs(
+        ohlc,
+        entry_conditions,
+        exit_conditions,
+        initial_balance,
+        direction,
+        &ExecutionCosts::default(),
+    )
+}
+
+/// Executes trades like `execute_trades_with_direction`, paying taker fees
+/// and slippage on every fill at the close.
+///
+/// # Returns
+///
+/// The final balance net of costs after closing any open position at the
+/// last close.
+pub fn execute_trades_with_costs(
+    ohlc: &[Ohlc],
+    entry_conditions: &[bool],
+    exit_conditions: &[bool],
+    initial_balance: f64,
+    direction: Direction,
+    costs: &ExecutionCosts,
+) -> f64 {
+    backtest_trades(
+        ohlc,
+        entry_conditions,
+        exit_conditions,
+        initial_balance,
+        direction,
+        costs,
+    )
+    .final_balance
+}
+
+/// Backtests trades like `execute_trades_with_costs`.
+///
+/// # Returns
+///
+/// The report of the backtest, with one trade per round trip.
+pub fn backtest_trades(
+    ohlc: &[Ohlc],
+    entry_conditions: &[bool],
+    exit_conditions: &[bool],
+    initial_balance: f64,
+    direction: Direction,
+    costs: &ExecutionCosts,
+) -> BacktestReport {
+    backtest_trades_with_funding(
+        ohlc,
+        entry_conditions,
+        exit_conditions,
+        initial_balance,
+        direction,
+        costs,
+        &[],
+    )
+}
+
+/// Backtests trades on a perpetual like `backtest_trades`, paying or
+/// receiving funding on the position held at each settlement.
+///
+/// A settlement is charged at the open of the first bar starting at or after
+/// it, on the position held since the previous close and marked at the open.
+///
+/// # Arguments
+///
+/// * `funding` - The funding settlements, in increasing time order.
+///
+/// # Returns
+///
+/// The report of the backtest, with the total funding paid.
+pub fn backtest_trades_with_funding(
+    ohlc: &[Ohlc],
+    entry_conditions: &[bool],
+    exit_conditions: &[bool],
+    initial_balance: f64,
+    direction: Direction,
+    costs: &ExecutionCosts,
+    funding: &[FundingRate],
+) -> BacktestReport {
     let mut state = TradingState {
         balance: initial_balance,
         position: 0.0,
+        fees_paid: 0.0,
+        funding_paid: 0.0,
     };
+    let long = direction != Direction::ShortOnly;
+    let short = direction != Direction::LongOnly;
+    let mut recorder = TradeRecorder::new(initial_balance, ohlc.len());
+    let mut funding = FundingSchedule::new(funding);
 
     for i in 0..ohlc.len() {
+        funding.settle(&mut state, &ohlc[i]);
+        let price = ohlc[i].close;
+        let balance = state.balance;
         if entry_conditions[i] {
-            handle_entry(&mut state, ohlc[i].close);
+            if state.position < 0.0 {
+                handle_cover_with_costs(&mut state, price, costs);
+            } else if long {
+                handle_entry_with_costs(&mut state, price, costs);
+            }
         } else if exit_conditions[i] {
-            handle_exit(&mut state, ohlc[i].close);
+            if state.position > 0.0 {
+                handle_exit_with_costs(&mut state, price, costs);
+            } else if short {
+                handle_short_entry_with_costs(&mut state, price, costs);
+            }
         }
+        recorder.fill(&state, balance, ohlc[i].timestamp, price, costs);
+        recorder.mark(&state, price);
     }
 
-    finalize_balance(&mut state, ohlc.last().unwrap().close);
+    if let Some(last) = ohlc.last() {
+        let balance = state.balance;
+        finalize_balance_with_costs(&mut state, last.close, costs);
+        recorder.fill(&state, balance, last.timestamp, last.close, costs);
+    }
 
-    state.balance
+    recorder.finish(&state)
+}
+
+/// Backtests a grid on its levels with the fills of `fill_model`.
+///
+/// With `FillModel::Close` the levels only decide which bars trade, at the
+/// close, like `backtest_trades`. With `FillModel::Intrabar` resting limit
+/// orders at the levels fill during the bars reaching them, at the level
+/// price and paying the maker fee: the entry order when flat, then the exit
+/// order of the position until it is closed. A partially filled entry keeps
+/// the filled part only, a partially filled exit keeps selling (or buying)
+/// the rest on the following bars. When a flat bar reaches both levels, the
+/// one closer to the open fills.
+///
+/// # Arguments
+///
+/// * `ohlc` - A slice of `Ohlc` structs representing market data.
+/// * `premium_levels` - The premium level of every bar.
+/// * `discount_levels` - The discount level of every bar.
+/// * `initial_balance` - The initial balance for the trading account.
+/// * `direction` - The sides to trade.
+/// * `costs` - The fees, and the slippage of the market orders.
+/// * `fill_model` - How the orders fill.
+///
+/// # Returns
+///
+/// The report of the backtest, with one trade per round trip.
+pub fn backtest_trades_with_fills(
+    ohlc: &[Ohlc],
+    premium_levels: &[f64],
+    discount_levels: &[f64],
+    initial_balance: f64,
+    direction: Direction,
+    costs: &ExecutionCosts,
+    fill_model: FillModel,
+) -> BacktestReport {
+    let fills = match fill_model {
+        FillModel::Close => {
+            let entries = check_entry_conditions(ohlc, discount_levels);
+            let exits = check_exit_conditions(ohlc, premium_levels);
+            return backtest_trades(ohlc, &entries, &exits, initial_balance, direction, costs);
+        }
+        FillModel::Intrabar(fills) => fills,
+    };
+
+    let mut state = TradingState {
+        balance: initial_balance,
+        position: 0.0,
+        fees_paid: 0.0,
+        funding_paid: 0.0,
+    };
+    let long = direction != Direction::ShortOnly;
+    let short = direction != Direction::LongOnly;
+    // Limit orders fill at their price
+    let limit_costs = ExecutionCosts {
+        slippage: Slippage::None,
+        ..*costs
+    };
+    let mut recorder = TradeRecorder::new(initial_balance, ohlc.len());
+
+    for (i, candle) in ohlc.iter().enumerate() {
+        let (premium, discount) = (premium_levels[i], discount_levels[i]);
+        let balance = state.balance;
+        let (side, price, qty) = if state.position > 0.0 {
+            let ratio = fills.fill_ratio(Side::Sell, premium, candle);
+            (Side::Sell, premium, state.position * ratio)
+        } else if state.position < 0.0 {
+            let ratio = fills.fill_ratio(Side::Buy, discount, candle);
+            (Side::Buy, discount, -state.position * ratio)
+        } else {
+            let buy = fills.fill_ratio(Side::Buy, discount, candle);
+            let sell = fills.fill_ratio(Side::Sell, premium, candle);
+            let buy_first = (candle.open - discount).abs() <= (premium - candle.open).abs();
+            if long && buy > 0.0 && (!short || sell == 0.0 || buy_first) {
+                let notional = costs.fees.notional_for(state.balance, true) * buy;
+                (Side::Buy, discount, notional / discount)
+            } else if short && sell > 0.0 {
+                (Side::Sell, premium, state.balance * sell / premium)
+            } else {
+                (Side::Buy, discount, 0.0)
+            }
+        };
+
+        if qty > 0.0 {
+            let notional = qty * price;
+            let fee = costs.fees.fee(notional, true);
+            state.balance -= side.sign() * notional + fee;
+            state.position += side.sign() * qty;
+            state.fees_paid += fee;
+            // Close out the dust left by rounding
+            if state.position.abs() < 1e-12 * qty {
+                state.position = 0.0;
+            }
+            recorder.fill(&state, balance, candle.timestamp, price, &limit_costs);
+        }
+        recorder.mark(&state, candle.close);
+    }
+
+    if let Some(last) = ohlc.last() {
+        let balance = state.balance;
+        finalize_balance_with_costs(&mut state, last.close, costs);
+        recorder.fill(&state, balance, last.timestamp, last.close, costs);
+    }
+
+    recorder.finish(&state)
+}
+
+/// Funding settlements charged as a backtest goes through the bars.
+struct FundingSchedule<'a> {
+    rates: &'a [FundingRate],
+    /// Index of the next settlement to charge.
+    next: usize,
+}
+
+impl<'a> FundingSchedule<'a> {
+    fn new(rates: &'a [FundingRate]) -> Self {
+        Self { rates, next: 0 }
+    }
+
+    /// Charges the settlements up to the open of `candle` on the position.
+    fn settle(&mut self, state: &mut TradingState, candle: &Ohlc) {
+        while let Some(rate) = self.rates.get(self.next) {
+            if rate.timestamp > candle.timestamp {
+                break;
+            }
+            let payment = rate.payment(state.position, candle.open);
+            state.balance -= payment;
+            state.funding_paid += payment;
+            self.next += 1;
+        }
+    }
+}
+
+/// An open position of `backtest_trades`.
+struct OpenTrade {
+    side: Side,
+    entry_time: i64,
+    entry_price: f64,
+    qty: f64,
+    /// Balance before the entry.
+    balance: f64,
+}
+
+/// Collects the trades, equity and exposure of a backtest.
+struct TradeRecorder {
+    report: BacktestReport,
+    open: Option<OpenTrade>,
+    bars: usize,
+    exposed: usize,
+}
+
+impl TradeRecorder {
+    fn new(initial_balance: f64, bars: usize) -> Self {
+        Self {
+            report: BacktestReport {
+                initial_balance,
+                equity: Vec::with_capacity(bars),
+                ..Default::default()
+            },
+            open: None,
+            bars,
+            exposed: 0,
+        }
+    }
+
+    /// Records the opening or closing of the single position at `price`,
+    /// given the balance before the fill.
+    fn fill(
+        &mut self,
+        state: &TradingState,
+        balance: f64,
+        time: i64,
+        price: f64,
+        costs: &ExecutionCosts,
+    ) {
+        match self.open.take() {
+            None if state.position != 0.0 => {
+                let side = if state.position > 0.0 {
+                    Side::Buy
+                } else {
+                    Side::Sell
+                };
+                self.open = Some(OpenTrade {
+                    side,
+                    entry_time: time,
+                    entry_price: costs.slippage.fill_price(side, price),
+                    qty: state.position.abs(),
+                    balance,
+                });
+            }
+            Some(open) if state.position == 0.0 => {
+                let exit_side = match open.side {
+                    Side::Buy => Side::Sell,
+                    Side::Sell => Side::Buy,
+                };
+                self.report.trades.push(ClosedTrade {
+                    level: 0,
+                    side: open.side,
+                    entry_time: open.entry_time,
+                    exit_time: time,
+                    entry_price: open.entry_price,
+                    exit_price: costs.slippage.fill_price(exit_side, price),
+                    qty: open.qty,
+                    pnl: state.balance - open.balance,
+                });
+            }
+            open => self.open = open,
+        }
+    }
+
+    /// Records the equity and exposure at the close of a bar.
+    fn mark(&mut self, state: &TradingState, close: f64) {
+        self.report
+            .equity
+            .push(state.balance + state.position * close);
+        if state.position != 0.0 {
+            self.exposed += 1;
+        }
+    }
+
+    /// Records the sale of the lot of a level.
+    fn close_lot(&mut self, level: usize, entry: (i64, f64), time: i64, price: f64, qty: f64) {
+        let (entry_time, entry_price) = entry;
+        self.report.trades.push(ClosedTrade {
+            level,
+            side: Side::Buy,
+            entry_time,
+            exit_time: time,
+            entry_price,
+            exit_price: price,
+            qty,
+            pnl: qty * (price - entry_price),
+        });
+    }
+
+    fn finish(mut self, state: &TradingState) -> BacktestReport {
+        self.report.final_balance = state.balance;
+        self.report.funding_paid = state.funding_paid;
+        if self.bars > 0 {
+            self.report.exposure = self.exposed as f64 / self.bars as f64;
+        }
+        self.report
+    }
+}
+
+/// Executes a long multi-level grid with per-level sizing.
+///
+/// Each discount level buys its share of the exposure, sized by `sizing`,
+/// when the low reaches it, and the lot is sold when the high reaches the
+/// premium level of the same index. Orders fill at the level price, like the
+/// resting limit orders of a live grid, and the position never exceeds
+/// `sizing.max_exposure` times the equity.
+///
+/// # Arguments
+///
+/// * `ohlc` - A slice of `Ohlc` structs representing market data.
+/// * `params` - The grid parameters, including the number of levels.
+/// * `initial_balance` - The initial balance for the trading account.
+/// * `sizing` - How the equity is split across the levels.
+///
+/// # Returns
+///
+/// The final balance after closing any open position at the last close.
+pub fn execute_grid_trades(
+    ohlc: &[Ohlc],
+    params: &GridParams,
+    initial_balance: f64,
+    sizing: &LevelSizing,
+) -> f64 {
+    let levels = generate_multi_grid_levels(ohlc, params);
+    let atr_values = atr_nan(ohlc, params.atr_len);
+    execute_level_trades(ohlc, &levels, &atr_values, initial_balance, sizing)
+}
+
+/// Executes a long multi-level grid on precomputed levels, e.g. from
+/// `generate_recentering_grid_levels`, like `execute_grid_trades`.
+///
+/// # Arguments
+///
+/// * `ohlc` - A slice of `Ohlc` structs representing market data.
+/// * `levels` - The grid levels of every bar.
+/// * `atr` - The ATR of every bar, used by `Allocation::VolatilityWeighted`.
+/// * `initial_balance` - The initial balance for the trading account.
+/// * `sizing` - How the equity is split across the levels.
+///
+/// # Returns
+///
+/// The final balance after closing any open position at the last close.
+pub fn execute_level_trades(
+    ohlc: &[Ohlc],
+    levels: &GridLevels,
+    atr: &[f64],
+    initial_balance: f64,
+    sizing: &LevelSizing,
+) -> f64 {
+    backtest_level_trades(ohlc, levels, atr, initial_balance, sizing).final_balance
+}
+
+/// Backtests a long multi-level grid like `execute_level_trades`.
+///
+/// # Returns
+///
+/// The report of the backtest, with one trade per filled lot.
+pub fn backtest_level_trades(
+    ohlc: &[Ohlc],
+    levels: &GridLevels,
+    atr: &[f64],
+    initial_balance: f64,
+    sizing: &LevelSizing,
+) -> BacktestReport {
+    backtest_level_trades_with_funding(ohlc, levels, atr, initial_balance, sizing, &[])
+}
+
+/// Backtests a long multi-level grid on a perpetual like
+/// `backtest_level_trades`, paying or receiving funding on the inventory held
+/// at each settlement, see `backtest_trades_with_funding`.
+pub fn backtest_level_trades_with_funding(
+    ohlc: &[Ohlc],
+    levels: &GridLevels,
+    atr: &[f64],
+    initial_balance: f64,
+    sizing: &LevelSizing,
+    funding: &[FundingRate],
+) -> BacktestReport {
+    let weights = sizing.weights(levels.discount.len());
+
+    let mut state = TradingState {
+        balance: initial_balance,
+        position: 0.0,
+        fees_paid: 0.0,
+        funding_paid: 0.0,
+    };
+    let mut recorder = TradeRecorder::new(initial_balance, ohlc.len());
+    // Quantity held by each level, with its entry time and price
+    let mut lots = vec![0.0; levels.discount.len()];
+    let mut entries = vec![(0, 0.0); levels.discount.len()];
+    let mut funding = FundingSchedule::new(funding);
+
+    for (i, candle) in ohlc.iter().enumerate() {
+        funding.settle(&mut state, candle);
+        for (k, lot) in lots.iter_mut().enumerate() {
+            let premium = levels.premium[k][i];
+            if *lot > 0.0 && candle.high > premium {
+                state.balance += *lot * premium;
+                state.position -= *lot;
+                recorder.close_lot(k, entries[k], candle.timestamp, premium, *lot);
+                *lot = 0.0;
+            }
+        }
+
+        let equity = state.balance + state.position * candle.close;
+        let volatility = atr[i] / candle.close;
+        for (k, lot) in lots.iter_mut().enumerate() {
+            let discount = levels.discount[k][i];
+            if *lot == 0.0 && candle.low < discount && discount > 0.0 {
+                let room = sizing.max_exposure * equity - state.position * discount;
+                let notional = sizing.notional(weights[k], equity, volatility).min(room);
+                if notional > 0.0 {
+                    *lot = notional / discount;
+                    state.balance -= notional;
+                    state.position += *lot;
+                    entries[k] = (candle.timestamp, discount);
+                }
+            }
+        }
+        recorder.mark(&state, candle.close);
+    }
+
+    if let Some(last) = ohlc.last() {
+        for (k, &lot) in lots.iter().enumerate() {
+            if lot > 0.0 {
+                recorder.close_lot(k, entries[k], last.timestamp, last.close, lot);
+            }
+        }
+        finalize_balance(&mut state, last.close);
+    }
+
+    recorder.finish(&state)
 }
 
 /// Handles trade entry.
@@ -242,9 +1372,19 @@ pub fn execute_trades(
 /// * `state` - The current trading state.
 /// * `price` - The current price of the asset.
 pub fn handle_entry(state: &mut TradingState, price: f64) {
+    handle_entry_with_costs(state, price, &ExecutionCosts::default());
+}
+
+/// Handles trade entry with a market order, spending the balance on the
+/// asset and the taker fee.
+pub fn handle_entry_with_costs(state: &mut TradingState, price: f64, costs: &ExecutionCosts) {
     if state.position == 0.0 {
-        state.position = state.balance / price;
-        state.balance = 0.0;
+        let price = costs.slippage.fill_price(Side::Buy, price);
+        let notional = costs.fees.taker_notional_for(state.balance);
+        let fee = costs.fees.fee(notional, false);
+        state.position = notional / price;
+        state.balance -= notional + fee;
+        state.fees_paid += fee;
     }
 }
 
@@ -255,30 +1395,106 @@ pub fn handle_entry(state: &mut TradingState, price: f64) {
 /// * `state` - The current trading state.
 /// * `price` - The current price of the asset.
 pub fn handle_exit(state: &mut TradingState, price: f64) {
+    handle_exit_with_costs(state, price, &ExecutionCosts::default());
+}
+
+/// Handles trade exit with a market order.
+pub fn handle_exit_with_costs(state: &mut TradingState, price: f64, costs: &ExecutionCosts) {
     if state.position > 0.0 {
-        state.balance = state.position * price;
+        let price = costs.slippage.fill_price(Side::Sell, price);
+        let notional = state.position * price;
+        let fee = costs.fees.fee(notional, false);
+        state.balance += notional - fee;
+        state.position = 0.0;
+        state.fees_paid += fee;
+    }
+}
+
+/// Handles a short entry, selling the balance worth of the asset.
+///
+/// # Arguments
+///
+/// * `state` - The current trading state.
+/// * `price` - The current price of the asset.
+pub fn handle_short_entry(state: &mut TradingState, price: f64) {
+    handle_short_entry_with_costs(state, price, &ExecutionCosts::default());
+}
+
+/// Handles a short entry with a market order.
+pub fn handle_short_entry_with_costs(state: &mut TradingState, price: f64, costs: &ExecutionCosts) {
+    if state.position == 0.0 {
+        let price = costs.slippage.fill_price(Side::Sell, price);
+        let notional = state.balance;
+        let fee = costs.fees.fee(notional, false);
+        state.position = -notional / price;
+        state.balance += notional - fee;
+        state.fees_paid += fee;
+    }
+}
+
+/// Handles covering a short position.
+///
+/// # Arguments
+///
+/// * `state` - The current trading state.
+/// * `price` - The current price of the asset.
+pub fn handle_cover(state: &mut TradingState, price: f64) {
+    handle_cover_with_costs(state, price, &ExecutionCosts::default());
+}
+
+/// Handles covering a short position with a market order.
+pub fn handle_cover_with_costs(state: &mut TradingState, price: f64, costs: &ExecutionCosts) {
+    if state.position < 0.0 {
+        let price = costs.slippage.fill_price(Side::Buy, price);
+        let notional = -state.position * price;
+        let fee = costs.fees.fee(notional, false);
+        state.balance -= notional + fee;
         state.position = 0.0;
+        state.fees_paid += fee;
     }
 }
 
-/// Finalizes the balance at the end of the trading period.
+/// Finalizes the balance at the end of the trading period, closing a long or
+/// short position.
 ///
 /// # Arguments
 ///
 /// * `state` - The current trading state.
 /// * `price` - The final price of the asset.
 pub fn finalize_balance(state: &mut TradingState, price: f64) {
+    finalize_balance_with_costs(state, price, &ExecutionCosts::default());
+}
+
+/// Finalizes the balance like `finalize_balance`, closing the position with
+/// a market order.
+pub fn finalize_balance_with_costs(state: &mut TradingState, price: f64, costs: &ExecutionCosts) {
     if state.position > 0.0 {
-        state.balance = state.position * price;
-        state.position = 0.0;
+        handle_exit_with_costs(state, price, costs);
+    } else if state.position < 0.0 {
+        handle_cover_with_costs(state, price, costs);
     }
 }
 
+/// Manages a multi-level grid, see `manage_grids`.
+///
+/// # Returns
+///
+/// A tuple containing the entry and the exit conditions, indexed by level and
+/// then by bar.
+pub fn manage_multi_grids(ohlc: &[Ohlc], params: &GridParams) -> (Vec<Vec<bool>>, Vec<Vec<bool>>) {
+    let levels = generate_multi_grid_levels(ohlc, params);
+    let entry_conditions = check_level_entry_conditions(ohlc, &levels);
+    let exit_conditions = check_level_exit_conditions(ohlc, &levels);
+
+    (entry_conditions, exit_conditions)
+}
+
 #[cfg(test)]
 mod tests {
     use strato_utils::vars::ohlc::Ohlc;
 
     use super::*;
+    use crate::grid::costs::FeeModel;
 
     #[test]
     fn test_calculate_src() {
@@ -319,6 +1535,491 @@ mod tests {
         assert_eq!(discount_levels, expected_discount_levels);
     }
 
+    #[test]
+    fn test_calculate_multi_grid_levels() {
+        let ma = vec![100.0, 105.0];
+        let atr = vec![5.0, 10.0];
+
+        let levels = calculate_multi_grid_levels(&ma, &atr, 2.0, 3, 0.5);
+
+        assert_eq!(levels.premium.len(), 3);
+        assert_eq!(levels.premium[0], vec![110.0, 125.0]);
+        assert_eq!(levels.premium[2], vec![115.0, 135.0]);
+        assert_eq!(levels.discount[1], vec![87.5, 80.0]);
+
+        let ohlc = vec![
+            Ohlc {
+                high: 112.0,
+                low: 88.0,
+                ..Default::default()
+            },
+            Ohlc {
+                high: 131.0,
+                low: 100.0,
+                ..Default::default()
+            },
+        ];
+        let entries = check_level_entry_conditions(&ohlc, &levels);
+        let exits = check_level_exit_conditions(&ohlc, &levels);
+        assert_eq!(
+            entries,
+            vec![vec![true, false], vec![false, false], vec![false, false]]
+        );
+        assert_eq!(
+            exits,
+            vec![vec![true, true], vec![false, true], vec![false, false]]
+        );
+    }
+
+    #[test]
+    fn test_execute_trades_with_direction() {
+        let ohlc: Vec<Ohlc> = [100.0, 120.0, 90.0, 110.0]
+            .iter()
+            .map(|&close| Ohlc {
+                close,
+                ..Default::default()
+            })
+            .collect();
+        let entries = [false, false, true, false];
+        let exits = [false, true, false, true];
+
+        // Long at 90 and sold at 110
+        let balance = execute_trades(&ohlc, &entries, &exits, 1_000.0);
+        assert!((balance - 1_000.0 / 90.0 * 110.0).abs() < 1e-9);
+        // Shorted at 120 and covered at 90, then shorted again at 110 and
+        // closed at the last close
+        assert_eq!(
+            execute_trades_with_direction(&ohlc, &entries, &exits, 1_200.0, Direction::ShortOnly),
+            1_200.0 + 10.0 * 30.0
+        );
+        // The cover at 90 does not also open a long
+        assert_eq!(
+            execute_trades_with_direction(&ohlc, &entries, &exits, 1_200.0, Direction::Both),
+            1_500.0
+        );
+
+        // Long at 100 and sold at 120, then shorted flat at 110
+        let entries = [true, false, false, false];
+        assert_eq!(
+            execute_trades_with_direction(&ohlc, &entries, &exits, 1_000.0, Direction::Both),
+            1_200.0
+        );
+    }
+
+    #[test]
+    fn test_level_sizing() {
+        let sizing = LevelSizing {
+            allocation: Allocation::Geometric { ratio: 2.0 },
+            max_exposure: 0.7,
+        };
+        let weights = sizing.weights(3);
+        assert!((weights[0] - 1.0 / 7.0).abs() < 1e-12);
+        assert!((weights[2] - 4.0 / 7.0).abs() < 1e-12);
+        assert!((sizing.notional(weights[2], 1_000.0, 0.5) - 400.0).abs() < 1e-9);
+
+        let sizing = LevelSizing {
+            allocation: Allocation::VolatilityWeighted { target_vol: 0.01 },
+            max_exposure: 1.0,
+        };
+        assert_eq!(sizing.weights(4), vec![0.25; 4]);
+        assert_eq!(sizing.notional(0.25, 1_000.0, 0.005), 250.0);
+        assert_eq!(sizing.notional(0.25, 1_000.0, 0.02), 125.0);
+    }
+
+    #[test]
+    fn test_execute_grid_trades() {
+        // Flat at 100, dips to 90 and 70, then rallies to 120
+        let ohlc: Vec<Ohlc> = [100.0, 100.0, 90.0, 70.0, 100.0, 120.0]
+            .iter()
+            .map(|&close| Ohlc {
+                open: close,
+                high: close,
+                low: close,
+                close,
+                ..Default::default()
+            })
+            .collect();
+        let params = GridParams {
+            ma_len: 1,
+            ma_type: MaType::Sma,
+            grid_logic: GridLogic::Percent,
+            band_mult: 5.0,
+            levels: 2,
+            level_spacing: 5.0,
+            ..Default::default()
+        };
+
+        // With a one-bar average, the levels move with the price and are
+        // never crossed
+        let balance = execute_grid_trades(&ohlc, &params, 1_000.0, &LevelSizing::default());
+        assert_eq!(balance, 1_000.0);
+
+        let params = GridParams {
+            ma_len: 2,
+            ..params
+        };
+        let sizing = LevelSizing {
+            allocation: Allocation::Equal,
+            max_exposure: 0.5,
+        };
+        // Bar 2 (average 95) buys 250 at level 0 (90.25). Bar 3 (average 80)
+        // buys a quarter of the marked-down equity at level 1 (72). Bar 4
+        // (average 85) sells level 0 at 89.25 and level 1 at 93.5.
+        let lot_0 = 250.0 / 90.25;
+        let lot_1 = 0.25 * (750.0 + lot_0 * 70.0) / 72.0;
+        let expected = 1_000.0 + lot_0 * (89.25 - 90.25) + lot_1 * (93.5 - 72.0);
+        let balance = execute_grid_trades(&ohlc, &params, 1_000.0, &sizing);
+        assert!((balance - expected).abs() < 1e-9, "{}", balance);
+    }
+
+    #[test]
+    fn test_recentering_grid_levels() {
+        // Ranging around 100, then trending down to 60
+        let closes = [100.0, 100.0, 100.0, 95.0, 80.0, 75.0, 70.0, 65.0, 60.0];
+        let ohlc: Vec<Ohlc> = closes
+            .iter()
+            .map(|&close| Ohlc {
+                open: close,
+                high: close,
+                low: close,
+                close,
+                ..Default::default()
+            })
+            .collect();
+        let params = GridParams {
+            ma_len: 2,
+            ma_type: MaType::Sma,
+            grid_logic: GridLogic::Percent,
+            band_mult: 5.0,
+            levels: 2,
+            level_spacing: 5.0,
+            ..Default::default()
+        };
+
+        let (levels, recenters) = generate_recentering_grid_levels(&ohlc, &params, 2);
+
+        // Anchored at 100 with levels at 95 and 90 until the closes at 80 and
+        // 75 are both below 90
+        assert!(levels.discount[0][0].is_nan());
+        assert!((levels.discount[1][1] - 90.0).abs() < 1e-9);
+        assert!((levels.discount[1][5] - 90.0).abs() < 1e-9);
+        assert_eq!(recenters[0].index, 5);
+        assert!((recenters[0].from - 100.0).abs() < 1e-9);
+        assert_eq!(recenters[0].to, 75.0);
+        assert!((levels.premium[0][6] - 78.75).abs() < 1e-9);
+        // Recentered again after 65 and 60 are both below 67.5
+        assert_eq!(recenters.len(), 2);
+        assert_eq!(recenters[1].index, 8);
+    }
+
+    #[test]
+    fn test_backtest_report() {
+        let ohlc: Vec<Ohlc> = [100.0, 90.0, 110.0, 120.0, 100.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| Ohlc {
+                timestamp: i as i64 * 60_000,
+                close,
+                ..Default::default()
+            })
+            .collect();
+        let entries = [false, true, false, false, false];
+        let exits = [false, false, true, true, false];
+
+        // Long from 90 to 110, then short from 120 closed at 100
+        let report = backtest_trades(
+            &ohlc,
+            &entries,
+            &exits,
+            900.0,
+            Direction::Both,
+            &ExecutionCosts::default(),
+        );
+        assert_eq!(report.trades.len(), 2);
+        let long = report.trades[0];
+        assert_eq!(
+            (long.side, long.entry_time, long.exit_time),
+            (Side::Buy, 60_000, 120_000)
+        );
+        assert_eq!(
+            (long.entry_price, long.exit_price, long.qty),
+            (90.0, 110.0, 10.0)
+        );
+        assert!((long.pnl - 200.0).abs() < 1e-9);
+        let short = report.trades[1];
+        assert_eq!((short.side, short.exit_time), (Side::Sell, 240_000));
+        assert!((short.pnl - 1_100.0 / 6.0).abs() < 1e-9);
+        assert_eq!(report.equity.len(), 5);
+        assert_eq!(report.equity[0], 900.0);
+        assert!((report.equity[2] - 1_100.0).abs() < 1e-9);
+        assert_eq!(report.exposure, 0.6);
+        assert_eq!(report.win_rate(), 1.0);
+        assert_eq!(
+            report.final_balance,
+            execute_trades_with_direction(&ohlc, &entries, &exits, 900.0, Direction::Both)
+        );
+    }
+
+    #[test]
+    fn test_intrabar_fills() {
+        let candle = |open: f64, high: f64, low: f64, close: f64| Ohlc {
+            open,
+            high,
+            low,
+            close,
+            ..Default::default()
+        };
+        let fills = IntrabarFill {
+            touch_fill: 0.5,
+            full_fill_bps: 100.0,
+        };
+        let bar = candle(100.0, 101.0, 99.5, 100.5);
+        assert_eq!(fills.fill_ratio(Side::Buy, 99.0, &bar), 0.0);
+        assert_eq!(fills.fill_ratio(Side::Buy, 99.5, &bar), 0.5);
+        assert!((fills.fill_ratio(Side::Buy, 100.0, &bar) - 0.75).abs() < 1e-9);
+        assert_eq!(fills.fill_ratio(Side::Sell, 99.0, &bar), 1.0);
+        assert_eq!(
+            IntrabarFill::default().fill_ratio(Side::Sell, 101.0, &bar),
+            1.0
+        );
+
+        // A large candle reaching the discount level of 95 and closing at 104
+        let ohlc = vec![
+            candle(100.0, 101.0, 94.0, 104.0),
+            candle(104.0, 111.0, 103.0, 108.0),
+        ];
+        let premium = [110.0; 2];
+        let discount = [95.0; 2];
+        let costs = ExecutionCosts::default();
+
+        // At the close it buys at 104 and sells at 108
+        let at_close = backtest_trades_with_fills(
+            &ohlc,
+            &premium,
+            &discount,
+            1_000.0,
+            Direction::LongOnly,
+            &costs,
+            FillModel::Close,
+        );
+        assert!((at_close.final_balance - 1_000.0 * 108.0 / 104.0).abs() < 1e-9);
+
+        // Intrabar it buys at 95 and sells at 110
+        let intrabar = backtest_trades_with_fills(
+            &ohlc,
+            &premium,
+            &discount,
+            1_000.0,
+            Direction::LongOnly,
+            &costs,
+            FillModel::Intrabar(IntrabarFill::default()),
+        );
+        assert_eq!(intrabar.trades.len(), 1);
+        assert_eq!(
+            (
+                intrabar.trades[0].entry_price,
+                intrabar.trades[0].exit_price
+            ),
+            (95.0, 110.0)
+        );
+        assert!((intrabar.final_balance - 1_000.0 * 110.0 / 95.0).abs() < 1e-9);
+
+        // Half filled on the touch at 110, the rest sold at the last close
+        let fills = IntrabarFill {
+            touch_fill: 0.5,
+            full_fill_bps: 1_000.0,
+        };
+        let ohlc = vec![
+            candle(100.0, 101.0, 94.0, 104.0),
+            candle(104.0, 110.0, 103.0, 108.0),
+        ];
+        let partial = backtest_trades_with_fills(
+            &ohlc,
+            &premium,
+            &discount,
+            1_000.0,
+            Direction::LongOnly,
+            &costs,
+            FillModel::Intrabar(fills),
+        );
+        let ratio = 0.5 + 0.5 * (1.0 / 95.0 * 10_000.0) / 1_000.0;
+        let qty = 1_000.0 * ratio / 95.0;
+        let expected = 1_000.0 - qty * 95.0 + qty / 2.0 * 110.0 + qty / 2.0 * 108.0;
+        assert!((partial.final_balance - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_funding_payments() {
+        let ohlc: Vec<Ohlc> = [100.0, 100.0, 110.0, 110.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &price)| Ohlc {
+                timestamp: i as i64 * 3_600_000,
+                open: price,
+                close: price,
+                ..Default::default()
+            })
+            .collect();
+        let funding = [
+            // Before the entry
+            FundingRate {
+                timestamp: 0,
+                rate: 0.01,
+            },
+            // Charged at the open of the third bar, then received
+            FundingRate {
+                timestamp: 5_000_000,
+                rate: 0.001,
+            },
+            FundingRate {
+                timestamp: 10_800_000,
+                rate: -0.002,
+            },
+        ];
+        let entries = [false, true, false, false];
+        let exits = [false, false, false, true];
+
+        // Long 10 units from 100, paying 1.1 then receiving 2.2
+        let report = backtest_trades_with_funding(
+            &ohlc,
+            &entries,
+            &exits,
+            1_000.0,
+            Direction::LongOnly,
+            &ExecutionCosts::default(),
+            &funding,
+        );
+        assert!((report.funding_paid - (1.1 - 2.2)).abs() < 1e-9);
+        assert!((report.final_balance - (1_100.0 + 1.1)).abs() < 1e-9);
+        assert!((report.trades[0].pnl - 101.1).abs() < 1e-9);
+        assert_eq!(
+            backtest_trades(
+                &ohlc,
+                &entries,
+                &exits,
+                1_000.0,
+                Direction::LongOnly,
+                &ExecutionCosts::default()
+            )
+            .funding_paid,
+            0.0
+        );
+
+        // No bars, no trades
+        let report = backtest_trades_with_funding(
+            &[],
+            &[],
+            &[],
+            1_000.0,
+            Direction::LongOnly,
+            &ExecutionCosts::default(),
+            &funding,
+        );
+        assert!(report.trades.is_empty());
+        assert_eq!(report.final_balance, 1_000.0);
+    }
+
+    #[test]
+    fn test_grid_params_builder() {
+        let params = GridParams::builder()
+            .ma_len(20)
+            .ma_type(MaType::Sma)
+            .band_mult(2.0)
+            .levels(3)
+            .build()
+            .unwrap();
+        assert_eq!(params.ma_len, 20);
+        assert_eq!(params.atr_len, GridParams::default().atr_len);
+
+        assert_eq!(
+            GridParams::builder().atr_len(0).build(),
+            Err(GridParamsError::Zero { field: "atr_len" })
+        );
+        assert_eq!(
+            GridParams::builder().band_mult(-1.0).build(),
+            Err(GridParamsError::NonPositive {
+                field: "band_mult",
+                value: -1.0
+            })
+        );
+        assert!(GridParams::builder()
+            .level_spacing(f64::NAN)
+            .build()
+            .is_err());
+
+        let ohlc = vec![Ohlc::default(); 50];
+        let err = GridParams::builder()
+            .ma_len(20)
+            .atr_len(60)
+            .build_for(ohlc.len());
+        assert_eq!(
+            err,
+            Err(GridParamsError::InsufficientData {
+                field: "atr_len",
+                required: 60,
+                len: 50
+            })
+        );
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "atr_len of 60 exceeds the 50 candles of the data"
+        );
+        // The ATR is not used with percent bands
+        let params = GridParams::builder()
+            .ma_len(20)
+            .atr_len(60)
+            .grid_logic(GridLogic::Percent)
+            .build_for(ohlc.len())
+            .unwrap();
+        assert!(try_generate_grid_levels(&ohlc, &params).is_ok());
+        assert!(try_generate_grid_levels(&ohlc[..10], &params).is_err());
+    }
+
+    #[test]
+    fn test_execute_trades_with_costs() {
+        let ohlc: Vec<Ohlc> = [100.0, 110.0]
+            .iter()
+            .map(|&close| Ohlc {
+                close,
+                ..Default::default()
+            })
+            .collect();
+        let costs = ExecutionCosts {
+            fees: FeeModel {
+                maker_bps: 0.0,
+                taker_bps: 10.0,
+                fixed: 1.0,
+            },
+            slippage: Slippage::Bps(10.0),
+        };
+
+        // Buys at 100.1 with 1000 minus fees, sells at 109.89 minus fees
+        let balance = execute_trades_with_costs(
+            &ohlc,
+            &[true, false],
+            &[false, true],
+            1_000.0,
+            Direction::LongOnly,
+            &costs,
+        );
+        let qty = (1_000.0 - 1.0) / 1.001 / 100.1;
+        let expected = qty * 109.89 * 0.999 - 1.0;
+        assert!((balance - expected).abs() < 1e-9, "{}", balance);
+
+        let mut state = TradingState {
+            balance: 1_000.0,
+            position: 0.0,
+            fees_paid: 0.0,
+            funding_paid: 0.0,
+        };
+        handle_short_entry_with_costs(&mut state, 100.0, &costs);
+        finalize_balance_with_costs(&mut state, 100.0, &costs);
+        // Sold 1000 worth at 99.9 and covered at 100.1
+        let slippage = 1_000.0 * (100.1 / 99.9 - 1.0);
+        assert!((state.fees_paid - (2.0 + (1_000.0 + slippage) * 0.001 + 1.0)).abs() < 1e-9);
+        assert!((1_000.0 - state.balance - state.fees_paid - slippage).abs() < 1e-9);
+    }
+
     #[test]
     fn test_generate_grid_levels() {
         let ohlc = vec![
@@ -345,4 +2046,124 @@ mod tests {
         assert_eq!(premium_levels.len(), ohlc.len());
         assert_eq!(discount_levels.len(), ohlc.len());
     }
+
+    #[test]
+    fn test_short_history_never_triggers() {
+        let ohlc = vec![
+            Ohlc {
+                open: 100.0,
+                high: 110.0,
+                low: 90.0,
+                close: 105.0,
+                ..Default::default()
+            };
+            10
+        ];
+
+        let (premium_levels, discount_levels) = generate_grid_levels(&ohlc, &GridParams::default());
+        assert!(premium_levels.iter().all(|l| l.is_nan()));
+        assert!(discount_levels.iter().all(|l| l.is_nan()));
+
+        let (entries, exits) = manage_grids(&ohlc, &GridParams::default());
+        assert!(!entries.iter().any(|&e| e));
+        assert!(!exits.iter().any(|&e| e));
+    }
+
+    #[test]
+    fn test_generate_grid_levels_with_hma() {
+        let ohlc: Vec<Ohlc> = (0..20)
+            .map(|i| {
+                let price = 100.0 + i as f64;
+                Ohlc {
+                    open: price,
+                    high: price + 1.0,
+                    low: price - 1.0,
+                    close: price,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let params = GridParams {
+            ma_len: 9,
+            ma_type: MaType::Hma,
+            ..Default::default()
+        };
+
+        let (premium_levels, discount_levels) = generate_grid_levels(&ohlc, &params);
+
+        // The HMA follows the trend, so the bands stay centered on the price
+        let center = (premium_levels[19] + discount_levels[19]) / 2.0;
+        assert!((center - calculate_src(&ohlc)[19]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_generate_grid_levels_with_ema_and_wma() {
+        let ohlc: Vec<Ohlc> = [10.0, 12.0, 11.0, 14.0, 13.0, 16.0]
+            .iter()
+            .map(|&price| Ohlc {
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                ..Default::default()
+            })
+            .collect();
+        let center = |ma_type| {
+            let params = GridParams {
+                ma_len: 3,
+                ma_type,
+                grid_logic: GridLogic::Percent,
+                ..Default::default()
+            };
+            let (premium_levels, discount_levels) = generate_grid_levels(&ohlc, &params);
+            premium_levels
+                .iter()
+                .zip(&discount_levels)
+                .map(|(p, d)| (p + d) / 2.0)
+                .collect::<Vec<f64>>()
+        };
+
+        // ta.ema(close, 3): the SMA of the first 3 closes, then alpha = 0.5
+        let ema_center = center(MaType::Ema);
+        assert!(ema_center[..2].iter().all(|v| v.is_nan()));
+        for (value, expected) in ema_center[2..].iter().zip([11.0, 12.5, 12.75, 14.375]) {
+            assert!((value - expected).abs() < 1e-9);
+        }
+
+        // ta.wma(close, 3) weights the closes 1, 2, 3
+        let wma_center = center(MaType::Wma);
+        assert!(wma_center[1].is_nan());
+        let expected = (10.0 + 2.0 * 12.0 + 3.0 * 11.0) / 6.0;
+        assert!((wma_center[2] - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_generate_grid_levels_with_percent() {
+        let ohlc: Vec<Ohlc> = (0..5)
+            .map(|i| {
+                let price = 100.0 + 10.0 * i as f64;
+                Ohlc {
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let params = GridParams {
+            ma_len: 2,
+            ma_type: MaType::Sma,
+            grid_logic: GridLogic::Percent,
+            band_mult: 2.0,
+            ..Default::default()
+        };
+
+        let (premium_levels, discount_levels) = generate_grid_levels(&ohlc, &params);
+
+        // No ATR warm-up: levels are set as soon as the SMA is
+        assert!(premium_levels[0].is_nan());
+        assert!((premium_levels[1] - 105.0 * 1.02).abs() < 1e-9);
+        assert!((discount_levels[4] - 135.0 * 0.98).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,1021 @@
+/*!
+Bar-by-bar execution of a multi-level grid with explicit orders.
+
+`manage_grids` and the `execute_*` functions of `grid::dynamic` evaluate the
+grid in hindsight over a whole history. `GridEngine` runs the same grid the
+way it trades live: after every bar close it works out the resting limit
+order of each level and reports what changed as `GridEvent`s, and during the
+next bar it fills the orders the price reaches. Every fill is attributed to
+its level.
+
+Each level holds at most one order: a buy at its discount level while it is
+flat, and a sell of its whole lot at its premium level of the same index once
+bought. Orders are placed from the moving average and band unit known at the
+close, so there is no lookahead, and are replaced whenever the levels move.
+With `with_recentering` the grid is anchored instead and recentered like
+`generate_recentering_grid_levels`.
+
+`with_risk` adds `RiskRules` on top of the grid: stop-loss and take-profit
+of each level's lot, of the whole account, and a cap on the inventory. They
+trigger on the close or intrabar on the low and high, and close positions at
+market with a `GridEvent::Exit`. A portfolio stop or target flattens the
+grid and halts the engine.
+
+`with_hooks` attaches `GridHooks` called on every entry, exit, recentering and
+stop, to wire alerts, logging or custom risk checks. A hook returning
+`HookAction::Stop` flattens and halts the grid like a portfolio rule.
+
+With the `serde` feature the whole engine state, levels, orders, lots and
+balance included, serializes, except for the hooks. The `json` feature adds
+`save_json` and `load_json` to checkpoint a session and resume it exactly
+where it stopped.
+*/
+
+#[cfg(feature = "json")]
+use std::fs::File;
+#[cfg(feature = "json")]
+use std::io::BufReader;
+#[cfg(feature = "json")]
+use std::io::BufWriter;
+#[cfg(feature = "json")]
+use std::path::Path;
+
+use strato_utils::vars::ohlc::Ohlc;
+use strato_utils::vars::trade::Side;
+
+use crate::grid::dynamic::calculate_bands;
+use crate::grid::dynamic::level_distances;
+use crate::grid::dynamic::Anchor;
+use crate::grid::dynamic::GridLogic;
+use crate::grid::dynamic::GridParams;
+use crate::grid::dynamic::LevelSizing;
+use crate::grid::dynamic::Recenter;
+
+/// A resting limit order of a grid level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridOrder {
+    /// Identifier of the order, unique within an engine.
+    pub id: u64,
+    /// Index of the level, 0 being the closest to the center.
+    pub level: usize,
+    pub side: Side,
+    pub price: f64,
+    /// Quantity in base units.
+    pub qty: f64,
+}
+
+/// A change of the orders or of the position of a grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GridEvent {
+    /// A limit order to place on the exchange.
+    PlaceLimit(GridOrder),
+    /// A resting order filled in full during the bar at `index`.
+    Fill { index: usize, order: GridOrder },
+    /// A resting order to cancel, replaced or no longer needed.
+    Cancel(GridOrder),
+    /// The anchored grid moved its center.
+    Recenter(Recenter),
+    /// The lot of a level closed at market by a risk rule during the bar at
+    /// `index`.
+    Exit {
+        index: usize,
+        level: usize,
+        price: f64,
+        /// Quantity sold in base units.
+        qty: f64,
+        reason: ExitReason,
+    },
+}
+
+/// The risk rule closing a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExitReason {
+    StopLoss,
+    TakeProfit,
+    PortfolioStopLoss,
+    PortfolioTakeProfit,
+    /// A hook returned `HookAction::Stop`.
+    Hook,
+}
+
+/// What the engine does after calling a hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HookAction {
+    /// Carry on trading.
+    #[default]
+    Continue,
+    /// Flatten the grid at market and halt the engine.
+    Stop,
+}
+
+/// Callbacks of a `GridEngine` on the transitions of the grid. Every method
+/// does nothing by default.
+pub trait GridHooks {
+    /// Called when the buy order of a level fills.
+    fn on_entry(&mut self, index: usize, order: &GridOrder) -> HookAction {
+        let _ = (index, order);
+        HookAction::Continue
+    }
+
+    /// Called when the lot of a level is sold, by its sell order filling with
+    /// `reason` `None` or by a risk rule.
+    fn on_exit(
+        &mut self,
+        index: usize,
+        level: usize,
+        price: f64,
+        qty: f64,
+        reason: Option<ExitReason>,
+    ) -> HookAction {
+        let _ = (index, level, price, qty, reason);
+        HookAction::Continue
+    }
+
+    /// Called when the anchored grid moves its center.
+    fn on_recenter(&mut self, recenter: &Recenter) -> HookAction {
+        let _ = recenter;
+        HookAction::Continue
+    }
+
+    /// Called once the grid is flattened and halted during the bar at
+    /// `index`.
+    fn on_stop(&mut self, index: usize, reason: ExitReason) {
+        let _ = (index, reason);
+    }
+}
+
+/// No hooks.
+impl GridHooks for () {}
+
+/// The prices a risk rule is checked against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trigger {
+    /// Checked at the close and exited at the close.
+    #[default]
+    Close,
+    /// Checked on the low and high of the bar and exited at the trigger
+    /// price, or at the open when the bar gaps through it.
+    Intrabar,
+}
+
+/// Stop-loss, take-profit and inventory rules of a `GridEngine`. Every rule
+/// is off by default.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RiskRules {
+    /// Loss of a level's lot, as a fraction of its entry price, at which it
+    /// is closed.
+    pub stop_loss: Option<f64>,
+    /// Gain of a level's lot, as a fraction of its entry price, at which it
+    /// is closed.
+    pub take_profit: Option<f64>,
+    /// Loss of the equity, as a fraction of the initial balance, at which the
+    /// grid is flattened and halted.
+    pub portfolio_stop_loss: Option<f64>,
+    /// Gain of the equity, as a fraction of the initial balance, at which the
+    /// grid is flattened and halted.
+    pub portfolio_take_profit: Option<f64>,
+    /// Largest position in base units, including the resting buy orders.
+    pub max_inventory: Option<f64>,
+    pub trigger: Trigger,
+}
+
+/// Runs a long multi-level grid bar by bar.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridEngine<H = ()> {
+    params: GridParams,
+    sizing: LevelSizing,
+    risk: RiskRules,
+    anchor: Option<Anchor>,
+    weights: Vec<f64>,
+    /// Resting order of each level.
+    orders: Vec<Option<GridOrder>>,
+    /// Quantity held by each level.
+    lots: Vec<f64>,
+    /// Entry price of the lot of each level.
+    entries: Vec<f64>,
+    initial_balance: f64,
+    balance: f64,
+    position: f64,
+    /// Index of the next bar.
+    index: usize,
+    next_id: u64,
+    /// Set once a portfolio rule has flattened the grid.
+    halted: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hooks: H,
+    /// Set when a hook asked to stop, until the grid is flattened.
+    stop_requested: bool,
+}
+
+impl GridEngine {
+    /// Creates an engine following the moving average, with no position.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The grid parameters, including the number of levels.
+    /// * `sizing` - How the equity is split across the levels.
+    /// * `balance` - The initial balance for the trading account.
+    pub fn new(params: GridParams, sizing: LevelSizing, balance: f64) -> Self {
+        Self {
+            weights: sizing.weights(params.levels),
+            orders: vec![None; params.levels],
+            lots: vec![0.0; params.levels],
+            entries: vec![0.0; params.levels],
+            params,
+            sizing,
+            risk: RiskRules::default(),
+            anchor: None,
+            initial_balance: balance,
+            balance,
+            position: 0.0,
+            index: 0,
+            next_id: 0,
+            halted: false,
+            hooks: (),
+            stop_requested: false,
+        }
+    }
+
+    /// Restores an engine saved with `save_json`, without hooks; reattach
+    /// them with `with_hooks`.
+    #[cfg(feature = "json")]
+    pub fn load_json<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+impl<H: GridHooks> GridEngine<H> {
+    /// Calls `hooks` on the transitions of the grid.
+    pub fn with_hooks<G: GridHooks>(self, hooks: G) -> GridEngine<G> {
+        GridEngine {
+            params: self.params,
+            sizing: self.sizing,
+            risk: self.risk,
+            anchor: self.anchor,
+            weights: self.weights,
+            orders: self.orders,
+            lots: self.lots,
+            entries: self.entries,
+            initial_balance: self.initial_balance,
+            balance: self.balance,
+            position: self.position,
+            index: self.index,
+            next_id: self.next_id,
+            halted: self.halted,
+            hooks,
+            stop_requested: self.stop_requested,
+        }
+    }
+
+    /// Anchors the grid and recenters it after `bars` consecutive closes
+    /// beyond the outer levels.
+    pub fn with_recentering(mut self, bars: usize) -> Self {
+        self.anchor = Some(Anchor::new(bars));
+        self
+    }
+
+    /// Applies stop-loss, take-profit and inventory rules.
+    pub fn with_risk(mut self, risk: RiskRules) -> Self {
+        self.risk = risk;
+        self
+    }
+
+    /// Processes a closed bar.
+    ///
+    /// # Arguments
+    ///
+    /// * `candle` - The bar, during which the resting orders may fill.
+    /// * `ma` - The moving average at the close of the bar.
+    /// * `band` - The band unit at the close of the bar: the ATR, or one
+    ///   percent of the average with `GridLogic::Percent`. `f64::NAN` while
+    ///   warming up.
+    ///
+    /// # Returns
+    ///
+    /// The exits and fills of the bar, followed by the recentering and the
+    /// order changes to apply before the next bar, or the exits flattening the
+    /// grid at the close if a hook stopped it. Nothing once halted.
+    pub fn on_bar(&mut self, candle: &Ohlc, ma: f64, band: f64) -> Vec<GridEvent> {
+        let mut events = Vec::new();
+        let index = self.index;
+        self.index += 1;
+        if self.halted {
+            return events;
+        }
+
+        // Lots held at the open are checked before the orders fill
+        if self.check_portfolio(index, candle, &mut events) {
+            return events;
+        }
+        self.check_lots(index, candle, &mut events);
+
+        for level in 0..self.orders.len() {
+            let Some(order) = self.orders[level] else {
+                continue;
+            };
+            let filled = match order.side {
+                Side::Buy => candle.low < order.price,
+                Side::Sell => candle.high > order.price,
+            };
+            if filled {
+                self.orders[level] = None;
+                events.push(self.apply_fill(index, level, order));
+            }
+        }
+
+        self.close_bar(index, candle.close, ma, band, &mut events);
+        events
+    }
+
+    /// Applies a fill reported by a venue matching the orders itself, e.g.
+    /// the hftbacktest adapter, at the executed price and quantity.
+    ///
+    /// # Returns
+    ///
+    /// The fill, or `None` if no resting order has the identifier.
+    pub fn fill(&mut self, id: u64, price: f64, qty: f64) -> Option<GridEvent> {
+        let level = self
+            .orders
+            .iter()
+            .position(|slot| slot.is_some_and(|order| order.id == id))?;
+        let order = GridOrder {
+            price,
+            qty,
+            ..self.orders[level].take()?
+        };
+        Some(self.apply_fill(self.index, level, order))
+    }
+
+    /// Applies a fill of an order the engine already cancelled or replaced,
+    /// which the venue executed before the cancellation reached it. The fill
+    /// is booked on the level of the order, whatever rests there now.
+    pub fn fill_cancelled(&mut self, order: GridOrder, price: f64, qty: f64) -> GridEvent {
+        let order = GridOrder {
+            price,
+            qty,
+            ..order
+        };
+        self.apply_fill(self.index, order.level, order)
+    }
+
+    /// Forgets a resting order the venue rejected or expired, so that it is
+    /// placed again at the next close.
+    pub fn remove(&mut self, id: u64) -> Option<GridOrder> {
+        self.orders
+            .iter_mut()
+            .find(|slot| slot.is_some_and(|order| order.id == id))?
+            .take()
+    }
+
+    /// Processes a closed bar whose fills were reported with `fill`, like
+    /// `on_bar` without simulating the fills. The risk rules are not applied,
+    /// but a hook stopping the grid during a `fill` flattens it at this close.
+    ///
+    /// # Returns
+    ///
+    /// The recentering and the order changes to apply before the next bar, or
+    /// the exits flattening the grid.
+    pub fn on_close(&mut self, close: f64, ma: f64, band: f64) -> Vec<GridEvent> {
+        let mut events = Vec::new();
+        let index = self.index;
+        self.index += 1;
+        if !self.halted {
+            self.close_bar(index, close, ma, band, &mut events);
+        }
+        events
+    }
+
+    /// Runs the engine over a history, computing the moving average and the
+    /// band unit with `params`.
+    ///
+    /// # Returns
+    ///
+    /// The events of every bar in order.
+    pub fn run(&mut self, ohlc: &[Ohlc]) -> Vec<GridEvent> {
+        let (ma_values, band_values) = calculate_bands(ohlc, &self.params);
+        ohlc.iter()
+            .zip(ma_values.iter().zip(&band_values))
+            .flat_map(|(candle, (&ma, &band))| self.on_bar(candle, ma, band))
+            .collect()
+    }
+
+    /// The resting orders, by level.
+    pub fn orders(&self) -> impl Iterator<Item = &GridOrder> {
+        self.orders.iter().flatten()
+    }
+
+    /// Quantity held by each level.
+    pub fn lots(&self) -> &[f64] {
+        &self.lots
+    }
+
+    pub fn balance(&self) -> f64 {
+        self.balance
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    /// Whether a portfolio rule or a hook has flattened and stopped the grid.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Balance plus the position marked at `price`.
+    pub fn equity(&self, price: f64) -> f64 {
+        self.balance + self.position * price
+    }
+
+    /// Saves the state of the engine, without the hooks, to a JSON file.
+    #[cfg(feature = "json")]
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    /// Books the fill of an order taken off its level.
+    fn apply_fill(&mut self, index: usize, level: usize, order: GridOrder) -> GridEvent {
+        let qty = order.side.sign() * order.qty;
+        self.balance -= qty * order.price;
+        self.position += qty;
+        if qty > 0.0 {
+            self.entries[level] = order.price;
+        }
+        self.lots[level] += qty;
+        let action = match order.side {
+            Side::Buy => self.hooks.on_entry(index, &order),
+            Side::Sell => self
+                .hooks
+                .on_exit(index, level, order.price, order.qty, None),
+        };
+        self.stop_requested |= action == HookAction::Stop;
+        GridEvent::Fill { index, order }
+    }
+
+    /// Recenters the grid if needed and reprices the orders at the close, or
+    /// flattens it if a hook stopped it.
+    fn close_bar(
+        &mut self,
+        index: usize,
+        close: f64,
+        ma: f64,
+        band: f64,
+        events: &mut Vec<GridEvent>,
+    ) {
+        if !self.stop_requested {
+            let grid = match self.anchor.as_mut() {
+                Some(anchor) => {
+                    anchor.current(ma, band);
+                    if let Some(recenter) = anchor.update(index, close, band, &self.params) {
+                        events.push(GridEvent::Recenter(recenter));
+                        let action = self.hooks.on_recenter(&recenter);
+                        self.stop_requested |= action == HookAction::Stop;
+                    }
+                    anchor.current(ma, band)
+                }
+                None if ma.is_finite() && band.is_finite() => Some((ma, band)),
+                None => None,
+            };
+            if !self.stop_requested {
+                self.reprice(close, grid, band, events);
+                return;
+            }
+        }
+        self.halt(index, close, ExitReason::Hook, events);
+    }
+
+    /// Flattens the grid and halts the engine if the equity reached the
+    /// portfolio stop-loss or take-profit during the bar. Without a position
+    /// the equity is the realized balance, and the engine halts at the open.
+    fn check_portfolio(
+        &mut self,
+        index: usize,
+        candle: &Ohlc,
+        events: &mut Vec<GridEvent>,
+    ) -> bool {
+        let initial = self.initial_balance;
+        if self.position <= 0.0 {
+            let reason = if self
+                .risk
+                .portfolio_stop_loss
+                .is_some_and(|loss| self.balance <= initial * (1.0 - loss))
+            {
+                ExitReason::PortfolioStopLoss
+            } else if self
+                .risk
+                .portfolio_take_profit
+                .is_some_and(|gain| self.balance >= initial * (1.0 + gain))
+            {
+                ExitReason::PortfolioTakeProfit
+            } else {
+                return false;
+            };
+            self.halt(index, candle.open, reason, events);
+            return true;
+        }
+        let stop = self
+            .risk
+            .portfolio_stop_loss
+            .map(|loss| (initial * (1.0 - loss) - self.balance) / self.position);
+        let target = self
+            .risk
+            .portfolio_take_profit
+            .map(|gain| (initial * (1.0 + gain) - self.balance) / self.position);
+        let Some((price, reason)) = self.triggered(candle, stop, target) else {
+            return false;
+        };
+        let reason = match reason {
+            ExitReason::StopLoss => ExitReason::PortfolioStopLoss,
+            _ => ExitReason::PortfolioTakeProfit,
+        };
+        self.halt(index, price, reason, events);
+        true
+    }
+
+    /// Sells every lot at `price`, cancels the resting orders and halts the
+    /// engine.
+    fn halt(&mut self, index: usize, price: f64, reason: ExitReason, events: &mut Vec<GridEvent>) {
+        for level in 0..self.lots.len() {
+            self.exit_level(index, level, price, reason, events);
+        }
+        for order in self.orders.iter_mut().filter_map(Option::take) {
+            events.push(GridEvent::Cancel(order));
+        }
+        self.halted = true;
+        self.stop_requested = false;
+        self.hooks.on_stop(index, reason);
+    }
+
+    /// Closes the lots that reached their stop-loss or take-profit during the
+    /// bar.
+    fn check_lots(&mut self, index: usize, candle: &Ohlc, events: &mut Vec<GridEvent>) {
+        for level in 0..self.lots.len() {
+            if self.lots[level] <= 0.0 {
+                continue;
+            }
+            let entry = self.entries[level];
+            let stop = self.risk.stop_loss.map(|loss| entry * (1.0 - loss));
+            let target = self.risk.take_profit.map(|gain| entry * (1.0 + gain));
+            if let Some((price, reason)) = self.triggered(candle, stop, target) {
+                if let Some(order) = self.orders[level].take() {
+                    events.push(GridEvent::Cancel(order));
+                }
+                self.exit_level(index, level, price, reason, events);
+            }
+        }
+    }
+
+    /// Returns the exit price and reason if the bar reached the stop or the
+    /// target price of a long position. The stop wins when the bar reached
+    /// both.
+    fn triggered(
+        &self,
+        candle: &Ohlc,
+        stop: Option<f64>,
+        target: Option<f64>,
+    ) -> Option<(f64, ExitReason)> {
+        match self.risk.trigger {
+            Trigger::Close => {
+                if stop.is_some_and(|stop| candle.close <= stop) {
+                    Some((candle.close, ExitReason::StopLoss))
+                } else if target.is_some_and(|target| candle.close >= target) {
+                    Some((candle.close, ExitReason::TakeProfit))
+                } else {
+                    None
+                }
+            }
+            Trigger::Intrabar => {
+                if let Some(stop) = stop.filter(|&stop| candle.low <= stop) {
+                    Some((stop.min(candle.open), ExitReason::StopLoss))
+                } else {
+                    target
+                        .filter(|&target| candle.high >= target)
+                        .map(|target| (target.max(candle.open), ExitReason::TakeProfit))
+                }
+            }
+        }
+    }
+
+    /// Sells the lot of a level at market.
+    fn exit_level(
+        &mut self,
+        index: usize,
+        level: usize,
+        price: f64,
+        reason: ExitReason,
+        events: &mut Vec<GridEvent>,
+    ) {
+        let qty = self.lots[level];
+        if qty <= 0.0 {
+            return;
+        }
+        self.balance += qty * price;
+        self.position -= qty;
+        self.lots[level] = 0.0;
+        events.push(GridEvent::Exit {
+            index,
+            level,
+            price,
+            qty,
+            reason,
+        });
+        let action = self.hooks.on_exit(index, level, price, qty, Some(reason));
+        self.stop_requested |= action == HookAction::Stop;
+    }
+
+    /// Works out the order of every level for the next bar and records the
+    /// cancellations and placements needed to get there.
+    fn reprice(
+        &mut self,
+        close: f64,
+        grid: Option<(f64, f64)>,
+        band: f64,
+        events: &mut Vec<GridEvent>,
+    ) {
+        let equity = self.equity(close);
+        let volatility = match self.params.grid_logic {
+            GridLogic::Atr => band / close,
+            GridLogic::Percent => f64::NAN,
+        };
+        // Notional and quantity committed by the position and the buy orders
+        // so far
+        let mut committed = self.position * close;
+        let mut inventory = self.position;
+
+        for (level, distance) in level_distances(&self.params).into_iter().enumerate() {
+            let wanted = grid.and_then(|(center, unit)| {
+                if self.lots[level] > 0.0 {
+                    Some((Side::Sell, center + unit * distance, self.lots[level]))
+                } else {
+                    let price = center - unit * distance;
+                    let room = self.sizing.max_exposure * equity - committed;
+                    let mut notional = self
+                        .sizing
+                        .notional(self.weights[level], equity, volatility)
+                        .min(room);
+                    if let Some(max_inventory) = self.risk.max_inventory {
+                        notional = notional.min((max_inventory - inventory) * price);
+                    }
+                    (price > 0.0 && notional > 0.0).then(|| {
+                        committed += notional;
+                        inventory += notional / price;
+                        (Side::Buy, price, notional / price)
+                    })
+                }
+            });
+
+            let current = self.orders[level];
+            let unchanged = match (current, wanted) {
+                (Some(order), Some((side, price, qty))) => {
+                    order.side == side && order.price == price && order.qty == qty
+                }
+                (None, None) => true,
+                _ => false,
+            };
+            if unchanged {
+                continue;
+            }
+
+            if let Some(order) = current {
+                events.push(GridEvent::Cancel(order));
+            }
+            self.orders[level] = wanted.map(|(side, price, qty)| {
+                let order = GridOrder {
+                    id: self.next_id,
+                    level,
+                    side,
+                    price,
+                    qty,
+                };
+                self.next_id += 1;
+                events.push(GridEvent::PlaceLimit(order));
+                order
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::dynamic::MaType;
+
+    fn candle(low: f64, high: f64, close: f64) -> Ohlc {
+        Ohlc {
+            open: close,
+            high,
+            low,
+            close,
+            ..Default::default()
+        }
+    }
+
+    fn params() -> GridParams {
+        GridParams {
+            ma_type: MaType::Sma,
+            grid_logic: GridLogic::Percent,
+            band_mult: 5.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_engine_events() {
+        let mut engine = GridEngine::new(params(), LevelSizing::default(), 1_000.0);
+
+        // Warming up: no order
+        assert!(engine
+            .on_bar(&candle(99.0, 101.0, 100.0), f64::NAN, f64::NAN)
+            .is_empty());
+
+        // Buy 1000 worth at 95
+        let events = engine.on_bar(&candle(99.0, 101.0, 100.0), 100.0, 1.0);
+        let buy = match events[..] {
+            [GridEvent::PlaceLimit(order)] => order,
+            _ => panic!("{:?}", events),
+        };
+        assert_eq!((buy.side, buy.price, buy.level), (Side::Buy, 95.0, 0));
+        assert!((buy.qty - 1_000.0 / 95.0).abs() < 1e-9);
+
+        // The average moves: the order follows it
+        let events = engine.on_bar(&candle(98.0, 100.0, 99.0), 101.0, 1.01);
+        assert_eq!(events[0], GridEvent::Cancel(buy));
+        assert!(
+            matches!(events[1], GridEvent::PlaceLimit(order) if order.price == 101.0 - 1.01 * 5.0)
+        );
+
+        // Filled, then the lot is offered at the premium level
+        let events = engine.on_bar(&candle(94.0, 99.0, 96.0), 101.0, 1.01);
+        assert!(
+            matches!(events[0], GridEvent::Fill { index: 3, order } if order.side == Side::Buy)
+        );
+        assert!(matches!(events[1], GridEvent::PlaceLimit(order) if order.side == Side::Sell));
+        assert!(engine.lots()[0] > 0.0);
+        assert!(engine.balance().abs() < 1e-9);
+
+        // Sold at 106.05
+        let events = engine.on_bar(&candle(100.0, 107.0, 106.0), 101.0, 1.01);
+        assert!(matches!(events[0], GridEvent::Fill { order, .. } if order.side == Side::Sell));
+        assert_eq!(engine.position(), 0.0);
+        assert!(engine.balance() > 1_000.0);
+        assert_eq!(engine.orders().count(), 1);
+    }
+
+    #[test]
+    fn test_engine_risk_rules() {
+        let risk = RiskRules {
+            stop_loss: Some(0.1),
+            max_inventory: Some(5.0),
+            trigger: Trigger::Intrabar,
+            ..Default::default()
+        };
+        let mut engine = GridEngine::new(params(), LevelSizing::default(), 1_000.0).with_risk(risk);
+
+        // Capped at 5 units instead of 1000 / 95
+        let events = engine.on_bar(&candle(99.0, 101.0, 100.0), 100.0, 1.0);
+        assert!(matches!(events[0], GridEvent::PlaceLimit(order) if order.qty == 5.0));
+        engine.on_bar(&candle(94.0, 101.0, 95.0), 100.0, 1.0);
+        assert_eq!(engine.position(), 5.0);
+
+        // The lot entered at 95 stops out at 85.5, cancelling its sell order
+        let bar = Ohlc {
+            open: 95.0,
+            ..candle(80.0, 95.0, 82.0)
+        };
+        let events = engine.on_bar(&bar, 100.0, 1.0);
+        assert!(matches!(events[0], GridEvent::Cancel(order) if order.side == Side::Sell));
+        match events[1] {
+            GridEvent::Exit {
+                index: 2,
+                level: 0,
+                price,
+                qty,
+                reason: ExitReason::StopLoss,
+            } => assert_eq!((price, qty), (95.0 * 0.9, 5.0)),
+            event => panic!("{:?}", event),
+        }
+        assert_eq!(engine.position(), 0.0);
+
+        // Flat, the realized loss of 4.75% reaches a 4% portfolio stop
+        let mut engine = engine.with_risk(RiskRules {
+            portfolio_stop_loss: Some(0.04),
+            ..risk
+        });
+        let events = engine.on_bar(&candle(99.0, 101.0, 100.0), 100.0, 1.0);
+        assert!(engine.is_halted());
+        assert!(events
+            .iter()
+            .all(|event| matches!(event, GridEvent::Cancel(_))));
+        assert_eq!(engine.orders().count(), 0);
+
+        // The portfolio stop on the close flattens and halts the grid
+        let risk = RiskRules {
+            portfolio_stop_loss: Some(0.05),
+            ..Default::default()
+        };
+        let mut engine = GridEngine::new(params(), LevelSizing::default(), 1_000.0).with_risk(risk);
+        engine.on_bar(&candle(99.0, 101.0, 100.0), 100.0, 1.0);
+        engine.on_bar(&candle(94.0, 101.0, 95.0), 100.0, 1.0);
+        let events = engine.on_bar(&candle(85.0, 95.0, 90.0), 100.0, 1.0);
+        assert!(matches!(
+            events[0],
+            GridEvent::Exit {
+                reason: ExitReason::PortfolioStopLoss,
+                ..
+            }
+        ));
+        assert!(engine.is_halted());
+        assert_eq!(engine.orders().count(), 0);
+        assert!(engine
+            .on_bar(&candle(99.0, 101.0, 100.0), 100.0, 1.0)
+            .is_empty());
+    }
+
+    /// Records the transitions and stops the grid after `max_entries`.
+    #[derive(Debug, Default)]
+    struct Recorder {
+        max_entries: usize,
+        entries: usize,
+        exits: Vec<Option<ExitReason>>,
+        recenters: usize,
+        stops: Vec<(usize, ExitReason)>,
+    }
+
+    impl GridHooks for Recorder {
+        fn on_entry(&mut self, _: usize, _: &GridOrder) -> HookAction {
+            self.entries += 1;
+            if self.entries >= self.max_entries {
+                HookAction::Stop
+            } else {
+                HookAction::Continue
+            }
+        }
+
+        fn on_exit(
+            &mut self,
+            _: usize,
+            _: usize,
+            _: f64,
+            _: f64,
+            reason: Option<ExitReason>,
+        ) -> HookAction {
+            self.exits.push(reason);
+            HookAction::Continue
+        }
+
+        fn on_recenter(&mut self, _: &Recenter) -> HookAction {
+            self.recenters += 1;
+            HookAction::Continue
+        }
+
+        fn on_stop(&mut self, index: usize, reason: ExitReason) {
+            self.stops.push((index, reason));
+        }
+    }
+
+    #[test]
+    fn test_engine_hooks() {
+        let hooks = Recorder {
+            max_entries: 2,
+            ..Default::default()
+        };
+        let mut engine = GridEngine::new(params(), LevelSizing::default(), 1_000.0)
+            .with_recentering(2)
+            .with_hooks(hooks);
+
+        engine.on_bar(&candle(100.0, 100.0, 100.0), 100.0, 1.0);
+        engine.on_bar(&candle(94.0, 100.0, 96.0), 100.0, 1.0);
+        engine.on_bar(&candle(96.0, 106.0, 100.0), 100.0, 1.0);
+        assert_eq!((engine.hooks.entries, engine.hooks.exits.len()), (1, 1));
+        assert_eq!(engine.hooks.exits[0], None);
+
+        // The second entry stops the grid, which sells its lot at the close
+        let events = engine.on_bar(&candle(94.0, 100.0, 97.0), 100.0, 1.0);
+        assert!(matches!(events[0], GridEvent::Fill { .. }));
+        assert!(matches!(
+            events[1],
+            GridEvent::Exit {
+                index: 3,
+                price: 97.0,
+                reason: ExitReason::Hook,
+                ..
+            }
+        ));
+        assert!(engine.is_halted());
+        assert_eq!(engine.position(), 0.0);
+        assert_eq!(engine.hooks.exits[1], Some(ExitReason::Hook));
+        assert_eq!(engine.hooks.stops, vec![(3, ExitReason::Hook)]);
+        assert_eq!(engine.hooks.recenters, 0);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_engine_json_resume() {
+        let ohlc: Vec<Ohlc> = (0..60)
+            .map(|i| {
+                let close = 100.0 + 8.0 * (i as f64 / 5.0).sin();
+                candle(close - 2.0, close + 2.0, close)
+            })
+            .collect();
+        let params = GridParams {
+            ma_len: 10,
+            levels: 3,
+            ..params()
+        };
+        let (ma, band) = calculate_bands(&ohlc, &params);
+        let mut engine = GridEngine::new(params, LevelSizing::default(), 1_000.0)
+            .with_recentering(3)
+            .with_hooks(Recorder {
+                max_entries: usize::MAX,
+                ..Default::default()
+            });
+        for i in 0..30 {
+            engine.on_bar(&ohlc[i], ma[i], band[i]);
+        }
+        assert!(engine.orders().count() > 0);
+
+        let path =
+            std::env::temp_dir().join(format!("strato-grid-engine-{}.json", std::process::id()));
+        engine.save_json(&path).unwrap();
+        let mut resumed = GridEngine::load_json(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(resumed.lots(), engine.lots());
+        assert_eq!(resumed.balance(), engine.balance());
+        for i in 30..ohlc.len() {
+            assert_eq!(
+                resumed.on_bar(&ohlc[i], ma[i], band[i]),
+                engine.on_bar(&ohlc[i], ma[i], band[i])
+            );
+        }
+    }
+
+    #[test]
+    fn test_engine_external_fills() {
+        let mut engine = GridEngine::new(params(), LevelSizing::default(), 1_000.0);
+
+        let events = engine.on_close(100.0, 100.0, 1.0);
+        let buy = match events[..] {
+            [GridEvent::PlaceLimit(order)] => order,
+            _ => panic!("{:?}", events),
+        };
+        assert!(engine.fill(buy.id + 1, 95.0, 10.0).is_none());
+
+        // Filled for a rounded quantity, which the sell order then offers
+        let fill = engine.fill(buy.id, 95.0, 10.0).unwrap();
+        assert!(matches!(fill, GridEvent::Fill { index: 1, order } if order.qty == 10.0));
+        assert_eq!(engine.lots()[0], 10.0);
+        let events = engine.on_close(96.0, 100.0, 1.0);
+        let sell = match events[..] {
+            [GridEvent::PlaceLimit(order)] => order,
+            _ => panic!("{:?}", events),
+        };
+        assert_eq!((sell.side, sell.price, sell.qty), (Side::Sell, 105.0, 10.0));
+
+        // A rejected order is placed again at the next close
+        assert_eq!(engine.remove(sell.id), Some(sell));
+        let events = engine.on_close(96.0, 100.0, 1.0);
+        assert!(matches!(events[..], [GridEvent::PlaceLimit(order)] if order.id != sell.id));
+
+        // A sell replaced by the next close still books its late fill
+        let events = engine.on_close(96.0, 101.0, 1.0);
+        let (replaced, sell) = match events[..] {
+            [GridEvent::Cancel(replaced), GridEvent::PlaceLimit(sell)] => (replaced, sell),
+            _ => panic!("{:?}", events),
+        };
+        assert!(engine.fill(replaced.id, 105.0, 4.0).is_none());
+        let fill = engine.fill_cancelled(replaced, 105.0, 4.0);
+        assert!(matches!(fill, GridEvent::Fill { order, .. } if order.qty == 4.0));
+        assert_eq!(engine.lots()[0], 6.0);
+        assert_eq!(engine.orders().next(), Some(&sell));
+    }
+
+    #[test]
+    fn test_engine_recentering() {
+        let mut engine =
+            GridEngine::new(params(), LevelSizing::default(), 1_000.0).with_recentering(2);
+
+        engine.on_bar(&candle(100.0, 100.0, 100.0), 100.0, 1.0);
+        // The average follows the trend but the anchored grid does not
+        let events = engine.on_bar(&candle(90.0, 100.0, 90.0), 95.0, 0.95);
+        assert!(matches!(events[0], GridEvent::Fill { .. }));
+        assert!(matches!(events[1], GridEvent::PlaceLimit(order) if order.price == 105.0));
+
+        let events = engine.on_bar(&candle(80.0, 90.0, 80.0), 85.0, 0.85);
+        assert!(matches!(events[0], GridEvent::Recenter(r) if r.index == 2 && r.to == 80.0));
+        // The lot is offered at the new premium level
+        assert!(matches!(events[1], GridEvent::Cancel(_)));
+        assert!(
+            matches!(events[2], GridEvent::PlaceLimit(order) if (order.price - 84.0).abs() < 1e-9)
+        );
+    }
+}
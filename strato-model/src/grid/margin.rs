@@ -0,0 +1,440 @@
+/*!
+This module forecasts the margin usage and leverage of a grid before it is
+started.
+
+The worst case for a grid is a straight-line move from the current price to
+one of its boundaries: every level on the way fills and none of the exits do,
+so the position keeps growing while its unrealized PnL deteriorates. The
+projection walks that path level by level and reports where the account
+limits would be breached.
+*/
+
+use strato_utils::vars::instrument::Instrument;
+use tracing::warn;
+
+/// How the order quantity of each grid level is sized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SizingPolicy {
+    /// The same base quantity at every level.
+    FixedQuantity(f64),
+    /// The same quote notional at every level.
+    FixedNotional(f64),
+    /// A base quantity multiplied by `multiplier` at each successive level.
+    Martingale { base_qty: f64, multiplier: f64 },
+}
+
+impl SizingPolicy {
+    /// Quantity of the `index`-th level filled on the way to the boundary.
+    pub fn quantity(&self, index: usize, price: f64) -> f64 {
+        match *self {
+            SizingPolicy::FixedQuantity(qty) => qty,
+            SizingPolicy::FixedNotional(notional) => notional / price,
+            SizingPolicy::Martingale {
+                base_qty,
+                multiplier,
+            } => base_qty * multiplier.powi(index as i32),
+        }
+    }
+
+    /// Quantity of the `index`-th level rounded down to whole lots of
+    /// `instrument`, in units of the underlying.
+    pub fn rounded_quantity(&self, index: usize, price: f64, instrument: &Instrument) -> f64 {
+        instrument.contracts(self.quantity(index, price)) * instrument.multiplier
+    }
+}
+
+/// Limits of the account the grid runs on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccountLimits {
+    /// Account equity in quote currency.
+    pub equity: f64,
+    /// Leverage setting used by the exchange to compute initial margin.
+    pub leverage: f64,
+    /// Maximum effective leverage (notional / equity) allowed.
+    pub max_leverage: f64,
+    /// Maximum fraction of the equity that may be used as margin.
+    pub max_margin_usage: f64,
+}
+
+/// The account state after a level fills.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelProjection {
+    /// Price of the filled level.
+    pub price: f64,
+    /// Quantity filled at the level.
+    pub qty: f64,
+    /// Signed position after the fill; negative when short.
+    pub position: f64,
+    /// Unrealized PnL of the position at the level price.
+    pub unrealized_pnl: f64,
+    /// Equity including the unrealized PnL.
+    pub equity: f64,
+    /// Initial margin required by the position.
+    pub margin: f64,
+    /// Effective leverage, notional / equity.
+    pub leverage: f64,
+    /// Margin as a fraction of the equity.
+    pub margin_usage: f64,
+}
+
+/// A limit breach found by the projection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MarginWarning {
+    /// The effective leverage exceeds `AccountLimits::max_leverage`.
+    LeverageExceeded { price: f64, leverage: f64 },
+    /// The margin usage exceeds `AccountLimits::max_margin_usage`.
+    MarginExceeded { price: f64, margin_usage: f64 },
+    /// The unrealized loss wipes out the equity.
+    EquityDepleted { price: f64 },
+}
+
+/// The projected path of a straight-line move to a boundary.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarginForecast {
+    /// Boundary the price moves to.
+    pub boundary: f64,
+    /// Account state after each fill, in fill order, followed by the state at
+    /// the boundary.
+    pub levels: Vec<LevelProjection>,
+    /// Highest margin usage along the path.
+    pub worst_margin_usage: f64,
+    /// Highest effective leverage along the path.
+    pub worst_leverage: f64,
+    /// Every limit breach along the path, in price order.
+    pub warnings: Vec<MarginWarning>,
+}
+
+impl MarginForecast {
+    /// Whether the path stays within the account limits.
+    pub fn is_within_limits(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Projects the margin usage and leverage of a straight-line move from
+/// `current_price` to `boundary`.
+///
+/// Levels between the current price and the boundary fill in order: levels
+/// below the price open longs on a move down, levels above the price open
+/// shorts on a move up. Exits are assumed never to fill.
+///
+/// # Arguments
+///
+/// * `current_price` - Price at which the grid is started.
+/// * `levels` - Grid level prices, in any order.
+/// * `boundary` - Price the move ends at.
+/// * `sizing` - Sizing policy of the grid orders.
+/// * `limits` - Limits of the account.
+///
+/// # Returns
+///
+/// A `MarginForecast` of the path.
+///
+/// # Mathematical Formulation
+///
+/// At price `p`, with fills `(p_i, q_i)` and direction `d` (`1` long, `-1`
+/// short):
+/// - `PnL = d * Σ q_i * (p - p_i)`
+/// - `Equity = E_0 + PnL`
+/// - `Margin = |Σ q_i| * p / L`
+/// - `Leverage = |Σ q_i| * p / Equity`
+pub fn project_move(
+    current_price: f64,
+    levels: &[f64],
+    boundary: f64,
+    sizing: &SizingPolicy,
+    limits: &AccountLimits,
+) -> MarginForecast {
+    project(current_price, levels, boundary, sizing, limits, None)
+}
+
+/// Projects a straight-line move like `project_move`, with the quantity of
+/// every level rounded down to whole lots of `instrument`, as the exchange
+/// would accept it.
+pub fn project_move_for_instrument(
+    current_price: f64,
+    levels: &[f64],
+    boundary: f64,
+    sizing: &SizingPolicy,
+    limits: &AccountLimits,
+    instrument: &Instrument,
+) -> MarginForecast {
+    project(
+        current_price,
+        levels,
+        boundary,
+        sizing,
+        limits,
+        Some(instrument),
+    )
+}
+
+/// Projects both straight-line moves, to the lower and to the upper boundary,
+/// and logs a warning for every limit breach.
+///
+/// Call this before starting a live grid and refuse to start it if either
+/// forecast is not within limits.
+///
+/// # Returns
+///
+/// A tuple of (move to lower boundary, move to upper boundary).
+pub fn forecast_grid_margin(
+    current_price: f64,
+    levels: &[f64],
+    lower_boundary: f64,
+    upper_boundary: f64,
+    sizing: &SizingPolicy,
+    limits: &AccountLimits,
+) -> (MarginForecast, MarginForecast) {
+    let down = project_move(current_price, levels, lower_boundary, sizing, limits);
+    let up = project_move(current_price, levels, upper_boundary, sizing, limits);
+    log_warnings(&down, &up);
+
+    (down, up)
+}
+
+/// Projects both straight-line moves like `forecast_grid_margin`, with the
+/// level quantities rounded to whole lots of `instrument`.
+pub fn forecast_grid_margin_for_instrument(
+    current_price: f64,
+    levels: &[f64],
+    lower_boundary: f64,
+    upper_boundary: f64,
+    sizing: &SizingPolicy,
+    limits: &AccountLimits,
+    instrument: &Instrument,
+) -> (MarginForecast, MarginForecast) {
+    let down = project_move_for_instrument(
+        current_price,
+        levels,
+        lower_boundary,
+        sizing,
+        limits,
+        instrument,
+    );
+    let up = project_move_for_instrument(
+        current_price,
+        levels,
+        upper_boundary,
+        sizing,
+        limits,
+        instrument,
+    );
+    log_warnings(&down, &up);
+
+    (down, up)
+}
+
+fn log_warnings(down: &MarginForecast, up: &MarginForecast) {
+    for warning in down.warnings.iter().chain(&up.warnings) {
+        warn!("Grid exceeds account limits: {:?}", warning);
+    }
+}
+
+fn project(
+    current_price: f64,
+    levels: &[f64],
+    boundary: f64,
+    sizing: &SizingPolicy,
+    limits: &AccountLimits,
+    instrument: Option<&Instrument>,
+) -> MarginForecast {
+    let direction = if boundary < current_price { 1.0 } else { -1.0 };
+    let (low, high) = if boundary < current_price {
+        (boundary, current_price)
+    } else {
+        (current_price, boundary)
+    };
+
+    let mut fills: Vec<f64> = levels
+        .iter()
+        .copied()
+        .filter(|&p| p >= low && p <= high && p != current_price)
+        .collect();
+    // Order the fills along the path, nearest to the current price first
+    fills.sort_by(|a, b| {
+        (a - current_price)
+            .abs()
+            .total_cmp(&(b - current_price).abs())
+    });
+
+    let mut forecast = MarginForecast {
+        boundary,
+        ..Default::default()
+    };
+    let mut position = 0.0;
+    let mut cost = 0.0;
+
+    for (index, &price) in fills.iter().enumerate() {
+        let qty = match instrument {
+            Some(instrument) => sizing.rounded_quantity(index, price, instrument),
+            None => sizing.quantity(index, price),
+        };
+        position += qty;
+        cost += qty * price;
+        let projection = project_state(price, qty, position, cost, direction, limits);
+        record(&mut forecast, projection, limits);
+    }
+    if fills.last() != Some(&boundary) {
+        let projection = project_state(boundary, 0.0, position, cost, direction, limits);
+        record(&mut forecast, projection, limits);
+    }
+
+    forecast
+}
+
+/// Calculates the account state at `price` for the accumulated fills.
+fn project_state(
+    price: f64,
+    qty: f64,
+    position: f64,
+    cost: f64,
+    direction: f64,
+    limits: &AccountLimits,
+) -> LevelProjection {
+    let notional = position * price;
+    let unrealized_pnl = direction * (notional - cost);
+    let equity = limits.equity + unrealized_pnl;
+    let margin = notional / limits.leverage;
+    let (leverage, margin_usage) = if equity > 0.0 {
+        (notional / equity, margin / equity)
+    } else {
+        (f64::INFINITY, f64::INFINITY)
+    };
+
+    LevelProjection {
+        price,
+        qty,
+        position: direction * position,
+        unrealized_pnl,
+        equity,
+        margin,
+        leverage,
+        margin_usage,
+    }
+}
+
+/// Adds a projection to the forecast and records the limits it breaches.
+fn record(forecast: &mut MarginForecast, projection: LevelProjection, limits: &AccountLimits) {
+    let price = projection.price;
+    if projection.equity <= 0.0 {
+        forecast
+            .warnings
+            .push(MarginWarning::EquityDepleted { price });
+    } else {
+        if projection.leverage > limits.max_leverage {
+            forecast.warnings.push(MarginWarning::LeverageExceeded {
+                price,
+                leverage: projection.leverage,
+            });
+        }
+        if projection.margin_usage > limits.max_margin_usage {
+            forecast.warnings.push(MarginWarning::MarginExceeded {
+                price,
+                margin_usage: projection.margin_usage,
+            });
+        }
+    }
+
+    forecast.worst_leverage = forecast.worst_leverage.max(projection.leverage);
+    forecast.worst_margin_usage = forecast.worst_margin_usage.max(projection.margin_usage);
+    forecast.levels.push(projection);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: AccountLimits = AccountLimits {
+        equity: 1_000.0,
+        leverage: 10.0,
+        max_leverage: 3.0,
+        max_margin_usage: 0.5,
+    };
+
+    #[test]
+    fn test_project_move_down() {
+        let levels = [110.0, 95.0, 90.0, 85.0];
+        let forecast = project_move(
+            100.0,
+            &levels,
+            80.0,
+            &SizingPolicy::FixedQuantity(10.0),
+            &LIMITS,
+        );
+
+        let prices: Vec<f64> = forecast.levels.iter().map(|l| l.price).collect();
+        assert_eq!(prices, vec![95.0, 90.0, 85.0, 80.0]);
+
+        let last = forecast.levels.last().unwrap();
+        assert_eq!(last.position, 30.0);
+        // Fills at 95, 90 and 85 marked at 80
+        assert_eq!(last.unrealized_pnl, -300.0);
+        assert_eq!(last.equity, 700.0);
+        assert_eq!(last.margin, 240.0);
+        assert!((forecast.worst_leverage - 2400.0 / 700.0).abs() < 1e-12);
+        assert_eq!(
+            forecast.warnings,
+            vec![MarginWarning::LeverageExceeded {
+                price: 80.0,
+                leverage: 2400.0 / 700.0
+            }]
+        );
+        assert!(!forecast.is_within_limits());
+    }
+
+    #[test]
+    fn test_project_move_up_is_short() {
+        let levels = [105.0, 110.0];
+        let forecast = project_move(
+            100.0,
+            &levels,
+            110.0,
+            &SizingPolicy::FixedNotional(1_050.0),
+            &LIMITS,
+        );
+
+        assert_eq!(forecast.levels.len(), 2);
+        let first = forecast.levels[0];
+        assert_eq!(first.position, -10.0);
+        assert_eq!(first.unrealized_pnl, 0.0);
+        let last = forecast.levels[1];
+        assert!(last.position < -19.0);
+        assert!((last.unrealized_pnl + 50.0).abs() < 1e-9);
+        assert!(forecast.is_within_limits());
+    }
+
+    #[test]
+    fn test_rounds_to_instrument_lots() {
+        let instrument = Instrument::new("ETHUSDT", 0.01, 0.1);
+        let sizing = SizingPolicy::FixedNotional(1_000.0);
+        assert_eq!(sizing.rounded_quantity(0, 95.0, &instrument), 10.5);
+
+        let forecast =
+            project_move_for_instrument(100.0, &[95.0, 90.0], 90.0, &sizing, &LIMITS, &instrument);
+        let qty: Vec<f64> = forecast.levels.iter().map(|l| l.qty).collect();
+        assert_eq!(qty, vec![10.5, 11.1]);
+    }
+
+    #[test]
+    fn test_martingale_depletes_equity() {
+        let levels = [90.0, 80.0, 70.0, 60.0];
+        let sizing = SizingPolicy::Martingale {
+            base_qty: 5.0,
+            multiplier: 2.0,
+        };
+        let (down, up) = forecast_grid_margin(100.0, &levels, 50.0, 150.0, &sizing, &LIMITS);
+
+        assert!(down
+            .warnings
+            .contains(&MarginWarning::EquityDepleted { price: 50.0 }));
+        assert_eq!(down.worst_margin_usage, f64::INFINITY);
+        assert_eq!(up.levels.len(), 1);
+        assert!(up.is_within_limits());
+    }
+}
@@ -0,0 +1,369 @@
+/*!
+Market-neutral grids quoting both sides around the centerline.
+
+Instead of waiting for the price to reach a band before entering and for the
+other band before exiting, a neutral grid keeps a bid and an ask resting
+around the moving average at all times, like a simple market maker. Every bar
+the bid is quoted `band_mult` band units below the average and the ask as far
+above it, and the net inventory they build up, long or short, is leaned
+against with `calculate_relative_depths`: a long inventory moves both quotes
+down, making the next buy less likely and the next sell more likely, and a
+short inventory moves them up.
+
+The spread captured between the two sides is the edge; the inventory limit
+and the skew bound the directional risk taken while the price trends away.
+*/
+
+use std::collections::VecDeque;
+
+use strato_utils::relative_depths::calculate_relative_depths;
+use strato_utils::vars::ohlc::Ohlc;
+use strato_utils::vars::trade::Side;
+
+use crate::grid::costs::ExecutionCosts;
+use crate::grid::dynamic::calculate_bands;
+use crate::grid::dynamic::GridParams;
+use crate::grid::dynamic::GridParamsError;
+use crate::grid::dynamic::TradingState;
+use crate::grid::report::BacktestReport;
+use crate::grid::report::ClosedTrade;
+
+const DEFAULT_MAX_ORDERS: f64 = 5.0;
+
+/// Parameters of a neutral grid.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NeutralGridParams {
+    /// The centerline and the distance of the quotes from it; only the first
+    /// level is quoted.
+    pub grid: GridParams,
+    /// Quantity of each quote in base units.
+    pub order_qty: f64,
+    /// Shift of both quotes, relative to the centerline, per `order_qty` of
+    /// inventory.
+    pub skew: f64,
+    /// Largest inventory on either side in base units; the side that would
+    /// exceed it is not quoted.
+    pub max_position: f64,
+}
+
+impl NeutralGridParams {
+    /// Creates parameters without skew and with an inventory limit of five
+    /// orders, validated with `validate`.
+    pub fn new(grid: GridParams, order_qty: f64) -> Result<Self, GridParamsError> {
+        let params = Self {
+            grid,
+            order_qty,
+            skew: 0.0,
+            max_position: order_qty * DEFAULT_MAX_ORDERS,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    pub fn with_skew(mut self, skew: f64) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    pub fn with_max_position(mut self, max_position: f64) -> Self {
+        self.max_position = max_position;
+        self
+    }
+
+    /// Checks the grid parameters, that the order quantity and the inventory
+    /// limit are positive and finite, and that the skew is not negative.
+    pub fn validate(&self) -> Result<(), GridParamsError> {
+        self.grid.validate()?;
+        for (field, value) in [
+            ("order_qty", self.order_qty),
+            ("max_position", self.max_position),
+        ] {
+            if !(value > 0.0 && value.is_finite()) {
+                return Err(GridParamsError::NonPositive { field, value });
+            }
+        }
+        if !(self.skew >= 0.0 && self.skew.is_finite()) {
+            return Err(GridParamsError::Negative {
+                field: "skew",
+                value: self.skew,
+            });
+        }
+        Ok(())
+    }
+
+    /// The bid and ask around `center` for an inventory of `position`, given
+    /// the distance `band` of one band unit.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the bid and the ask price.
+    pub fn quotes(&self, center: f64, band: f64, position: f64) -> (f64, f64) {
+        let half_spread = self.grid.band_mult * band / center;
+        let (bid_depth, ask_depth) =
+            calculate_relative_depths(half_spread, self.skew, position, self.order_qty);
+        (center * (1.0 - bid_depth), center * (1.0 + ask_depth))
+    }
+}
+
+/// A part of the inventory not yet offset by an opposite fill.
+struct Lot {
+    side: Side,
+    time: i64,
+    price: f64,
+    qty: f64,
+    /// Entry fee per unit.
+    fee: f64,
+}
+
+/// Inventory of a neutral grid, matched first in, first out.
+struct Inventory {
+    state: TradingState,
+    lots: VecDeque<Lot>,
+    trades: Vec<ClosedTrade>,
+}
+
+impl Inventory {
+    fn fill(&mut self, side: Side, time: i64, price: f64, qty: f64, fee: f64) {
+        self.state.balance -= side.sign() * qty * price + fee;
+        self.state.position += side.sign() * qty;
+        self.state.fees_paid += fee;
+
+        let unit_fee = fee / qty;
+        let mut left = qty;
+        while left > 0.0 {
+            let Some(lot) = self.lots.front_mut().filter(|lot| lot.side != side) else {
+                break;
+            };
+            let matched = left.min(lot.qty);
+            let sign = lot.side.sign();
+            self.trades.push(ClosedTrade {
+                level: 0,
+                side: lot.side,
+                entry_time: lot.time,
+                exit_time: time,
+                entry_price: lot.price,
+                exit_price: price,
+                qty: matched,
+                pnl: sign * matched * (price - lot.price) - matched * (lot.fee + unit_fee),
+            });
+            lot.qty -= matched;
+            left -= matched;
+            if lot.qty <= 1e-12 * qty {
+                self.lots.pop_front();
+            }
+        }
+        if left > 1e-12 * qty {
+            self.lots.push_back(Lot {
+                side,
+                time,
+                price,
+                qty: left,
+                fee: unit_fee,
+            });
+        }
+        // Close out the dust left by rounding
+        if self.state.position.abs() < 1e-12 * qty {
+            self.state.position = 0.0;
+        }
+    }
+}
+
+/// Backtests a neutral grid.
+///
+/// The quotes of a bar are set from the centerline and the inventory at its
+/// open and rest as limit orders paying the maker fee: the bid fills when the
+/// low reaches it, the ask when the high does, both on the same bar when its
+/// range spans them, the one closer to the open first. A quote already crossed
+/// by the open fills at the open. No quotes rest until the averages are
+/// warmed up. Any inventory left is closed at the last close with a market
+/// order.
+///
+/// # Arguments
+///
+/// * `ohlc` - A slice of `Ohlc` structs representing market data.
+/// * `params` - The centerline, quote size, skew and inventory limit.
+/// * `initial_balance` - The initial balance for the trading account.
+/// * `costs` - The fees, and the slippage of the final market order.
+///
+/// # Returns
+///
+/// The report of the backtest, with one trade per offset part of the
+/// inventory.
+pub fn backtest_neutral_grid(
+    ohlc: &[Ohlc],
+    params: &NeutralGridParams,
+    initial_balance: f64,
+    costs: &ExecutionCosts,
+) -> BacktestReport {
+    let (ma_values, band_values) = calculate_bands(ohlc, &params.grid);
+    let mut inventory = Inventory {
+        state: TradingState {
+            balance: initial_balance,
+            position: 0.0,
+            fees_paid: 0.0,
+            funding_paid: 0.0,
+        },
+        lots: VecDeque::new(),
+        trades: Vec::new(),
+    };
+    let mut report = BacktestReport {
+        initial_balance,
+        equity: Vec::with_capacity(ohlc.len()),
+        ..Default::default()
+    };
+    let mut exposed = 0;
+
+    for (i, candle) in ohlc.iter().enumerate() {
+        let (center, band) = (ma_values[i], band_values[i]);
+        if center.is_finite() && band.is_finite() {
+            let position = inventory.state.position;
+            let (bid, ask) = params.quotes(center, band, position);
+            let buy_qty = params.order_qty.min(params.max_position - position);
+            let sell_qty = params.order_qty.min(params.max_position + position);
+            let mut orders = Vec::with_capacity(2);
+            if buy_qty > 0.0 && candle.low <= bid {
+                orders.push((Side::Buy, bid.min(candle.open), buy_qty));
+            }
+            if sell_qty > 0.0 && candle.high >= ask {
+                orders.push((Side::Sell, ask.max(candle.open), sell_qty));
+            }
+            orders.sort_by(|a, b| {
+                (a.1 - candle.open)
+                    .abs()
+                    .total_cmp(&(b.1 - candle.open).abs())
+            });
+            for (side, price, qty) in orders {
+                let fee = costs.fees.fee(qty * price, true);
+                inventory.fill(side, candle.timestamp, price, qty, fee);
+            }
+        }
+
+        let state = &inventory.state;
+        report
+            .equity
+            .push(state.balance + state.position * candle.close);
+        if state.position != 0.0 {
+            exposed += 1;
+        }
+    }
+
+    if let Some(last) = ohlc.last() {
+        let position = inventory.state.position;
+        if position != 0.0 {
+            let side = if position > 0.0 {
+                Side::Sell
+            } else {
+                Side::Buy
+            };
+            let price = costs.slippage.fill_price(side, last.close);
+            let qty = position.abs();
+            let fee = costs.fees.fee(qty * price, false);
+            inventory.fill(side, last.timestamp, price, qty, fee);
+        }
+        report.exposure = exposed as f64 / ohlc.len() as f64;
+    }
+
+    report.final_balance = inventory.state.balance;
+    report.trades = inventory.trades;
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::costs::FeeModel;
+    use crate::grid::dynamic::GridLogic;
+    use crate::grid::dynamic::MaType;
+
+    fn params() -> NeutralGridParams {
+        let grid = GridParams {
+            ma_len: 1,
+            ma_type: MaType::Sma,
+            grid_logic: GridLogic::Percent,
+            band_mult: 1.0,
+            ..Default::default()
+        };
+        NeutralGridParams::new(grid, 1.0).unwrap()
+    }
+
+    /// A bar opening at 100 whose source price is 100.
+    fn candle(timestamp: i64, low: f64, high: f64) -> Ohlc {
+        Ohlc {
+            timestamp,
+            open: 100.0,
+            high,
+            low,
+            close: 300.0 - high - low,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_neutral_quotes_skew() {
+        let params = params().with_skew(0.005);
+
+        assert_eq!(params.quotes(100.0, 1.0, 0.0), (99.0, 101.0));
+        let (bid, ask) = params.quotes(100.0, 1.0, 2.0);
+        assert!((bid - 98.0).abs() < 1e-9 && (ask - 100.0).abs() < 1e-9);
+        let (bid, ask) = params.quotes(100.0, 1.0, -2.0);
+        assert!((bid - 100.0).abs() < 1e-9 && (ask - 102.0).abs() < 1e-9);
+
+        assert_eq!(
+            params.clone().with_skew(-1.0).validate(),
+            Err(GridParamsError::Negative {
+                field: "skew",
+                value: -1.0
+            })
+        );
+        assert_eq!(
+            NeutralGridParams::new(params.grid, 0.0),
+            Err(GridParamsError::NonPositive {
+                field: "order_qty",
+                value: 0.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_backtest_neutral_grid() {
+        // The centerline stays at 100: the first bar spans both quotes, the
+        // next two only reach the bid
+        let ohlc = [
+            candle(0, 98.5, 101.5),
+            candle(1, 98.5, 100.5),
+            candle(2, 98.5, 100.5),
+            candle(3, 99.5, 100.5),
+        ];
+        let costs = ExecutionCosts {
+            fees: FeeModel {
+                maker_bps: 1.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let params = params().with_max_position(2.0);
+        let report = backtest_neutral_grid(&ohlc, &params, 1_000.0, &costs);
+
+        // Round trip on the first bar, then a long of 2 units capped by the
+        // inventory limit and closed at the last close
+        assert_eq!(report.trades.len(), 3);
+        let spread = &report.trades[0];
+        assert_eq!((spread.entry_price, spread.exit_price), (99.0, 101.0));
+        assert!((spread.pnl - (2.0 - 0.0001 * 200.0)).abs() < 1e-9);
+        assert!(report.trades[1..]
+            .iter()
+            .all(|t| t.side == Side::Buy && t.entry_price == 99.0 && t.exit_price == 100.0));
+        assert_eq!(report.exposure, 0.75);
+
+        let pnl: f64 = report.trades.iter().map(|t| t.pnl).sum();
+        assert!((report.final_balance - 1_000.0 - pnl).abs() < 1e-9);
+        assert_eq!(report.equity.len(), 4);
+
+        // Skewed, the long unit of the second bar moves the ask of the third
+        // one below its open, where it is sold
+        let skewed = params.with_skew(0.02);
+        let report = backtest_neutral_grid(&ohlc, &skewed, 1_000.0, &costs);
+        assert_eq!(report.trades.len(), 2);
+    }
+}
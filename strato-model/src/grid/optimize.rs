@@ -0,0 +1,391 @@
+/*!
+Parameter sweeps of the grid strategy with a train/test split.
+
+`GridOptimizer` backtests every combination of moving average length, ATR
+length, band multiplier and moving average type of a `ParamGrid` with
+`execute_trades_with_costs`. The candles are split in two: the results are
+ranked on the first, in-sample part only, and the second, out-of-sample part
+shows how much of that performance survives. A large gap between the two
+returns is the usual sign of an overfit parameter set.
+
+The signals are computed over the whole dataset so that the indicators of the
+test part are warmed up on the train part. They only look back, so no test
+data leaks into the train results.
+
+With the `rayon` feature `GridOptimizer::run` backtests the combinations on
+separate threads. `run_with_progress` runs them one by one, reporting progress
+and stopping early when cancelled.
+*/
+
+use std::cmp::Ordering;
+use std::fmt;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use strato_utils::progress::ProgressTracker;
+use strato_utils::vars::ohlc::Ohlc;
+
+use crate::grid::costs::ExecutionCosts;
+use crate::grid::dynamic::execute_trades_with_costs;
+use crate::grid::dynamic::manage_grids;
+use crate::grid::dynamic::Direction;
+use crate::grid::dynamic::GridParams;
+use crate::grid::dynamic::MaType;
+
+const DEFAULT_TRAIN_FRACTION: f64 = 0.7;
+const DEFAULT_BALANCE: f64 = 10_000.0;
+
+/// The values of each parameter to sweep. Parameters not swept keep the value
+/// of the base parameters.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParamGrid {
+    base: GridParams,
+    ma_lens: Vec<usize>,
+    atr_lens: Vec<usize>,
+    band_mults: Vec<f64>,
+    ma_types: Vec<MaType>,
+}
+
+impl ParamGrid {
+    /// Creates a grid holding the single parameter set `base`.
+    pub fn new(base: GridParams) -> Self {
+        Self {
+            ma_lens: vec![base.ma_len],
+            atr_lens: vec![base.atr_len],
+            band_mults: vec![base.band_mult],
+            ma_types: vec![base.ma_type],
+            base,
+        }
+    }
+
+    pub fn with_ma_lens(mut self, ma_lens: &[usize]) -> Self {
+        self.ma_lens = ma_lens.to_vec();
+        self
+    }
+
+    pub fn with_atr_lens(mut self, atr_lens: &[usize]) -> Self {
+        self.atr_lens = atr_lens.to_vec();
+        self
+    }
+
+    pub fn with_band_mults(mut self, band_mults: &[f64]) -> Self {
+        self.band_mults = band_mults.to_vec();
+        self
+    }
+
+    pub fn with_ma_types(mut self, ma_types: &[MaType]) -> Self {
+        self.ma_types = ma_types.to_vec();
+        self
+    }
+
+    /// Number of parameter sets in the grid.
+    pub fn len(&self) -> usize {
+        self.ma_lens.len() * self.atr_lens.len() * self.band_mults.len() * self.ma_types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every parameter set of the grid.
+    pub fn combinations(&self) -> Vec<GridParams> {
+        let mut combinations = Vec::with_capacity(self.len());
+        for &ma_type in &self.ma_types {
+            for &ma_len in &self.ma_lens {
+                for &atr_len in &self.atr_lens {
+                    for &band_mult in &self.band_mults {
+                        combinations.push(GridParams {
+                            ma_type,
+                            ma_len,
+                            atr_len,
+                            band_mult,
+                            ..self.base.clone()
+                        });
+                    }
+                }
+            }
+        }
+        combinations
+    }
+}
+
+/// The backtest of one parameter set.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptimizationResult {
+    pub params: GridParams,
+    /// Return over the train part, e.g. `0.1` for 10%.
+    pub train_return: f64,
+    /// Return over the test part.
+    pub test_return: f64,
+}
+
+impl OptimizationResult {
+    /// Train return minus test return, positive when the parameters did
+    /// worse out of sample.
+    pub fn degradation(&self) -> f64 {
+        self.train_return - self.test_return
+    }
+}
+
+/// The results of a sweep, ranked by train return.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptimizationReport {
+    pub results: Vec<OptimizationResult>,
+    /// Number of candles of the train part.
+    pub train_len: usize,
+    /// Number of candles of the test part.
+    pub test_len: usize,
+}
+
+impl OptimizationReport {
+    /// The parameter set with the best train return.
+    pub fn best(&self) -> Option<&OptimizationResult> {
+        self.results.first()
+    }
+
+    /// The results whose test return is at least the train return minus
+    /// `tolerance`, in rank order.
+    pub fn robust(&self, tolerance: f64) -> impl Iterator<Item = &OptimizationResult> {
+        self.results
+            .iter()
+            .filter(move |result| result.degradation() <= tolerance)
+    }
+}
+
+impl fmt::Display for OptimizationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>4}  {:<5} {:>6} {:>7} {:>9} {:>9} {:>9}",
+            "rank", "ma", "ma_len", "atr_len", "band_mult", "train", "test"
+        )?;
+        for (rank, result) in self.results.iter().enumerate() {
+            let params = &result.params;
+            writeln!(
+                f,
+                "{:>4}  {:<5} {:>6} {:>7} {:>9.2} {:>8.2}% {:>8.2}%",
+                rank + 1,
+                format!("{:?}", params.ma_type),
+                params.ma_len,
+                params.atr_len,
+                params.band_mult,
+                result.train_return * 100.0,
+                result.test_return * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Sweeps a `ParamGrid` over a dataset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridOptimizer {
+    grid: ParamGrid,
+    train_fraction: f64,
+    balance: f64,
+    direction: Direction,
+    costs: ExecutionCosts,
+}
+
+impl GridOptimizer {
+    /// Creates an optimizer training on the first 70% of the candles, long
+    /// only and without costs.
+    pub fn new(grid: ParamGrid) -> Self {
+        Self {
+            grid,
+            train_fraction: DEFAULT_TRAIN_FRACTION,
+            balance: DEFAULT_BALANCE,
+            direction: Direction::default(),
+            costs: ExecutionCosts::default(),
+        }
+    }
+
+    /// Sets the share of the candles used for training, between 0 and 1.
+    pub fn with_train_fraction(mut self, train_fraction: f64) -> Self {
+        self.train_fraction = train_fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_balance(mut self, balance: f64) -> Self {
+        self.balance = balance;
+        self
+    }
+
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_costs(mut self, costs: ExecutionCosts) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Backtests every parameter set, in parallel with the `rayon` feature.
+    ///
+    /// # Returns
+    ///
+    /// The results ranked by train return.
+    pub fn run(&self, ohlc: &[Ohlc]) -> OptimizationReport {
+        let combinations = self.grid.combinations();
+        #[cfg(feature = "rayon")]
+        let results = combinations
+            .into_par_iter()
+            .map(|params| self.evaluate(ohlc, params))
+            .collect();
+        #[cfg(not(feature = "rayon"))]
+        let results = combinations
+            .into_iter()
+            .map(|params| self.evaluate(ohlc, params))
+            .collect();
+        self.report(ohlc, results)
+    }
+
+    /// Backtests the parameter sets one by one, reporting one step per set.
+    ///
+    /// # Returns
+    ///
+    /// The results ranked by train return, of the sets run before
+    /// cancellation.
+    pub fn run_with_progress(
+        &self,
+        ohlc: &[Ohlc],
+        progress: &mut ProgressTracker,
+    ) -> OptimizationReport {
+        let mut results = Vec::new();
+        for params in self.grid.combinations() {
+            if progress.is_cancelled() {
+                break;
+            }
+            let label = format!(
+                "{:?} ma_len={} atr_len={} band_mult={}",
+                params.ma_type, params.ma_len, params.atr_len, params.band_mult
+            );
+            results.push(self.evaluate(ohlc, params));
+            if !progress.step(&label) {
+                break;
+            }
+        }
+        self.report(ohlc, results)
+    }
+
+    /// Index of the first candle of the test part.
+    fn split(&self, len: usize) -> usize {
+        (len as f64 * self.train_fraction).round() as usize
+    }
+
+    fn evaluate(&self, ohlc: &[Ohlc], params: GridParams) -> OptimizationResult {
+        let (entries, exits) = manage_grids(ohlc, &params);
+        let split = self.split(ohlc.len());
+        let segment_return = |range: std::ops::Range<usize>| {
+            if range.is_empty() {
+                return 0.0;
+            }
+            let balance = execute_trades_with_costs(
+                &ohlc[range.clone()],
+                &entries[range.clone()],
+                &exits[range],
+                self.balance,
+                self.direction,
+                &self.costs,
+            );
+            balance / self.balance - 1.0
+        };
+        OptimizationResult {
+            train_return: segment_return(0..split),
+            test_return: segment_return(split..ohlc.len()),
+            params,
+        }
+    }
+
+    fn report(&self, ohlc: &[Ohlc], mut results: Vec<OptimizationResult>) -> OptimizationReport {
+        results.sort_by(|a, b| {
+            b.train_return
+                .partial_cmp(&a.train_return)
+                .unwrap_or(Ordering::Equal)
+        });
+        let split = self.split(ohlc.len());
+        OptimizationReport {
+            results,
+            train_len: split,
+            test_len: ohlc.len() - split,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use strato_utils::progress::CancellationToken;
+    use strato_utils::progress::Progress;
+
+    use super::*;
+    use crate::grid::dynamic::GridLogic;
+
+    fn ohlc() -> Vec<Ohlc> {
+        (0..200)
+            .map(|i| {
+                let close = 100.0 + 10.0 * (i as f64 / 8.0).sin();
+                Ohlc {
+                    open: close,
+                    high: close + 1.0,
+                    low: close - 1.0,
+                    close,
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    fn grid() -> ParamGrid {
+        let base = GridParams {
+            ma_type: MaType::Sma,
+            grid_logic: GridLogic::Percent,
+            ..Default::default()
+        };
+        ParamGrid::new(base)
+            .with_ma_lens(&[10, 20])
+            .with_band_mults(&[2.0, 5.0, 20.0])
+    }
+
+    #[test]
+    fn test_optimizer_ranks_by_train_return() {
+        let report = GridOptimizer::new(grid()).run(&ohlc());
+
+        assert_eq!(report.results.len(), 6);
+        assert_eq!((report.train_len, report.test_len), (140, 60));
+        assert!(report
+            .results
+            .windows(2)
+            .all(|w| w[0].train_return >= w[1].train_return));
+        // Bands far out of the range never trade, the others lose to the lag
+        // of the average
+        let best = report.best().unwrap();
+        assert_eq!(best.params.band_mult, 20.0);
+        assert_eq!((best.train_return, best.test_return), (0.0, 0.0));
+        assert!(report.results[2].train_return < 0.0);
+        assert_eq!(report.robust(f64::INFINITY).count(), 6);
+        assert_eq!(report.to_string().lines().count(), 7);
+    }
+
+    #[test]
+    fn test_optimizer_progress() {
+        let mut steps = Vec::new();
+        let mut reporter = |p: &Progress| steps.push(p.completed);
+        let cancel = CancellationToken::new();
+        let mut progress = ProgressTracker::new(&mut reporter, cancel.clone(), Some(6), 1);
+
+        let report = GridOptimizer::new(grid()).run_with_progress(&ohlc(), &mut progress);
+        assert_eq!(report, GridOptimizer::new(grid()).run(&ohlc()));
+        drop(progress);
+        assert_eq!(steps, vec![1, 2, 3, 4, 5, 6]);
+
+        cancel.cancel();
+        let mut reporter = |_: &Progress| {};
+        let mut progress = ProgressTracker::new(&mut reporter, cancel, Some(6), 1);
+        let report = GridOptimizer::new(grid()).run_with_progress(&ohlc(), &mut progress);
+        assert!(report.results.is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+/*!
+Results of a grid backtest.
+
+`backtest_trades` and `backtest_level_trades` in `grid::dynamic` return a
+`BacktestReport` holding every round trip, the equity curve marked at each
+close and the share of bars spent in the market. The usual statistics (win
+rate, profit factor, maximum drawdown) are computed from those.
+*/
+
+use strato_utils::ta::drawdown::max_drawdown;
+use strato_utils::vars::trade::Side;
+
+/// A round trip of a backtest.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClosedTrade {
+    /// Index of the grid level, 0 for single-level grids.
+    pub level: usize,
+    /// `Side::Buy` for a long, `Side::Sell` for a short.
+    pub side: Side,
+    /// Timestamp of the entry bar in Unix milliseconds.
+    pub entry_time: i64,
+    /// Timestamp of the exit bar in Unix milliseconds.
+    pub exit_time: i64,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    /// Quantity in base units.
+    pub qty: f64,
+    /// Profit net of fees.
+    pub pnl: f64,
+}
+
+/// Trades, equity curve and exposure of a backtest.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BacktestReport {
+    pub initial_balance: f64,
+    /// Balance after closing any open position at the last close.
+    pub final_balance: f64,
+    /// Round trips in the order they closed.
+    pub trades: Vec<ClosedTrade>,
+    /// Equity at the close of every bar.
+    pub equity: Vec<f64>,
+    /// Share of the bars closing with an open position, between 0 and 1.
+    pub exposure: f64,
+    /// Funding paid on perpetuals, negative when received.
+    pub funding_paid: f64,
+}
+
+impl BacktestReport {
+    /// Final balance over initial balance minus one, e.g. `0.1` for 10%.
+    pub fn total_return(&self) -> f64 {
+        self.final_balance / self.initial_balance - 1.0
+    }
+
+    /// Share of the trades with a positive profit, 0 without trades.
+    pub fn win_rate(&self) -> f64 {
+        if self.trades.is_empty() {
+            return 0.0;
+        }
+        let wins = self.trades.iter().filter(|t| t.pnl > 0.0).count();
+        wins as f64 / self.trades.len() as f64
+    }
+
+    /// Gross profit over gross loss: infinite without losing trades, 0
+    /// without winning trades.
+    pub fn profit_factor(&self) -> f64 {
+        let profit: f64 = self.trades.iter().map(|t| t.pnl.max(0.0)).sum();
+        let loss: f64 = self.trades.iter().map(|t| (-t.pnl).max(0.0)).sum();
+        if profit == 0.0 {
+            0.0
+        } else {
+            profit / loss
+        }
+    }
+
+    /// Maximum drawdown of the equity curve, as a fraction of the peak.
+    pub fn max_drawdown(&self) -> f64 {
+        max_drawdown(&self.equity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(pnl: f64) -> ClosedTrade {
+        ClosedTrade {
+            level: 0,
+            side: Side::Buy,
+            entry_time: 0,
+            exit_time: 1,
+            entry_price: 100.0,
+            exit_price: 100.0 + pnl,
+            qty: 1.0,
+            pnl,
+        }
+    }
+
+    #[test]
+    fn test_report_statistics() {
+        let report = BacktestReport {
+            initial_balance: 100.0,
+            final_balance: 110.0,
+            trades: vec![trade(15.0), trade(-5.0), trade(5.0), trade(-5.0)],
+            equity: vec![100.0, 115.0, 110.0, 92.0, 110.0],
+            exposure: 0.5,
+            funding_paid: 0.0,
+        };
+
+        assert!((report.total_return() - 0.1).abs() < 1e-12);
+        assert_eq!(report.win_rate(), 0.5);
+        assert_eq!(report.profit_factor(), 2.0);
+        assert!((report.max_drawdown() - 0.2).abs() < 1e-12);
+
+        let empty = BacktestReport::default();
+        assert_eq!((empty.win_rate(), empty.profit_factor()), (0.0, 0.0));
+        let winner = BacktestReport {
+            trades: vec![trade(1.0)],
+            ..Default::default()
+        };
+        assert_eq!(winner.profit_factor(), f64::INFINITY);
+    }
+}
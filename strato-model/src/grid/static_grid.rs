@@ -0,0 +1,203 @@
+/*!
+Static grids between fixed bounds, the classic exchange grid bot.
+
+Instead of following a moving average, the range is given explicitly: the
+`levels` cells between `lower` and `upper` each buy at their lower price and
+sell at their upper price. The prices are spaced evenly, either by the same
+amount (arithmetic) or by the same ratio (geometric, the usual choice for wide
+ranges).
+
+The grid is expressed as `GridLevels` constant over time, so it runs on the
+same executor as the dynamic grid, `execute_level_trades`, with the same
+`LevelSizing`. Level 0 is the highest cell; the deeper cells get the larger
+shares with `Allocation::Geometric`.
+*/
+
+use strato_utils::ta::atr::atr_nan;
+use strato_utils::vars::ohlc::Ohlc;
+
+use crate::grid::dynamic::execute_level_trades;
+use crate::grid::dynamic::GridLevels;
+use crate::grid::dynamic::GridParamsError;
+use crate::grid::dynamic::LevelSizing;
+
+const DEFAULT_ATR_LEN: usize = 14;
+
+/// How the prices of a static grid are spaced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Spacing {
+    /// The same price difference between consecutive prices.
+    #[default]
+    Arithmetic,
+    /// The same ratio between consecutive prices.
+    Geometric,
+}
+
+/// Parameters of a static grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StaticGridParams {
+    /// Lowest buy price.
+    pub lower: f64,
+    /// Highest sell price.
+    pub upper: f64,
+    /// Number of cells between the bounds.
+    pub levels: usize,
+    pub spacing: Spacing,
+    /// Length of the ATR used by `Allocation::VolatilityWeighted`.
+    pub atr_len: usize,
+}
+
+impl StaticGridParams {
+    /// Creates arithmetic grid parameters, validated with `validate`.
+    pub fn new(lower: f64, upper: f64, levels: usize) -> Result<Self, GridParamsError> {
+        let params = Self {
+            lower,
+            upper,
+            levels,
+            spacing: Spacing::default(),
+            atr_len: DEFAULT_ATR_LEN,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    pub fn with_spacing(mut self, spacing: Spacing) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn with_atr_len(mut self, atr_len: usize) -> Self {
+        self.atr_len = atr_len;
+        self
+    }
+
+    /// Checks that the bounds are positive and finite with `lower` below
+    /// `upper`, and that there is at least one cell.
+    pub fn validate(&self) -> Result<(), GridParamsError> {
+        for (field, value) in [("lower", self.lower), ("upper", self.upper)] {
+            if !(value > 0.0 && value.is_finite()) {
+                return Err(GridParamsError::NonPositive { field, value });
+            }
+        }
+        if self.lower >= self.upper {
+            return Err(GridParamsError::InvertedBounds {
+                lower: self.lower,
+                upper: self.upper,
+            });
+        }
+        if self.levels == 0 {
+            return Err(GridParamsError::Zero { field: "levels" });
+        }
+        if self.atr_len == 0 {
+            return Err(GridParamsError::Zero { field: "atr_len" });
+        }
+        Ok(())
+    }
+
+    /// The `levels + 1` prices of the grid in increasing order, from `lower`
+    /// to `upper`.
+    pub fn prices(&self) -> Vec<f64> {
+        let n = self.levels as f64;
+        (0..=self.levels)
+            .map(|i| match self.spacing {
+                _ if i == self.levels => self.upper,
+                Spacing::Arithmetic => self.lower + (self.upper - self.lower) * i as f64 / n,
+                Spacing::Geometric => self.lower * (self.upper / self.lower).powf(i as f64 / n),
+            })
+            .collect()
+    }
+}
+
+/// Generates the levels of a static grid for `len` bars.
+///
+/// # Returns
+///
+/// The grid levels, the same on every bar: level `k` buys at the `k`-th
+/// highest cell's lower price and sells at its upper price.
+pub fn generate_static_grid_levels(len: usize, params: &StaticGridParams) -> GridLevels {
+    let prices = params.prices();
+    let cells = prices.windows(2).rev();
+    let (premium, discount) = cells
+        .map(|cell| (vec![cell[1]; len], vec![cell[0]; len]))
+        .unzip();
+    GridLevels { premium, discount }
+}
+
+/// Executes a long static grid.
+///
+/// # Arguments
+///
+/// * `ohlc` - A slice of `Ohlc` structs representing market data.
+/// * `params` - The bounds and cells of the grid.
+/// * `initial_balance` - The initial balance for the trading account.
+/// * `sizing` - How the equity is split across the cells.
+///
+/// # Returns
+///
+/// The final balance after closing any open position at the last close.
+pub fn execute_static_grid_trades(
+    ohlc: &[Ohlc],
+    params: &StaticGridParams,
+    initial_balance: f64,
+    sizing: &LevelSizing,
+) -> f64 {
+    let levels = generate_static_grid_levels(ohlc.len(), params);
+    let atr_values = atr_nan(ohlc, params.atr_len);
+    execute_level_trades(ohlc, &levels, &atr_values, initial_balance, sizing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_static_grid_prices() {
+        let params = StaticGridParams::new(100.0, 200.0, 4).unwrap();
+        assert_eq!(params.prices(), vec![100.0, 125.0, 150.0, 175.0, 200.0]);
+
+        let geometric = params.with_spacing(Spacing::Geometric).prices();
+        assert_eq!(geometric.len(), 5);
+        assert!((geometric[2] - 100.0 * 2f64.sqrt()).abs() < 1e-9);
+        assert_eq!(geometric[4], 200.0);
+
+        let levels = generate_static_grid_levels(3, &params);
+        assert_eq!(levels.discount[0], vec![175.0; 3]);
+        assert_eq!(levels.premium[0], vec![200.0; 3]);
+        assert_eq!(levels.discount[3], vec![100.0; 3]);
+
+        assert_eq!(
+            StaticGridParams::new(200.0, 100.0, 4),
+            Err(GridParamsError::InvertedBounds {
+                lower: 200.0,
+                upper: 100.0
+            })
+        );
+        assert!(StaticGridParams::new(0.0, 100.0, 4).is_err());
+        assert!(StaticGridParams::new(100.0, 200.0, 0).is_err());
+    }
+
+    #[test]
+    fn test_execute_static_grid_trades() {
+        let ohlc: Vec<Ohlc> = [100.0, 90.0, 80.0, 90.0, 100.0]
+            .iter()
+            .map(|&close| Ohlc {
+                open: close,
+                high: close + 1.0,
+                low: close - 1.0,
+                close,
+                ..Default::default()
+            })
+            .collect();
+        let params = StaticGridParams::new(80.0, 100.0, 2).unwrap();
+
+        // The upper cell buys half the equity at 90 and sells at 100, the
+        // lower one buys half the equity marked at 80 there and sells at 90
+        let balance = execute_static_grid_trades(&ohlc, &params, 1_000.0, &LevelSizing::default());
+        let upper = 500.0 / 90.0;
+        let lower = (500.0 + upper * 80.0) / 2.0 / 80.0;
+        let expected = 1_000.0 + upper * 10.0 + lower * 10.0;
+        assert!((balance - expected).abs() < 1e-9, "{}", balance);
+    }
+}
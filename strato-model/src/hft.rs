@@ -1 +1,13 @@
+pub mod avellaneda_stoikov;
+pub mod book_bars;
+pub mod feature_recorder;
+pub mod gridmm;
+pub mod hft_grid;
 pub mod hft_oir;
+pub mod kill_switch;
+#[cfg(feature = "live")]
+pub mod live;
+pub mod npz;
+pub mod oir_calibration;
+pub mod recorder;
+pub mod toxicity;
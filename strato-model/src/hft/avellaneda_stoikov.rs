@@ -0,0 +1,318 @@
+/*!
+The Avellaneda-Stoikov market-making model.
+
+A market maker holding an inventory `q` values the asset at the reservation
+price `r = s - q γ σ² τ` instead of the mid-price `s`, where `γ` is its risk
+aversion, `σ²` the variance of the mid-price per second and `τ` the time left
+to the horizon in seconds. It quotes symmetrically around `r` with the
+optimal spread `δ = γ σ² τ + 2 / γ ln(1 + γ / κ)`, `κ` being the rate at
+which the arrival of market orders decays with the distance of the quotes.
+
+The prices come from `calculate_reservation_quotes`, which expresses both
+quotes as relative depths from the mid-price with `calculate_relative_depths`:
+the half-spread is `δ / 2` and the skew per order of inventory is the shift
+`γ σ² τ` of the reservation price per unit.
+
+The horizon is rolling: `τ` stays at `horizon`, the usual approximation for
+venues that trade around the clock.
+*/
+
+use std::fmt::Debug;
+
+use hftbacktest::prelude::Bot;
+use hftbacktest::prelude::L2MarketDepth;
+use hftbacktest::prelude::MarketDepth;
+use hftbacktest::prelude::Recorder;
+use strato_utils::progress::CancellationToken;
+use strato_utils::progress::NoProgress;
+use strato_utils::progress::ProgressTracker;
+use strato_utils::relative_depths::calculate_optimal_half_spread;
+use strato_utils::relative_depths::calculate_reservation_price;
+use strato_utils::relative_depths::calculate_reservation_quotes;
+use strato_utils::ta::indicator::Indicator;
+use strato_utils::ta::indicator::Sma;
+
+use crate::hft::gridmm::sync_quotes;
+use crate::hft::kill_switch::KillSwitch;
+use crate::hft::kill_switch::KillSwitchLimits;
+use crate::hft::toxicity::ToxicityParams;
+
+/// Interval between two quote updates, 100ms in nanoseconds.
+const STEP: i64 = 100_000_000;
+
+/// Number of steps per second.
+const STEPS_PER_SECOND: f64 = 10.0;
+
+/// Default number of steps the variance of the mid-price is averaged over,
+/// one minute.
+const DEFAULT_VOL_WINDOW: usize = 600;
+
+/// Parameters of the Avellaneda-Stoikov model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AvellanedaStoikov {
+    /// Risk aversion `γ`.
+    pub gamma: f64,
+    /// Decay `κ` of the arrival rate of market orders with the distance of
+    /// the quotes from the mid-price, per unit of price.
+    pub kappa: f64,
+    /// Time to the horizon `τ` in seconds.
+    pub horizon: f64,
+    /// Quantity of each quote in base units.
+    pub order_qty: f64,
+    /// Largest absolute inventory in base units; the side whose fill would
+    /// exceed it is not quoted.
+    pub max_position: f64,
+    /// Number of 100ms steps the variance of the mid-price is averaged over.
+    pub vol_window: usize,
+    /// Loss, message rate and reject rate past which the orders are
+    /// cancelled, the position closed and the loop stopped.
+    pub kill_switch: KillSwitchLimits,
+    /// VPIN filter of the market trades, widening the quotes around the
+    /// reservation price in toxic flow and pulling them when it is extreme.
+    /// Off by default.
+    pub toxicity: Option<ToxicityParams>,
+}
+
+impl AvellanedaStoikov {
+    /// Creates a model holding at most ten orders of inventory, estimating
+    /// the variance over one minute.
+    pub fn new(gamma: f64, kappa: f64, horizon: f64, order_qty: f64) -> Self {
+        Self {
+            gamma,
+            kappa,
+            horizon,
+            order_qty,
+            max_position: 10.0 * order_qty,
+            vol_window: DEFAULT_VOL_WINDOW,
+            kill_switch: KillSwitchLimits::default(),
+            toxicity: None,
+        }
+    }
+
+    pub fn with_max_position(mut self, max_position: f64) -> Self {
+        self.max_position = max_position;
+        self
+    }
+
+    pub fn with_vol_window(mut self, vol_window: usize) -> Self {
+        self.vol_window = vol_window;
+        self
+    }
+
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitchLimits) -> Self {
+        self.kill_switch = kill_switch;
+        self
+    }
+
+    pub fn with_toxicity(mut self, toxicity: ToxicityParams) -> Self {
+        self.toxicity = Some(toxicity);
+        self
+    }
+
+    /// Calculates the reservation price, the mid-price adjusted for the
+    /// inventory.
+    ///
+    /// # Arguments
+    ///
+    /// * `mid_price` - Mid-price of the book.
+    /// * `position` - Inventory in base units, negative when short.
+    /// * `variance` - Variance of the mid-price per second.
+    pub fn reservation_price(&self, mid_price: f64, position: f64, variance: f64) -> f64 {
+        calculate_reservation_price(
+            mid_price,
+            variance.sqrt(),
+            self.horizon,
+            position,
+            self.gamma,
+        )
+    }
+
+    /// Calculates the optimal distance between the bid and the ask.
+    ///
+    /// # Arguments
+    ///
+    /// * `variance` - Variance of the mid-price per second.
+    pub fn optimal_spread(&self, variance: f64) -> f64 {
+        2.0 * calculate_optimal_half_spread(variance.sqrt(), self.horizon, self.gamma, self.kappa)
+    }
+
+    /// Calculates the bid and ask prices, `optimal_spread` apart around the
+    /// reservation price.
+    ///
+    /// # Arguments
+    ///
+    /// * `mid_price` - Mid-price of the book.
+    /// * `position` - Inventory in base units, negative when short.
+    /// * `variance` - Variance of the mid-price per second.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the bid and the ask price.
+    pub fn quotes(&self, mid_price: f64, position: f64, variance: f64) -> (f64, f64) {
+        calculate_reservation_quotes(
+            mid_price,
+            variance.sqrt(),
+            self.horizon,
+            position,
+            self.order_qty,
+            self.gamma,
+            self.kappa,
+        )
+    }
+}
+
+/// Runs the model on asset 0 of `hbt`, recording every second.
+///
+/// Every 100ms the variance is updated from the change of the mid-price and
+/// the quotes are recomputed. They are posted as post-only limit orders,
+/// never through the touch, and replaced whenever their price tick changes. A
+/// side is not quoted when its fill would take the inventory past
+/// `max_position`.
+/// Nothing is quoted until the variance is warmed up over `vol_window`
+/// steps. With `toxicity`, the market trades feed a VPIN filter that widens
+/// the quotes around the reservation price or pulls them in toxic flow. Once
+/// `kill_switch` trips, the quotes are cancelled, the inventory
+/// is closed and the loop stops.
+///
+/// # Arguments
+///
+/// * `hbt` - The backtest.
+/// * `recorder` - Receives the state of the backtest every second.
+/// * `model` - The model parameters.
+pub fn exec_backtest_avellaneda_stoikov<MD, I, R>(
+    hbt: &mut I,
+    recorder: &mut R,
+    model: &AvellanedaStoikov,
+) -> anyhow::Result<(), anyhow::Error>
+where
+    MD: L2MarketDepth + MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+    R: Recorder,
+    <R as Recorder>::Error: Debug,
+{
+    let mut reporter = NoProgress;
+    let mut progress = ProgressTracker::new(&mut reporter, CancellationToken::new(), None, 1);
+    exec_backtest_avellaneda_stoikov_with_progress(hbt, recorder, model, &mut progress)
+}
+
+/// Runs the model like `exec_backtest_avellaneda_stoikov`, reporting progress
+/// after every 100ms step and stopping early once the tracker's cancellation
+/// token is cancelled.
+///
+/// The label of each progress update is the current backtest timestamp.
+pub fn exec_backtest_avellaneda_stoikov_with_progress<MD, I, R>(
+    hbt: &mut I,
+    recorder: &mut R,
+    model: &AvellanedaStoikov,
+    progress: &mut ProgressTracker,
+) -> anyhow::Result<(), anyhow::Error>
+where
+    MD: L2MarketDepth + MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+    R: Recorder,
+    <R as Recorder>::Error: Debug,
+{
+    let asset_no = 0;
+    let mut variance = Sma::new(model.vol_window);
+    let mut prev_mid = None;
+    let mut next_order_id = 0;
+    let mut kill_switch = KillSwitch::new(model.kill_switch);
+    let mut toxicity = model
+        .toxicity
+        .map(|toxicity| toxicity.filter())
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("invalid toxicity filter: {}", e))?;
+    let mut int = 0;
+
+    while hbt
+        .elapse(STEP)
+        .map_err(|e| anyhow::anyhow!("elapse failed: {:?}", e))?
+    {
+        int += 1;
+        if int % 10 == 0 {
+            // Records every 1-sec
+            recorder
+                .record(hbt)
+                .map_err(|e| anyhow::anyhow!("record failed: {:?}", e))?;
+        }
+        if kill_switch.guard(hbt)? {
+            break;
+        }
+        hbt.clear_inactive_orders(Some(asset_no));
+        if let Some(filter) = toxicity.as_mut() {
+            filter.on_trades(hbt.last_trades(asset_no));
+            hbt.clear_last_trades(Some(asset_no));
+        }
+
+        let depth = hbt.depth(asset_no);
+        let (best_bid, best_ask) = (depth.best_bid(), depth.best_ask());
+        let mid_price = (best_bid + best_ask) / 2.0;
+        let step_variance = if mid_price.is_finite() {
+            prev_mid
+                .replace(mid_price)
+                .and_then(|prev: f64| variance.next((mid_price - prev).powi(2)))
+        } else {
+            None
+        };
+
+        if let Some(step_variance) = step_variance {
+            let position = hbt.position(asset_no);
+            let (bid, ask) = model.quotes(mid_price, position, step_variance * STEPS_PER_SECOND);
+            // The quotes are centered on the reservation price
+            let (reservation_price, half_spread) = ((bid + ask) / 2.0, (ask - bid) / 2.0);
+            let half_spread = match toxicity.as_ref() {
+                Some(filter) => filter.adjust_half_spread(half_spread),
+                None => Some(half_spread),
+            };
+            let bid = half_spread
+                .filter(|_| position + model.order_qty <= model.max_position)
+                .map(|half_spread| reservation_price - half_spread);
+            let ask = half_spread
+                .filter(|_| position - model.order_qty >= -model.max_position)
+                .map(|half_spread| reservation_price + half_spread);
+            sync_quotes(
+                hbt,
+                asset_no,
+                bid.as_slice(),
+                ask.as_slice(),
+                model.order_qty,
+                &mut next_order_id,
+            )?;
+        }
+
+        let now = hbt.current_timestamp();
+        if !progress.step_with(|| now.to_string()) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quotes_around_reservation_price() {
+        let model = AvellanedaStoikov::new(0.1, 1.5, 60.0, 2.0);
+        let variance = 0.04;
+
+        // Flat: symmetric around the mid-price
+        let spread = model.optimal_spread(variance);
+        assert!((spread - (0.1 * 0.04 * 60.0 + 20.0 * (1.0_f64 + 0.1 / 1.5).ln())).abs() < 1e-12);
+        let (bid, ask) = model.quotes(100.0, 0.0, variance);
+        assert!((bid - (100.0 - spread / 2.0)).abs() < 1e-9);
+        assert!((ask - (100.0 + spread / 2.0)).abs() < 1e-9);
+
+        // Long: both quotes shift down to the reservation price
+        let reservation = model.reservation_price(100.0, 3.0, variance);
+        assert!((reservation - (100.0 - 3.0 * 0.24)).abs() < 1e-12);
+        let (bid, ask) = model.quotes(100.0, 3.0, variance);
+        assert!((bid - (reservation - spread / 2.0)).abs() < 1e-9);
+        assert!((ask - (reservation + spread / 2.0)).abs() < 1e-9);
+    }
+}
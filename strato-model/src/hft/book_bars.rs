@@ -0,0 +1,301 @@
+use hftbacktest::prelude::MarketDepth;
+use strato_utils::vars::ohlc::Ohlc;
+use strato_utils::vars::order_book::Level;
+use strato_utils::vars::order_book::OrderBook;
+
+use crate::hft::hft_oir::TradingState;
+
+/// The default number of book levels aggregated into the depth features.
+pub const DEFAULT_DEPTH_LEVELS: usize = 5;
+
+/// A candle enriched with order book features averaged over the bar.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MicrostructureBar {
+    /// Start of the bar in the timestamp unit of the feed.
+    pub start: i64,
+    /// OHLC of the mid-price.
+    pub ohlc: Ohlc,
+    /// Average bid-ask spread, as a percentage of the bid.
+    pub avg_spread: f64,
+    /// Average bid quantity over the top levels.
+    pub avg_bid_depth: f64,
+    /// Average ask quantity over the top levels.
+    pub avg_ask_depth: f64,
+    /// Mean Order Imbalance Ratio (OIR) of the top levels, over the snapshots
+    /// with a non-empty book, or NaN without any.
+    pub mean_oir: f64,
+    /// Number of book snapshots aggregated into the bar.
+    pub samples: usize,
+}
+
+/// Aggregates order book snapshots into time bars of fixed length.
+#[derive(Debug, Clone)]
+pub struct BookBarAggregator {
+    interval: i64,
+    current: Option<MicrostructureBar>,
+    spread_sum: f64,
+    bid_depth_sum: f64,
+    ask_depth_sum: f64,
+    oir_sum: f64,
+    /// Number of snapshots summed into `oir_sum`.
+    oir_samples: usize,
+}
+
+impl BookBarAggregator {
+    /// Creates an aggregator producing bars of `interval` length, in the same
+    /// unit as the timestamps passed to `update`.
+    ///
+    /// Returns an error if `interval` is not positive.
+    pub fn new(interval: i64) -> Result<Self, String> {
+        if interval <= 0 {
+            return Err(format!(
+                "Invalid bar interval {}: it must be positive.",
+                interval
+            ));
+        }
+        Ok(Self {
+            interval,
+            current: None,
+            spread_sum: 0.0,
+            bid_depth_sum: 0.0,
+            ask_depth_sum: 0.0,
+            oir_sum: 0.0,
+            oir_samples: 0,
+        })
+    }
+
+    /// Adds a book snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - Time of the snapshot.
+    /// * `bid` - Best bid price.
+    /// * `ask` - Best ask price.
+    /// * `bid_depth` - Bid quantity over the top levels.
+    /// * `ask_depth` - Ask quantity over the top levels.
+    ///
+    /// # Returns
+    ///
+    /// The completed bar if the snapshot starts a new one.
+    pub fn update(
+        &mut self,
+        timestamp: i64,
+        bid: f64,
+        ask: f64,
+        bid_depth: f64,
+        ask_depth: f64,
+    ) -> Option<MicrostructureBar> {
+        let start = timestamp - timestamp.rem_euclid(self.interval);
+        let completed = match self.current {
+            Some(bar) if bar.start != start => self.finish(),
+            _ => None,
+        };
+
+        let mid = TradingState::calculate_mid_price(bid, ask);
+        let bar = self.current.get_or_insert(MicrostructureBar {
+            start,
+            ohlc: Ohlc {
+                timestamp: start,
+                open: mid,
+                high: mid,
+                low: mid,
+                close: mid,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        bar.ohlc.high = bar.ohlc.high.max(mid);
+        bar.ohlc.low = bar.ohlc.low.min(mid);
+        bar.ohlc.close = mid;
+        bar.samples += 1;
+
+        self.spread_sum += TradingState::calculate_spread(bid, ask);
+        self.bid_depth_sum += bid_depth;
+        self.ask_depth_sum += ask_depth;
+        if bid_depth + ask_depth > 0.0 {
+            self.oir_sum += TradingState::calculate_oir(bid_depth, ask_depth);
+            self.oir_samples += 1;
+        }
+
+        completed
+    }
+
+    /// Adds a snapshot of an hftbacktest market depth, aggregating the top
+    /// `levels` price levels.
+    pub fn update_from_depth<MD: MarketDepth>(
+        &mut self,
+        timestamp: i64,
+        depth: &MD,
+        levels: usize,
+    ) -> Option<MicrostructureBar> {
+        let (bid_depth, ask_depth) = top_levels_depth(depth, levels);
+        self.update(
+            timestamp,
+            depth.best_bid(),
+            depth.best_ask(),
+            bid_depth,
+            ask_depth,
+        )
+    }
+
+    /// Adds an order book snapshot, aggregating the top `levels` price levels.
+    /// Books without a bid or an ask are ignored.
+    pub fn update_from_book(
+        &mut self,
+        timestamp: i64,
+        book: &OrderBook,
+        levels: usize,
+    ) -> Option<MicrostructureBar> {
+        let (bid, ask) = (book.best_bid()?, book.best_ask()?);
+        let (bid_depth, ask_depth) = book.depth(levels);
+        self.update(timestamp, bid.price, ask.price, bid_depth, ask_depth)
+    }
+
+    /// Completes and returns the bar in progress, if any.
+    pub fn finish(&mut self) -> Option<MicrostructureBar> {
+        let mut bar = self.current.take()?;
+        let samples = bar.samples as f64;
+        bar.avg_spread = self.spread_sum / samples;
+        bar.avg_bid_depth = self.bid_depth_sum / samples;
+        bar.avg_ask_depth = self.ask_depth_sum / samples;
+        bar.mean_oir = if self.oir_samples > 0 {
+            self.oir_sum / self.oir_samples as f64
+        } else {
+            f64::NAN
+        };
+
+        self.spread_sum = 0.0;
+        self.bid_depth_sum = 0.0;
+        self.ask_depth_sum = 0.0;
+        self.oir_sum = 0.0;
+        self.oir_samples = 0;
+
+        Some(bar)
+    }
+}
+
+/// Sums the bid and ask quantities within `levels` ticks of the best bid and
+/// ask.
+///
+/// # Returns
+///
+/// A tuple of (bid quantity, ask quantity).
+pub fn top_levels_depth<MD: MarketDepth>(depth: &MD, levels: usize) -> (f64, f64) {
+    let best_bid_tick = depth.best_bid_tick();
+    let best_ask_tick = depth.best_ask_tick();
+    let bid_depth = (0..levels as i64)
+        .map(|i| depth.bid_qty_at_tick(best_bid_tick - i))
+        .sum();
+    let ask_depth = (0..levels as i64)
+        .map(|i| depth.ask_qty_at_tick(best_ask_tick + i))
+        .sum();
+    (bid_depth, ask_depth)
+}
+
+/// Calculates the depth-weighted mid-price of the top `levels` ticks of each
+/// side, see `TradingState::calculate_weighted_mid`. With a single level it
+/// is the microprice.
+pub fn depth_weighted_mid<MD: MarketDepth>(depth: &MD, levels: usize) -> f64 {
+    let tick_size = depth.tick_size();
+    let best_bid_tick = depth.best_bid_tick();
+    let best_ask_tick = depth.best_ask_tick();
+    let bids: Vec<_> = (0..levels as i64)
+        .map(|i| {
+            let tick = best_bid_tick - i;
+            (tick as f64 * tick_size, depth.bid_qty_at_tick(tick))
+        })
+        .collect();
+    let asks: Vec<_> = (0..levels as i64)
+        .map(|i| {
+            let tick = best_ask_tick + i;
+            (tick as f64 * tick_size, depth.ask_qty_at_tick(tick))
+        })
+        .collect();
+    TradingState::calculate_weighted_mid(&bids, &asks)
+}
+
+/// Converts the top `levels` ticks of each side of an hftbacktest market
+/// depth into an order book, skipping empty ticks.
+pub fn order_book_from_depth<MD: MarketDepth>(
+    depth: &MD,
+    timestamp: i64,
+    levels: usize,
+) -> OrderBook {
+    let tick_size = depth.tick_size();
+    let level = |tick: i64, qty: f64| Level {
+        price: tick as f64 * tick_size,
+        qty,
+    };
+    let best_bid_tick = depth.best_bid_tick();
+    let best_ask_tick = depth.best_ask_tick();
+    let bids = (0..levels as i64)
+        .map(|i| best_bid_tick - i)
+        .map(|tick| level(tick, depth.bid_qty_at_tick(tick)))
+        .collect();
+    let asks = (0..levels as i64)
+        .map(|i| best_ask_tick + i)
+        .map(|tick| level(tick, depth.ask_qty_at_tick(tick)))
+        .collect();
+    OrderBook::new(timestamp, bids, asks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregates_features_per_bar() {
+        let mut aggregator = BookBarAggregator::new(1_000).unwrap();
+
+        assert_eq!(aggregator.update(0, 99.0, 101.0, 30.0, 10.0), None);
+        assert_eq!(aggregator.update(500, 101.0, 103.0, 10.0, 10.0), None);
+        let bar = aggregator.update(1_000, 98.0, 100.0, 5.0, 5.0).unwrap();
+
+        assert_eq!(bar.start, 0);
+        assert_eq!(bar.samples, 2);
+        assert_eq!(bar.ohlc.open, 100.0);
+        assert_eq!(bar.ohlc.high, 102.0);
+        assert_eq!(bar.ohlc.close, 102.0);
+        assert_eq!(bar.avg_bid_depth, 20.0);
+        assert_eq!(bar.avg_ask_depth, 10.0);
+        assert_eq!(bar.mean_oir, 0.25);
+
+        let last = aggregator.finish().unwrap();
+        assert_eq!(last.start, 1_000);
+        assert_eq!(last.ohlc.low, 99.0);
+        assert_eq!(aggregator.finish(), None);
+
+        // Empty books leave the imbalance out of the mean
+        aggregator.update(2_000, 99.0, 101.0, 30.0, 10.0);
+        aggregator.update(2_500, 99.0, 101.0, 0.0, 0.0);
+        assert_eq!(aggregator.finish().unwrap().mean_oir, 0.5);
+        aggregator.update(3_000, 99.0, 101.0, 0.0, 0.0);
+        assert!(aggregator.finish().unwrap().mean_oir.is_nan());
+
+        assert!(BookBarAggregator::new(0).is_err());
+        assert!(BookBarAggregator::new(-1_000).is_err());
+    }
+
+    #[test]
+    fn test_update_from_book() {
+        let mut aggregator = BookBarAggregator::new(1_000).unwrap();
+        let level = |price, qty| Level { price, qty };
+        let book = OrderBook::new(
+            0,
+            vec![level(99.0, 20.0), level(98.0, 10.0)],
+            vec![level(101.0, 10.0)],
+        );
+
+        assert_eq!(aggregator.update_from_book(0, &book, 5), None);
+        assert_eq!(
+            aggregator.update_from_book(100, &OrderBook::default(), 5),
+            None
+        );
+        let bar = aggregator.finish().unwrap();
+        assert_eq!(bar.samples, 1);
+        assert_eq!(bar.ohlc.close, 100.0);
+        assert_eq!(bar.avg_bid_depth, 30.0);
+        assert_eq!(bar.mean_oir, 0.5);
+    }
+}
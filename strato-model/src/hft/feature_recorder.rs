@@ -0,0 +1,218 @@
+/*!
+This module records the features and signals of the OIR model at every step
+of `exec_backtest_hft_oir`, so that the model can be studied and fitted
+outside the backtest loop.
+
+Every evaluated step produces one `FeatureRow` per asset, passed to a
+`FeatureSink`. Unlike the `Recorder` of the backtest, which samples the
+account every second, the features are recorded every 100ms step whose book
+has both sides.
+
+Sinks:
+- `CsvFeatureSink`: any `std::io::Write`.
+- `Vec<FeatureRow>`: keeps the rows in memory.
+- `ParquetFeatureSink`: a Parquet file, behind the `parquet` feature.
+*/
+
+use std::io::Write;
+
+/// The features and the signal of one asset at a step.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeatureRow {
+    /// Backtest timestamp in nanoseconds.
+    pub timestamp: i64,
+    pub asset_no: usize,
+    pub voi: f64,
+    pub oir: f64,
+    pub mpb: f64,
+    /// Bid-ask spread as a percentage of the bid.
+    pub spread: f64,
+    pub mid_price: f64,
+    /// Position before the signal is executed.
+    pub position: f64,
+    /// Signal acted upon, after the throttle and the costs: 1.0 for buy,
+    /// -1.0 for sell, 0.0 for hold.
+    pub signal: f64,
+}
+
+/// A destination for feature rows.
+pub trait FeatureSink {
+    /// Writes one row.
+    fn write(&mut self, row: &FeatureRow) -> anyhow::Result<()>;
+
+    /// Flushes buffered rows. Called at the end of the backtest.
+    fn flush(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+impl FeatureSink for Vec<FeatureRow> {
+    fn write(&mut self, row: &FeatureRow) -> anyhow::Result<()> {
+        self.push(*row);
+        Ok(())
+    }
+}
+
+/// Writes rows as CSV with a header line.
+pub struct CsvFeatureSink<W: Write> {
+    writer: W,
+    header_written: bool,
+}
+
+impl<W: Write> CsvFeatureSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header_written: false,
+        }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> FeatureSink for CsvFeatureSink<W> {
+    fn write(&mut self, row: &FeatureRow) -> anyhow::Result<()> {
+        if !self.header_written {
+            writeln!(
+                self.writer,
+                "timestamp,asset_no,voi,oir,mpb,spread,mid_price,position,signal"
+            )?;
+            self.header_written = true;
+        }
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{},{},{}",
+            row.timestamp,
+            row.asset_no,
+            row.voi,
+            row.oir,
+            row.mpb,
+            row.spread,
+            row.mid_price,
+            row.position,
+            row.signal
+        )?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parquet")]
+pub use self::parquet_sink::ParquetFeatureSink;
+
+#[cfg(feature = "parquet")]
+mod parquet_sink {
+    use std::fs::File;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use arrow::array::ArrayRef;
+    use arrow::array::Float64Array;
+    use arrow::array::Int64Array;
+    use arrow::array::UInt64Array;
+    use arrow::datatypes::DataType;
+    use arrow::datatypes::Field;
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::RecordBatch;
+    use parquet::arrow::ArrowWriter;
+
+    use super::FeatureRow;
+    use super::FeatureSink;
+
+    /// Buffers rows and writes them to a Parquet file on flush.
+    pub struct ParquetFeatureSink {
+        path: PathBuf,
+        rows: Vec<FeatureRow>,
+    }
+
+    impl ParquetFeatureSink {
+        pub fn new(path: impl Into<PathBuf>) -> Self {
+            Self {
+                path: path.into(),
+                rows: Vec::new(),
+            }
+        }
+
+        fn column(&self, f: impl Fn(&FeatureRow) -> f64) -> ArrayRef {
+            Arc::new(Float64Array::from_iter_values(self.rows.iter().map(f)))
+        }
+    }
+
+    impl FeatureSink for ParquetFeatureSink {
+        fn write(&mut self, row: &FeatureRow) -> anyhow::Result<()> {
+            self.rows.push(*row);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> anyhow::Result<()> {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("timestamp", DataType::Int64, false),
+                Field::new("asset_no", DataType::UInt64, false),
+                Field::new("voi", DataType::Float64, false),
+                Field::new("oir", DataType::Float64, false),
+                Field::new("mpb", DataType::Float64, false),
+                Field::new("spread", DataType::Float64, false),
+                Field::new("mid_price", DataType::Float64, false),
+                Field::new("position", DataType::Float64, false),
+                Field::new("signal", DataType::Float64, false),
+            ]));
+            let columns: Vec<ArrayRef> = vec![
+                Arc::new(Int64Array::from_iter_values(
+                    self.rows.iter().map(|r| r.timestamp),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    self.rows.iter().map(|r| r.asset_no as u64),
+                )),
+                self.column(|r| r.voi),
+                self.column(|r| r.oir),
+                self.column(|r| r.mpb),
+                self.column(|r| r.spread),
+                self.column(|r| r.mid_price),
+                self.column(|r| r.position),
+                self.column(|r| r.signal),
+            ];
+            let batch = RecordBatch::try_new(schema.clone(), columns)?;
+
+            let mut writer = ArrowWriter::try_new(File::create(&self.path)?, schema, None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_feature_sink() {
+        let mut sink = CsvFeatureSink::new(Vec::new());
+        let row = FeatureRow {
+            timestamp: 5,
+            voi: 2.0,
+            oir: 0.5,
+            mpb: -0.25,
+            spread: 0.1,
+            mid_price: 100.0,
+            signal: 1.0,
+            ..Default::default()
+        };
+        sink.write(&row).unwrap();
+        sink.flush().unwrap();
+
+        let csv = String::from_utf8(sink.into_inner()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("timestamp,asset_no,voi"));
+        assert_eq!(lines[1], "5,0,2,0.5,-0.25,0.1,100,0,1");
+    }
+}
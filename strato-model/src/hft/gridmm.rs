@@ -0,0 +1,433 @@
+/*!
+A high-frequency grid market maker.
+
+`levels` bids and `levels` asks rest around a fair price, the first ones
+`half_spread` away from it and the following ones `level_spacing` further
+each, all relative to the fair price. The inventory skews the whole grid with
+`calculate_relative_depths`: a long position moves both sides down so that
+the asks fill first, a short position moves them up. A side stops adding
+levels once their fills could take the position past `max_position`.
+
+The fair price is the mid-price or the microprice, the mid-price weighted by
+the quantities at the touch, which leans toward the side more likely to be
+taken out.
+*/
+
+use std::fmt::Debug;
+
+use hftbacktest::prelude::Bot;
+use hftbacktest::prelude::L2MarketDepth;
+use hftbacktest::prelude::MarketDepth;
+use hftbacktest::prelude::OrdType;
+use hftbacktest::prelude::Recorder;
+use hftbacktest::prelude::Side;
+use hftbacktest::prelude::TimeInForce;
+use strato_utils::progress::CancellationToken;
+use strato_utils::progress::NoProgress;
+use strato_utils::progress::ProgressTracker;
+use strato_utils::relative_depths::calculate_relative_depths;
+use strato_utils::vars::instrument::Instrument;
+
+use crate::hft::hft_oir::TradingState;
+use crate::hft::kill_switch::KillSwitch;
+use crate::hft::kill_switch::KillSwitchLimits;
+use crate::hft::toxicity::ToxicityParams;
+
+/// Interval between two quote updates, 100ms in nanoseconds.
+const STEP: i64 = 100_000_000;
+
+const DEFAULT_LEVELS: usize = 5;
+const DEFAULT_HALF_SPREAD: f64 = 0.0005;
+const DEFAULT_LEVEL_SPACING: f64 = 0.0005;
+const DEFAULT_SKEW: f64 = 0.0001;
+const DEFAULT_MAX_ORDERS: f64 = 20.0;
+
+/// The price the grid is centered on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FairPrice {
+    /// Average of the best bid and ask.
+    #[default]
+    Mid,
+    /// Best bid and ask weighted by the quantity of the opposite side.
+    Microprice,
+}
+
+impl FairPrice {
+    /// Calculates the fair price of a book.
+    ///
+    /// # Arguments
+    ///
+    /// * `best_bid` - Best bid price.
+    /// * `best_ask` - Best ask price.
+    /// * `bid_qty` - Quantity at the best bid.
+    /// * `ask_qty` - Quantity at the best ask.
+    pub fn price(&self, best_bid: f64, best_ask: f64, bid_qty: f64, ask_qty: f64) -> f64 {
+        match self {
+            FairPrice::Microprice => {
+                TradingState::calculate_microprice(best_bid, best_ask, bid_qty, ask_qty)
+            }
+            FairPrice::Mid => TradingState::calculate_mid_price(best_bid, best_ask),
+        }
+    }
+}
+
+/// Parameters of the grid market maker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridMmParams {
+    /// Number of bids and of asks.
+    pub levels: usize,
+    /// Distance of the first bid and ask from the fair price, relative to it.
+    pub half_spread: f64,
+    /// Distance between consecutive levels, relative to the fair price.
+    pub level_spacing: f64,
+    /// Shift of the grid, relative to the fair price, per `order_qty` of
+    /// inventory.
+    pub skew: f64,
+    /// Quantity of each order in base units.
+    pub order_qty: f64,
+    /// Largest absolute inventory in base units.
+    pub max_position: f64,
+    pub fair_price: FairPrice,
+    /// Loss, message rate and reject rate past which the orders are
+    /// cancelled, the position closed and the loop stopped.
+    pub kill_switch: KillSwitchLimits,
+    /// VPIN filter of the market trades, widening the half-spread in toxic
+    /// flow and pulling the grid when it is extreme. Off by default.
+    pub toxicity: Option<ToxicityParams>,
+}
+
+impl GridMmParams {
+    /// Creates a grid of five 5bp-spaced levels per side starting 5bp from the
+    /// mid-price, skewed by 1bp per order of inventory, with an inventory
+    /// limit of twenty orders.
+    pub fn new(order_qty: f64) -> Self {
+        Self {
+            levels: DEFAULT_LEVELS,
+            half_spread: DEFAULT_HALF_SPREAD,
+            level_spacing: DEFAULT_LEVEL_SPACING,
+            skew: DEFAULT_SKEW,
+            order_qty,
+            max_position: DEFAULT_MAX_ORDERS * order_qty,
+            fair_price: FairPrice::default(),
+            kill_switch: KillSwitchLimits::default(),
+            toxicity: None,
+        }
+    }
+
+    pub fn with_levels(mut self, levels: usize) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    pub fn with_half_spread(mut self, half_spread: f64) -> Self {
+        self.half_spread = half_spread;
+        self
+    }
+
+    pub fn with_level_spacing(mut self, level_spacing: f64) -> Self {
+        self.level_spacing = level_spacing;
+        self
+    }
+
+    pub fn with_skew(mut self, skew: f64) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    pub fn with_max_position(mut self, max_position: f64) -> Self {
+        self.max_position = max_position;
+        self
+    }
+
+    pub fn with_fair_price(mut self, fair_price: FairPrice) -> Self {
+        self.fair_price = fair_price;
+        self
+    }
+
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitchLimits) -> Self {
+        self.kill_switch = kill_switch;
+        self
+    }
+
+    pub fn with_toxicity(mut self, toxicity: ToxicityParams) -> Self {
+        self.toxicity = Some(toxicity);
+        self
+    }
+
+    /// Calculates the bid and ask prices of the grid.
+    ///
+    /// # Arguments
+    ///
+    /// * `fair_price` - The price the grid is centered on.
+    /// * `position` - Inventory in base units, negative when short.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the bids, in decreasing order, and the asks, in increasing
+    /// order. A side has fewer levels when its fills could exceed
+    /// `max_position`.
+    pub fn quotes(&self, fair_price: f64, position: f64) -> (Vec<f64>, Vec<f64>) {
+        let (bid_depth, ask_depth) =
+            calculate_relative_depths(self.half_spread, self.skew, position, self.order_qty);
+        // Number of whole orders fitting in `room`, up to `levels`
+        let levels =
+            |room: f64| ((room / self.order_qty + 1e-9).floor().max(0.0) as usize).min(self.levels);
+        let bids = (0..levels(self.max_position - position))
+            .map(|i| fair_price * (1.0 - bid_depth - i as f64 * self.level_spacing))
+            .collect();
+        let asks = (0..levels(self.max_position + position))
+            .map(|i| fair_price * (1.0 + ask_depth + i as f64 * self.level_spacing))
+            .collect();
+        (bids, asks)
+    }
+}
+
+/// Runs the grid market maker on asset 0 of `hbt`, recording every second.
+///
+/// Every 100ms the grid is recomputed from the book and the position. Its
+/// orders are posted as post-only limit orders, never through the touch, and
+/// the orders no longer on the grid are cancelled. With `params.toxicity`,
+/// the market trades feed a VPIN filter that widens the half-spread or pulls
+/// the whole grid in toxic flow. Once `params.kill_switch`
+/// trips, the orders are cancelled, the position is closed and the loop
+/// stops.
+///
+/// # Arguments
+///
+/// * `hbt` - The backtest.
+/// * `recorder` - Receives the state of the backtest every second.
+/// * `params` - The grid parameters.
+pub fn exec_backtest_gridmm<MD, I, R>(
+    hbt: &mut I,
+    recorder: &mut R,
+    params: &GridMmParams,
+) -> anyhow::Result<(), anyhow::Error>
+where
+    MD: L2MarketDepth + MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+    R: Recorder,
+    <R as Recorder>::Error: Debug,
+{
+    let mut reporter = NoProgress;
+    let mut progress = ProgressTracker::new(&mut reporter, CancellationToken::new(), None, 1);
+    exec_backtest_gridmm_with_progress(hbt, recorder, params, &mut progress)
+}
+
+/// Runs the grid market maker like `exec_backtest_gridmm`, reporting progress
+/// after every 100ms step and stopping early once the tracker's cancellation
+/// token is cancelled.
+///
+/// The label of each progress update is the current backtest timestamp.
+pub fn exec_backtest_gridmm_with_progress<MD, I, R>(
+    hbt: &mut I,
+    recorder: &mut R,
+    params: &GridMmParams,
+    progress: &mut ProgressTracker,
+) -> anyhow::Result<(), anyhow::Error>
+where
+    MD: L2MarketDepth + MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+    R: Recorder,
+    <R as Recorder>::Error: Debug,
+{
+    let asset_no = 0;
+    let mut next_order_id = 0;
+    let mut kill_switch = KillSwitch::new(params.kill_switch);
+    let mut toxicity = params
+        .toxicity
+        .map(|toxicity| toxicity.filter())
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("invalid toxicity filter: {}", e))?;
+    let mut int = 0;
+
+    while hbt
+        .elapse(STEP)
+        .map_err(|e| anyhow::anyhow!("elapse failed: {:?}", e))?
+    {
+        int += 1;
+        if int % 10 == 0 {
+            // Records every 1-sec
+            recorder
+                .record(hbt)
+                .map_err(|e| anyhow::anyhow!("record failed: {:?}", e))?;
+        }
+        if kill_switch.guard(hbt)? {
+            break;
+        }
+        hbt.clear_inactive_orders(Some(asset_no));
+        if let Some(filter) = toxicity.as_mut() {
+            filter.on_trades(hbt.last_trades(asset_no));
+            hbt.clear_last_trades(Some(asset_no));
+        }
+
+        let depth = hbt.depth(asset_no);
+        let (best_bid, best_ask) = (depth.best_bid(), depth.best_ask());
+        if best_bid.is_finite() && best_ask.is_finite() {
+            let fair_price = params.fair_price.price(
+                best_bid,
+                best_ask,
+                depth.bid_qty_at_tick(depth.best_bid_tick()),
+                depth.ask_qty_at_tick(depth.best_ask_tick()),
+            );
+            let mut grid = *params;
+            let half_spread = match toxicity.as_ref() {
+                Some(filter) => filter.adjust_half_spread(params.half_spread),
+                None => Some(params.half_spread),
+            };
+            // Pulled quotes leave an empty grid, cancelling the working orders
+            let (bids, asks) = match half_spread {
+                Some(half_spread) => {
+                    grid.half_spread = half_spread;
+                    grid.quotes(fair_price, hbt.position(asset_no))
+                }
+                None => (Vec::new(), Vec::new()),
+            };
+            sync_quotes(
+                hbt,
+                asset_no,
+                &bids,
+                &asks,
+                params.order_qty,
+                &mut next_order_id,
+            )?;
+        }
+
+        let now = hbt.current_timestamp();
+        if !progress.step_with(|| now.to_string()) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Makes the working orders of an asset match the given bids and asks.
+///
+/// The prices are rounded away from the touch to the tick and kept off the
+/// other side, so that the post-only orders are not rejected. Working orders
+/// at one of the resulting ticks are kept, the others cancelled, and the
+/// missing ticks get a new order of `qty` rounded to the lot size.
+pub(crate) fn sync_quotes<MD, I>(
+    hbt: &mut I,
+    asset_no: usize,
+    bids: &[f64],
+    asks: &[f64],
+    qty: f64,
+    next_order_id: &mut u64,
+) -> anyhow::Result<(), anyhow::Error>
+where
+    MD: L2MarketDepth + MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+{
+    let depth = hbt.depth(asset_no);
+    let instrument = Instrument::new("", depth.tick_size(), depth.lot_size());
+    let tick_size = instrument.tick_size;
+    let (best_bid_tick, best_ask_tick) = (depth.best_bid_tick(), depth.best_ask_tick());
+    let qty = instrument.round_qty(qty);
+    if qty <= 0.0 {
+        return Ok(());
+    }
+
+    let mut wanted: Vec<(Side, i64)> = bids
+        .iter()
+        .map(|bid| {
+            (
+                Side::Buy,
+                ((bid / tick_size).floor() as i64).min(best_bid_tick),
+            )
+        })
+        .chain(asks.iter().map(|ask| {
+            (
+                Side::Sell,
+                ((ask / tick_size).ceil() as i64).max(best_ask_tick),
+            )
+        }))
+        .collect();
+    wanted.dedup();
+
+    let mut stale = Vec::new();
+    for order in hbt.orders(asset_no).values() {
+        if !(order.active() || order.pending()) {
+            continue;
+        }
+        if let Some(i) = wanted
+            .iter()
+            .position(|&(side, tick)| side == order.side && tick == order.price_tick)
+        {
+            wanted.remove(i);
+        } else if order.cancellable() {
+            stale.push(order.order_id);
+        }
+    }
+    for order_id in stale {
+        hbt.cancel(asset_no, order_id, false)
+            .map_err(|e| anyhow::anyhow!("failed to cancel order: {:?}", e))?;
+    }
+
+    let (time_in_force, order_type, wait) = (TimeInForce::GTX, OrdType::Limit, false);
+    for (side, tick) in wanted {
+        let order_id = *next_order_id;
+        *next_order_id += 1;
+        let price = tick as f64 * tick_size;
+        match side {
+            Side::Buy => hbt.submit_buy_order(
+                asset_no,
+                order_id,
+                price,
+                qty,
+                time_in_force,
+                order_type,
+                wait,
+            ),
+            _ => hbt.submit_sell_order(
+                asset_no,
+                order_id,
+                price,
+                qty,
+                time_in_force,
+                order_type,
+                wait,
+            ),
+        }
+        .map_err(|e| anyhow::anyhow!("failed to submit order: {:?}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gridmm_quotes() {
+        let params = GridMmParams::new(1.0)
+            .with_levels(3)
+            .with_half_spread(0.01)
+            .with_level_spacing(0.01)
+            .with_skew(0.005)
+            .with_max_position(4.0);
+
+        let close = |a: &[f64], b: &[f64]| {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9)
+        };
+
+        let (bids, asks) = params.quotes(100.0, 0.0);
+        assert!(close(&bids, &[99.0, 98.0, 97.0]));
+        assert!(close(&asks, &[101.0, 102.0, 103.0]));
+
+        // Long 2: skewed down by 1%, with room for two more buys only
+        let (bids, asks) = params.quotes(100.0, 2.0);
+        assert!(close(&bids, &[98.0, 97.0]));
+        assert!(close(&asks, &[100.0, 101.0, 102.0]));
+        assert!(params.quotes(100.0, -4.0).1.is_empty());
+
+        assert_eq!(FairPrice::Mid.price(99.0, 101.0, 1.0, 3.0), 100.0);
+        assert_eq!(FairPrice::Microprice.price(99.0, 101.0, 1.0, 3.0), 99.5);
+        assert_eq!(FairPrice::Microprice.price(99.0, 101.0, 0.0, 0.0), 100.0);
+    }
+}
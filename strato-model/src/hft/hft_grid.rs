@@ -0,0 +1,310 @@
+/*!
+Runs the dynamic grid against an hftbacktest `Bot`.
+
+The candle executors of `grid::dynamic` fill an order as soon as a bar trades
+through its price. Here the orders of a `GridEngine` are posted as post-only
+limit orders to the backtest instead, so that they fill according to its
+queue position and latency models.
+
+Bars are built from the mid-price every `bar_interval` nanoseconds. At each
+bar close the moving average and the band unit are computed over the recent
+bars, the engine reprices its levels and the resulting placements and
+cancellations are sent to the `Bot`. Fills reported by the `Bot` are booked
+on the engine once their orders finish, partial fills of cancelled orders and
+late fills of replaced ones included. The risk rules and the recentering of
+the engine are not used.
+*/
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use hftbacktest::prelude::Bot;
+use hftbacktest::prelude::L2MarketDepth;
+use hftbacktest::prelude::MarketDepth;
+use hftbacktest::prelude::OrdType;
+use hftbacktest::prelude::Recorder;
+use hftbacktest::prelude::TimeInForce;
+use strato_utils::progress::CancellationToken;
+use strato_utils::progress::NoProgress;
+use strato_utils::progress::ProgressTracker;
+use strato_utils::vars::instrument::Instrument;
+use strato_utils::vars::ohlc::Ohlc;
+use strato_utils::vars::trade::Side;
+use tracing::debug;
+
+use crate::grid::dynamic::calculate_bands;
+use crate::grid::dynamic::GridParams;
+use crate::grid::dynamic::LevelSizing;
+use crate::grid::engine::GridEngine;
+use crate::grid::engine::GridEvent;
+use crate::grid::engine::GridOrder;
+use crate::hft::kill_switch::KillSwitch;
+use crate::hft::kill_switch::KillSwitchLimits;
+
+/// Interval between two steps of the backtest, 100ms in nanoseconds.
+const STEP: i64 = 100_000_000;
+
+/// Number of nanoseconds in a millisecond, converting hftbacktest timestamps
+/// to the Unix milliseconds of `Ohlc`.
+const NANOS_PER_MILLI: i64 = 1_000_000;
+
+/// Number of bars of history kept per bar of the longest average, enough for
+/// the recursive averages to forget their seed.
+const HISTORY_FACTOR: usize = 4;
+
+/// Parameters of the grid run against a `Bot`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HftGridParams {
+    pub grid: GridParams,
+    /// How the equity is split across the levels.
+    pub sizing: LevelSizing,
+    /// The capital the levels are sized from.
+    pub balance: f64,
+    /// Length of the bars in nanoseconds.
+    pub bar_interval: i64,
+    /// Loss, message rate and reject rate past which the orders are
+    /// cancelled, the position closed and the loop stopped.
+    pub kill_switch: KillSwitchLimits,
+}
+
+impl HftGridParams {
+    pub fn new(grid: GridParams, sizing: LevelSizing, balance: f64, bar_interval: i64) -> Self {
+        Self {
+            grid,
+            sizing,
+            balance,
+            bar_interval,
+            kill_switch: KillSwitchLimits::default(),
+        }
+    }
+
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitchLimits) -> Self {
+        self.kill_switch = kill_switch;
+        self
+    }
+}
+
+/// Runs the grid on asset 0 of `hbt`, recording every second.
+///
+/// Once `params.kill_switch` trips, the orders are cancelled, the position is
+/// closed and the loop stops.
+///
+/// # Arguments
+///
+/// * `hbt` - The backtest.
+/// * `recorder` - Receives the state of the backtest every second.
+/// * `params` - The grid, its sizing and the bar interval.
+pub fn exec_backtest_grid<MD, I, R>(
+    hbt: &mut I,
+    recorder: &mut R,
+    params: &HftGridParams,
+) -> anyhow::Result<(), anyhow::Error>
+where
+    MD: L2MarketDepth + MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+    R: Recorder,
+    <R as Recorder>::Error: Debug,
+{
+    let mut reporter = NoProgress;
+    let mut progress = ProgressTracker::new(&mut reporter, CancellationToken::new(), None, 1);
+    exec_backtest_grid_with_progress(hbt, recorder, params, &mut progress)
+}
+
+/// Runs the grid like `exec_backtest_grid`, reporting progress after every
+/// 100ms step and stopping early once the tracker's cancellation token is
+/// cancelled.
+///
+/// The label of each progress update is the current backtest timestamp.
+pub fn exec_backtest_grid_with_progress<MD, I, R>(
+    hbt: &mut I,
+    recorder: &mut R,
+    params: &HftGridParams,
+    progress: &mut ProgressTracker,
+) -> anyhow::Result<(), anyhow::Error>
+where
+    MD: L2MarketDepth + MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+    R: Recorder,
+    <R as Recorder>::Error: Debug,
+{
+    let (grid, bar_interval) = (&params.grid, params.bar_interval);
+    let mut kill_switch = KillSwitch::new(params.kill_switch);
+    let asset_no = 0;
+    let mut engine = GridEngine::new(grid.clone(), params.sizing, params.balance);
+    let history_len = HISTORY_FACTOR * grid.ma_len.max(grid.atr_len);
+    let mut bars: Vec<Ohlc> = Vec::with_capacity(history_len + 1);
+    let mut bar: Option<Ohlc> = None;
+    let mut bar_end = None;
+    // Orders the engine cancelled, kept until the `Bot` reports them finished
+    // so that their last fills are booked
+    let mut cancelled: HashMap<u64, GridOrder> = HashMap::new();
+    let mut int = 0;
+
+    while hbt
+        .elapse(STEP)
+        .map_err(|e| anyhow::anyhow!("elapse failed: {:?}", e))?
+    {
+        int += 1;
+        if int % 10 == 0 {
+            // Records every 1-sec
+            recorder
+                .record(hbt)
+                .map_err(|e| anyhow::anyhow!("record failed: {:?}", e))?;
+        }
+        if kill_switch.guard(hbt)? {
+            break;
+        }
+
+        // Book the fills, including the part of an order filled before it was
+        // cancelled, and forget the orders the exchange dropped. The orders
+        // are post-only, so they fill at their price.
+        let finished: Vec<_> = hbt
+            .orders(asset_no)
+            .values()
+            .filter(|order| !order.active() && !order.pending())
+            .map(|order| {
+                let price = order.price_tick as f64 * order.tick_size;
+                (order.order_id, price, order.qty - order.leaves_qty)
+            })
+            .collect();
+        for (order_id, price, qty) in finished {
+            let fill = match cancelled.remove(&order_id) {
+                Some(order) => (qty > 0.0).then(|| engine.fill_cancelled(order, price, qty)),
+                None if qty > 0.0 => engine.fill(order_id, price, qty),
+                None => {
+                    engine.remove(order_id);
+                    None
+                }
+            };
+            if let Some(fill) = fill {
+                debug!("Grid fill: {:?}", fill);
+            }
+        }
+        hbt.clear_inactive_orders(Some(asset_no));
+
+        let depth = hbt.depth(asset_no);
+        // Orders that are not a whole number of lots are rejected by the venue
+        let instrument = Instrument::new("", depth.tick_size(), depth.lot_size());
+        let mid_price = (depth.best_bid() + depth.best_ask()) / 2.0;
+        let now = hbt.current_timestamp();
+
+        if mid_price.is_finite() {
+            let candle = bar.get_or_insert(Ohlc {
+                timestamp: now / NANOS_PER_MILLI,
+                open: mid_price,
+                high: mid_price,
+                low: mid_price,
+                close: mid_price,
+                ..Default::default()
+            });
+            candle.high = candle.high.max(mid_price);
+            candle.low = candle.low.min(mid_price);
+            candle.close = mid_price;
+
+            let end = *bar_end.get_or_insert(now + bar_interval);
+            if now >= end {
+                bar_end = Some(end + bar_interval);
+                bars.extend(bar.take());
+                if bars.len() > history_len {
+                    bars.drain(..bars.len() - history_len);
+                }
+
+                let (ma_values, band_values) = calculate_bands(&bars, grid);
+                let events = engine.on_close(
+                    mid_price,
+                    ma_values.last().copied().unwrap_or(f64::NAN),
+                    band_values.last().copied().unwrap_or(f64::NAN),
+                );
+                for event in events {
+                    submit_event(
+                        hbt,
+                        asset_no,
+                        &instrument,
+                        &mut engine,
+                        &mut cancelled,
+                        event,
+                    )?;
+                }
+            }
+        }
+
+        // Cancel the orders the engine cancelled, again while an earlier cancel
+        // was rejected or sent before the order was acknowledged
+        cancelled.retain(|order_id, _| hbt.orders(asset_no).contains_key(order_id));
+        let retries: Vec<_> = cancelled
+            .keys()
+            .copied()
+            .filter(|order_id| hbt.orders(asset_no)[order_id].cancellable())
+            .collect();
+        for order_id in retries {
+            hbt.cancel(asset_no, order_id, false)
+                .map_err(|e| anyhow::anyhow!("failed to cancel order: {:?}", e))?;
+        }
+
+        if !progress.step_with(|| now.to_string()) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends an order change of the engine to the `Bot`.
+///
+/// An order rounding to no lot is taken back off the engine, and a cancelled
+/// order is added to `cancelled` until the `Bot` reports it finished. Its
+/// cancel is sent at the end of the step.
+fn submit_event<MD, I>(
+    hbt: &mut I,
+    asset_no: usize,
+    instrument: &Instrument,
+    engine: &mut GridEngine,
+    cancelled: &mut HashMap<u64, GridOrder>,
+    event: GridEvent,
+) -> anyhow::Result<(), anyhow::Error>
+where
+    MD: L2MarketDepth + MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+{
+    match event {
+        GridEvent::PlaceLimit(order) => {
+            let price = instrument.round_price(order.price);
+            let qty = instrument.round_qty(order.qty);
+            if qty <= 0.0 {
+                engine.remove(order.id);
+                return Ok(());
+            }
+            let (time_in_force, order_type, wait) = (TimeInForce::GTX, OrdType::Limit, false);
+            match order.side {
+                Side::Buy => hbt.submit_buy_order(
+                    asset_no,
+                    order.id,
+                    price,
+                    qty,
+                    time_in_force,
+                    order_type,
+                    wait,
+                ),
+                Side::Sell => hbt.submit_sell_order(
+                    asset_no,
+                    order.id,
+                    price,
+                    qty,
+                    time_in_force,
+                    order_type,
+                    wait,
+                ),
+            }
+            .map_err(|e| anyhow::anyhow!("failed to submit order: {:?}", e))?;
+        }
+        GridEvent::Cancel(order) => {
+            cancelled.insert(order.id, order);
+        }
+        GridEvent::Fill { .. } | GridEvent::Recenter(_) | GridEvent::Exit { .. } => {}
+    }
+    Ok(())
+}
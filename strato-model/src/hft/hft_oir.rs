@@ -1,15 +1,35 @@
+use std::collections::VecDeque;
 use std::fmt::Debug;
 
 use chrono::Utc;
 use hftbacktest::prelude::*;
+use hftbacktest::types::BUY_EVENT;
+use hftbacktest::types::SELL_EVENT;
+use strato_utils::progress::CancellationToken;
+use strato_utils::progress::NoProgress;
+use strato_utils::progress::ProgressTracker;
+use strato_utils::vars::instrument::Instrument;
 use tracing::debug;
 use tracing::error;
+use tracing::warn;
+
+use crate::grid::costs::ExecutionCosts;
+use crate::hft::book_bars::depth_weighted_mid;
+use crate::hft::book_bars::top_levels_depth;
+use crate::hft::feature_recorder::FeatureRow;
+use crate::hft::feature_recorder::FeatureSink;
+use crate::hft::kill_switch::KillSwitch;
+use crate::hft::kill_switch::KillSwitchLimits;
+use crate::hft::oir_calibration::OirCalibrator;
+use crate::hft::recorder::RecordRow;
+use crate::hft::toxicity::QuoteAction;
+use crate::hft::toxicity::ToxicityParams;
 
 /// The number of historical values (window size) to consider in the model. This
 /// parameter determines the depth of the historical data used to calculate the
 /// weighted sum of VOI, OIR, and MPB. According to the study, a window size of
 /// 5 provides a balance between responsiveness and stability in trading
-/// signals.
+/// signals. The default `ModelWeights` cover this many lags.
 pub const DEFAULT_K: usize = 5;
 
 /// The threshold value for decision making in the model. This parameter sets
@@ -19,121 +39,1387 @@ pub const DEFAULT_K: usize = 5;
 /// effect of VOI, OIR, and MPB.
 pub const DEFAULT_Q: f64 = 0.15;
 
-/// Future implementation for live trading
-// fn exec_live_trading() {}
+/// The number of price ticks of each side of the book whose quantities are
+/// summed into the bid and ask volumes. The study uses the best level only.
+pub const DEFAULT_DEPTH_LEVELS: usize = 1;
 
-pub fn exec_backtest_hft_oir<MD, I, R>(
+/// The number of steps whose traded volumes are summed into the Trade Flow
+/// Imbalance (TFI), one second of 100ms steps.
+pub const DEFAULT_TFI_WINDOW: usize = 10;
+
+/// Interval between two evaluations of the signal, 100ms in nanoseconds.
+const STEP: i64 = 100_000_000;
+
+/// Default time after which a working live order is cancelled, 5s in
+/// nanoseconds.
+const DEFAULT_ORDER_TIMEOUT: i64 = 5_000_000_000;
+
+/// Coefficients of the linear model, one per lag of each feature.
+///
+/// Index 0 of each vector weighs the current value, index 1 the previous one,
+/// and so on; the model keeps as many past values as the longest vector. The
+/// study fits these coefficients by regressing the future change of the
+/// mid-price on the lagged features, and the intercept `β0` on the constant.
+///
+/// The Trade Flow Imbalance is optional: it is left out of the model while
+/// `tfi` is empty, as it is by default.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModelWeights {
+    pub intercept: f64,
+    pub voi: Vec<f64>,
+    pub oir: Vec<f64>,
+    pub mpb: Vec<f64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tfi: Vec<f64>,
+}
+
+impl ModelWeights {
+    /// Weighs the last `k` values of every feature equally, without intercept.
+    pub fn uniform(k: usize) -> Self {
+        Self {
+            intercept: 0.0,
+            voi: vec![1.0; k],
+            oir: vec![1.0; k],
+            mpb: vec![1.0; k],
+            tfi: Vec::new(),
+        }
+    }
+
+    /// Adds the Trade Flow Imbalance to the model with one coefficient per
+    /// lag.
+    pub fn with_tfi(mut self, tfi: Vec<f64>) -> Self {
+        self.tfi = tfi;
+        self
+    }
+
+    /// Number of values of each feature the model depends on.
+    pub fn lags(&self) -> usize {
+        self.voi
+            .len()
+            .max(self.oir.len())
+            .max(self.mpb.len())
+            .max(self.tfi.len())
+    }
+
+    /// Weighted sum of the features, each history ordered from the oldest to
+    /// the current value. Lags missing from a history weigh nothing.
+    pub fn predict(
+        &self,
+        voi_history: &[f64],
+        oir_history: &[f64],
+        mpb_history: &[f64],
+        tfi_history: &[f64],
+    ) -> f64 {
+        let lagged = |weights: &[f64], history: &[f64]| -> f64 {
+            weights
+                .iter()
+                .zip(history.iter().rev())
+                .map(|(weight, value)| weight * value)
+                .sum()
+        };
+        self.intercept
+            + lagged(&self.voi, voi_history)
+            + lagged(&self.oir, oir_history)
+            + lagged(&self.mpb, mpb_history)
+            + lagged(&self.tfi, tfi_history)
+    }
+
+    /// The coefficient shared by all lags of each feature, in the order VOI,
+    /// OIR, MPB and TFI, or `None` for a feature whose lags weigh differently
+    /// or that has fewer coefficients than `lags`. A feature left out of the
+    /// model weighs zero.
+    fn uniform_lags(&self) -> [Option<f64>; 4] {
+        let lags = self.lags();
+        [&self.voi, &self.oir, &self.mpb, &self.tfi].map(|weights| match weights.first() {
+            None => Some(0.0),
+            Some(&weight) => {
+                (weights.len() == lags && weights.iter().all(|w| *w == weight)).then_some(weight)
+            }
+        })
+    }
+}
+
+impl Default for ModelWeights {
+    fn default() -> Self {
+        Self::uniform(DEFAULT_K)
+    }
+}
+
+/// How the OIR backtest executes the signals of the model.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OirExecution {
+    /// A fill-or-kill market order at the last price on every signal.
+    #[default]
+    Market,
+    /// A post-only limit order joining the best bid for a buy signal, or the
+    /// best ask for a sell signal, so that it rests in the book and earns the
+    /// spread instead of crossing it.
+    ///
+    /// An order still working after `timeout` nanoseconds is cancelled and
+    /// posted again at the touch of the moment, chasing the price up to
+    /// `max_chases` times after the last signal. An opposite signal cancels
+    /// the working order and chases the new side instead.
+    Passive { timeout: i64, max_chases: usize },
+}
+
+/// Limits on how often the signals of the OIR model are acted upon, which
+/// keeps a weighted sum oscillating around `±q` from churning orders. Both
+/// are off by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignalThrottle {
+    /// Nanoseconds after a fill during which signals are ignored.
+    pub cooldown: i64,
+    /// Nanoseconds a position is held before a signal against it may reduce
+    /// or reverse it. Signals adding to the position are not held back.
+    pub min_hold: i64,
+}
+
+impl SignalThrottle {
+    pub fn new(cooldown: i64, min_hold: i64) -> Self {
+        Self { cooldown, min_hold }
+    }
+}
+
+/// Fills of an asset as seen by a `SignalThrottle`, followed from the
+/// changes of its position.
+#[derive(Debug, Clone, Copy, Default)]
+struct ThrottleState {
+    position: f64,
+    last_fill: Option<i64>,
+    opened_at: Option<i64>,
+}
+
+impl ThrottleState {
+    fn new(position: f64) -> Self {
+        Self {
+            position,
+            ..Default::default()
+        }
+    }
+
+    /// Records a change of the position as a fill at `now`, and a position
+    /// opened from flat or reversed as opened at `now`.
+    fn update(&mut self, now: i64, position: f64) {
+        if position == self.position {
+            return;
+        }
+        self.last_fill = Some(now);
+        if position * self.position <= 0.0 {
+            self.opened_at = (position != 0.0).then_some(now);
+        }
+        self.position = position;
+    }
+
+    /// Passes `signal` through, or `0.0` when the throttle holds it back.
+    fn filter(&self, throttle: &SignalThrottle, now: i64, signal: f64) -> f64 {
+        let cooling = self
+            .last_fill
+            .is_some_and(|fill| now - fill < throttle.cooldown);
+        let holding = signal * self.position < 0.0
+            && self
+                .opened_at
+                .is_some_and(|opened| now - opened < throttle.min_hold);
+        if cooling || holding {
+            0.0
+        } else {
+            signal
+        }
+    }
+}
+
+/// Parameters of the OIR backtest.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OirBacktestParams {
+    /// Quantity of each order in base units, rounded to the lot size.
+    pub order_qty: f64,
+    /// Ticks of each side summed into the volumes, see
+    /// `DEFAULT_DEPTH_LEVELS`.
+    pub depth_levels: usize,
+    /// How the signals are executed.
+    pub execution: OirExecution,
+    /// Initial coefficients of the model of every asset.
+    pub weights: ModelWeights,
+    /// Threshold of the model, see `DEFAULT_Q`.
+    pub q: f64,
+    /// Cooldown after fills and minimum holding period of the positions.
+    pub throttle: SignalThrottle,
+    /// Fees and slippage a signal must be expected to earn back, see
+    /// `TradingState::covers_costs`. Market orders pay the taker fee and the
+    /// slippage, passive orders the maker fee.
+    pub costs: ExecutionCosts,
+    /// Loss, message rate and reject rate past which the orders are
+    /// cancelled, the positions closed and the loop stopped.
+    pub kill_switch: KillSwitchLimits,
+    /// VPIN filter of the market trades; the signals are ignored while it
+    /// would pull the quotes of a market maker. Off by default.
+    pub toxicity: Option<ToxicityParams>,
+    /// Inventory, notional and drawdown limits of the `TradingState` of every
+    /// asset. The signals they refuse are ignored, and the trades they force
+    /// are sent at market. Off by default.
+    pub limits: RiskLimits,
+}
+
+impl OirBacktestParams {
+    /// Creates parameters trading the default model with market orders.
+    pub fn new(order_qty: f64) -> Self {
+        Self {
+            order_qty,
+            depth_levels: DEFAULT_DEPTH_LEVELS,
+            execution: OirExecution::Market,
+            weights: ModelWeights::default(),
+            q: DEFAULT_Q,
+            throttle: SignalThrottle::default(),
+            costs: ExecutionCosts::default(),
+            kill_switch: KillSwitchLimits::default(),
+            toxicity: None,
+            limits: RiskLimits::default(),
+        }
+    }
+
+    pub fn with_depth_levels(mut self, depth_levels: usize) -> Self {
+        self.depth_levels = depth_levels;
+        self
+    }
+
+    pub fn with_execution(mut self, execution: OirExecution) -> Self {
+        self.execution = execution;
+        self
+    }
+
+    pub fn with_weights(mut self, weights: ModelWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    pub fn with_q(mut self, q: f64) -> Self {
+        self.q = q;
+        self
+    }
+
+    pub fn with_throttle(mut self, throttle: SignalThrottle) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    pub fn with_costs(mut self, costs: ExecutionCosts) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitchLimits) -> Self {
+        self.kill_switch = kill_switch;
+        self
+    }
+
+    pub fn with_toxicity(mut self, toxicity: ToxicityParams) -> Self {
+        self.toxicity = Some(toxicity);
+        self
+    }
+
+    pub fn with_limits(mut self, limits: RiskLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// Parameters of the live OIR strategy.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LiveOirParams {
+    /// Quantity of each order in base units, rounded to the lot size.
+    pub order_qty: f64,
+    /// Largest absolute position in base units: a buy signal trades toward
+    /// `max_position` and a sell signal toward `-max_position`.
+    pub max_position: f64,
+    /// Nanoseconds after which an order that has not fully filled is
+    /// cancelled.
+    pub order_timeout: i64,
+    /// Coefficients of the model.
+    pub weights: ModelWeights,
+    /// Threshold of the model, see `DEFAULT_Q`.
+    pub q: f64,
+    /// Ticks of each side summed into the volumes, see
+    /// `DEFAULT_DEPTH_LEVELS`.
+    pub depth_levels: usize,
+    /// Cooldown after fills and minimum holding period of the position.
+    pub throttle: SignalThrottle,
+    /// Fees and slippage a signal must be expected to earn back, see
+    /// `TradingState::covers_costs`. Orders at the opposite touch pay the
+    /// taker fee and the slippage.
+    pub costs: ExecutionCosts,
+    /// Loss, message rate and reject rate past which the orders are
+    /// cancelled, the position closed and the loop stopped.
+    pub kill_switch: KillSwitchLimits,
+    /// VPIN filter of the market trades; the signals are ignored while it
+    /// would pull the quotes of a market maker. Off by default.
+    pub toxicity: Option<ToxicityParams>,
+}
+
+impl LiveOirParams {
+    /// Creates parameters holding at most one order of position, with the
+    /// default model and a 5s order timeout.
+    pub fn new(order_qty: f64) -> Self {
+        Self {
+            order_qty,
+            max_position: order_qty,
+            order_timeout: DEFAULT_ORDER_TIMEOUT,
+            weights: ModelWeights::default(),
+            q: DEFAULT_Q,
+            depth_levels: DEFAULT_DEPTH_LEVELS,
+            throttle: SignalThrottle::default(),
+            costs: ExecutionCosts::default(),
+            kill_switch: KillSwitchLimits::default(),
+            toxicity: None,
+        }
+    }
+
+    pub fn with_max_position(mut self, max_position: f64) -> Self {
+        self.max_position = max_position;
+        self
+    }
+
+    pub fn with_order_timeout(mut self, order_timeout: i64) -> Self {
+        self.order_timeout = order_timeout;
+        self
+    }
+
+    pub fn with_model(mut self, weights: ModelWeights, q: f64) -> Self {
+        self.weights = weights;
+        self.q = q;
+        self
+    }
+
+    pub fn with_depth_levels(mut self, depth_levels: usize) -> Self {
+        self.depth_levels = depth_levels;
+        self
+    }
+
+    pub fn with_throttle(mut self, throttle: SignalThrottle) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    pub fn with_costs(mut self, costs: ExecutionCosts) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitchLimits) -> Self {
+        self.kill_switch = kill_switch;
+        self
+    }
+
+    pub fn with_toxicity(mut self, toxicity: ToxicityParams) -> Self {
+        self.toxicity = Some(toxicity);
+        self
+    }
+}
+
+/// Trades the OIR strategy on asset 0 of a live `Bot`, e.g. an hftbacktest
+/// `LiveBot` connected to an exchange connector.
+///
+/// Every 100ms the bid and ask volumes of the top `depth_levels` ticks, their
+/// depth-weighted mid-price and the last trade price feed the same model as
+/// the backtest. While no order is
+/// working, a buy or sell signal that passes `throttle` sends a limit order at
+/// the opposite touch for up to `order_qty` toward the position limit. Orders
+/// still working after `order_timeout` are cancelled. With `toxicity`, no
+/// order is sent while the VPIN of the market trades is extreme.
+///
+/// The position is tracked from the fills of the strategy's own orders and
+/// reconciled with the position reported by the venue whenever no order is
+/// in flight: a mismatch, e.g. after a manual trade or a missed fill, is
+/// logged and the venue's position is adopted.
+///
+/// Runs until the `Bot` stops, the tracker's cancellation token is cancelled
+/// or `params.kill_switch` trips, reporting progress after every step
+/// labelled with the current timestamp. Working orders are cancelled on the
+/// way out, and the position is closed too when the kill switch trips.
+pub fn exec_live_trading<MD, I>(
     hbt: &mut I,
-    recorder: &mut R,
-    order_qty: f64,
+    params: &LiveOirParams,
+    progress: &mut ProgressTracker,
 ) -> anyhow::Result<(), anyhow::Error>
 where
     MD: L2MarketDepth + MarketDepth,
     I: Bot<MD>,
     <I as Bot<MD>>::Error: Debug,
-    R: Recorder,
-    <R as Recorder>::Error: Debug,
 {
-    let mut int = 0;
-    let mut trading_state = TradingState::new();
+    let asset_no = 0;
+    let mut trading_state = TradingState::new().with_weights(params.weights.clone());
+    let mut position = hbt.position(asset_no);
+    let mut throttle = ThrottleState::new(position);
+    let mut last_price = f64::NAN;
+    // Identifiers must not collide with the orders of a previous session
+    let mut next_order_id = hbt.current_timestamp() as u64;
+    let mut kill_switch = KillSwitch::new(params.kill_switch);
+    let mut toxicity = params
+        .toxicity
+        .map(|toxicity| toxicity.filter())
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("invalid toxicity filter: {}", e))?;
 
-    // 100ms
-    while hbt.elapse(100_000_000).unwrap() {
-        int += 1;
-        if int % 10 == 0 {
-            // Records every 1-sec
-            recorder.record(hbt).unwrap();
+    while hbt
+        .elapse(STEP)
+        .map_err(|e| anyhow::anyhow!("elapse failed: {:?}", e))?
+    {
+        if progress.is_cancelled() || kill_switch.guard(hbt)? {
+            break;
         }
+        let now = hbt.current_timestamp();
+        let trades = hbt.last_trades(asset_no);
+        if let Some(trade) = trades.last() {
+            last_price = trade.px;
+        }
+        trading_state.add_trade_flow(signed_volume(trades));
+        let toxic = toxicity
+            .as_mut()
+            .is_some_and(|filter| filter.on_trades(trades) == QuoteAction::Pull);
+        hbt.clear_last_trades(Some(asset_no));
 
-        // --- Generate signal from trading strategy ---
-        let depth = hbt.depth(0);
+        // Book the finished orders and cancel the stale ones
+        let mut expired = Vec::new();
+        let mut working = false;
+        for order in hbt.orders(asset_no).values() {
+            if order.active() || order.pending() {
+                working = true;
+                if order.cancellable() && now - order.local_timestamp > params.order_timeout {
+                    expired.push(order.order_id);
+                }
+            } else {
+                // The whole quantity executed, also when cancelled after
+                // partial fills
+                position += order_sign(order.side) * (order.qty - order.leaves_qty);
+            }
+        }
+        for order_id in expired {
+            debug!("Cancelling order {} after the timeout", order_id);
+            hbt.cancel(asset_no, order_id, false)
+                .map_err(|e| anyhow::anyhow!("failed to cancel order: {:?}", e))?;
+        }
+        hbt.clear_inactive_orders(Some(asset_no));
 
-        let last_price = 0.0; // Get from market feed or historical data
-        let mid_price = (depth.best_bid() + depth.best_ask()) / 2.0;
+        let depth = hbt.depth(asset_no);
+        let instrument = Instrument::new("", depth.tick_size(), depth.lot_size());
+        if !working {
+            let venue_position = hbt.position(asset_no);
+            if (venue_position - position).abs() >= instrument.lot_size / 2.0 {
+                warn!(
+                    "Position mismatch: expected {}, venue reports {}",
+                    position, venue_position
+                );
+            }
+            position = venue_position;
+        }
+        throttle.update(now, position);
 
-        let bid_volume = 0.0;
-        let ask_volume = 0.0;
+        let (best_bid, best_ask) = (depth.best_bid(), depth.best_ask());
+        let (bid_volume, ask_volume) = top_levels_depth(depth, params.depth_levels);
+        if !(best_bid.is_finite() && best_ask.is_finite()) || bid_volume + ask_volume <= 0.0 {
+            if !progress.step_with(|| now.to_string()) {
+                break;
+            }
+            continue;
+        }
+        let mid_price = TradingState::calculate_mid_price(best_bid, best_ask);
+        if last_price.is_nan() {
+            last_price = mid_price;
+        }
 
-        let current_voi = TradingState::calculate_voi(bid_volume, ask_volume);
-        let current_oir = TradingState::calculate_oir(bid_volume, ask_volume);
-        let current_mpb = TradingState::calculate_mpb(last_price, mid_price);
-
-        let signal = trading_state.parametrized_linear_model(
-            current_voi,
-            current_oir,
-            current_mpb,
-            Some(DEFAULT_K),
-            Some(DEFAULT_Q),
+        let reference_price = depth_weighted_mid(depth, params.depth_levels);
+        let signal = trading_state.signal(
+            bid_volume,
+            ask_volume,
+            last_price,
+            reference_price,
+            params.q,
         );
-        // ---
-
-        let asset_no = 0;
-        let order_id = 0;
-        let price = last_price;
-        let time_in_force = TimeInForce::FOK; // Could prevent any order from being executed
-        let order_type = OrdType::Market;
-        let wait = true;
-        let mut result = false;
-
-        // Use the signal to open a position. We might have to close any current
-        // position before opening a new one that is if the current position is
-        // the opposite of the signal
-        if signal == 1.0 {
-            result = hbt
-                .submit_buy_order(
+        let signal = throttle.filter(&params.throttle, now, signal);
+        let signal = if !toxic
+            && trading_state.covers_costs(&params.costs, mid_price, params.order_qty, false)
+        {
+            signal
+        } else {
+            0.0
+        };
+
+        let target = signal * params.max_position;
+        let qty = instrument.round_qty((target - position).abs().min(params.order_qty));
+        if !working && signal != 0.0 && qty > 0.0 {
+            let (time_in_force, order_type, wait) = (TimeInForce::GTC, OrdType::Limit, false);
+            let order_id = next_order_id;
+            next_order_id += 1;
+            if target > position {
+                hbt.submit_buy_order(
                     asset_no,
                     order_id,
-                    price,
-                    order_qty,
+                    best_ask,
+                    qty,
                     time_in_force,
                     order_type,
                     wait,
                 )
-                .expect("Failed to submit buy order");
-        } else if signal == -1.0 {
-            result = hbt
-                .submit_sell_order(
+            } else {
+                hbt.submit_sell_order(
                     asset_no,
                     order_id,
-                    price,
-                    order_qty,
+                    best_bid,
+                    qty,
                     time_in_force,
                     order_type,
                     wait,
                 )
-                .expect("Failed to submit sell order");
+            }
+            .map_err(|e| anyhow::anyhow!("failed to submit order: {:?}", e))?;
         }
 
-        if !result {
-            error!("Failed to submit order");
+        if !progress.step_with(|| now.to_string()) {
+            break;
         }
     }
 
+    let working: Vec<_> = hbt
+        .orders(asset_no)
+        .values()
+        .filter(|order| order.cancellable())
+        .map(|order| order.order_id)
+        .collect();
+    for order_id in working {
+        hbt.cancel(asset_no, order_id, false)
+            .map_err(|e| anyhow::anyhow!("failed to cancel order: {:?}", e))?;
+    }
+
     Ok(())
 }
 
+/// Execution state of an asset.
+#[derive(Debug, Clone, Copy, Default)]
+struct Chase {
+    /// The side being chased and the number of orders it may still post,
+    /// cleared once an order fills or the chases are exhausted.
+    target: Option<(Side, usize)>,
+    /// Identifier of the next order of the asset, passive or market.
+    next_order_id: u64,
+}
+
+/// Executes the signals of the OIR model with post-only limit orders, see
+/// `OirExecution::Passive`.
+fn chase_passive<MD, I>(
+    hbt: &mut I,
+    asset_no: usize,
+    signal: f64,
+    order_qty: f64,
+    timeout: i64,
+    max_chases: usize,
+    chase: &mut Chase,
+) -> anyhow::Result<(), anyhow::Error>
+where
+    MD: MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+{
+    let now = hbt.current_timestamp();
+    let mut working = false;
+    let mut cancellable = Vec::new();
+    for order in hbt.orders(asset_no).values() {
+        if order.active() || order.pending() {
+            working = true;
+            if order.cancellable() {
+                cancellable.push((order.order_id, order.side, order.local_timestamp));
+            }
+        }
+    }
+
+    if signal == 1.0 {
+        chase.target = Some((Side::Buy, max_chases + 1));
+    } else if signal == -1.0 {
+        chase.target = Some((Side::Sell, max_chases + 1));
+    }
+
+    if working {
+        let chased = chase.target.map(|(side, _)| match side {
+            Side::Buy => 1.0,
+            Side::Sell => -1.0,
+        });
+        for (order_id, side, submitted) in cancellable {
+            if chased != Some(order_sign(side)) || now - submitted > timeout {
+                debug!("Cancelling order {} to chase the touch", order_id);
+                hbt.cancel(asset_no, order_id, false)
+                    .map_err(|e| anyhow::anyhow!("failed to cancel order: {:?}", e))?;
+            }
+        }
+        return Ok(());
+    }
+
+    let Some((side, left)) = chase.target.as_mut() else {
+        return Ok(());
+    };
+    if *left == 0 {
+        chase.target = None;
+        return Ok(());
+    }
+    *left -= 1;
+    let side = *side;
+    let order_id = chase.next_order_id;
+    chase.next_order_id += 1;
+    let depth = hbt.depth(asset_no);
+    match side {
+        Side::Buy => hbt.submit_buy_order(
+            asset_no,
+            order_id,
+            depth.best_bid(),
+            order_qty,
+            TimeInForce::GTX,
+            OrdType::Limit,
+            false,
+        ),
+        Side::Sell => hbt.submit_sell_order(
+            asset_no,
+            order_id,
+            depth.best_ask(),
+            order_qty,
+            TimeInForce::GTX,
+            OrdType::Limit,
+            false,
+        ),
+    }
+    .map_err(|e| anyhow::anyhow!("failed to submit order: {:?}", e))?;
+    Ok(())
+}
+
+/// Sends a market order of the OIR backtest, filled in full or not at all,
+/// and waits for its response. The order takes the next identifier of the
+/// asset, as the `Bot` refuses an identifier it still holds.
+fn submit_market<MD, I>(
+    hbt: &mut I,
+    asset_no: usize,
+    side: Side,
+    price: f64,
+    qty: f64,
+    next_order_id: &mut u64,
+) -> anyhow::Result<(), anyhow::Error>
+where
+    MD: MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+{
+    let order_id = *next_order_id;
+    *next_order_id += 1;
+    let time_in_force = TimeInForce::FOK; // Could prevent any order from being executed
+    let order_type = OrdType::Market;
+    let wait = true;
+    let responded = match side {
+        Side::Buy => hbt.submit_buy_order(
+            asset_no,
+            order_id,
+            price,
+            qty,
+            time_in_force,
+            order_type,
+            wait,
+        ),
+        Side::Sell => hbt.submit_sell_order(
+            asset_no,
+            order_id,
+            price,
+            qty,
+            time_in_force,
+            order_type,
+            wait,
+        ),
+    }
+    .map_err(|e| anyhow::anyhow!("failed to submit order: {:?}", e))?;
+    if !responded {
+        error!("Failed to submit order on asset {}", asset_no);
+    }
+    Ok(())
+}
+
+/// Volume of the buyer-initiated trades less the volume of the
+/// seller-initiated ones.
+fn signed_volume(trades: &[Event]) -> f64 {
+    trades
+        .iter()
+        .map(|trade| {
+            if trade.ev & BUY_EVENT != 0 {
+                trade.qty
+            } else if trade.ev & SELL_EVENT != 0 {
+                -trade.qty
+            } else {
+                0.0
+            }
+        })
+        .sum()
+}
+
+/// `1.0` for buy orders and `-1.0` for sell orders of a `Bot`.
+fn order_sign(side: hftbacktest::prelude::Side) -> f64 {
+    match side {
+        hftbacktest::prelude::Side::Buy => 1.0,
+        _ => -1.0,
+    }
+}
+
+/// Books the executed quantity of the finished orders of an asset into its
+/// trading state, paying the fees of `costs`, and forgets the orders. Limit
+/// orders are post-only, so they fill at their price.
+///
+/// # Returns
+///
+/// Whether an order filled in full.
+fn book_fills<MD, I>(
+    hbt: &mut I,
+    asset_no: usize,
+    state: &mut TradingState,
+    costs: &ExecutionCosts,
+) -> bool
+where
+    MD: MarketDepth,
+    I: Bot<MD>,
+{
+    let mut filled = false;
+    for order in hbt.orders(asset_no).values() {
+        if order.active() || order.pending() {
+            continue;
+        }
+        filled |= order.status == Status::Filled;
+        let qty = order.qty - order.leaves_qty;
+        if qty <= 0.0 {
+            continue;
+        }
+        let maker = order.order_type == OrdType::Limit;
+        let tick = if maker {
+            order.price_tick
+        } else {
+            order.exec_price_tick
+        };
+        let price = tick as f64 * order.tick_size;
+        let side = if order_sign(order.side) > 0.0 {
+            Side::Buy
+        } else {
+            Side::Sell
+        };
+        let fee = costs.fees.fee(price * qty, maker) / (price * qty);
+        state.book_trade(price, side, qty, fee);
+    }
+    hbt.clear_inactive_orders(Some(asset_no));
+    filled
+}
+
+/// Final state of the assets of an OIR backtest.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OirBacktestSummary {
+    /// State of each asset at the end of the backtest, indexed by asset
+    /// number.
+    pub assets: Vec<RecordRow>,
+    /// Profit and loss of each asset as accounted by its `TradingState` from
+    /// the fills, marked to its last mid-price and net of the fees of
+    /// `OirBacktestParams::costs`.
+    pub strategy_pnl: Vec<f64>,
+}
+
+impl OirBacktestSummary {
+    /// Profit and loss of each asset, marked to its last mid-price and net of
+    /// fees.
+    pub fn asset_pnl(&self) -> Vec<f64> {
+        self.assets.iter().map(RecordRow::equity).collect()
+    }
+
+    /// Profit and loss summed over the assets.
+    pub fn pnl(&self) -> f64 {
+        self.assets.iter().map(RecordRow::equity).sum()
+    }
+}
+
+/// Backtests the OIR strategy on every asset of `hbt`, recording every
+/// second.
+///
+/// Each asset is traded independently with its own `TradingState`. The
+/// volumes are those of the best bid and ask, and the last price is the
+/// price of the last trade of the feed, or the mid-price until the first
+/// trade. The backtest must keep the last trades, e.g. with
+/// `AssetBuilder::last_trades_capacity`, for the last price to follow the
+/// feed.
+///
+/// # Returns
+///
+/// The final state of each asset, whose PnL is aggregated by
+/// `OirBacktestSummary::pnl`.
+pub fn exec_backtest_hft_oir<MD, I, R>(
+    hbt: &mut I,
+    recorder: &mut R,
+    order_qty: f64,
+) -> anyhow::Result<OirBacktestSummary, anyhow::Error>
+where
+    MD: L2MarketDepth + MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+    R: Recorder,
+    <R as Recorder>::Error: Debug,
+{
+    let mut reporter = NoProgress;
+    let mut progress = ProgressTracker::new(&mut reporter, CancellationToken::new(), None, 1);
+    exec_backtest_hft_oir_with_progress(
+        hbt,
+        recorder,
+        &OirBacktestParams::new(order_qty),
+        &mut [],
+        None,
+        &mut progress,
+    )
+}
+
+/// Runs the OIR backtest like `exec_backtest_hft_oir`, reporting progress
+/// after every 100ms step and stopping early once the tracker's cancellation
+/// token is cancelled.
+///
+/// The volumes are summed over the top `params.depth_levels` ticks of each
+/// side of the book, and the signals are executed as set by
+/// `params.execution`, once past `params.throttle`, within `params.limits`
+/// and, with `params.toxicity`, outside of toxic flow. The trades the limits
+/// force, e.g. flattening after the drawdown limit, are sent at market. The
+/// label of each progress update is the current backtest timestamp.
+///
+/// The model of every asset starts with `params.weights`. The calibrator at
+/// index `asset_no` of `calibrators`, if any, is fed the features and the
+/// mid-price of that asset every step, and the asset's model switches to the
+/// coefficients it fits as soon as they are refitted; the fits over time are
+/// kept by the calibrator. Assets without a calibrator keep the initial
+/// weights.
+///
+/// With a `features` sink, the features, spread, mid-price, position and
+/// signal of every asset are written to it at every step the signal is
+/// evaluated, and the sink is flushed at the end.
+///
+/// The fills of every asset are booked into its `TradingState` at the fees
+/// of `params.costs`, and the PnL it accounts is reported alongside the
+/// venue's in `OirBacktestSummary::strategy_pnl`.
+///
+/// The backtest stops early, with the orders of every asset cancelled and the
+/// positions closed, once `params.kill_switch` trips.
+pub fn exec_backtest_hft_oir_with_progress<MD, I, R>(
+    hbt: &mut I,
+    recorder: &mut R,
+    params: &OirBacktestParams,
+    calibrators: &mut [OirCalibrator],
+    mut features: Option<&mut dyn FeatureSink>,
+    progress: &mut ProgressTracker,
+) -> anyhow::Result<OirBacktestSummary, anyhow::Error>
+where
+    MD: L2MarketDepth + MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+    R: Recorder,
+    <R as Recorder>::Error: Debug,
+{
+    let num_assets = hbt.num_assets();
+    let mut int = 0;
+    let mut trading_states: Vec<_> = (0..num_assets)
+        .map(|_| {
+            TradingState::new()
+                .with_weights(params.weights.clone())
+                .with_limits(params.limits)
+        })
+        .collect();
+    let mut last_trades = vec![f64::NAN; num_assets];
+    let mut chases = vec![Chase::default(); num_assets];
+    let mut throttles: Vec<_> = (0..num_assets)
+        .map(|asset_no| ThrottleState::new(hbt.position(asset_no)))
+        .collect();
+    let mut kill_switch = KillSwitch::new(params.kill_switch);
+    let toxicity = params
+        .toxicity
+        .map(|toxicity| toxicity.filter())
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("invalid toxicity filter: {}", e))?;
+    let mut toxicity = vec![toxicity; num_assets];
+
+    // 100ms
+    while hbt
+        .elapse(100_000_000)
+        .map_err(|e| anyhow::anyhow!("elapse failed: {:?}", e))?
+    {
+        int += 1;
+        if int % 10 == 0 {
+            // Records every 1-sec
+            recorder
+                .record(hbt)
+                .map_err(|e| anyhow::anyhow!("record failed: {:?}", e))?;
+        }
+        if kill_switch.guard(hbt)? {
+            break;
+        }
+
+        for asset_no in 0..num_assets {
+            let trading_state = &mut trading_states[asset_no];
+            let trades = hbt.last_trades(asset_no);
+            if let Some(trade) = trades.last() {
+                last_trades[asset_no] = trade.px;
+            }
+            trading_state.add_trade_flow(signed_volume(trades));
+            let toxic = toxicity[asset_no]
+                .as_mut()
+                .is_some_and(|filter| filter.on_trades(trades) == QuoteAction::Pull);
+            hbt.clear_last_trades(Some(asset_no));
+            // A filled order ends the chase of its side
+            if book_fills(hbt, asset_no, trading_state, &params.costs) {
+                chases[asset_no].target = None;
+            }
+
+            // --- Generate signal from trading strategy ---
+            let depth = hbt.depth(asset_no);
+            // Orders that are not a whole number of lots are rejected by the venue
+            let instrument = Instrument::new("", depth.tick_size(), depth.lot_size());
+            let order_qty = instrument.round_qty(params.order_qty);
+
+            let mid_price = (depth.best_bid() + depth.best_ask()) / 2.0;
+            let spread = TradingState::calculate_spread(depth.best_bid(), depth.best_ask());
+            let (bid_volume, ask_volume) = top_levels_depth(depth, params.depth_levels);
+            // An empty side leaves the imbalance undefined
+            if !mid_price.is_finite() || bid_volume + ask_volume <= 0.0 {
+                continue;
+            }
+            let last_price = if last_trades[asset_no].is_nan() {
+                mid_price
+            } else {
+                last_trades[asset_no]
+            };
+            let reference_price = depth_weighted_mid(depth, params.depth_levels);
+
+            if let Some(calibrator) = calibrators.get_mut(asset_no) {
+                let fitted = calibrator.update(
+                    hbt.current_timestamp(),
+                    TradingState::calculate_voi(bid_volume, ask_volume),
+                    TradingState::calculate_oir(bid_volume, ask_volume),
+                    TradingState::calculate_mpb(last_price, reference_price),
+                    mid_price,
+                );
+                if let Some(weights) = fitted {
+                    trading_state.set_weights(weights.clone());
+                }
+            }
+
+            let signal = trading_state.signal(
+                bid_volume,
+                ask_volume,
+                last_price,
+                reference_price,
+                params.q,
+            );
+            let now = hbt.current_timestamp();
+            throttles[asset_no].update(now, hbt.position(asset_no));
+            let signal = throttles[asset_no].filter(&params.throttle, now, signal);
+            let maker = matches!(params.execution, OirExecution::Passive { .. });
+            let side = if signal > 0.0 { Side::Buy } else { Side::Sell };
+            let signal = if !toxic
+                && trading_state.covers_costs(&params.costs, mid_price, order_qty, maker)
+                && trading_state.allows(mid_price, side, order_qty)
+            {
+                signal
+            } else {
+                0.0
+            };
+            // ---
+
+            if let Some(features) = features.as_deref_mut() {
+                let last = |history: &LagHistory| history.last().unwrap_or(f64::NAN);
+                features.write(&FeatureRow {
+                    timestamp: now,
+                    asset_no,
+                    voi: last(&trading_state.voi_history),
+                    oir: last(&trading_state.oir_history),
+                    mpb: last(&trading_state.mpb_history),
+                    spread,
+                    mid_price,
+                    position: hbt.position(asset_no),
+                    signal,
+                })?;
+            }
+
+            // A position past the limits, or held once the drawdown halted the
+            // asset, is reduced at market before any new signal
+            if let Some((side, size)) = trading_state.mark(mid_price) {
+                let size = instrument.round_qty(size);
+                if size > 0.0 {
+                    submit_market(
+                        hbt,
+                        asset_no,
+                        side,
+                        mid_price,
+                        size,
+                        &mut chases[asset_no].next_order_id,
+                    )?;
+                    continue;
+                }
+            }
+
+            let OirExecution::Passive {
+                timeout,
+                max_chases,
+            } = params.execution
+            else {
+                // Use the signal to open a position. We might have to close any current
+                // position before opening a new one that is if the current position is
+                // the opposite of the signal
+                let side = if signal == 1.0 {
+                    Side::Buy
+                } else if signal == -1.0 {
+                    Side::Sell
+                } else {
+                    continue;
+                };
+                submit_market(
+                    hbt,
+                    asset_no,
+                    side,
+                    last_price,
+                    order_qty,
+                    &mut chases[asset_no].next_order_id,
+                )?;
+                continue;
+            };
+            chase_passive(
+                hbt,
+                asset_no,
+                signal,
+                order_qty,
+                timeout,
+                max_chases,
+                &mut chases[asset_no],
+            )?;
+        }
+
+        let now = hbt.current_timestamp();
+        if !progress.step_with(|| now.to_string()) {
+            break;
+        }
+    }
+    if let Some(features) = features {
+        features.flush()?;
+    }
+
+    let mut strategy_pnl = Vec::with_capacity(num_assets);
+    for (asset_no, trading_state) in trading_states.iter_mut().enumerate() {
+        book_fills(hbt, asset_no, trading_state, &params.costs);
+        let depth = hbt.depth(asset_no);
+        strategy_pnl.push(trading_state.total_pnl((depth.best_bid() + depth.best_ask()) / 2.0));
+    }
+    let assets = (0..num_assets)
+        .map(|asset_no| {
+            let depth = hbt.depth(asset_no);
+            let state = hbt.state_values(asset_no);
+            RecordRow {
+                timestamp: hbt.current_timestamp(),
+                asset_no,
+                price: (depth.best_bid() + depth.best_ask()) / 2.0,
+                position: state.position,
+                balance: state.balance,
+                fee: state.fee,
+                trading_volume: state.trading_volume,
+                trading_value: state.trading_value,
+            }
+        })
+        .collect();
+    Ok(OirBacktestSummary {
+        assets,
+        strategy_pnl,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Side {
     Buy,
     Sell,
 }
 
+/// Inventory and loss limits of a `TradingState`. Every limit is off by
+/// default.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RiskLimits {
+    /// Largest absolute position in base units.
+    pub max_position: Option<f64>,
+    /// Largest absolute position value in quote currency.
+    pub max_notional: Option<f64>,
+    /// Largest fall of the equity from its peak in quote currency, past which
+    /// the position is flattened and trading stops.
+    pub max_drawdown: Option<f64>,
+}
+
+/// The last values of a feature, oldest first, with their running sum.
+///
+/// Adding a value drops the oldest ones past the capacity in constant time,
+/// and the sum is kept up to date on the way. It is recomputed from the values
+/// once as many values have been dropped as are held, so that the rounding
+/// errors of the updates do not build up, at a constant cost per value.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LagHistory {
+    values: VecDeque<f64>,
+    sum: f64,
+    /// Values dropped since the sum was last recomputed.
+    dropped: usize,
+}
+
+impl LagHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `value`, then drops the oldest values past `capacity`.
+    pub fn push(&mut self, value: f64, capacity: usize) {
+        self.values.push_back(value);
+        self.sum += value;
+        while self.values.len() > capacity {
+            if let Some(oldest) = self.values.pop_front() {
+                self.sum -= oldest;
+                self.dropped += 1;
+            }
+        }
+        if self.dropped >= self.values.len() {
+            self.sum = self.values.iter().sum();
+            self.dropped = 0;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Sum of the values held.
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// The current value.
+    pub fn last(&self) -> Option<f64> {
+        self.values.back().copied()
+    }
+
+    /// The values, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &f64> {
+        self.values.iter()
+    }
+
+    pub fn to_vec(&self) -> Vec<f64> {
+        self.values.iter().copied().collect()
+    }
+
+    /// Weighted sum of the values, `weights[0]` weighing the current value,
+    /// `weights[1]` the previous one, and so on.
+    pub fn weighted_sum(&self, weights: &[f64]) -> f64 {
+        weights
+            .iter()
+            .zip(self.values.iter().rev())
+            .map(|(weight, value)| weight * value)
+            .sum()
+    }
+}
+
 // Struct to hold the trading state
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TradingState {
     pub positions: Vec<f64>,
-    pub voi_history: Vec<f64>,
-    pub oir_history: Vec<f64>,
-    pub mpb_history: Vec<f64>,
+    pub voi_history: LagHistory,
+    pub oir_history: LagHistory,
+    pub mpb_history: LagHistory,
+    pub tfi_history: LagHistory,
+    /// Signed traded volume of the last `tfi_window` steps.
+    pub trade_flow: LagHistory,
+    pub tfi_window: usize,
+    /// Coefficients of the model, see `set_weights`.
+    weights: ModelWeights,
+    /// `ModelWeights::uniform_lags` of `weights`, under which a feature
+    /// weighs the running sum of its history. Unknown, and so summed lag by
+    /// lag, until the weights are set.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    uniform_lags: [Option<f64>; 4],
+    pub limits: RiskLimits,
+    /// Net position in base units, negative when short.
+    pub position: f64,
+    /// Cash flow of the trades net of fees, in quote currency.
+    pub cash: f64,
+    /// Average price the open position was entered at, zero when flat.
+    pub avg_entry: f64,
+    /// Profit of the closed part of the positions, before fees.
+    pub realized_pnl: f64,
+    /// Fees paid on all trades.
+    pub fees_paid: f64,
+    /// Highest equity marked so far.
+    pub peak_equity: f64,
+    /// Set once the drawdown limit is breached; only trades reducing the
+    /// position are executed afterwards.
+    pub halted: bool,
 }
 
 impl TradingState {
     pub fn new() -> Self {
         Self {
             positions: Vec::new(),
-            voi_history: Vec::new(),
-            oir_history: Vec::new(),
-            mpb_history: Vec::new(),
+            voi_history: LagHistory::new(),
+            oir_history: LagHistory::new(),
+            mpb_history: LagHistory::new(),
+            tfi_history: LagHistory::new(),
+            trade_flow: LagHistory::new(),
+            tfi_window: DEFAULT_TFI_WINDOW,
+            weights: ModelWeights::default(),
+            uniform_lags: ModelWeights::default().uniform_lags(),
+            limits: RiskLimits::default(),
+            position: 0.0,
+            cash: 0.0,
+            avg_entry: 0.0,
+            realized_pnl: 0.0,
+            fees_paid: 0.0,
+            peak_equity: 0.0,
+            halted: false,
+        }
+    }
+
+    /// Sets the coefficients of `parametrized_linear_model`.
+    pub fn with_weights(mut self, weights: ModelWeights) -> Self {
+        self.set_weights(weights);
+        self
+    }
+
+    /// The coefficients of `parametrized_linear_model`.
+    pub fn weights(&self) -> &ModelWeights {
+        &self.weights
+    }
+
+    /// Replaces the coefficients of `parametrized_linear_model`, e.g. with
+    /// the ones an `OirCalibrator` refitted. The histories are kept.
+    ///
+    /// A feature whose lags all weigh the same is weighed through the running
+    /// sum of its history, in constant time whatever the number of lags.
+    pub fn set_weights(&mut self, weights: ModelWeights) {
+        self.uniform_lags = weights.uniform_lags();
+        self.weights = weights;
+    }
+
+    /// Sets the number of steps the Trade Flow Imbalance sums the traded
+    /// volumes over.
+    pub fn with_tfi_window(mut self, tfi_window: usize) -> Self {
+        self.tfi_window = tfi_window;
+        self
+    }
+
+    /// Applies inventory and loss limits to the trades.
+    pub fn with_limits(mut self, limits: RiskLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Cash flow plus the position marked at `price`.
+    pub fn equity(&self, price: f64) -> f64 {
+        self.cash + self.position * price
+    }
+
+    /// Profit of the open position marked at `mid_price`, against its average
+    /// entry.
+    pub fn unrealized_pnl(&self, mid_price: f64) -> f64 {
+        self.position * (mid_price - self.avg_entry)
+    }
+
+    /// Realized and unrealized profit net of fees, marked at `mid_price`.
+    /// Equal to `equity` at the same price.
+    pub fn total_pnl(&self, mid_price: f64) -> f64 {
+        self.realized_pnl + self.unrealized_pnl(mid_price) - self.fees_paid
+    }
+
+    /// Checks a trade against the limits.
+    ///
+    /// Trades reducing the position are always allowed. Others are refused
+    /// once halted, or when the resulting position would exceed
+    /// `max_position` or, valued at `price`, `max_notional`.
+    ///
+    /// # Arguments
+    ///
+    /// * `price` - Trade price.
+    /// * `side` - Trade side (Buy or Sell).
+    /// * `trade_size` - Size of the trade.
+    ///
+    /// # Returns
+    ///
+    /// * `allowed` - Whether the trade may be executed.
+    pub fn allows(&self, price: f64, side: Side, trade_size: f64) -> bool {
+        let position = match side {
+            Side::Buy => self.position + trade_size,
+            Side::Sell => self.position - trade_size,
+        };
+        if position.abs() <= self.position.abs() && position * self.position >= 0.0 {
+            return true;
         }
+        !self.halted
+            && self
+                .limits
+                .max_position
+                .is_none_or(|max| position.abs() <= max)
+            && self
+                .limits
+                .max_notional
+                .is_none_or(|max| position.abs() * price <= max)
+    }
+
+    /// Marks the position at `price` and enforces the limits.
+    ///
+    /// Breaching the drawdown limit halts the state. The position must then
+    /// be flattened, and while it exceeds `max_position` or `max_notional`,
+    /// e.g. after the price moved, it must be reduced to fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `price` - Current price of the asset.
+    ///
+    /// # Returns
+    ///
+    /// * `trade` - The side and size of the trade forced by the limits, if any,
+    ///   to execute with `execute_trade`.
+    pub fn mark(&mut self, price: f64) -> Option<(Side, f64)> {
+        let equity = self.equity(price);
+        self.peak_equity = self.peak_equity.max(equity);
+        if let Some(max_drawdown) = self.limits.max_drawdown {
+            if !self.halted && self.peak_equity - equity > max_drawdown {
+                error!(
+                    "Drawdown of {} exceeds {}, flattening",
+                    self.peak_equity - equity,
+                    max_drawdown
+                );
+                self.halted = true;
+            }
+        }
+
+        let mut target = if self.halted {
+            0.0
+        } else {
+            self.position.abs()
+        };
+        if let Some(max) = self.limits.max_position {
+            target = target.min(max);
+        }
+        if let Some(max) = self.limits.max_notional {
+            target = target.min(max / price);
+        }
+        let excess = self.position.abs() - target;
+        if excess <= 0.0 {
+            return None;
+        }
+        let side = if self.position > 0.0 {
+            Side::Sell
+        } else {
+            Side::Buy
+        };
+        Some((side, excess))
     }
 
     /// Calculates a smoothed price using a weighted average of the last traded
@@ -214,22 +1500,157 @@ impl TradingState {
         (bid_volume - ask_volume) / (bid_volume + ask_volume)
     }
 
+    /// The weighted sum of the features last fed to
+    /// `parametrized_linear_model`, i.e. the change of the mid-price the model
+    /// expects when its weights are fitted to it, e.g. by an `OirCalibrator`.
+    pub fn prediction(&self) -> f64 {
+        let features = [
+            (&self.weights.voi, &self.voi_history),
+            (&self.weights.oir, &self.oir_history),
+            (&self.weights.mpb, &self.mpb_history),
+            (&self.weights.tfi, &self.tfi_history),
+        ];
+        self.weights.intercept
+            + features
+                .into_iter()
+                .zip(self.uniform_lags)
+                .map(|((weights, history), uniform)| match uniform {
+                    Some(weight) => weight * history.sum(),
+                    None => history.weighted_sum(weights),
+                })
+                .sum::<f64>()
+    }
+
+    /// Calculates the cost of a round trip of `qty` at `price` per unit of
+    /// quantity, in price units.
+    ///
+    /// Both legs pay the maker or taker fee, and taker legs also cross the
+    /// slippage.
+    ///
+    /// # Arguments
+    ///
+    /// * `costs` - Fee schedule and slippage.
+    /// * `price` - Price of both legs.
+    /// * `qty` - Quantity of each leg.
+    /// * `maker` - Whether the legs rest in the book.
+    ///
+    /// # Returns
+    ///
+    /// * `cost` - Round-trip cost per unit.
+    pub fn round_trip_cost(costs: &ExecutionCosts, price: f64, qty: f64, maker: bool) -> f64 {
+        let fee = costs.fees.fee(qty * price, maker) / qty;
+        let slippage = if maker {
+            0.0
+        } else {
+            (costs
+                .slippage
+                .fill_price(strato_utils::vars::trade::Side::Buy, price)
+                - price)
+                .abs()
+        };
+        2.0 * (fee + slippage)
+    }
+
+    /// Whether the expected edge of the last signal, the absolute
+    /// `prediction`, exceeds `round_trip_cost`. Always true without costs.
+    pub fn covers_costs(&self, costs: &ExecutionCosts, price: f64, qty: f64, maker: bool) -> bool {
+        let cost = TradingState::round_trip_cost(costs, price, qty, maker);
+        cost <= 0.0 || self.prediction().abs() > cost
+    }
+
+    /// Adds the signed traded volume of a step to the Trade Flow Imbalance,
+    /// dropping the steps older than `tfi_window`.
+    ///
+    /// # Arguments
+    ///
+    /// * `signed_volume` - Volume bought by takers less the volume sold by
+    ///   takers over the step.
+    pub fn add_trade_flow(&mut self, signed_volume: f64) {
+        self.trade_flow.push(signed_volume, self.tfi_window);
+    }
+
+    /// Calculates the Trade Flow Imbalance (TFI).
+    ///
+    /// TFI is the signed volume traded over the last `tfi_window` steps:
+    /// aggressive buying pushes it up and aggressive selling down. Unlike the
+    /// imbalances of the book, it reflects orders that actually executed.
+    ///
+    /// # Returns
+    ///
+    /// * `tfi` - Trade Flow Imbalance value.
+    pub fn calculate_tfi(&self) -> f64 {
+        self.trade_flow.sum()
+    }
+
     /// Calculates the Mid-Price Basis (MPB).
     ///
     /// MPB is calculated as the difference between the last traded price and
-    /// the mid-price. This metric indicates the deviation of the last trade
-    /// price from the mid-price.
+    /// a reference price of the book. This metric indicates the deviation of
+    /// the last trade price from the price the book points to. The backtest
+    /// and live trading use the depth-weighted mid-price, which leans toward
+    /// the side with less quantity where the plain mid-price does not.
     ///
     /// # Arguments
     ///
     /// * `last_price` - Last traded price.
-    /// * `mid_price` - Mid-price of the current bid-ask spread.
+    /// * `reference_price` - Reference price of the book, e.g. the mid-price or
+    ///   the microprice.
     ///
     /// # Returns
     ///
     /// * `mpb` - Mid-Price Basis.
-    pub fn calculate_mpb(last_price: f64, mid_price: f64) -> f64 {
-        last_price - mid_price
+    pub fn calculate_mpb(last_price: f64, reference_price: f64) -> f64 {
+        last_price - reference_price
+    }
+
+    /// Calculates the microprice, the best bid and ask weighted by the
+    /// quantity of the opposite side.
+    ///
+    /// A large bid quantity moves the microprice toward the ask, where the
+    /// next trade is more likely, and a large ask quantity toward the bid.
+    /// Without quantity it is the mid-price.
+    ///
+    /// # Arguments
+    ///
+    /// * `bid` - Current bid price.
+    /// * `ask` - Current ask price.
+    /// * `bid_qty` - Quantity at the bid.
+    /// * `ask_qty` - Quantity at the ask.
+    ///
+    /// # Returns
+    ///
+    /// * `microprice` - Microprice of the book.
+    pub fn calculate_microprice(bid: f64, ask: f64, bid_qty: f64, ask_qty: f64) -> f64 {
+        if bid_qty + ask_qty > 0.0 {
+            (bid * ask_qty + ask * bid_qty) / (bid_qty + ask_qty)
+        } else {
+            TradingState::calculate_mid_price(bid, ask)
+        }
+    }
+
+    /// Calculates the depth-weighted mid-price, the microprice of the
+    /// volume-weighted average prices of several levels of each side.
+    ///
+    /// With a single level per side it is the microprice.
+    ///
+    /// # Arguments
+    ///
+    /// * `bids` - Price and quantity of the bid levels.
+    /// * `asks` - Price and quantity of the ask levels.
+    ///
+    /// # Returns
+    ///
+    /// * `weighted_mid` - Depth-weighted mid-price, NaN when a side has no
+    ///   quantity.
+    pub fn calculate_weighted_mid(bids: &[(f64, f64)], asks: &[(f64, f64)]) -> f64 {
+        let side = |levels: &[(f64, f64)]| {
+            let qty: f64 = levels.iter().map(|(_, qty)| qty).sum();
+            let value: f64 = levels.iter().map(|(price, qty)| price * qty).sum();
+            (value / qty, qty)
+        };
+        let (bid, bid_qty) = side(bids);
+        let (ask, ask_qty) = side(asks);
+        TradingState::calculate_microprice(bid, ask, bid_qty, ask_qty)
     }
 
     /// Calculates the bid-ask spread as a percentage of the bid price.
@@ -251,9 +1672,6 @@ impl TradingState {
 
     /// Calculates the mid-price as the average of the bid and ask prices.
     ///
-    /// This metric provides a reference price point that is used to calculate
-    /// the Mid-Price Basis (MPB).
-    ///
     /// # Arguments
     ///
     /// * `bid` - Current bid price.
@@ -268,17 +1686,19 @@ impl TradingState {
 
     /// Implements the Parametrized Linear Model for trading decisions.
     ///
-    /// This model uses a weighted sum of the historical values of VOI, OIR, and
-    /// MPB to make trading decisions. A buy signal is generated if the
-    /// weighted sum exceeds the positive threshold `q`. A sell signal is
-    /// generated if the weighted sum falls below the negative threshold `-q`.
+    /// This model uses a weighted sum of the historical values of VOI, OIR,
+    /// MPB and, when weighted, TFI, with the coefficients of `weights`, to
+    /// make trading decisions. The TFI is the one of the trade flow added so
+    /// far with `add_trade_flow`. A
+    /// buy signal is generated if the weighted sum exceeds the positive
+    /// threshold `q`. A sell signal is generated if the weighted sum falls
+    /// below the negative threshold `-q`.
     ///
     /// # Arguments
     ///
     /// * `current_voi` - Current VOI value.
     /// * `current_oir` - Current OIR value.
     /// * `current_mpb` - Current MPB value.
-    /// * `k` - Number of historical values to consider (window size).
     /// * `q` - Threshold for decision making.
     ///
     /// # Returns
@@ -289,32 +1709,20 @@ impl TradingState {
         current_voi: f64,
         current_oir: f64,
         current_mpb: f64,
-        k: Option<usize>,
         q: Option<f64>,
     ) -> f64 {
-        let k = k.unwrap_or(DEFAULT_K);
+        let k = self.weights.lags();
         let q = q.unwrap_or(DEFAULT_Q);
 
-        // Update history
-        self.voi_history.push(current_voi);
-        self.oir_history.push(current_oir);
-        self.mpb_history.push(current_mpb);
-
-        // Keep history size to k
-        if self.voi_history.len() > k {
-            self.voi_history.remove(0);
-        }
-        if self.oir_history.len() > k {
-            self.oir_history.remove(0);
-        }
-        if self.mpb_history.len() > k {
-            self.mpb_history.remove(0);
-        }
+        // Update history, keeping its size to k
+        let tfi = self.calculate_tfi();
+        self.voi_history.push(current_voi, k);
+        self.oir_history.push(current_oir, k);
+        self.mpb_history.push(current_mpb, k);
+        self.tfi_history.push(tfi, k);
 
         // Calculate the weighted sum of VOI, OIR, and MPB
-        let weighted_sum: f64 = self.voi_history.iter().sum::<f64>()
-            + self.oir_history.iter().sum::<f64>()
-            + self.mpb_history.iter().sum::<f64>();
+        let weighted_sum = self.prediction();
 
         // Decision based on weighted sum and threshold q
         if weighted_sum > q {
@@ -329,6 +1737,34 @@ impl TradingState {
         0.0
     }
 
+    /// Computes the VOI, OIR and MPB of the book and feeds them to
+    /// `parametrized_linear_model`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bid_volume` - Total volume of buy orders.
+    /// * `ask_volume` - Total volume of sell orders.
+    /// * `last_price` - Last traded price.
+    /// * `reference_price` - Reference price of the MPB, see `calculate_mpb`.
+    /// * `q` - Threshold for decision making.
+    ///
+    /// # Returns
+    ///
+    /// * `signal` - Trading signal (1.0 for buy, -1.0 for sell, 0.0 for hold).
+    pub fn signal(
+        &mut self,
+        bid_volume: f64,
+        ask_volume: f64,
+        last_price: f64,
+        reference_price: f64,
+        q: f64,
+    ) -> f64 {
+        let current_voi = TradingState::calculate_voi(bid_volume, ask_volume);
+        let current_oir = TradingState::calculate_oir(bid_volume, ask_volume);
+        let current_mpb = TradingState::calculate_mpb(last_price, reference_price);
+        self.parametrized_linear_model(current_voi, current_oir, current_mpb, Some(q))
+    }
+
     /// Ensure the spread is within the acceptable threshold
     ///
     /// A wide spread may indicate lower liquidity or higher uncertainty in the
@@ -390,7 +1826,35 @@ impl TradingState {
     /// Executes a trade based on the provided price and side.
     ///
     /// This function updates the cash balance and position size based on the
-    /// trade details.
+    /// trade details. The part of the trade offsetting the position realizes
+    /// its profit against the average entry, and the rest moves the average
+    /// entry. Trades refused by `allows` are not executed.
+    ///
+    /// # Arguments
+    ///
+    /// * `price` - Trade price.
+    /// * `side` - Trade side (Buy or Sell).
+    /// * `trade_size` - Size of the trade.
+    /// * `fee` - Transaction fee percentage.
+    ///
+    /// # Returns
+    ///
+    /// * `executed` - Whether the trade was executed.
+    pub fn execute_trade(&mut self, price: f64, side: Side, trade_size: f64, fee: f64) -> bool {
+        if !self.allows(price, side, trade_size) {
+            debug!(
+                "Refusing to {:?} {} at {}: risk limits reached",
+                side, trade_size, price
+            );
+            return false;
+        }
+        self.book_trade(price, side, trade_size, fee);
+        true
+    }
+
+    /// Books a trade already executed by the venue, like `execute_trade` but
+    /// whatever the limits, e.g. a fill of an order sent before they were
+    /// reached.
     ///
     /// # Arguments
     ///
@@ -398,8 +1862,31 @@ impl TradingState {
     /// * `side` - Trade side (Buy or Sell).
     /// * `trade_size` - Size of the trade.
     /// * `fee` - Transaction fee percentage.
-    pub fn execute_trade(&mut self, price: f64, side: Side, trade_size: f64, fee: f64) {
+    pub fn book_trade(&mut self, price: f64, side: Side, trade_size: f64, fee: f64) {
         let transaction_cost = trade_size * price * fee;
+        let sign = match side {
+            Side::Buy => 1.0,
+            Side::Sell => -1.0,
+        };
+        self.cash -= transaction_cost + sign * trade_size * price;
+        self.fees_paid += transaction_cost;
+
+        let closed = if self.position * sign < 0.0 {
+            trade_size.min(self.position.abs())
+        } else {
+            0.0
+        };
+        self.realized_pnl += closed * (price - self.avg_entry) * self.position.signum();
+        let opened = trade_size - closed;
+        let held = self.position.abs() - closed;
+        self.avg_entry = if opened > 0.0 {
+            (self.avg_entry * held + price * opened) / (held + opened)
+        } else if held > 0.0 {
+            self.avg_entry
+        } else {
+            0.0
+        };
+        self.position += sign * trade_size;
         match side {
             Side::Buy => {
                 self.positions.push(price);
@@ -425,3 +1912,216 @@ impl TradingState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::costs::FeeModel;
+    use crate::grid::costs::Slippage;
+
+    #[test]
+    fn test_risk_limits() {
+        let mut state = TradingState::new().with_limits(RiskLimits {
+            max_position: Some(2.0),
+            max_notional: Some(250.0),
+            max_drawdown: Some(15.0),
+        });
+
+        assert!(state.execute_trade(100.0, Side::Buy, 2.0, 0.0));
+        assert!(!state.execute_trade(100.0, Side::Buy, 1.0, 0.0));
+        assert_eq!(state.position, 2.0);
+        assert_eq!(state.mark(110.0), None);
+
+        // Past the notional limit the excess is sold
+        let (side, size) = state.mark(150.0).unwrap();
+        assert_eq!(side, Side::Sell);
+        assert!((size - (2.0 - 250.0 / 150.0)).abs() < 1e-12);
+        assert!(state.execute_trade(150.0, side, size, 0.0));
+
+        // A drawdown of more than 15 from the peak halts and flattens
+        assert_eq!(state.mark(145.0), None);
+        let (side, size) = state.mark(135.0).unwrap();
+        assert!(state.halted);
+        assert_eq!(side, Side::Sell);
+        assert!(!state.execute_trade(135.0, Side::Buy, 0.1, 0.0));
+        assert!(state.execute_trade(135.0, side, size, 0.0));
+        assert!(state.position.abs() < 1e-12);
+        assert_eq!(state.mark(135.0), None);
+        assert!(!state.execute_trade(135.0, Side::Sell, 0.1, 0.0));
+
+        // A fill of the venue is booked whatever the limits
+        state.book_trade(135.0, Side::Buy, 0.1, 0.0);
+        assert!((state.position - 0.1).abs() < 1e-12);
+        assert_eq!(state.avg_entry, 135.0);
+    }
+
+    #[test]
+    fn test_model_weights() {
+        let weights = ModelWeights {
+            intercept: 0.1,
+            voi: vec![1.0, 0.5],
+            oir: vec![2.0],
+            mpb: vec![],
+            tfi: vec![],
+        };
+        assert_eq!(weights.lags(), 2);
+        // The current VOI weighs 1.0 and the previous one 0.5
+        let sum = weights.predict(&[4.0, 2.0], &[0.0, 0.25], &[9.0, 9.0], &[]);
+        assert!((sum - (0.1 + 2.0 + 2.0 + 0.5)).abs() < 1e-12);
+
+        let mut state = TradingState::new().with_weights(weights);
+        assert_eq!(
+            state.parametrized_linear_model(0.0, 0.0, 5.0, Some(0.15)),
+            0.0
+        );
+        assert_eq!(
+            state.parametrized_linear_model(-1.0, 0.0, 0.0, Some(0.15)),
+            -1.0
+        );
+        assert_eq!(
+            state.parametrized_linear_model(1.0, 0.0, 0.0, Some(0.15)),
+            1.0
+        );
+        assert_eq!(state.voi_history.to_vec(), vec![-1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_lag_history() {
+        let mut history = LagHistory::new();
+        for value in 1..=10 {
+            history.push(value as f64, 3);
+        }
+        assert_eq!(history.to_vec(), vec![8.0, 9.0, 10.0]);
+        assert_eq!(history.sum(), 27.0);
+        assert_eq!(history.last(), Some(10.0));
+        assert_eq!(history.weighted_sum(&[1.0, 0.5]), 14.5);
+
+        // Equal lags weigh the running sum, and predict like the lag by lag sum
+        let k = 50;
+        let uniform = ModelWeights::uniform(k).with_tfi(vec![0.1; k]);
+        let mut state = TradingState::new().with_weights(uniform.clone());
+        let mut lagged = TradingState::new().with_weights(uniform);
+        lagged.uniform_lags = [None; 4];
+        for step in 0..200 {
+            let x = (step as f64 * 0.7).sin();
+            state.add_trade_flow(x);
+            lagged.add_trade_flow(x);
+            state.parametrized_linear_model(x, -x, 0.5 * x, None);
+            lagged.parametrized_linear_model(x, -x, 0.5 * x, None);
+            assert!((state.prediction() - lagged.prediction()).abs() < 1e-9);
+        }
+        assert_eq!(state.voi_history.len(), k);
+    }
+
+    #[test]
+    fn test_trade_flow_imbalance() {
+        let mut state = TradingState::new()
+            .with_weights(ModelWeights::uniform(1).with_tfi(vec![0.1]))
+            .with_tfi_window(2);
+        for signed_volume in [5.0, -1.0, 3.0] {
+            state.add_trade_flow(signed_volume);
+        }
+        assert_eq!(state.calculate_tfi(), 2.0);
+
+        // A balanced book trades on the flow alone
+        assert_eq!(
+            state.parametrized_linear_model(0.0, 0.0, 0.0, Some(0.15)),
+            1.0
+        );
+        assert_eq!(state.tfi_history.to_vec(), vec![2.0]);
+        state.add_trade_flow(-6.0);
+        assert_eq!(
+            state.parametrized_linear_model(0.0, 0.0, 0.0, Some(0.15)),
+            -1.0
+        );
+    }
+
+    #[test]
+    fn test_microprice_and_weighted_mid() {
+        // Three times more on the bid: a quarter of the spread from the ask
+        assert_eq!(
+            TradingState::calculate_microprice(99.0, 101.0, 3.0, 1.0),
+            100.5
+        );
+        assert_eq!(
+            TradingState::calculate_microprice(99.0, 101.0, 0.0, 0.0),
+            100.0
+        );
+
+        let bids = [(99.0, 1.0), (98.0, 1.0)];
+        let asks = [(101.0, 2.0)];
+        // Bid VWAP 98.5 for 2, ask 101 for 2
+        assert_eq!(TradingState::calculate_weighted_mid(&bids, &asks), 99.75);
+        assert_eq!(
+            TradingState::calculate_weighted_mid(&bids[..1], &asks),
+            TradingState::calculate_microprice(99.0, 101.0, 1.0, 2.0)
+        );
+        assert!(TradingState::calculate_weighted_mid(&bids, &[]).is_nan());
+    }
+
+    #[test]
+    fn test_pnl_accounting() {
+        let mut state = TradingState::new();
+        state.execute_trade(100.0, Side::Buy, 2.0, 0.001);
+        state.execute_trade(110.0, Side::Buy, 2.0, 0.0);
+        assert_eq!(state.avg_entry, 105.0);
+        assert_eq!(state.unrealized_pnl(106.0), 4.0);
+
+        // Selling 5 closes the long of 4 and opens a short of 1 at 120
+        state.execute_trade(120.0, Side::Sell, 5.0, 0.0);
+        assert_eq!(state.realized_pnl, 60.0);
+        assert_eq!((state.position, state.avg_entry), (-1.0, 120.0));
+        assert_eq!(state.unrealized_pnl(118.0), 2.0);
+        assert!((state.fees_paid - 0.2).abs() < 1e-12);
+        assert!((state.total_pnl(118.0) - state.equity(118.0)).abs() < 1e-9);
+
+        state.execute_trade(118.0, Side::Buy, 1.0, 0.0);
+        assert_eq!((state.position, state.avg_entry), (0.0, 0.0));
+        assert_eq!(state.realized_pnl, 62.0);
+    }
+
+    #[test]
+    fn test_signal_throttle() {
+        let throttle = SignalThrottle::new(100, 1_000);
+        let mut state = ThrottleState::new(0.0);
+        assert_eq!(state.filter(&throttle, 0, 1.0), 1.0);
+
+        // Long filled at 10: nothing until 110, then only adding until 1010
+        state.update(10, 1.0);
+        assert_eq!(state.filter(&throttle, 50, 1.0), 0.0);
+        assert_eq!(state.filter(&throttle, 110, 1.0), 1.0);
+        assert_eq!(state.filter(&throttle, 500, -1.0), 0.0);
+        assert_eq!(state.filter(&throttle, 1_010, -1.0), -1.0);
+
+        // Adding keeps the opening time, reversing resets it
+        state.update(1_200, 2.0);
+        assert_eq!(state.filter(&throttle, 1_300, -1.0), -1.0);
+        state.update(1_300, -1.0);
+        assert_eq!(state.filter(&throttle, 1_500, 1.0), 0.0);
+        assert_eq!(state.filter(&throttle, 2_300, 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_covers_costs() {
+        let costs = ExecutionCosts {
+            fees: FeeModel {
+                maker_bps: 1.0,
+                taker_bps: 5.0,
+                fixed: 0.0,
+            },
+            slippage: Slippage::HalfTick(0.02),
+        };
+        // 1bp each way at 100 is 0.02, 5bp plus 0.01 of slippage is 0.12
+        assert!((TradingState::round_trip_cost(&costs, 100.0, 2.0, true) - 0.02).abs() < 1e-12);
+        assert!((TradingState::round_trip_cost(&costs, 100.0, 2.0, false) - 0.12).abs() < 1e-12);
+
+        let mut state = TradingState::new().with_weights(ModelWeights::uniform(1));
+        assert_eq!(
+            state.parametrized_linear_model(0.1, 0.0, 0.0, Some(0.05)),
+            1.0
+        );
+        assert!(state.covers_costs(&costs, 100.0, 2.0, true));
+        assert!(!state.covers_costs(&costs, 100.0, 2.0, false));
+        assert!(state.covers_costs(&ExecutionCosts::default(), 100.0, 2.0, false));
+    }
+}
@@ -0,0 +1,463 @@
+/*!
+A kill switch shared by the HFT execution loops.
+
+`KillSwitch` watches the orders of a `Bot` after every step and trips when
+one of its `KillSwitchLimits` is breached:
+- the loss of the strategy's fills, the open positions marked at the
+  mid-price,
+- the number of order submissions and cancellations within a time window,
+- the share of the last finished orders that the venue rejected.
+
+Once tripped, the working orders of every asset are cancelled, the positions
+are closed with market orders and the loop stops. The switch only reads the
+orders, fills and books the `Bot` reports, so it behaves the same in a
+backtest and against a `LiveBot`, whose account values are not maintained.
+
+The orders have to be checked before `clear_inactive_orders` drops the
+finished ones, otherwise their fills and rejections go unnoticed.
+*/
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+use hftbacktest::prelude::Bot;
+use hftbacktest::prelude::MarketDepth;
+use hftbacktest::prelude::OrdType;
+use hftbacktest::prelude::Order;
+use hftbacktest::prelude::Side;
+use hftbacktest::prelude::Status;
+use hftbacktest::prelude::TimeInForce;
+use strato_utils::vars::instrument::Instrument;
+use tracing::error;
+use tracing::warn;
+
+/// Default window of the message rate, 1s in nanoseconds.
+const DEFAULT_MESSAGE_WINDOW: i64 = 1_000_000_000;
+
+/// Default number of finished orders the reject rate is measured over.
+const DEFAULT_REJECT_WINDOW: usize = 20;
+
+/// Thresholds of a `KillSwitch`. Every threshold is off by default.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KillSwitchLimits {
+    /// Largest loss of the strategy's fills in quote currency, before fees.
+    pub max_loss: Option<f64>,
+    /// Largest number of order submissions and cancellations within
+    /// `message_window`.
+    pub max_messages: Option<usize>,
+    /// Window of the message rate in nanoseconds.
+    pub message_window: i64,
+    /// Largest share of the last `reject_window` finished orders that were
+    /// rejected, post-only orders expired for crossing the book included.
+    pub max_reject_rate: Option<f64>,
+    /// Number of finished orders the reject rate is measured over; the rate
+    /// is not checked before that many orders have finished.
+    pub reject_window: usize,
+}
+
+impl Default for KillSwitchLimits {
+    fn default() -> Self {
+        Self {
+            max_loss: None,
+            max_messages: None,
+            message_window: DEFAULT_MESSAGE_WINDOW,
+            max_reject_rate: None,
+            reject_window: DEFAULT_REJECT_WINDOW,
+        }
+    }
+}
+
+impl KillSwitchLimits {
+    pub fn with_max_loss(mut self, max_loss: f64) -> Self {
+        self.max_loss = Some(max_loss);
+        self
+    }
+
+    pub fn with_max_messages(mut self, max_messages: usize, message_window: i64) -> Self {
+        self.max_messages = Some(max_messages);
+        self.message_window = message_window;
+        self
+    }
+
+    pub fn with_max_reject_rate(mut self, max_reject_rate: f64, reject_window: usize) -> Self {
+        self.max_reject_rate = Some(max_reject_rate);
+        self.reject_window = reject_window.max(1);
+        self
+    }
+}
+
+/// The threshold a `KillSwitch` tripped on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trip {
+    /// Loss in quote currency.
+    Loss(f64),
+    /// Number of messages within the window.
+    MessageRate(usize),
+    /// Share of the finished orders that were rejected.
+    RejectRate(f64),
+}
+
+/// What the kill switch last saw of an order.
+#[derive(Debug, Clone, Copy)]
+struct SeenOrder {
+    local_timestamp: i64,
+    exch_timestamp: i64,
+    cancel_sent: bool,
+    finished: bool,
+    generation: u64,
+}
+
+/// Average-cost book of the fills of an asset.
+#[derive(Debug, Clone, Copy, Default)]
+struct FillBook {
+    position: f64,
+    avg_entry: f64,
+    realized_pnl: f64,
+}
+
+impl FillBook {
+    fn fill(&mut self, qty: f64, price: f64) {
+        let closing = self.position * qty < 0.0;
+        if closing {
+            let closed = qty.abs().min(self.position.abs());
+            self.realized_pnl += closed * (price - self.avg_entry) * self.position.signum();
+        }
+        let position = self.position + qty;
+        if !closing {
+            self.avg_entry = (self.avg_entry * self.position + price * qty) / position;
+        } else if position * self.position < 0.0 {
+            // Reversed through zero: the rest is opened at the fill price
+            self.avg_entry = price;
+        } else if position == 0.0 {
+            self.avg_entry = 0.0;
+        }
+        self.position = position;
+    }
+
+    fn pnl(&self, mid_price: f64) -> f64 {
+        if self.position == 0.0 || !mid_price.is_finite() {
+            return self.realized_pnl;
+        }
+        self.realized_pnl + self.position * (mid_price - self.avg_entry)
+    }
+}
+
+/// Monitors the orders of a `Bot` against `KillSwitchLimits`.
+#[derive(Debug, Clone, Default)]
+pub struct KillSwitch {
+    pub limits: KillSwitchLimits,
+    orders: HashMap<(usize, u64), SeenOrder>,
+    books: Vec<FillBook>,
+    /// Timestamps of the messages within the window, oldest first.
+    messages: VecDeque<i64>,
+    /// Whether each of the last finished orders was rejected, oldest first.
+    outcomes: VecDeque<bool>,
+    generation: u64,
+    trip: Option<Trip>,
+}
+
+impl KillSwitch {
+    pub fn new(limits: KillSwitchLimits) -> Self {
+        Self {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    /// The threshold the switch tripped on, `None` while it has not.
+    pub fn trip(&self) -> Option<Trip> {
+        self.trip
+    }
+
+    /// Profit of the fills seen so far, before fees.
+    ///
+    /// # Arguments
+    ///
+    /// * `mid_price` - Mid-price of each asset, the open positions are marked
+    ///   at.
+    pub fn pnl(&self, mid_price: impl Fn(usize) -> f64) -> f64 {
+        self.books
+            .iter()
+            .enumerate()
+            .map(|(asset_no, book)| book.pnl(mid_price(asset_no)))
+            .sum()
+    }
+
+    /// Reads the orders of every asset of `hbt` and checks the limits.
+    ///
+    /// # Returns
+    ///
+    /// * `trip` - The threshold breached, on this step or an earlier one.
+    pub fn check<MD, I>(&mut self, hbt: &I) -> Option<Trip>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        if self.trip.is_some() {
+            return self.trip;
+        }
+        let now = hbt.current_timestamp();
+        self.generation += 1;
+        for asset_no in 0..hbt.num_assets() {
+            for order in hbt.orders(asset_no).values() {
+                self.observe(now, asset_no, order);
+            }
+        }
+        // Forget the orders the `Bot` no longer reports
+        let generation = self.generation;
+        self.orders.retain(|_, seen| seen.generation == generation);
+
+        self.evaluate(now, |asset_no| {
+            let depth = hbt.depth(asset_no);
+            (depth.best_bid() + depth.best_ask()) / 2.0
+        })
+    }
+
+    /// Checks the limits and, once they are breached, cancels all orders and
+    /// closes all positions of `hbt`.
+    ///
+    /// # Returns
+    ///
+    /// * `tripped` - Whether the loop has to stop.
+    pub fn guard<MD, I>(&mut self, hbt: &mut I) -> anyhow::Result<bool, anyhow::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+        <I as Bot<MD>>::Error: Debug,
+    {
+        let Some(trip) = self.check(hbt) else {
+            return Ok(false);
+        };
+        error!("Kill switch tripped: {:?}", trip);
+        flatten(hbt)?;
+        Ok(true)
+    }
+
+    /// Books the fills, messages and rejection of an order since it was last
+    /// seen.
+    fn observe(&mut self, now: i64, asset_no: usize, order: &Order) {
+        let generation = self.generation;
+        let key = (asset_no, order.order_id);
+        // The same identifier reused by a new order
+        if self
+            .orders
+            .get(&key)
+            .is_some_and(|seen| seen.local_timestamp != order.local_timestamp)
+        {
+            self.orders.remove(&key);
+        }
+        let seen = self.orders.entry(key).or_insert_with(|| {
+            self.messages.push_back(now);
+            SeenOrder {
+                local_timestamp: order.local_timestamp,
+                exch_timestamp: i64::MIN,
+                cancel_sent: false,
+                finished: false,
+                generation,
+            }
+        });
+        seen.generation = generation;
+
+        let mut fill = None;
+        if matches!(order.status, Status::Filled | Status::PartiallyFilled)
+            && order.exch_timestamp != seen.exch_timestamp
+            && order.exec_qty > 0.0
+        {
+            seen.exch_timestamp = order.exch_timestamp;
+            let sign = if order.side == Side::Buy { 1.0 } else { -1.0 };
+            fill = Some((
+                sign * order.exec_qty,
+                order.exec_price_tick as f64 * order.tick_size,
+            ));
+        }
+        let mut cancelled = false;
+        if !seen.cancel_sent && (order.req == Status::Canceled || order.status == Status::Canceled)
+        {
+            seen.cancel_sent = true;
+            cancelled = true;
+        }
+        let mut outcome = None;
+        if !seen.finished && !order.active() && !order.pending() {
+            seen.finished = true;
+            outcome = Some(
+                order.status == Status::Rejected
+                    || (order.status == Status::Expired
+                        && order.time_in_force == TimeInForce::GTX
+                        && order.exec_qty == 0.0),
+            );
+        }
+
+        if let Some((qty, price)) = fill {
+            self.book_fill(asset_no, qty, price);
+        }
+        if cancelled {
+            self.messages.push_back(now);
+        }
+        if let Some(rejected) = outcome {
+            self.add_outcome(rejected);
+        }
+    }
+
+    fn book_fill(&mut self, asset_no: usize, qty: f64, price: f64) {
+        if self.books.len() <= asset_no {
+            self.books.resize(asset_no + 1, FillBook::default());
+        }
+        self.books[asset_no].fill(qty, price);
+    }
+
+    fn add_outcome(&mut self, rejected: bool) {
+        if self.outcomes.len() == self.limits.reject_window {
+            self.outcomes.pop_front();
+        }
+        self.outcomes.push_back(rejected);
+    }
+
+    /// Checks the limits against the state booked so far.
+    fn evaluate(&mut self, now: i64, mid_price: impl Fn(usize) -> f64) -> Option<Trip> {
+        while self
+            .messages
+            .front()
+            .is_some_and(|&timestamp| now - timestamp >= self.limits.message_window)
+        {
+            self.messages.pop_front();
+        }
+
+        let loss = -self.pnl(mid_price);
+        let rejects = self.outcomes.iter().filter(|&&rejected| rejected).count();
+        let reject_rate = rejects as f64 / self.outcomes.len().max(1) as f64;
+
+        self.trip = if self.limits.max_loss.is_some_and(|max| loss > max) {
+            Some(Trip::Loss(loss))
+        } else if self
+            .limits
+            .max_messages
+            .is_some_and(|max| self.messages.len() > max)
+        {
+            Some(Trip::MessageRate(self.messages.len()))
+        } else if self.outcomes.len() >= self.limits.reject_window
+            && self
+                .limits
+                .max_reject_rate
+                .is_some_and(|max| reject_rate > max)
+        {
+            Some(Trip::RejectRate(reject_rate))
+        } else {
+            None
+        };
+        self.trip
+    }
+}
+
+/// Cancels the working orders of every asset of `hbt` and closes the
+/// positions with market orders, waiting for their responses.
+///
+/// A position is left open when its side of the book is empty.
+pub fn flatten<MD, I>(hbt: &mut I) -> anyhow::Result<(), anyhow::Error>
+where
+    MD: MarketDepth,
+    I: Bot<MD>,
+    <I as Bot<MD>>::Error: Debug,
+{
+    // Identifiers must not collide with the orders of the strategy
+    let mut next_order_id = hbt.current_timestamp() as u64;
+    for asset_no in 0..hbt.num_assets() {
+        let working: Vec<_> = hbt
+            .orders(asset_no)
+            .values()
+            .filter(|order| order.cancellable())
+            .map(|order| order.order_id)
+            .collect();
+        for order_id in working {
+            hbt.cancel(asset_no, order_id, false)
+                .map_err(|e| anyhow::anyhow!("failed to cancel order: {:?}", e))?;
+        }
+
+        let depth = hbt.depth(asset_no);
+        let instrument = Instrument::new("", depth.tick_size(), depth.lot_size());
+        let (best_bid, best_ask) = (depth.best_bid(), depth.best_ask());
+        let position = hbt.position(asset_no);
+        let qty = instrument.round_qty(position.abs());
+        if qty <= 0.0 {
+            continue;
+        }
+        let price = if position > 0.0 { best_bid } else { best_ask };
+        if !price.is_finite() {
+            warn!(
+                "Cannot close the position {} of asset {}: empty book",
+                position, asset_no
+            );
+            continue;
+        }
+
+        let (time_in_force, order_type, wait) = (TimeInForce::IOC, OrdType::Market, true);
+        let order_id = next_order_id;
+        next_order_id += 1;
+        if position > 0.0 {
+            hbt.submit_sell_order(
+                asset_no,
+                order_id,
+                price,
+                qty,
+                time_in_force,
+                order_type,
+                wait,
+            )
+        } else {
+            hbt.submit_buy_order(
+                asset_no,
+                order_id,
+                price,
+                qty,
+                time_in_force,
+                order_type,
+                wait,
+            )
+        }
+        .map_err(|e| anyhow::anyhow!("failed to submit order: {:?}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kill_switch_limits() {
+        // Loss: long 2 at 100, 1 sold at 99, the rest marked at 97
+        let mut kill_switch = KillSwitch::new(KillSwitchLimits::default().with_max_loss(3.5));
+        kill_switch.book_fill(0, 2.0, 100.0);
+        kill_switch.book_fill(0, -1.0, 99.0);
+        assert_eq!(kill_switch.evaluate(0, |_| 98.0), None);
+        assert_eq!(kill_switch.evaluate(0, |_| 97.0), Some(Trip::Loss(4.0)));
+
+        // Messages: at most 3 within 10ns
+        let limits = KillSwitchLimits::default().with_max_messages(3, 10);
+        let mut kill_switch = KillSwitch::new(limits);
+        kill_switch.messages.extend([0, 5, 8]);
+        assert_eq!(kill_switch.evaluate(9, |_| f64::NAN), None);
+        kill_switch.messages.push_back(12);
+        assert_eq!(kill_switch.evaluate(12, |_| f64::NAN), None);
+        kill_switch.messages.push_back(14);
+        assert_eq!(
+            kill_switch.evaluate(14, |_| f64::NAN),
+            Some(Trip::MessageRate(4))
+        );
+
+        // Rejects: more than half of the last 4 orders
+        let limits = KillSwitchLimits::default().with_max_reject_rate(0.5, 4);
+        let mut kill_switch = KillSwitch::new(limits);
+        for rejected in [true, true, true] {
+            kill_switch.add_outcome(rejected);
+        }
+        assert_eq!(kill_switch.evaluate(0, |_| f64::NAN), None);
+        kill_switch.add_outcome(false);
+        assert_eq!(
+            kill_switch.evaluate(0, |_| f64::NAN),
+            Some(Trip::RejectRate(0.75))
+        );
+    }
+}
@@ -0,0 +1,144 @@
+/*!
+Connects the HFT strategies to Binance USD-m Futures.
+
+The strategies of this module tree are written against the `Bot` trait of
+hftbacktest, which is implemented both by the backtest and by its `LiveBot`.
+`connect_binance_futures` builds a `LiveBot` on the Binance Futures connector
+of hftbacktest and starts it, so that a strategy runs live unchanged:
+
+```ignore
+let config = BinanceFuturesConfig::new(api_key, secret)
+    .with_endpoint(Endpoint::Testnet)
+    .with_asset("BTCUSDT", 0.1, 0.001);
+let mut hbt = connect_binance_futures(&config)?;
+
+// The OIR strategy
+exec_live_trading(&mut hbt, &LiveOirParams::new(0.001), &mut progress)?;
+
+// A market maker, with the account logged instead of recorded
+exec_backtest_gridmm(&mut hbt, &mut LoggingRecorder::new(), &params)?;
+```
+
+The assets are numbered in the order they are added, the first being asset
+0 of the strategies. Connector errors are logged; they do not stop the bot,
+and the strategies keep running until the `Bot` stops.
+*/
+
+use hftbacktest::connector::binancefutures::BinanceFutures;
+pub use hftbacktest::connector::binancefutures::Endpoint;
+use hftbacktest::live::LiveBot;
+use hftbacktest::prelude::HashMapMarketDepth;
+use tracing::error;
+
+/// Name the connector is registered under in the `LiveBot`.
+const CONNECTOR_NAME: &str = "binancefutures";
+
+/// Default number of market trades kept between two steps of a strategy.
+pub const DEFAULT_TRADE_LEN: usize = 1000;
+
+/// A symbol traded on Binance Futures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveAsset {
+    pub symbol: String,
+    pub tick_size: f64,
+    pub lot_size: f64,
+}
+
+/// Credentials, endpoint and symbols of a Binance Futures `LiveBot`.
+#[derive(Clone)]
+pub struct BinanceFuturesConfig {
+    pub api_key: String,
+    pub secret: String,
+    pub endpoint: Endpoint,
+    /// Prefix of the client order ids, telling the orders of this bot apart
+    /// from the other orders of the account.
+    pub order_prefix: String,
+    pub assets: Vec<LiveAsset>,
+    /// Number of market trades kept between two steps of a strategy. The
+    /// trade flow and the last price of the OIR model are taken from them.
+    pub trade_len: usize,
+}
+
+impl BinanceFuturesConfig {
+    /// Creates a configuration for the public endpoint without symbols.
+    pub fn new(api_key: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            secret: secret.into(),
+            endpoint: Endpoint::Public,
+            order_prefix: "strato".to_string(),
+            assets: Vec::new(),
+            trade_len: DEFAULT_TRADE_LEN,
+        }
+    }
+
+    /// Sets the endpoint. `Endpoint::Custom` is not supported, as it cannot
+    /// name the stream and the REST urls at once.
+    pub fn with_endpoint(mut self, endpoint: Endpoint) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    pub fn with_order_prefix(mut self, order_prefix: impl Into<String>) -> Self {
+        self.order_prefix = order_prefix.into();
+        self
+    }
+
+    /// Adds a symbol, numbered after the symbols already added.
+    pub fn with_asset(mut self, symbol: impl Into<String>, tick_size: f64, lot_size: f64) -> Self {
+        self.assets.push(LiveAsset {
+            symbol: symbol.into(),
+            tick_size,
+            lot_size,
+        });
+        self
+    }
+
+    pub fn with_trade_len(mut self, trade_len: usize) -> Self {
+        self.trade_len = trade_len;
+        self
+    }
+}
+
+/// Builds a `LiveBot` trading the symbols of `config` on Binance Futures and
+/// starts its connection.
+///
+/// Fails when the configuration has no symbol, a custom endpoint or no
+/// credentials, or when the connection cannot be started.
+pub fn connect_binance_futures(
+    config: &BinanceFuturesConfig,
+) -> anyhow::Result<LiveBot<HashMapMarketDepth>> {
+    anyhow::ensure!(!config.assets.is_empty(), "no asset to trade");
+    anyhow::ensure!(
+        !matches!(config.endpoint, Endpoint::Custom(_)),
+        "custom endpoints are not supported"
+    );
+
+    let connector = BinanceFutures::builder()
+        .endpoint(config.endpoint.clone())
+        .api_key(&config.api_key)
+        .secret(&config.secret)
+        .order_prefix(&config.order_prefix)
+        .build()?;
+
+    let mut builder = LiveBot::builder()
+        .register(CONNECTOR_NAME, connector)
+        .depth(|asset| HashMapMarketDepth::new(asset.tick_size, asset.lot_size))
+        .trade_len(config.trade_len)
+        .error_handler(|err| {
+            error!(kind = ?err.kind, value = ?err.value(), "connector error");
+            Ok(())
+        });
+    for asset in &config.assets {
+        builder = builder.add(
+            CONNECTOR_NAME,
+            &asset.symbol,
+            asset.tick_size,
+            asset.lot_size,
+        );
+    }
+
+    let mut hbt = builder.build()?;
+    hbt.run()?;
+    Ok(hbt)
+}
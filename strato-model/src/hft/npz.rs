@@ -0,0 +1,214 @@
+/*!
+Conversions between the crate's candle and trade types and the event format
+of hftbacktest, so that the same datasets can drive both the event-driven HFT
+backtests (e.g. `hft_oir_backtest`) and the candle-based grid and trend
+backtests.
+
+hftbacktest events carry nanosecond timestamps, while `Ohlc` and `Trade` use
+Unix milliseconds.
+*/
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use hftbacktest::backtest::data::read_npy_file;
+use hftbacktest::backtest::data::read_npz_file;
+use hftbacktest::backtest::data::write_npy;
+use hftbacktest::backtest::data::Data;
+use hftbacktest::types::Event;
+use hftbacktest::types::BUY_EVENT;
+use hftbacktest::types::EXCH_EVENT;
+use hftbacktest::types::LOCAL_EVENT;
+use hftbacktest::types::SELL_EVENT;
+use hftbacktest::types::TRADE_EVENT;
+use strato_utils::ta::indicator::Indicator;
+use strato_utils::ta::indicator::Resample;
+use strato_utils::vars::ohlc::Ohlc;
+use strato_utils::vars::trade::Side;
+use strato_utils::vars::trade::Trade;
+
+const NANOS_PER_MILLI: i64 = 1_000_000;
+
+/// Reads events from a `.npz` archive (the `data` array, as written by the
+/// hftbacktest data utilities) or a `.npy` file.
+pub fn read_events<P: AsRef<Path>>(path: P) -> std::io::Result<Data<Event>> {
+    let path = path.as_ref().to_string_lossy();
+    if path.ends_with(".npz") {
+        read_npz_file(&path, "data")
+    } else {
+        read_npy_file(&path)
+    }
+}
+
+/// Writes events to a `.npy` file, which hftbacktest reads like a `.npz`
+/// archive, e.g. through `DataSource::File`.
+pub fn write_events<P: AsRef<Path>>(path: P, events: &[Event]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_npy(&mut writer, events)
+}
+
+/// Converts trades into exchange and local trade events, with the local
+/// timestamp equal to the exchange timestamp.
+pub fn trades_to_events(trades: &[Trade]) -> Vec<Event> {
+    trades
+        .iter()
+        .map(|trade| trade_event(trade.timestamp * NANOS_PER_MILLI, trade))
+        .collect()
+}
+
+/// Extracts the trades from events. Events without a side flag are taken to
+/// be sells.
+pub fn events_to_trades(events: &Data<Event>) -> Vec<Trade> {
+    (0..events.len())
+        .map(|i| &events[i])
+        .filter(|ev| is_exchange_trade(ev))
+        .map(|ev| Trade {
+            timestamp: ev.exch_ts.div_euclid(NANOS_PER_MILLI),
+            price: ev.px,
+            qty: ev.qty,
+            side: if ev.ev & BUY_EVENT != 0 {
+                Side::Buy
+            } else {
+                Side::Sell
+            },
+        })
+        .collect()
+}
+
+/// Aggregates the trades in the events into candles of `interval_ms`.
+/// Intervals without trades produce no candle.
+///
+/// Returns an error if `interval_ms` is not positive.
+pub fn events_to_candles(events: &Data<Event>, interval_ms: i64) -> Result<Vec<Ohlc>, String> {
+    let mut resample = Resample::new(interval_ms)?;
+    let mut candles: Vec<Ohlc> = events_to_trades(events)
+        .into_iter()
+        .filter_map(|trade| {
+            resample.next(Ohlc {
+                timestamp: trade.timestamp,
+                open: trade.price,
+                high: trade.price,
+                low: trade.price,
+                close: trade.price,
+                volume: trade.qty,
+            })
+        })
+        .collect();
+    candles.extend(resample.finish());
+    Ok(candles)
+}
+
+/// Converts candles into trade events so that candle datasets can drive the
+/// event-driven backtests.
+///
+/// Each candle is replayed as four trades spread over its interval, each with
+/// a quarter of the volume: open, low, high, close for a bullish candle and
+/// open, high, low, close for a bearish one. Trades are buys when the price
+/// rises from the previous trade and sells otherwise.
+pub fn candles_to_events(candles: &[Ohlc], interval_ms: i64) -> Vec<Event> {
+    let step = interval_ms * NANOS_PER_MILLI / 4;
+    let mut last_price: Option<f64> = None;
+    let mut events = Vec::with_capacity(candles.len() * 4);
+
+    for c in candles {
+        let path = if c.close >= c.open {
+            [c.open, c.low, c.high, c.close]
+        } else {
+            [c.open, c.high, c.low, c.close]
+        };
+        for (i, &price) in path.iter().enumerate() {
+            let side = match last_price {
+                Some(last) if price > last => Side::Buy,
+                Some(_) => Side::Sell,
+                None => Side::Buy,
+            };
+            last_price = Some(price);
+            let trade = Trade {
+                timestamp: c.timestamp,
+                price,
+                qty: c.volume / 4.0,
+                side,
+            };
+            events.push(trade_event(
+                c.timestamp * NANOS_PER_MILLI + step * i as i64,
+                &trade,
+            ));
+        }
+    }
+
+    events
+}
+
+fn trade_event(timestamp_ns: i64, trade: &Trade) -> Event {
+    let side = match trade.side {
+        Side::Buy => BUY_EVENT,
+        Side::Sell => SELL_EVENT,
+    };
+    Event {
+        ev: EXCH_EVENT | LOCAL_EVENT | TRADE_EVENT | side,
+        exch_ts: timestamp_ns,
+        local_ts: timestamp_ns,
+        px: trade.price,
+        qty: trade.qty,
+        order_id: 0,
+        ival: 0,
+        fval: 0.0,
+    }
+}
+
+fn is_exchange_trade(ev: &Event) -> bool {
+    ev.ev & 0xff == TRADE_EVENT && ev.ev & EXCH_EVENT != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_through_npy() {
+        let candles = vec![
+            Ohlc {
+                timestamp: 0,
+                open: 10.0,
+                high: 12.0,
+                low: 9.0,
+                close: 11.0,
+                volume: 4.0,
+            },
+            Ohlc {
+                timestamp: 60_000,
+                open: 11.0,
+                high: 11.5,
+                low: 10.0,
+                close: 10.5,
+                volume: 8.0,
+            },
+        ];
+        let events = candles_to_events(&candles, 60_000);
+        assert_eq!(events.len(), 8);
+        assert_eq!(events[2].exch_ts, 30_000 * NANOS_PER_MILLI);
+        assert_ne!(events[2].ev & BUY_EVENT, 0);
+
+        let path = std::env::temp_dir().join(format!("strato-events-{}.npy", std::process::id()));
+        write_events(&path, &events).unwrap();
+        let data = read_events(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let trades = events_to_trades(&data);
+        assert_eq!(trades.len(), 8);
+        assert_eq!(trades[1].side, Side::Sell);
+
+        let resampled = events_to_candles(&data, 60_000).unwrap();
+        assert_eq!(resampled.len(), 2);
+        for (a, b) in resampled.iter().zip(&candles) {
+            assert_eq!(a.timestamp, b.timestamp);
+            assert_eq!(
+                (a.open, a.high, a.low, a.close),
+                (b.open, b.high, b.low, b.close)
+            );
+            assert_eq!(a.volume, b.volume);
+        }
+        assert!(events_to_candles(&data, 0).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+/*!
+Online calibration of the OIR linear model.
+
+The study fits the coefficients of `ModelWeights` by regressing the change of
+the mid-price over the next few steps on the current and lagged VOI, OIR and
+MPB. `OirCalibrator` does the same during a backtest: every step adds the
+features of that step, and once the change of the mid-price `horizon` steps
+later is known, the pair becomes a sample of a sliding window. Every
+`refit_every` samples the coefficients are refitted on the window by ridge
+regression, which keeps the fit stable when the lagged features are nearly
+collinear; a `lambda` of zero is ordinary least squares.
+
+Fitted this way, the weighted sum of the model predicts the change of the
+mid-price in price units, so the threshold `q` of the model is a price
+change too.
+*/
+
+use std::collections::VecDeque;
+
+use crate::hft::hft_oir::ModelWeights;
+
+/// Rolling ridge regression of the future mid-price change on the lagged
+/// VOI, OIR and MPB.
+#[derive(Debug, Clone)]
+pub struct OirCalibrator {
+    /// Number of values of each feature in a sample.
+    pub lags: usize,
+    /// Number of steps between the features and the mid-price they predict.
+    pub horizon: usize,
+    /// Number of samples the coefficients are fitted on.
+    pub window: usize,
+    /// Penalty on the squared coefficients, the intercept excepted.
+    pub lambda: f64,
+    /// Number of new samples between two fits.
+    pub refit_every: usize,
+    voi: VecDeque<f64>,
+    oir: VecDeque<f64>,
+    mpb: VecDeque<f64>,
+    /// Regressors and mid-price of the steps whose target is not known yet.
+    pending: VecDeque<(Vec<f64>, f64)>,
+    samples: VecDeque<(Vec<f64>, f64)>,
+    since_fit: usize,
+    fits: Vec<(i64, ModelWeights)>,
+}
+
+impl OirCalibrator {
+    /// Creates a calibrator refitting after every `window` new samples, with a
+    /// light penalty.
+    pub fn new(lags: usize, horizon: usize, window: usize) -> Self {
+        Self {
+            lags,
+            horizon,
+            window,
+            lambda: 1e-6,
+            refit_every: window,
+            voi: VecDeque::with_capacity(lags),
+            oir: VecDeque::with_capacity(lags),
+            mpb: VecDeque::with_capacity(lags),
+            pending: VecDeque::with_capacity(horizon + 1),
+            samples: VecDeque::with_capacity(window),
+            since_fit: 0,
+            fits: Vec::new(),
+        }
+    }
+
+    pub fn with_lambda(mut self, lambda: f64) -> Self {
+        self.lambda = lambda;
+        self
+    }
+
+    pub fn with_refit_every(mut self, refit_every: usize) -> Self {
+        self.refit_every = refit_every.max(1);
+        self
+    }
+
+    /// The coefficients fitted so far with the timestamp of their fit, oldest
+    /// first.
+    pub fn fits(&self) -> &[(i64, ModelWeights)] {
+        &self.fits
+    }
+
+    /// The last fitted coefficients.
+    pub fn weights(&self) -> Option<&ModelWeights> {
+        self.fits.last().map(|(_, weights)| weights)
+    }
+
+    /// Adds the features and the mid-price of a step.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - Time of the step, recorded with the fits.
+    /// * `voi` - Current VOI value.
+    /// * `oir` - Current OIR value.
+    /// * `mpb` - Current MPB value.
+    /// * `mid_price` - Current mid-price.
+    ///
+    /// # Returns
+    ///
+    /// * `weights` - The coefficients if they were refitted on this step.
+    pub fn update(
+        &mut self,
+        timestamp: i64,
+        voi: f64,
+        oir: f64,
+        mpb: f64,
+        mid_price: f64,
+    ) -> Option<&ModelWeights> {
+        for (history, value) in [
+            (&mut self.voi, voi),
+            (&mut self.oir, oir),
+            (&mut self.mpb, mpb),
+        ] {
+            if history.len() == self.lags {
+                history.pop_front();
+            }
+            history.push_back(value);
+        }
+        if self.voi.len() < self.lags {
+            return None;
+        }
+
+        // Intercept, then the lags of each feature, the current value first
+        let regressors = std::iter::once(1.0)
+            .chain(self.voi.iter().rev().copied())
+            .chain(self.oir.iter().rev().copied())
+            .chain(self.mpb.iter().rev().copied())
+            .collect();
+        self.pending.push_back((regressors, mid_price));
+        if self.pending.len() <= self.horizon {
+            return None;
+        }
+        let (regressors, then) = self.pending.pop_front()?;
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((regressors, mid_price - then));
+        self.since_fit += 1;
+
+        if self.samples.len() < self.window || self.since_fit < self.refit_every {
+            return None;
+        }
+        let weights = self.fit()?;
+        self.since_fit = 0;
+        self.fits.push((timestamp, weights));
+        self.weights()
+    }
+
+    /// Fits the coefficients on the current window, `None` when the window
+    /// is empty or the regression is degenerate.
+    pub fn fit(&self) -> Option<ModelWeights> {
+        let n = 1 + 3 * self.lags;
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        // Normal equations (X'X + λI) β = X'y, the intercept not penalised
+        let mut xtx = vec![vec![0.0; n]; n];
+        let mut xty = vec![0.0; n];
+        for (x, y) in &self.samples {
+            for i in 0..n {
+                xty[i] += x[i] * y;
+                for j in 0..n {
+                    xtx[i][j] += x[i] * x[j];
+                }
+            }
+        }
+        for (i, row) in xtx.iter_mut().enumerate().skip(1) {
+            row[i] += self.lambda;
+        }
+
+        let beta = solve(xtx, xty)?;
+        let lags = self.lags;
+        Some(ModelWeights {
+            intercept: beta[0],
+            voi: beta[1..=lags].to_vec(),
+            oir: beta[1 + lags..=2 * lags].to_vec(),
+            mpb: beta[1 + 2 * lags..].to_vec(),
+            tfi: Vec::new(),
+        })
+    }
+}
+
+/// Solves `a x = b` by Gaussian elimination with partial pivoting, `None`
+/// when `a` is singular.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].is_nan() || a[pivot][col].abs() <= 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let (done, rest) = a.split_at_mut(col + 1);
+        let (pivot_row, pivot_b) = (&done[col], b[col]);
+        for (row, row_b) in rest.iter_mut().zip(&mut b[col + 1..]) {
+            let factor = row[col] / pivot_row[col];
+            for (value, pivot_value) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot_value;
+            }
+            *row_b -= factor * pivot_b;
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let dot: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - dot) / a[row][row];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovers_coefficients() {
+        // The mid-price moves one step later by 0.5 VOI - 2 OIR of the step
+        // before plus 0.1
+        let mut calibrator = OirCalibrator::new(2, 1, 50).with_lambda(0.0);
+        let mut mid = 100.0;
+        let mut prev = (0.0, 0.0);
+        for t in 0..120 {
+            let voi = ((t * 7) % 11) as f64 - 5.0;
+            let oir = ((t * 5) % 13) as f64 / 13.0 - 0.5;
+            let mpb = ((t * 3) % 7) as f64 / 7.0;
+            mid += 0.1 + 0.5 * prev.0 - 2.0 * prev.1;
+            prev = (voi, oir);
+            calibrator.update(t, voi, oir, mpb, mid);
+        }
+
+        assert_eq!(calibrator.fits().len(), 2);
+        let weights = calibrator.weights().unwrap();
+        let expected = [0.1, 0.5, 0.0, -2.0, 0.0, 0.0, 0.0];
+        let fitted: Vec<f64> = std::iter::once(weights.intercept)
+            .chain(weights.voi.iter().copied())
+            .chain(weights.oir.iter().copied())
+            .chain(weights.mpb.iter().copied())
+            .collect();
+        for (fitted, expected) in fitted.iter().zip(expected) {
+            assert!((fitted - expected).abs() < 1e-6, "{:?}", fitted);
+        }
+    }
+}
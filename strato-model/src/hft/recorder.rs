@@ -0,0 +1,453 @@
+/*!
+This module provides an hftbacktest `Recorder` that writes its records to
+pluggable sinks instead of the CSV-only `BacktestRecorder`.
+
+Every call to `record` produces one `RecordRow` per asset, which is passed to
+a `RecordSink`. The recorder also keeps the equity of each asset so that an
+equity curve, timestamped in Unix milliseconds like the candle backtests, can
+be extracted at the end of a run.
+
+Sinks:
+- `CsvSink`: any `std::io::Write`.
+- `MemorySink`: keeps the rows in memory, mainly for tests.
+- `ParquetSink`: a Parquet file, behind the `parquet` feature.
+- `SqliteSink`: a SQLite table, behind the `sqlite` feature.
+*/
+
+use std::io::Write;
+
+use hftbacktest::prelude::Bot;
+use hftbacktest::prelude::MarketDepth;
+use hftbacktest::prelude::Recorder;
+
+/// Number of nanoseconds in a millisecond, converting hftbacktest timestamps
+/// to the Unix milliseconds used elsewhere.
+const NANOS_PER_MILLI: i64 = 1_000_000;
+
+/// The state of one asset at a recording step.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordRow {
+    /// Backtest timestamp in nanoseconds.
+    pub timestamp: i64,
+    pub asset_no: usize,
+    /// Mid-price of the asset.
+    pub price: f64,
+    pub position: f64,
+    pub balance: f64,
+    /// Cumulative fees paid.
+    pub fee: f64,
+    pub trading_volume: f64,
+    pub trading_value: f64,
+}
+
+impl RecordRow {
+    /// Equity marked to the mid-price.
+    pub fn equity(&self) -> f64 {
+        self.balance + self.position * self.price - self.fee
+    }
+}
+
+/// A destination for recorded rows.
+pub trait RecordSink {
+    /// Writes one row.
+    fn write(&mut self, row: &RecordRow) -> anyhow::Result<()>;
+
+    /// Flushes buffered rows. Called by `SinkRecorder::finish`.
+    fn flush(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes rows as CSV with a header line.
+pub struct CsvSink<W: Write> {
+    writer: W,
+    header_written: bool,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header_written: false,
+        }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> RecordSink for CsvSink<W> {
+    fn write(&mut self, row: &RecordRow) -> anyhow::Result<()> {
+        if !self.header_written {
+            writeln!(
+                self.writer,
+                "timestamp,asset_no,price,position,balance,fee,trading_volume,trading_value"
+            )?;
+            self.header_written = true;
+        }
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{},{}",
+            row.timestamp,
+            row.asset_no,
+            row.price,
+            row.position,
+            row.balance,
+            row.fee,
+            row.trading_volume,
+            row.trading_value
+        )?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Keeps every row in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    pub rows: Vec<RecordRow>,
+}
+
+impl RecordSink for MemorySink {
+    fn write(&mut self, row: &RecordRow) -> anyhow::Result<()> {
+        self.rows.push(*row);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parquet")]
+pub use self::parquet_sink::ParquetSink;
+
+#[cfg(feature = "parquet")]
+mod parquet_sink {
+    use std::fs::File;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use arrow::array::ArrayRef;
+    use arrow::array::Float64Array;
+    use arrow::array::Int64Array;
+    use arrow::array::UInt64Array;
+    use arrow::datatypes::DataType;
+    use arrow::datatypes::Field;
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::RecordBatch;
+    use parquet::arrow::ArrowWriter;
+
+    use super::RecordRow;
+    use super::RecordSink;
+
+    /// Buffers rows and writes them to a Parquet file on flush.
+    pub struct ParquetSink {
+        path: PathBuf,
+        rows: Vec<RecordRow>,
+    }
+
+    impl ParquetSink {
+        pub fn new(path: impl Into<PathBuf>) -> Self {
+            Self {
+                path: path.into(),
+                rows: Vec::new(),
+            }
+        }
+
+        fn column(&self, f: impl Fn(&RecordRow) -> f64) -> ArrayRef {
+            Arc::new(Float64Array::from_iter_values(self.rows.iter().map(f)))
+        }
+    }
+
+    impl RecordSink for ParquetSink {
+        fn write(&mut self, row: &RecordRow) -> anyhow::Result<()> {
+            self.rows.push(*row);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> anyhow::Result<()> {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("timestamp", DataType::Int64, false),
+                Field::new("asset_no", DataType::UInt64, false),
+                Field::new("price", DataType::Float64, false),
+                Field::new("position", DataType::Float64, false),
+                Field::new("balance", DataType::Float64, false),
+                Field::new("fee", DataType::Float64, false),
+                Field::new("trading_volume", DataType::Float64, false),
+                Field::new("trading_value", DataType::Float64, false),
+            ]));
+            let columns: Vec<ArrayRef> = vec![
+                Arc::new(Int64Array::from_iter_values(
+                    self.rows.iter().map(|r| r.timestamp),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    self.rows.iter().map(|r| r.asset_no as u64),
+                )),
+                self.column(|r| r.price),
+                self.column(|r| r.position),
+                self.column(|r| r.balance),
+                self.column(|r| r.fee),
+                self.column(|r| r.trading_volume),
+                self.column(|r| r.trading_value),
+            ];
+            let batch = RecordBatch::try_new(schema.clone(), columns)?;
+
+            let mut writer = ArrowWriter::try_new(File::create(&self.path)?, schema, None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use self::sqlite_sink::SqliteSink;
+
+#[cfg(feature = "sqlite")]
+mod sqlite_sink {
+    use rusqlite::params;
+    use rusqlite::Connection;
+
+    use super::RecordRow;
+    use super::RecordSink;
+
+    /// Inserts rows into a SQLite table, creating it if needed.
+    pub struct SqliteSink {
+        conn: Connection,
+        /// The table name quoted as an identifier.
+        table: String,
+    }
+
+    /// Quotes `name` as a SQL identifier, so that any table name is taken
+    /// literally instead of being read as SQL.
+    fn quote_identifier(name: &str) -> String {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+
+    impl SqliteSink {
+        /// Opens (or creates) the database at `path` and the `table` in it.
+        pub fn open(path: &str, table: &str) -> anyhow::Result<Self> {
+            Self::with_connection(Connection::open(path)?, table)
+        }
+
+        pub fn with_connection(conn: Connection, table: &str) -> anyhow::Result<Self> {
+            anyhow::ensure!(!table.is_empty(), "the table name is empty");
+            let table = quote_identifier(table);
+            conn.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {table} (
+                        timestamp INTEGER NOT NULL,
+                        asset_no INTEGER NOT NULL,
+                        price REAL NOT NULL,
+                        position REAL NOT NULL,
+                        balance REAL NOT NULL,
+                        fee REAL NOT NULL,
+                        trading_volume REAL NOT NULL,
+                        trading_value REAL NOT NULL
+                    )"
+                ),
+                [],
+            )?;
+            Ok(Self { conn, table })
+        }
+
+        pub fn connection(&self) -> &Connection {
+            &self.conn
+        }
+    }
+
+    impl RecordSink for SqliteSink {
+        fn write(&mut self, row: &RecordRow) -> anyhow::Result<()> {
+            self.conn.execute(
+                &format!(
+                    "INSERT INTO {} VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    self.table
+                ),
+                params![
+                    row.timestamp,
+                    row.asset_no as i64,
+                    row.price,
+                    row.position,
+                    row.balance,
+                    row.fee,
+                    row.trading_volume,
+                    row.trading_value
+                ],
+            )?;
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_table_name_is_quoted() {
+            let table = "equity\"; DROP TABLE equity; --";
+            let mut sink =
+                SqliteSink::with_connection(Connection::open_in_memory().unwrap(), table).unwrap();
+            sink.write(&RecordRow::default()).unwrap();
+
+            let rows: i64 = sink
+                .connection()
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM {}", quote_identifier(table)),
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(rows, 1);
+            assert!(
+                SqliteSink::with_connection(Connection::open_in_memory().unwrap(), "").is_err()
+            );
+        }
+    }
+}
+
+/// An hftbacktest `Recorder` writing to a `RecordSink`.
+pub struct SinkRecorder<S: RecordSink> {
+    sink: S,
+    /// (timestamp in ms, equity) per asset.
+    equity: Vec<Vec<(i64, f64)>>,
+}
+
+impl<S: RecordSink> SinkRecorder<S> {
+    pub fn new(sink: S) -> Self {
+        Self {
+            sink,
+            equity: Vec::new(),
+        }
+    }
+
+    /// Writes a row to the sink and tracks its equity.
+    pub fn record_row(&mut self, row: RecordRow) -> anyhow::Result<()> {
+        if self.equity.len() <= row.asset_no {
+            self.equity.resize_with(row.asset_no + 1, Vec::new);
+        }
+        self.equity[row.asset_no].push((row.timestamp / NANOS_PER_MILLI, row.equity()));
+        self.sink.write(&row)
+    }
+
+    /// Equity curve of an asset as (Unix ms timestamp, equity) pairs.
+    pub fn equity_curve(&self, asset_no: usize) -> &[(i64, f64)] {
+        self.equity.get(asset_no).map_or(&[], Vec::as_slice)
+    }
+
+    /// Equity curve summed over all assets. Assumes every asset is recorded
+    /// at each step, as `record` does.
+    pub fn total_equity_curve(&self) -> Vec<(i64, f64)> {
+        let Some(first) = self.equity.first() else {
+            return Vec::new();
+        };
+        first
+            .iter()
+            .enumerate()
+            .map(|(i, &(timestamp, _))| {
+                let equity = self
+                    .equity
+                    .iter()
+                    .filter_map(|c| c.get(i))
+                    .map(|e| e.1)
+                    .sum();
+                (timestamp, equity)
+            })
+            .collect()
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Flushes the sink and returns it.
+    pub fn finish(mut self) -> anyhow::Result<S> {
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+}
+
+impl<S: RecordSink> Recorder for SinkRecorder<S> {
+    type Error = anyhow::Error;
+
+    fn record<MD, I>(&mut self, hbt: &I) -> Result<(), Self::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let timestamp = hbt.current_timestamp();
+        for asset_no in 0..hbt.num_assets() {
+            let depth = hbt.depth(asset_no);
+            let state = hbt.state_values(asset_no);
+            self.record_row(RecordRow {
+                timestamp,
+                asset_no,
+                price: (depth.best_bid() + depth.best_ask()) / 2.0,
+                position: state.position,
+                balance: state.balance,
+                fee: state.fee,
+                trading_volume: state.trading_volume,
+                trading_value: state.trading_value,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(timestamp: i64, asset_no: usize, price: f64, position: f64) -> RecordRow {
+        RecordRow {
+            timestamp,
+            asset_no,
+            price,
+            position,
+            balance: 100.0,
+            fee: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_equity_curve() {
+        let mut recorder = SinkRecorder::new(MemorySink::default());
+        recorder
+            .record_row(row(1_000_000_000, 0, 10.0, 2.0))
+            .unwrap();
+        recorder
+            .record_row(row(1_000_000_000, 1, 5.0, -1.0))
+            .unwrap();
+        recorder
+            .record_row(row(2_000_000_000, 0, 11.0, 2.0))
+            .unwrap();
+        recorder
+            .record_row(row(2_000_000_000, 1, 6.0, -1.0))
+            .unwrap();
+
+        assert_eq!(recorder.equity_curve(0), &[(1_000, 119.0), (2_000, 121.0)]);
+        assert_eq!(recorder.equity_curve(2), &[]);
+        assert_eq!(
+            recorder.total_equity_curve(),
+            vec![(1_000, 213.0), (2_000, 214.0)]
+        );
+        assert_eq!(recorder.finish().unwrap().rows.len(), 4);
+    }
+
+    #[test]
+    fn test_csv_sink() {
+        let mut sink = CsvSink::new(Vec::new());
+        sink.write(&row(5, 0, 10.0, 2.0)).unwrap();
+        sink.flush().unwrap();
+
+        let csv = String::from_utf8(sink.into_inner()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("timestamp,asset_no"));
+        assert_eq!(lines[1], "5,0,10,2,100,1,0,0");
+    }
+}
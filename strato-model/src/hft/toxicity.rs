@@ -0,0 +1,232 @@
+use hftbacktest::types::Event;
+use hftbacktest::types::BUY_EVENT;
+use hftbacktest::types::SELL_EVENT;
+use strato_utils::ta::vpin::Vpin;
+
+/// Default number of buckets VPIN is averaged over, as in the paper.
+const DEFAULT_WINDOW: usize = 50;
+const DEFAULT_WIDEN_THRESHOLD: f64 = 0.5;
+const DEFAULT_PULL_THRESHOLD: f64 = 0.8;
+const DEFAULT_MAX_WIDEN_MULT: f64 = 3.0;
+
+/// Quoting decision derived from order flow toxicity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuoteAction {
+    /// Quote with the normal half-spread.
+    Quote,
+    /// Widen the half-spread by the given multiplier.
+    Widen(f64),
+    /// Pull all quotes.
+    Pull,
+}
+
+/// Parameters of a `ToxicityFilter`, from which the strategies build one
+/// filter per asset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ToxicityParams {
+    /// Volume contained in each bucket, in base units.
+    pub bucket_volume: f64,
+    /// Number of buckets averaged into the VPIN value.
+    pub window: usize,
+    pub widen_threshold: f64,
+    pub pull_threshold: f64,
+    pub max_widen_mult: f64,
+}
+
+impl ToxicityParams {
+    /// Creates parameters averaging VPIN over 50 buckets, widening from a
+    /// VPIN of 0.5 up to 3x at 0.8, where quoting stops.
+    pub fn new(bucket_volume: f64) -> Self {
+        Self {
+            bucket_volume,
+            window: DEFAULT_WINDOW,
+            widen_threshold: DEFAULT_WIDEN_THRESHOLD,
+            pull_threshold: DEFAULT_PULL_THRESHOLD,
+            max_widen_mult: DEFAULT_MAX_WIDEN_MULT,
+        }
+    }
+
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn with_thresholds(
+        mut self,
+        widen_threshold: f64,
+        pull_threshold: f64,
+        max_widen_mult: f64,
+    ) -> Self {
+        self.widen_threshold = widen_threshold;
+        self.pull_threshold = pull_threshold;
+        self.max_widen_mult = max_widen_mult;
+        self
+    }
+
+    /// Builds a filter with no volume traded yet, or fails on the bucket
+    /// volume and window `Vpin::new` rejects.
+    pub fn filter(&self) -> Result<ToxicityFilter, String> {
+        Ok(ToxicityFilter::new(
+            Vpin::new(self.bucket_volume, self.window)?,
+            self.widen_threshold,
+            self.pull_threshold,
+            self.max_widen_mult,
+        ))
+    }
+}
+
+/// Filter that widens or pulls market-making quotes when VPIN spikes.
+///
+/// Below `widen_threshold` quotes are left untouched. Between
+/// `widen_threshold` and `pull_threshold` the half-spread is scaled linearly
+/// from 1x up to `max_widen_mult`. At or above `pull_threshold` quoting stops.
+#[derive(Debug, Clone)]
+pub struct ToxicityFilter {
+    pub vpin: Vpin,
+    pub widen_threshold: f64,
+    pub pull_threshold: f64,
+    pub max_widen_mult: f64,
+}
+
+impl ToxicityFilter {
+    pub fn new(vpin: Vpin, widen_threshold: f64, pull_threshold: f64, max_widen_mult: f64) -> Self {
+        Self {
+            vpin,
+            widen_threshold,
+            pull_threshold,
+            max_widen_mult,
+        }
+    }
+
+    /// Feeds a trade into the VPIN estimator and returns the updated action.
+    ///
+    /// # Arguments
+    ///
+    /// * `price` - Trade price.
+    /// * `qty` - Trade quantity.
+    pub fn on_trade(&mut self, price: f64, qty: f64) -> QuoteAction {
+        self.vpin.update(price, qty);
+        self.action()
+    }
+
+    /// Feeds the trade events of a `Bot` into the VPIN estimator and returns
+    /// the updated action.
+    ///
+    /// The aggressor side is taken from the event flags, and inferred with the
+    /// tick rule for the trades that carry none.
+    ///
+    /// # Arguments
+    ///
+    /// * `trades` - Trades since the last call, e.g. `Bot::last_trades`.
+    pub fn on_trades(&mut self, trades: &[Event]) -> QuoteAction {
+        for trade in trades {
+            if trade.ev & BUY_EVENT != 0 {
+                self.vpin.update_with_side(trade.qty, true);
+            } else if trade.ev & SELL_EVENT != 0 {
+                self.vpin.update_with_side(trade.qty, false);
+            } else {
+                self.vpin.update(trade.px, trade.qty);
+            }
+        }
+        self.action()
+    }
+
+    /// Returns the quoting decision for the current VPIN value. Until enough
+    /// volume has traded to estimate VPIN, quotes are left untouched.
+    pub fn action(&self) -> QuoteAction {
+        let Some(vpin) = self.vpin.value() else {
+            return QuoteAction::Quote;
+        };
+
+        if vpin >= self.pull_threshold {
+            QuoteAction::Pull
+        } else if vpin > self.widen_threshold {
+            let intensity =
+                (vpin - self.widen_threshold) / (self.pull_threshold - self.widen_threshold);
+            QuoteAction::Widen(1.0 + intensity * (self.max_widen_mult - 1.0))
+        } else {
+            QuoteAction::Quote
+        }
+    }
+
+    /// Applies the current action to a half-spread.
+    ///
+    /// # Returns
+    ///
+    /// The adjusted half-spread, or `None` if quotes should be pulled.
+    pub fn adjust_half_spread(&self, half_spread: f64) -> Option<f64> {
+        match self.action() {
+            QuoteAction::Quote => Some(half_spread),
+            QuoteAction::Widen(mult) => Some(half_spread * mult),
+            QuoteAction::Pull => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balanced_flow_keeps_quoting() {
+        let mut filter = ToxicityFilter::new(Vpin::new(10.0, 3).unwrap(), 0.5, 0.9, 3.0);
+
+        for i in 0..60 {
+            let price = if i % 2 == 0 { 100.0 } else { 100.5 };
+            filter.on_trade(price, 5.0);
+        }
+
+        assert_eq!(filter.action(), QuoteAction::Quote);
+        assert_eq!(filter.adjust_half_spread(0.01), Some(0.01));
+    }
+
+    #[test]
+    fn test_one_sided_flow_pulls_quotes() {
+        let mut filter = ToxicityFilter::new(Vpin::new(10.0, 3).unwrap(), 0.5, 0.9, 3.0);
+
+        for i in 0..30 {
+            filter.on_trade(100.0 + i as f64, 5.0);
+        }
+
+        assert_eq!(filter.action(), QuoteAction::Pull);
+        assert_eq!(filter.adjust_half_spread(0.01), None);
+    }
+
+    #[test]
+    fn test_trade_events_sides() {
+        let trade = |ev: u64, px: f64| Event {
+            ev,
+            exch_ts: 0,
+            local_ts: 0,
+            px,
+            qty: 5.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        };
+        let params = ToxicityParams::new(10.0)
+            .with_window(2)
+            .with_thresholds(0.25, 0.75, 2.0);
+
+        // The flags win over the falling prices: a bucket of buys, then a
+        // balanced one
+        let mut filter = params.filter().unwrap();
+        let trades = [
+            trade(BUY_EVENT, 100.0),
+            trade(BUY_EVENT, 99.0),
+            trade(BUY_EVENT, 98.0),
+            trade(SELL_EVENT, 97.0),
+        ];
+        assert_eq!(filter.on_trades(&trades), QuoteAction::Widen(1.5));
+        assert_eq!(filter.adjust_half_spread(2.0), Some(3.0));
+
+        // Without flags, the rising prices are buys
+        let mut filter = params.filter().unwrap();
+        let trades = [100.0, 101.0, 102.0, 103.0].map(|px| trade(0, px));
+        assert_eq!(filter.on_trades(&trades), QuoteAction::Pull);
+
+        assert!(ToxicityParams::new(0.0).filter().is_err());
+    }
+}
@@ -1,3 +1,10 @@
 pub mod delta_scalping;
+pub mod futures_basis;
 pub mod opre_risk_arbitrage;
+pub mod option_chain;
+pub mod pricing;
+pub mod rates;
+pub mod risk_neutral;
 pub mod stochastic_arbitrage;
+pub mod synthetic_instrument;
+pub mod variance;
@@ -0,0 +1,185 @@
+/*!
+This module provides cost-of-carry pricing for dated futures: theoretical
+futures prices, annualized basis, and the no-arbitrage band outside of which a
+cash-and-carry (or reverse cash-and-carry) trade is profitable after fees.
+*/
+
+/// Financing and trading costs of a cash-and-carry position.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CarryCosts {
+    /// Annualized, continuously compounded rate paid to borrow cash.
+    pub borrow_rate: f64,
+    /// Annualized, continuously compounded rate earned by lending cash.
+    pub lend_rate: f64,
+    /// Fee rate paid per spot trade (e.g., 0.001 for 0.1%).
+    pub spot_fee: f64,
+    /// Fee rate paid per futures trade (e.g., 0.0005 for 0.05%).
+    pub futures_fee: f64,
+}
+
+/// Futures price bounds within which no carry arbitrage exists.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NoArbitrageBand {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// A carry arbitrage opportunity with its profit per unit of the underlying.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CarryArbitrage {
+    /// Futures are rich: buy spot, sell futures.
+    CashAndCarry { profit: f64 },
+    /// Futures are cheap: sell spot, buy futures.
+    ReverseCashAndCarry { profit: f64 },
+    /// The futures price lies within the no-arbitrage band.
+    None,
+}
+
+/// Calculates the theoretical futures price under cost of carry.
+///
+/// # Arguments
+///
+/// * `spot` - Spot price of the underlying.
+/// * `r` - Annualized, continuously compounded risk-free rate.
+/// * `q` - Annualized yield earned by holding the underlying (e.g., staking).
+/// * `t` - Time to expiry in years.
+///
+/// # Returns
+///
+/// The fair futures price `F = S * exp((r - q) * T)`.
+pub fn futures_fair_value(spot: f64, r: f64, q: f64, t: f64) -> f64 {
+    spot * ((r - q) * t).exp()
+}
+
+/// Calculates the annualized basis of a futures contract.
+///
+/// # Arguments
+///
+/// * `futures_price` - Futures price.
+/// * `spot` - Spot price of the underlying.
+/// * `t` - Time to expiry in years.
+///
+/// # Returns
+///
+/// The simple annualized basis `(F / S - 1) / T`.
+pub fn annualized_basis(futures_price: f64, spot: f64, t: f64) -> f64 {
+    (futures_price / spot - 1.0) / t
+}
+
+/// Calculates the carry rate implied by a futures price.
+///
+/// # Arguments
+///
+/// * `futures_price` - Futures price.
+/// * `spot` - Spot price of the underlying.
+/// * `t` - Time to expiry in years.
+///
+/// # Returns
+///
+/// The continuously compounded implied rate `ln(F / S) / T`.
+pub fn implied_carry_rate(futures_price: f64, spot: f64, t: f64) -> f64 {
+    (futures_price / spot).ln() / t
+}
+
+/// Calculates the no-arbitrage band for a futures price.
+///
+/// Both trades open and close a spot and a futures position, paying four
+/// fees in total. The cash-and-carry trade finances the spot purchase at the
+/// borrow rate, and the reverse trade lends out the short-sale proceeds at the
+/// lend rate.
+///
+/// # Arguments
+///
+/// * `spot` - Spot price of the underlying.
+/// * `t` - Time to expiry in years.
+/// * `costs` - Financing and trading costs.
+///
+/// # Returns
+///
+/// The lower and upper futures price bounds.
+pub fn no_arbitrage_band(spot: f64, t: f64, costs: &CarryCosts) -> NoArbitrageBand {
+    let fees = 2.0 * spot * (costs.spot_fee + costs.futures_fee);
+    NoArbitrageBand {
+        lower: spot * (costs.lend_rate * t).exp() - fees,
+        upper: spot * (costs.borrow_rate * t).exp() + fees,
+    }
+}
+
+/// Checks a futures price for a cash-and-carry arbitrage opportunity.
+///
+/// # Arguments
+///
+/// * `futures_price` - Futures price.
+/// * `spot` - Spot price of the underlying.
+/// * `t` - Time to expiry in years.
+/// * `costs` - Financing and trading costs.
+///
+/// # Returns
+///
+/// The opportunity and its profit per unit of the underlying after costs.
+pub fn find_carry_arbitrage(
+    futures_price: f64,
+    spot: f64,
+    t: f64,
+    costs: &CarryCosts,
+) -> CarryArbitrage {
+    let band = no_arbitrage_band(spot, t, costs);
+    if futures_price > band.upper {
+        CarryArbitrage::CashAndCarry {
+            profit: futures_price - band.upper,
+        }
+    } else if futures_price < band.lower {
+        CarryArbitrage::ReverseCashAndCarry {
+            profit: band.lower - futures_price,
+        }
+    } else {
+        CarryArbitrage::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_futures_fair_value() {
+        let fair_value = futures_fair_value(100.0, 0.05, 0.0, 1.0);
+        assert!((fair_value - 105.12710963760242).abs() < 1e-9);
+        assert_eq!(futures_fair_value(100.0, 0.05, 0.05, 1.0), 100.0);
+    }
+
+    #[test]
+    fn test_basis() {
+        assert!((annualized_basis(102.0, 100.0, 0.25) - 0.08).abs() < 1e-12);
+        let rate = implied_carry_rate(futures_fair_value(100.0, 0.05, 0.0, 0.5), 100.0, 0.5);
+        assert!((rate - 0.05).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_find_carry_arbitrage() {
+        let costs = CarryCosts {
+            borrow_rate: 0.06,
+            lend_rate: 0.04,
+            spot_fee: 0.001,
+            futures_fee: 0.0005,
+        };
+        let band = no_arbitrage_band(100.0, 1.0, &costs);
+
+        assert!(band.lower < band.upper);
+        assert_eq!(
+            find_carry_arbitrage(105.0, 100.0, 1.0, &costs),
+            CarryArbitrage::None
+        );
+        assert!(matches!(
+            find_carry_arbitrage(110.0, 100.0, 1.0, &costs),
+            CarryArbitrage::CashAndCarry { profit } if profit > 0.0
+        ));
+        assert!(matches!(
+            find_carry_arbitrage(100.0, 100.0, 1.0, &costs),
+            CarryArbitrage::ReverseCashAndCarry { profit } if profit > 0.0
+        ));
+    }
+}
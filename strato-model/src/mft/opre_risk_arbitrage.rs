@@ -9,8 +9,12 @@ use good_lp::Solution;
 use good_lp::SolverModel;
 use good_lp::Variable;
 
+use crate::mft::rates::RateCurve;
+use crate::mft::risk_neutral::breeden_litzenberger;
+
 /// Define option data structure
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OptionData {
     pub name: String,
     /// Underlying asset price
@@ -31,6 +35,7 @@ pub struct OptionData {
 
 /// Struct for managing the portfolio's holdings
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Portfolio {
     /// Portfolio holdings (option name, position size)
     pub holdings: Vec<(String, f64)>,
@@ -71,6 +76,28 @@ pub fn estimate_probabilities(
     (asset_prices, probabilities)
 }
 
+/// Function to estimate scenario probabilities from the risk-neutral density
+/// implied by call prices (Breeden-Litzenberger), as a model-free alternative
+/// to the binomial tree of `estimate_probabilities`.
+///
+/// All calls must share the same maturity and rate; puts are ignored.
+pub fn estimate_probabilities_from_calls(
+    option_data: &[OptionData],
+) -> Result<(Vec<f64>, Vec<f64>), String> {
+    let mut calls: Vec<&OptionData> = option_data
+        .iter()
+        .filter(|o| o.option_type == "call")
+        .collect();
+    calls.sort_by(|a, b| a.k.total_cmp(&b.k));
+
+    let first = calls.first().ok_or("No call options provided.")?;
+    let strikes: Vec<f64> = calls.iter().map(|o| o.k).collect();
+    let call_prices: Vec<f64> = calls.iter().map(|o| o.market_price).collect();
+
+    let density = breeden_litzenberger(&strikes, &call_prices, first.r, first.t)?;
+    Ok(density.scenario_probabilities())
+}
+
 /// Helper function to calculate binomial coefficients
 fn binomial_coefficient(n: usize, k: usize) -> f64 {
     if k > n {
@@ -244,6 +271,40 @@ pub fn construct_portfolio(
     // Estimate probabilities using a binomial tree model
     let (asset_prices, _probabilities) = estimate_probabilities(s0, r, sigma, t, steps);
 
+    construct_portfolio_with_states(
+        option_data,
+        capital,
+        asset_prices,
+        transaction_costs,
+        liquidity,
+    )
+}
+
+/// Portfolio construction function like `construct_portfolio`, with the rate
+/// of each option read from `curve` at its maturity instead of its own `r`.
+pub fn construct_portfolio_with_rates(
+    mut option_data: Vec<OptionData>,
+    curve: &RateCurve,
+    capital: f64,
+    steps: usize,
+    transaction_costs: Vec<f64>,
+    liquidity: Vec<f64>,
+) -> Result<Portfolio, String> {
+    for option in option_data.iter_mut() {
+        option.r = curve.rate(option.t);
+    }
+    construct_portfolio(option_data, capital, steps, transaction_costs, liquidity)
+}
+
+/// Portfolio construction function for a given set of terminal asset price
+/// scenarios, e.g. from `estimate_probabilities_from_calls`.
+pub fn construct_portfolio_with_states(
+    option_data: Vec<OptionData>,
+    capital: f64,
+    asset_prices: Vec<f64>,
+    transaction_costs: Vec<f64>,
+    liquidity: Vec<f64>,
+) -> Result<Portfolio, String> {
     let market_prices: Vec<f64> = option_data.iter().map(|o| o.market_price).collect();
 
     // Find optimal portfolio weights via linear programming
@@ -316,4 +377,27 @@ mod tests {
             println!("Option: {}, Position Size: {}", name, position);
         }
     }
+
+    #[test]
+    fn test_estimate_probabilities_from_calls() {
+        let option_data: Vec<OptionData> = [(90.0, 12.0), (100.0, 5.0), (110.0, 1.5), (120.0, 0.4)]
+            .iter()
+            .map(|&(k, market_price)| OptionData {
+                name: format!("Call {}", k),
+                k,
+                t: 0.5,
+                r: 0.05,
+                market_price,
+                option_type: "call".to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        let (asset_prices, probabilities) =
+            estimate_probabilities_from_calls(&option_data).unwrap();
+
+        assert_eq!(asset_prices, vec![100.0, 110.0]);
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(estimate_probabilities_from_calls(&[]).is_err());
+    }
 }
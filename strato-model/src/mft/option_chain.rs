@@ -0,0 +1,176 @@
+/*!
+This module provides an `OptionChain` container holding the option quotes of
+one underlying. It prices the whole chain in bulk and ranks the quotes by how
+far their market price deviates from the Black-Scholes theoretical price.
+*/
+
+use crate::mft::pricing::implied_volatility;
+use crate::mft::pricing::PricingError;
+use crate::mft::rates::apply_rate_curve;
+use crate::mft::rates::RateCurve;
+use crate::mft::stochastic_arbitrage::compute_theoretical_prices;
+use crate::mft::stochastic_arbitrage::OptionData;
+
+/// The quotes of all options on a single underlying.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptionChain {
+    pub underlying: String,
+    pub options: Vec<OptionData>,
+}
+
+/// A single option's deviation from its theoretical price.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mispricing {
+    pub name: String,
+    pub market_price: f64,
+    pub theoretical_price: f64,
+    /// `theoretical_price - market_price`; positive when the option is cheap.
+    pub edge: f64,
+    /// Implied volatility of the market price, if one exists.
+    pub implied_vol: Option<f64>,
+}
+
+impl OptionChain {
+    pub fn new(underlying: &str, options: Vec<OptionData>) -> Self {
+        Self {
+            underlying: underlying.to_string(),
+            options,
+        }
+    }
+
+    /// Prices every option with the rate `curve` gives at its maturity
+    /// instead of its own `r`.
+    pub fn with_rate_curve(mut self, curve: &RateCurve) -> Self {
+        apply_rate_curve(&mut self.options, curve);
+        self
+    }
+
+    /// Adds a quote to the chain.
+    pub fn push(&mut self, option: OptionData) {
+        self.options.push(option);
+    }
+
+    pub fn len(&self) -> usize {
+        self.options.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+
+    /// Market prices of every option in the chain.
+    pub fn market_prices(&self) -> Vec<f64> {
+        self.options.iter().map(|o| o.market_price).collect()
+    }
+
+    /// Black-Scholes prices of every option in the chain.
+    pub fn theoretical_prices(&self) -> Result<Vec<f64>, PricingError> {
+        compute_theoretical_prices(&self.options)
+    }
+
+    /// Implied volatilities of every option's market price. Quotes that no
+    /// volatility can reproduce (e.g., below intrinsic value) yield `None`.
+    pub fn implied_vols(&self) -> Vec<Option<f64>> {
+        self.options
+            .iter()
+            .map(|o| implied_volatility(o.market_price, o.s, o.k, o.t, o.r, &o.option_type).ok())
+            .collect()
+    }
+
+    /// Ranks the options by absolute mispricing, largest first.
+    ///
+    /// # Returns
+    ///
+    /// The ranked mispricings, or the first `PricingError` encountered while
+    /// computing theoretical prices.
+    pub fn rank_mispricings(&self) -> Result<Vec<Mispricing>, PricingError> {
+        let theoretical_prices = self.theoretical_prices()?;
+        let implied_vols = self.implied_vols();
+
+        let mut ranked: Vec<Mispricing> = self
+            .options
+            .iter()
+            .zip(theoretical_prices)
+            .zip(implied_vols)
+            .map(|((option, theoretical_price), implied_vol)| Mispricing {
+                name: option.name.clone(),
+                market_price: option.market_price,
+                theoretical_price,
+                edge: theoretical_price - option.market_price,
+                implied_vol,
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| b.edge.abs().total_cmp(&a.edge.abs()));
+        Ok(ranked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option(name: &str, k: f64, option_type: &str, market_price: f64) -> OptionData {
+        OptionData {
+            name: name.to_string(),
+            s: 100.0,
+            k,
+            t: 0.5,
+            r: 0.05,
+            sigma: 0.2,
+            option_type: option_type.to_string(),
+            market_price,
+        }
+    }
+
+    #[test]
+    fn test_rank_mispricings() {
+        let mut chain = OptionChain::new("BTC", vec![option("C100", 100.0, "call", 6.9)]);
+        chain.push(option("C110", 110.0, "call", 0.5));
+        chain.push(option("P90", 90.0, "put", 3.0));
+
+        let ranked = chain.rank_mispricings().unwrap();
+
+        assert_eq!(ranked.len(), 3);
+        assert!(ranked
+            .windows(2)
+            .all(|w| w[0].edge.abs() >= w[1].edge.abs()));
+        assert_eq!(ranked[0].name, "C110");
+        assert!(ranked[0].edge > 0.0);
+        assert!(ranked[0].implied_vol.unwrap() < 0.2);
+    }
+
+    #[test]
+    fn test_invalid_quote_is_reported() {
+        let chain = OptionChain::new("BTC", vec![option("C100", 100.0, "straddle", 5.0)]);
+        assert_eq!(
+            chain.rank_mispricings(),
+            Err(PricingError::UnknownOptionType)
+        );
+        assert_eq!(chain.implied_vols(), vec![None]);
+    }
+
+    #[test]
+    fn test_rate_curve_pricing() {
+        let curve = RateCurve::from_points(vec![(0.0, 0.01), (1.0, 0.09)]).unwrap();
+        let chain = OptionChain::new("BTC", vec![option("C100", 100.0, "call", 6.9)]);
+        let flat = chain.theoretical_prices().unwrap();
+
+        // The curve gives 5% at the half-year maturity, like the quote itself
+        let priced = chain
+            .clone()
+            .with_rate_curve(&curve)
+            .theoretical_prices()
+            .unwrap();
+        assert!((priced[0] - flat[0]).abs() < 1e-12);
+
+        let steeper = RateCurve::flat(0.09);
+        let priced = chain
+            .with_rate_curve(&steeper)
+            .theoretical_prices()
+            .unwrap();
+        assert!(priced[0] > flat[0]);
+    }
+}
@@ -0,0 +1,315 @@
+/*!
+Checked wrappers around the Black-Scholes pricing functions.
+
+The unchecked functions in `strato_pricer::bs` silently return NaN (or a
+meaningless number) when fed invalid inputs. The variants in this module
+validate their inputs first and return a `PricingError` instead.
+*/
+
+use std::fmt;
+
+#[cfg(feature = "decimal")]
+use rust_decimal::prelude::FromPrimitive;
+#[cfg(feature = "decimal")]
+use rust_decimal::prelude::ToPrimitive;
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+use strato_pricer::bs::black_scholes_call;
+use strato_pricer::bs::black_scholes_put;
+
+/// Errors returned by the checked pricing functions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PricingError {
+    /// An input was NaN.
+    NaN { field: &'static str },
+    /// The underlying or strike price was negative.
+    NegativePrice { field: &'static str, value: f64 },
+    /// The volatility was zero or negative.
+    NonPositiveSigma(f64),
+    /// The time to maturity was zero or negative.
+    NonPositiveTime(f64),
+    /// The option type was neither `"call"` nor `"put"`.
+    UnknownOptionType,
+    /// A value could not be converted between `f64` and `Decimal`.
+    NotRepresentable { field: &'static str },
+    /// No volatility reproduces the given option price.
+    NoImpliedVolatility(f64),
+}
+
+impl fmt::Display for PricingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PricingError::NaN { field } => write!(f, "{} is NaN", field),
+            PricingError::NegativePrice { field, value } => {
+                write!(f, "{} must not be negative, got {}", field, value)
+            }
+            PricingError::NonPositiveSigma(sigma) => {
+                write!(f, "sigma must be positive, got {}", sigma)
+            }
+            PricingError::NonPositiveTime(t) => {
+                write!(f, "time to maturity must be positive, got {}", t)
+            }
+            PricingError::UnknownOptionType => write!(f, "option type must be \"call\" or \"put\""),
+            PricingError::NotRepresentable { field } => {
+                write!(f, "{} cannot be represented as a decimal/float", field)
+            }
+            PricingError::NoImpliedVolatility(price) => {
+                write!(f, "no implied volatility matches price {}", price)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PricingError {}
+
+/// Validates the Black-Scholes inputs.
+///
+/// # Arguments
+///
+/// * `s` - Underlying asset price.
+/// * `k` - Strike price.
+/// * `t` - Time to maturity in years.
+/// * `r` - Risk-free interest rate.
+/// * `sigma` - Volatility of the underlying asset.
+///
+/// # Returns
+///
+/// `Ok(())` if the inputs are valid, otherwise the first violation found.
+pub fn validate_inputs(s: f64, k: f64, t: f64, r: f64, sigma: f64) -> Result<(), PricingError> {
+    for (field, value) in [("s", s), ("k", k), ("t", t), ("r", r), ("sigma", sigma)] {
+        if value.is_nan() {
+            return Err(PricingError::NaN { field });
+        }
+    }
+    if s < 0.0 {
+        return Err(PricingError::NegativePrice {
+            field: "s",
+            value: s,
+        });
+    }
+    if k < 0.0 {
+        return Err(PricingError::NegativePrice {
+            field: "k",
+            value: k,
+        });
+    }
+    if sigma <= 0.0 {
+        return Err(PricingError::NonPositiveSigma(sigma));
+    }
+    if t <= 0.0 {
+        return Err(PricingError::NonPositiveTime(t));
+    }
+    Ok(())
+}
+
+/// Black-Scholes call price that rejects invalid inputs.
+pub fn checked_black_scholes_call(
+    s: f64,
+    k: f64,
+    t: f64,
+    r: f64,
+    sigma: f64,
+) -> Result<f64, PricingError> {
+    validate_inputs(s, k, t, r, sigma)?;
+    Ok(black_scholes_call(s, k, t, r, sigma))
+}
+
+/// Black-Scholes put price that rejects invalid inputs.
+pub fn checked_black_scholes_put(
+    s: f64,
+    k: f64,
+    t: f64,
+    r: f64,
+    sigma: f64,
+) -> Result<f64, PricingError> {
+    validate_inputs(s, k, t, r, sigma)?;
+    Ok(black_scholes_put(s, k, t, r, sigma))
+}
+
+/// Lower bound of the implied volatility search.
+const MIN_IMPLIED_VOL: f64 = 1e-4;
+/// Upper bound of the implied volatility search (500%).
+const MAX_IMPLIED_VOL: f64 = 5.0;
+
+/// Calculates the Black-Scholes implied volatility of an option price by
+/// bisection.
+///
+/// # Arguments
+///
+/// * `price` - Observed option price.
+/// * `s` - Underlying asset price.
+/// * `k` - Strike price.
+/// * `t` - Time to maturity in years.
+/// * `r` - Risk-free interest rate.
+/// * `option_type` - `"call"` or `"put"`.
+///
+/// # Returns
+///
+/// The implied volatility, or an error if the inputs are invalid or the price
+/// cannot be matched by any volatility between 0.01% and 500%.
+pub fn implied_volatility(
+    price: f64,
+    s: f64,
+    k: f64,
+    t: f64,
+    r: f64,
+    option_type: &str,
+) -> Result<f64, PricingError> {
+    let pricer = match option_type {
+        "call" => checked_black_scholes_call,
+        "put" => checked_black_scholes_put,
+        _ => return Err(PricingError::UnknownOptionType),
+    };
+    if price.is_nan() {
+        return Err(PricingError::NaN { field: "price" });
+    }
+
+    let mut lo = MIN_IMPLIED_VOL;
+    let mut hi = MAX_IMPLIED_VOL;
+    if price < pricer(s, k, t, r, lo)? || price > pricer(s, k, t, r, hi)? {
+        return Err(PricingError::NoImpliedVolatility(price));
+    }
+
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if pricer(s, k, t, r, mid)? < price {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+        if hi - lo < 1e-10 {
+            break;
+        }
+    }
+
+    Ok(0.5 * (lo + hi))
+}
+
+/// Converts `Decimal` inputs to `f64`, prices them with `price` and converts
+/// the result back to `Decimal`.
+#[cfg(feature = "decimal")]
+fn price_decimal(
+    inputs: [Decimal; 5],
+    price: fn(f64, f64, f64, f64, f64) -> Result<f64, PricingError>,
+) -> Result<Decimal, PricingError> {
+    let mut values = [0.0; 5];
+    for ((field, input), value) in ["s", "k", "t", "r", "sigma"]
+        .into_iter()
+        .zip(inputs)
+        .zip(values.iter_mut())
+    {
+        *value = input
+            .to_f64()
+            .ok_or(PricingError::NotRepresentable { field })?;
+    }
+    let [s, k, t, r, sigma] = values;
+    let premium = price(s, k, t, r, sigma)?;
+    Decimal::from_f64(premium).ok_or(PricingError::NotRepresentable { field: "premium" })
+}
+
+/// Black-Scholes call price for `Decimal` inputs.
+///
+/// The model itself is evaluated in `f64`; only the inputs and the returned
+/// premium are `Decimal`, so the premium can feed settlement calculations
+/// (notional, fees, margin) without further float arithmetic.
+#[cfg(feature = "decimal")]
+pub fn checked_black_scholes_call_decimal(
+    s: Decimal,
+    k: Decimal,
+    t: Decimal,
+    r: Decimal,
+    sigma: Decimal,
+) -> Result<Decimal, PricingError> {
+    price_decimal([s, k, t, r, sigma], checked_black_scholes_call)
+}
+
+/// Black-Scholes put price for `Decimal` inputs.
+///
+/// See `checked_black_scholes_call_decimal`.
+#[cfg(feature = "decimal")]
+pub fn checked_black_scholes_put_decimal(
+    s: Decimal,
+    k: Decimal,
+    t: Decimal,
+    r: Decimal,
+    sigma: Decimal,
+) -> Result<Decimal, PricingError> {
+    price_decimal([s, k, t, r, sigma], checked_black_scholes_put)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_prices_match_unchecked() {
+        let call = checked_black_scholes_call(100.0, 100.0, 1.0, 0.05, 0.2).unwrap();
+        let put = checked_black_scholes_put(100.0, 100.0, 1.0, 0.05, 0.2).unwrap();
+
+        assert_eq!(call, black_scholes_call(100.0, 100.0, 1.0, 0.05, 0.2));
+        assert_eq!(put, black_scholes_put(100.0, 100.0, 1.0, 0.05, 0.2));
+    }
+
+    #[test]
+    fn test_rejects_invalid_inputs() {
+        assert_eq!(
+            checked_black_scholes_call(-1.0, 100.0, 1.0, 0.05, 0.2),
+            Err(PricingError::NegativePrice {
+                field: "s",
+                value: -1.0
+            })
+        );
+        assert_eq!(
+            checked_black_scholes_put(100.0, 100.0, 1.0, 0.05, 0.0),
+            Err(PricingError::NonPositiveSigma(0.0))
+        );
+        assert_eq!(
+            checked_black_scholes_put(100.0, 100.0, 0.0, 0.05, 0.2),
+            Err(PricingError::NonPositiveTime(0.0))
+        );
+        assert_eq!(
+            checked_black_scholes_call(100.0, f64::NAN, 1.0, 0.05, 0.2),
+            Err(PricingError::NaN { field: "k" })
+        );
+    }
+
+    #[test]
+    fn test_implied_volatility_round_trip() {
+        let call = black_scholes_call(100.0, 110.0, 0.5, 0.05, 0.35);
+        let put = black_scholes_put(100.0, 90.0, 0.5, 0.05, 0.25);
+
+        let call_vol = implied_volatility(call, 100.0, 110.0, 0.5, 0.05, "call").unwrap();
+        let put_vol = implied_volatility(put, 100.0, 90.0, 0.5, 0.05, "put").unwrap();
+
+        assert!((call_vol - 0.35).abs() < 1e-6);
+        assert!((put_vol - 0.25).abs() < 1e-6);
+        assert_eq!(
+            implied_volatility(150.0, 100.0, 110.0, 0.5, 0.05, "call"),
+            Err(PricingError::NoImpliedVolatility(150.0))
+        );
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal_pricing() {
+        let call = checked_black_scholes_call_decimal(
+            Decimal::new(100, 0),
+            Decimal::new(100, 0),
+            Decimal::ONE,
+            Decimal::new(5, 2),
+            Decimal::new(2, 1),
+        )
+        .unwrap();
+        let expected = checked_black_scholes_call(100.0, 100.0, 1.0, 0.05, 0.2).unwrap();
+
+        assert!((call.to_f64().unwrap() - expected).abs() < 1e-9);
+        assert!(checked_black_scholes_put_decimal(
+            Decimal::new(100, 0),
+            Decimal::new(100, 0),
+            Decimal::ZERO,
+            Decimal::new(5, 2),
+            Decimal::new(2, 1),
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,278 @@
+/*!
+This module provides a `RateCurve` abstraction for risk-free rates together
+with sources that build curves from proxy rates, so option pricing can use a
+market rate per maturity instead of a hard-coded `r` per option.
+
+The arbitrage models take a curve through their `construct_portfolio_with_rates`
+variants, and an `OptionChain` through `OptionChain::with_rate_curve`.
+
+The HTTP fetchers (US Treasury bills and stablecoin lending yields) are
+enabled with the `rates-http` feature.
+*/
+
+use crate::mft::stochastic_arbitrage::OptionData;
+
+/// A term structure of continuously compounded, annualized rates.
+///
+/// Rates between tenors are linearly interpolated, and rates outside the
+/// covered tenors are extrapolated flat.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RateCurve {
+    /// (tenor in years, rate) pairs sorted by tenor.
+    points: Vec<(f64, f64)>,
+}
+
+impl RateCurve {
+    /// Creates a curve with the same rate for every tenor.
+    pub fn flat(rate: f64) -> Self {
+        Self {
+            points: vec![(0.0, rate)],
+        }
+    }
+
+    /// Creates a curve from (tenor in years, rate) pairs.
+    ///
+    /// # Returns
+    ///
+    /// The curve, or an error if `points` is empty or contains NaN values.
+    pub fn from_points(mut points: Vec<(f64, f64)>) -> Result<Self, String> {
+        if points.is_empty() {
+            return Err("A rate curve needs at least one point.".to_string());
+        }
+        if points.iter().any(|(t, r)| t.is_nan() || r.is_nan()) {
+            return Err("Rate curve points must not be NaN.".to_string());
+        }
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self { points })
+    }
+
+    /// The (tenor, rate) points defining the curve.
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+
+    /// Returns the interpolated rate for time to maturity `t` (in years).
+    pub fn rate(&self, t: f64) -> f64 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+
+        let upper = self.points.partition_point(|&(tenor, _)| tenor < t);
+        let (t0, r0) = self.points[upper - 1];
+        let (t1, r1) = self.points[upper];
+        r0 + (r1 - r0) * (t - t0) / (t1 - t0)
+    }
+
+    /// Returns the discount factor `exp(-r(t) * t)`.
+    pub fn discount_factor(&self, t: f64) -> f64 {
+        (-self.rate(t) * t).exp()
+    }
+}
+
+/// Converts an annual percentage yield (e.g., `5.0` for 5%) to a
+/// continuously compounded rate.
+pub fn apy_to_continuous(apy_percent: f64) -> f64 {
+    (1.0 + apy_percent / 100.0).ln()
+}
+
+/// A source of risk-free rate curves.
+pub trait RateSource {
+    /// Human readable name of the source.
+    fn name(&self) -> &str;
+
+    /// Fetches the current rate curve.
+    fn fetch_curve(&self) -> anyhow::Result<RateCurve>;
+}
+
+/// A rate source returning a fixed curve, useful for backtests and tests.
+#[derive(Debug, Clone)]
+pub struct StaticRateSource {
+    pub curve: RateCurve,
+}
+
+impl RateSource for StaticRateSource {
+    fn name(&self) -> &str {
+        "static"
+    }
+
+    fn fetch_curve(&self) -> anyhow::Result<RateCurve> {
+        Ok(self.curve.clone())
+    }
+}
+
+/// Sets the risk-free rate of each option from the curve at its maturity.
+///
+/// # Arguments
+///
+/// * `option_data` - Options whose `r` field is overwritten.
+/// * `curve` - Curve to read rates from.
+pub fn apply_rate_curve(option_data: &mut [OptionData], curve: &RateCurve) {
+    for option in option_data.iter_mut() {
+        option.r = curve.rate(option.t);
+    }
+}
+
+#[cfg(feature = "rates-http")]
+pub use self::http::*;
+
+#[cfg(feature = "rates-http")]
+mod http {
+    use anyhow::anyhow;
+    use anyhow::Context;
+    use serde_json::Value;
+
+    use super::apy_to_continuous;
+    use super::RateCurve;
+    use super::RateSource;
+
+    const TREASURY_AVG_RATES_URL: &str = "https://api.fiscaldata.treasury.gov/services/api/fiscal_service/v2/accounting/od/avg_interest_rates?filter=security_desc:eq:Treasury%20Bills&sort=-record_date&page[size]=1";
+    const DEFILLAMA_POOLS_URL: &str = "https://yields.llama.fi/pools";
+
+    fn get_json(url: &str) -> anyhow::Result<Value> {
+        let body = reqwest::blocking::get(url)
+            .with_context(|| format!("Failed to fetch {}", url))?
+            .error_for_status()?
+            .text()?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Average interest rate on outstanding US Treasury bills, published by
+    /// the US Treasury Fiscal Data API, as a flat curve.
+    #[derive(Debug, Clone, Default)]
+    pub struct TreasuryBillSource;
+
+    impl TreasuryBillSource {
+        /// Parses a Fiscal Data `avg_interest_rates` response.
+        pub fn parse(response: &Value) -> anyhow::Result<RateCurve> {
+            let rate = response["data"][0]["avg_interest_rate_amt"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Missing avg_interest_rate_amt"))?
+                .parse::<f64>()?;
+            Ok(RateCurve::flat(apy_to_continuous(rate)))
+        }
+    }
+
+    impl RateSource for TreasuryBillSource {
+        fn name(&self) -> &str {
+            "us_treasury_bills"
+        }
+
+        fn fetch_curve(&self) -> anyhow::Result<RateCurve> {
+            Self::parse(&get_json(TREASURY_AVG_RATES_URL)?)
+        }
+    }
+
+    /// Stablecoin lending yield of a DefiLlama pool (e.g., USDC on Aave), as
+    /// a flat curve.
+    #[derive(Debug, Clone)]
+    pub struct StablecoinLendingSource {
+        /// DefiLlama pool identifier.
+        pub pool_id: String,
+    }
+
+    impl StablecoinLendingSource {
+        /// Parses a DefiLlama `/pools` response.
+        pub fn parse(&self, response: &Value) -> anyhow::Result<RateCurve> {
+            let pool = response["data"]
+                .as_array()
+                .and_then(|pools| pools.iter().find(|p| p["pool"] == self.pool_id.as_str()))
+                .ok_or_else(|| anyhow!("Pool {} not found", self.pool_id))?;
+            let apy = pool["apy"]
+                .as_f64()
+                .ok_or_else(|| anyhow!("Missing apy for pool {}", self.pool_id))?;
+            Ok(RateCurve::flat(apy_to_continuous(apy)))
+        }
+    }
+
+    impl RateSource for StablecoinLendingSource {
+        fn name(&self) -> &str {
+            "stablecoin_lending"
+        }
+
+        fn fetch_curve(&self) -> anyhow::Result<RateCurve> {
+            self.parse(&get_json(DEFILLAMA_POOLS_URL)?)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use serde_json::json;
+
+        use super::*;
+
+        #[test]
+        fn test_parse_treasury_response() {
+            let response = json!({
+                "data": [{ "record_date": "2024-08-31", "avg_interest_rate_amt": "5.000" }]
+            });
+            let curve = TreasuryBillSource::parse(&response).unwrap();
+            assert!((curve.rate(1.0) - 1.05_f64.ln()).abs() < 1e-12);
+        }
+
+        #[test]
+        fn test_parse_defillama_response() {
+            let source = StablecoinLendingSource {
+                pool_id: "usdc-aave".to_string(),
+            };
+            let response = json!({
+                "status": "success",
+                "data": [
+                    { "pool": "usdt-aave", "apy": 3.0 },
+                    { "pool": "usdc-aave", "apy": 4.0 }
+                ]
+            });
+            let curve = source.parse(&response).unwrap();
+            assert!((curve.rate(0.5) - 1.04_f64.ln()).abs() < 1e-12);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_curve_interpolation() {
+        let curve = RateCurve::from_points(vec![(1.0, 0.05), (0.25, 0.03)]).unwrap();
+
+        assert_eq!(curve.rate(0.0), 0.03);
+        assert_eq!(curve.rate(0.25), 0.03);
+        assert!((curve.rate(0.625) - 0.04).abs() < 1e-12);
+        assert_eq!(curve.rate(2.0), 0.05);
+        assert!(RateCurve::from_points(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_apply_rate_curve() {
+        let curve = RateCurve::from_points(vec![(0.0, 0.02), (1.0, 0.06)]).unwrap();
+        let mut option_data = vec![
+            OptionData {
+                t: 0.5,
+                ..Default::default()
+            },
+            OptionData {
+                t: 1.0,
+                ..Default::default()
+            },
+        ];
+
+        apply_rate_curve(&mut option_data, &curve);
+
+        assert!((option_data[0].r - 0.04).abs() < 1e-12);
+        assert_eq!(option_data[1].r, 0.06);
+    }
+
+    #[test]
+    fn test_static_rate_source() {
+        let source = StaticRateSource {
+            curve: RateCurve::flat(0.05),
+        };
+        assert_eq!(source.fetch_curve().unwrap().rate(3.0), 0.05);
+    }
+}
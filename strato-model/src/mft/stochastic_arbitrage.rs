@@ -7,11 +7,16 @@ use good_lp::ProblemVariables;
 use good_lp::Solution;
 use good_lp::SolverModel;
 use good_lp::Variable;
-use strato_pricer::bs::black_scholes_call;
-use strato_pricer::bs::black_scholes_put;
+
+use crate::mft::pricing::checked_black_scholes_call;
+use crate::mft::pricing::checked_black_scholes_put;
+use crate::mft::pricing::PricingError;
+use crate::mft::rates::apply_rate_curve;
+use crate::mft::rates::RateCurve;
 
 /// Represents the data for an option.
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OptionData {
     pub name: String,
     /// Underlying asset price (S).
@@ -31,6 +36,7 @@ pub struct OptionData {
 }
 
 /// Manages the portfolio's holdings.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Portfolio {
     /// Portfolio holdings as a vector of (option name, position size).
     pub holdings: Vec<(String, f64)>,
@@ -53,7 +59,8 @@ pub struct Portfolio {
 ///
 /// # Returns
 ///
-/// A vector of optimal positions (weights) for each option.
+/// A vector of optimal positions (weights) for each option, or a
+/// `PricingError` if any option has invalid pricing inputs.
 ///
 /// # Mathematical Formulation
 ///
@@ -101,7 +108,7 @@ pub fn find_arbitrage(
     index_returns: Vec<f64>,
     risk_levels: &[f64],
     option_data: &[OptionData],
-) -> Vec<f64> {
+) -> Result<Vec<f64>, PricingError> {
     let num_assets = market_prices.len();
     let num_states = index_returns.len();
 
@@ -112,7 +119,7 @@ pub fn find_arbitrage(
         initialize_weights(&mut vars, num_assets, &liquidity);
 
     // Compute theoretical prices using the Black-Scholes model
-    let theoretical_prices = compute_theoretical_prices(option_data);
+    let theoretical_prices = compute_theoretical_prices(option_data)?;
 
     // Build the objective function (profit maximization)
     let objective = build_objective(
@@ -177,7 +184,7 @@ pub fn find_arbitrage(
     let solution = problem.solve().unwrap();
 
     // Retrieve final positions (weights) for each option
-    weights.iter().map(|&var| solution.value(var)).collect()
+    Ok(weights.iter().map(|&var| solution.value(var)).collect())
 }
 
 /// Initializes variables for option positions and sets up equality constraints.
@@ -242,7 +249,8 @@ fn initialize_weights(
 ///
 /// # Returns
 ///
-/// A vector of theoretical prices for each option.
+/// A vector of theoretical prices for each option, or the first
+/// `PricingError` encountered.
 ///
 /// # Mathematical Formulation
 ///
@@ -266,15 +274,19 @@ fn initialize_weights(
 /// - `r` is the risk-free interest rate.
 /// - `σ` is the volatility.
 /// - `T` is the time to maturity.
-fn compute_theoretical_prices(option_data: &[OptionData]) -> Vec<f64> {
+pub(crate) fn compute_theoretical_prices(
+    option_data: &[OptionData],
+) -> Result<Vec<f64>, PricingError> {
     option_data
         .iter()
-        .map(|option| {
-            if option.option_type == "call" {
-                black_scholes_call(option.s, option.k, option.t, option.r, option.sigma)
-            } else {
-                black_scholes_put(option.s, option.k, option.t, option.r, option.sigma)
+        .map(|option| match option.option_type.as_str() {
+            "call" => {
+                checked_black_scholes_call(option.s, option.k, option.t, option.r, option.sigma)
             }
+            "put" => {
+                checked_black_scholes_put(option.s, option.k, option.t, option.r, option.sigma)
+            }
+            _ => Err(PricingError::UnknownOptionType),
         })
         .collect()
 }
@@ -445,7 +457,8 @@ fn add_stochastic_dominance_constraints(
 ///
 /// # Returns
 ///
-/// A `Portfolio` containing the holdings (option names and positions).
+/// A `Portfolio` containing the holdings (option names and positions), or a
+/// `PricingError` if any option has invalid pricing inputs.
 ///
 /// # Example
 ///
@@ -486,7 +499,7 @@ pub fn construct_portfolio(
     index_returns: Vec<f64>,
     transaction_costs: Vec<f64>,
     liquidity: Vec<f64>,
-) -> Portfolio {
+) -> Result<Portfolio, PricingError> {
     let market_prices: Vec<f64> = option_data.iter().map(|o| o.market_price).collect();
 
     // Calculate expected payoffs for each option (not directly used in
@@ -510,7 +523,7 @@ pub fn construct_portfolio(
         index_returns,
         risk_levels,
         &option_data,
-    );
+    )?;
 
     // Create portfolio holdings
     let holdings = option_data
@@ -519,5 +532,37 @@ pub fn construct_portfolio(
         .map(|(option, &weight)| (option.name.clone(), weight))
         .collect();
 
-    Portfolio { holdings }
+    Ok(Portfolio { holdings })
+}
+
+/// Constructs the portfolio like `construct_portfolio`, pricing each option
+/// with the rate `curve` gives at its maturity instead of its own `r`.
+///
+/// # Arguments
+///
+/// * `option_data` - Vector of `OptionData` for each option.
+/// * `curve` - Risk-free rate curve, e.g. from a `RateSource`.
+/// * `capital` - Total capital available for investment.
+/// * `risk_levels` - Array of risk levels for stochastic dominance constraints.
+/// * `index_returns` - Real or simulated index returns for benchmarking.
+/// * `transaction_costs` - Transaction costs for each option.
+/// * `liquidity` - Liquidity constraints for each option.
+pub fn construct_portfolio_with_rates(
+    mut option_data: Vec<OptionData>,
+    curve: &RateCurve,
+    capital: f64,
+    risk_levels: &[f64],
+    index_returns: Vec<f64>,
+    transaction_costs: Vec<f64>,
+    liquidity: Vec<f64>,
+) -> Result<Portfolio, PricingError> {
+    apply_rate_curve(&mut option_data, curve);
+    construct_portfolio(
+        option_data,
+        capital,
+        risk_levels,
+        index_returns,
+        transaction_costs,
+        liquidity,
+    )
 }
@@ -0,0 +1,326 @@
+/*!
+This module provides a `SyntheticInstrument` type representing a weighted
+basket of legs, such as a perp-minus-spot basis trade or a vertical call
+spread.
+
+The basket exposes combined market and theoretical prices, aggregated Greeks,
+and an execution planner that legs into the market in slices small enough to
+keep the unhedged exposure between legs within configurable limits.
+*/
+
+use strato_pricer::bs::black_scholes_call;
+use strato_pricer::bs::black_scholes_put;
+
+use crate::hft::hft_oir::Side;
+use crate::mft::delta_scalping::calculate_d1;
+use crate::mft::delta_scalping::calculate_greeks_from_d1;
+use crate::mft::stochastic_arbitrage::OptionData;
+
+/// The kind of instrument traded by a single leg.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LegKind {
+    /// A delta-one instrument (spot, perpetual or dated future) quoted at
+    /// `price`.
+    Linear { price: f64 },
+    /// A European option priced with Black-Scholes.
+    Option(OptionData),
+}
+
+/// A single weighted leg of a synthetic instrument.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Leg {
+    pub name: String,
+    pub kind: LegKind,
+    /// Units of this leg per unit of the synthetic. Negative weights are
+    /// short legs.
+    pub weight: f64,
+}
+
+/// Aggregated sensitivities of an instrument.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Greeks {
+    pub delta: f64,
+    pub gamma: f64,
+    pub vega: f64,
+}
+
+/// Limits applied while legging into a synthetic instrument.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegRiskLimits {
+    /// Maximum absolute delta that may be left unhedged between legs.
+    pub max_leg_delta: f64,
+    /// Maximum absolute notional that may be left unhedged between legs.
+    pub max_leg_notional: f64,
+}
+
+/// A child order produced by the execution planner.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegOrder {
+    /// Index of the slice this order belongs to.
+    pub slice: usize,
+    pub leg: String,
+    pub side: Side,
+    pub qty: f64,
+}
+
+/// A weighted basket of legs traded as one instrument.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyntheticInstrument {
+    pub name: String,
+    pub legs: Vec<Leg>,
+}
+
+impl Leg {
+    /// Market price of one unit of the leg.
+    pub fn market_price(&self) -> f64 {
+        match &self.kind {
+            LegKind::Linear { price } => *price,
+            LegKind::Option(option) => option.market_price,
+        }
+    }
+
+    /// Theoretical price of one unit of the leg. Linear legs are assumed to
+    /// trade at fair value.
+    pub fn theoretical_price(&self) -> f64 {
+        match &self.kind {
+            LegKind::Linear { price } => *price,
+            LegKind::Option(o) if o.option_type == "call" => {
+                black_scholes_call(o.s, o.k, o.t, o.r, o.sigma)
+            }
+            LegKind::Option(o) => black_scholes_put(o.s, o.k, o.t, o.r, o.sigma),
+        }
+    }
+
+    /// Greeks of one unit of the leg.
+    pub fn greeks(&self) -> Greeks {
+        match &self.kind {
+            LegKind::Linear { .. } => Greeks {
+                delta: 1.0,
+                ..Default::default()
+            },
+            LegKind::Option(o) => {
+                let d1 = calculate_d1(o.s, o.k, o.t, o.r, o.sigma);
+                let (delta_call, delta_put, gamma) =
+                    calculate_greeks_from_d1(d1, o.s, o.t, o.sigma);
+                // Vega follows from gamma: S^2 * σ * T * Γ = S * φ(d1) * sqrt(T)
+                let vega = o.s * o.s * o.sigma * o.t * gamma;
+                Greeks {
+                    delta: if o.option_type == "call" {
+                        delta_call
+                    } else {
+                        delta_put
+                    },
+                    gamma,
+                    vega,
+                }
+            }
+        }
+    }
+}
+
+impl SyntheticInstrument {
+    pub fn new(name: &str, legs: Vec<Leg>) -> Self {
+        Self {
+            name: name.to_string(),
+            legs,
+        }
+    }
+
+    /// Combined market price of one unit of the synthetic.
+    pub fn market_price(&self) -> f64 {
+        self.legs
+            .iter()
+            .map(|leg| leg.weight * leg.market_price())
+            .sum()
+    }
+
+    /// Combined theoretical price of one unit of the synthetic.
+    pub fn theoretical_price(&self) -> f64 {
+        self.legs
+            .iter()
+            .map(|leg| leg.weight * leg.theoretical_price())
+            .sum()
+    }
+
+    /// Combined Greeks of one unit of the synthetic.
+    pub fn greeks(&self) -> Greeks {
+        self.legs.iter().fold(Greeks::default(), |acc, leg| {
+            let g = leg.greeks();
+            Greeks {
+                delta: acc.delta + leg.weight * g.delta,
+                gamma: acc.gamma + leg.weight * g.gamma,
+                vega: acc.vega + leg.weight * g.vega,
+            }
+        })
+    }
+
+    /// Plans the child orders needed to buy (`qty > 0`) or sell (`qty < 0`)
+    /// the synthetic by legging into the market.
+    ///
+    /// Legs are executed in the order they are defined. The total quantity
+    /// is split into equal slices so that the partially filled basket never
+    /// carries more than `limits.max_leg_delta` delta or
+    /// `limits.max_leg_notional` notional while waiting for the remaining legs
+    /// of a slice.
+    ///
+    /// # Arguments
+    ///
+    /// * `qty` - Signed number of synthetic units to trade.
+    /// * `limits` - Leg-risk limits.
+    ///
+    /// # Returns
+    ///
+    /// The child orders in execution order, or an error if the limits are not
+    /// positive.
+    pub fn plan_execution(
+        &self,
+        qty: f64,
+        limits: &LegRiskLimits,
+    ) -> Result<Vec<LegOrder>, String> {
+        if limits.max_leg_delta <= 0.0 || limits.max_leg_notional <= 0.0 {
+            return Err("Leg-risk limits must be positive.".to_string());
+        }
+        if qty == 0.0 || self.legs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Worst exposure per synthetic unit after each partial prefix of legs
+        let mut partial_delta = 0.0_f64;
+        let mut partial_notional = 0.0_f64;
+        let mut worst_delta = 0.0_f64;
+        let mut worst_notional = 0.0_f64;
+        for leg in &self.legs[..self.legs.len() - 1] {
+            partial_delta += leg.weight * leg.greeks().delta;
+            partial_notional += leg.weight * leg.market_price();
+            worst_delta = worst_delta.max(partial_delta.abs());
+            worst_notional = worst_notional.max(partial_notional.abs());
+        }
+
+        let slices_for_delta = (qty.abs() * worst_delta / limits.max_leg_delta).ceil();
+        let slices_for_notional = (qty.abs() * worst_notional / limits.max_leg_notional).ceil();
+        let slices = slices_for_delta.max(slices_for_notional).max(1.0) as usize;
+        let slice_qty = qty / slices as f64;
+
+        let mut orders = Vec::with_capacity(slices * self.legs.len());
+        for slice in 0..slices {
+            for leg in &self.legs {
+                let leg_qty = slice_qty * leg.weight;
+                if leg_qty == 0.0 {
+                    continue;
+                }
+                orders.push(LegOrder {
+                    slice,
+                    leg: leg.name.clone(),
+                    side: if leg_qty > 0.0 { Side::Buy } else { Side::Sell },
+                    qty: leg_qty.abs(),
+                });
+            }
+        }
+
+        Ok(orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(k: f64, market_price: f64) -> OptionData {
+        OptionData {
+            name: format!("C{}", k),
+            s: 100.0,
+            k,
+            t: 0.5,
+            r: 0.05,
+            sigma: 0.2,
+            option_type: "call".to_string(),
+            market_price,
+        }
+    }
+
+    fn basis_trade() -> SyntheticInstrument {
+        SyntheticInstrument::new(
+            "perp-spot",
+            vec![
+                Leg {
+                    name: "perp".to_string(),
+                    kind: LegKind::Linear { price: 101.0 },
+                    weight: 1.0,
+                },
+                Leg {
+                    name: "spot".to_string(),
+                    kind: LegKind::Linear { price: 100.0 },
+                    weight: -1.0,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_basis_trade_pricing_and_greeks() {
+        let synthetic = basis_trade();
+
+        assert_eq!(synthetic.market_price(), 1.0);
+        assert_eq!(synthetic.theoretical_price(), 1.0);
+        assert_eq!(synthetic.greeks(), Greeks::default());
+    }
+
+    #[test]
+    fn test_call_spread_greeks() {
+        let synthetic = SyntheticInstrument::new(
+            "100/110 call spread",
+            vec![
+                Leg {
+                    name: "C100".to_string(),
+                    kind: LegKind::Option(call(100.0, 7.0)),
+                    weight: 1.0,
+                },
+                Leg {
+                    name: "C110".to_string(),
+                    kind: LegKind::Option(call(110.0, 3.0)),
+                    weight: -1.0,
+                },
+            ],
+        );
+
+        let greeks = synthetic.greeks();
+        assert_eq!(synthetic.market_price(), 4.0);
+        assert!(greeks.delta > 0.0 && greeks.delta < 1.0);
+        assert!(synthetic.theoretical_price() > 0.0);
+        assert!(synthetic.theoretical_price() < 10.0);
+    }
+
+    #[test]
+    fn test_plan_execution_respects_leg_limits() {
+        let synthetic = basis_trade();
+        let limits = LegRiskLimits {
+            max_leg_delta: 2.5,
+            max_leg_notional: 1_000_000.0,
+        };
+
+        let orders = synthetic.plan_execution(10.0, &limits).unwrap();
+
+        // 10 units with 1 delta left open after the first leg need 4 slices
+        assert_eq!(orders.len(), 8);
+        assert_eq!(orders[0].leg, "perp");
+        assert_eq!(orders[0].side, Side::Buy);
+        assert_eq!(orders[1].side, Side::Sell);
+        assert!(orders.iter().all(|o| o.qty == 2.5));
+    }
+
+    #[test]
+    fn test_plan_execution_rejects_invalid_limits() {
+        let limits = LegRiskLimits {
+            max_leg_delta: 0.0,
+            max_leg_notional: 1.0,
+        };
+
+        assert!(basis_trade().plan_execution(1.0, &limits).is_err());
+    }
+}
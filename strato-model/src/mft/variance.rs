@@ -0,0 +1,206 @@
+/*!
+This module computes model-free implied variance from an option chain
+following the CBOE VIX methodology, together with the fair strike of a
+variance swap and a 30-day VIX-style index interpolated between two
+expiries.
+*/
+
+use crate::mft::stochastic_arbitrage::OptionData;
+
+/// Number of years in the 30-day target horizon of the VIX.
+const VIX_HORIZON: f64 = 30.0 / 365.0;
+
+/// Calculates the model-free implied variance of a single expiry.
+///
+/// All options must share the same time to maturity `t` and rate `r`. The
+/// forward price is implied from put-call parity at the strike where the call
+/// and put prices are closest, and out-of-the-money options are integrated
+/// across strikes.
+///
+/// # Arguments
+///
+/// * `chain` - Calls and puts of one expiry. Options with a non-positive market
+///   price are ignored.
+///
+/// # Returns
+///
+/// The annualized implied variance, or an error if the chain has no strike
+/// quoted on both sides.
+///
+/// # Mathematical Formulation
+///
+/// `σ² = (2 / T) Σ (ΔK_i / K_i²) * exp(rT) * Q(K_i) - (1 / T) * (F / K_0 - 1)²`
+///
+/// where:
+/// - `F = K* + exp(rT) * (C(K*) - P(K*))`, where `K*` is the strike with the
+///   smallest absolute call-put price difference.
+/// - `K_0` is the first strike at or below `F`.
+/// - `Q(K_i)` is the put price below `K_0`, the call price above `K_0`, and the
+///   average of both at `K_0`.
+/// - `ΔK_i` is half the distance between the neighbouring strikes.
+pub fn implied_variance(chain: &[OptionData]) -> Result<f64, String> {
+    let quoted: Vec<&OptionData> = chain.iter().filter(|o| o.market_price > 0.0).collect();
+    let Some(first) = quoted.first() else {
+        return Err("Option chain is empty.".to_string());
+    };
+    let t = first.t;
+    let r = first.r;
+    let growth = (r * t).exp();
+
+    // (strike, call price, put price) for every quoted strike
+    let mut strikes: Vec<(f64, Option<f64>, Option<f64>)> = Vec::new();
+    for option in &quoted {
+        let index = match strikes.iter().position(|(k, _, _)| *k == option.k) {
+            Some(index) => index,
+            None => {
+                strikes.push((option.k, None, None));
+                strikes.len() - 1
+            }
+        };
+        match option.option_type.as_str() {
+            "call" => strikes[index].1 = Some(option.market_price),
+            "put" => strikes[index].2 = Some(option.market_price),
+            _ => return Err(format!("Unknown option type for {}.", option.name)),
+        }
+    }
+    strikes.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let (k_star, call_star, put_star) = strikes
+        .iter()
+        .filter_map(|&(k, c, p)| Some((k, c?, p?)))
+        .min_by(|a, b| (a.1 - a.2).abs().total_cmp(&(b.1 - b.2).abs()))
+        .ok_or("No strike is quoted for both calls and puts.")?;
+    let forward = k_star + growth * (call_star - put_star);
+
+    let k0 = strikes
+        .iter()
+        .map(|&(k, _, _)| k)
+        .rev()
+        .find(|&k| k <= forward)
+        .unwrap_or(strikes[0].0);
+
+    // Out-of-the-money price at each strike
+    let otm: Vec<(f64, f64)> = strikes
+        .iter()
+        .filter_map(|&(k, c, p)| {
+            let q = if k < k0 {
+                p?
+            } else if k > k0 {
+                c?
+            } else {
+                match (c, p) {
+                    (Some(c), Some(p)) => (c + p) / 2.0,
+                    (c, p) => c.or(p)?,
+                }
+            };
+            Some((k, q))
+        })
+        .collect();
+
+    let mut sum = 0.0;
+    for (i, &(k, q)) in otm.iter().enumerate() {
+        let delta_k = match (i.checked_sub(1).map(|j| otm[j].0), otm.get(i + 1)) {
+            (Some(prev), Some(&(next, _))) => (next - prev) / 2.0,
+            (None, Some(&(next, _))) => next - k,
+            (Some(prev), None) => k - prev,
+            (None, None) => 0.0,
+        };
+        sum += delta_k / (k * k) * growth * q;
+    }
+
+    Ok(2.0 / t * sum - (forward / k0 - 1.0).powi(2) / t)
+}
+
+/// Calculates the fair volatility strike of a variance swap, in volatility
+/// points (e.g., `20.0` for 20% volatility).
+pub fn variance_swap_strike(chain: &[OptionData]) -> Result<f64, String> {
+    Ok(implied_variance(chain)?.max(0.0).sqrt() * 100.0)
+}
+
+/// Calculates a VIX-style 30-day volatility index from the near- and
+/// next-term option chains.
+///
+/// # Arguments
+///
+/// * `near_term` - Chain expiring before the 30-day horizon.
+/// * `next_term` - Chain expiring after the 30-day horizon.
+///
+/// # Returns
+///
+/// The index value in volatility points.
+pub fn vix_index(near_term: &[OptionData], next_term: &[OptionData]) -> Result<f64, String> {
+    let t1 = near_term.first().ok_or("Near-term chain is empty.")?.t;
+    let t2 = next_term.first().ok_or("Next-term chain is empty.")?.t;
+    if t1 >= t2 {
+        return Err("Near-term expiry must be before next-term expiry.".to_string());
+    }
+
+    let var1 = implied_variance(near_term)?;
+    let var2 = implied_variance(next_term)?;
+    let w1 = (t2 - VIX_HORIZON) / (t2 - t1);
+    let w2 = (VIX_HORIZON - t1) / (t2 - t1);
+    let variance = (t1 * var1 * w1 + t2 * var2 * w2) / VIX_HORIZON;
+
+    Ok(variance.max(0.0).sqrt() * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use strato_pricer::bs::black_scholes_call;
+    use strato_pricer::bs::black_scholes_put;
+
+    use super::*;
+
+    fn chain(t: f64, sigma: f64) -> Vec<OptionData> {
+        let (s, r) = (100.0, 0.05);
+        (40..=250)
+            .flat_map(|k| {
+                let k = k as f64;
+                [
+                    OptionData {
+                        name: format!("C{}", k),
+                        s,
+                        k,
+                        t,
+                        r,
+                        sigma,
+                        option_type: "call".to_string(),
+                        market_price: black_scholes_call(s, k, t, r, sigma),
+                    },
+                    OptionData {
+                        name: format!("P{}", k),
+                        s,
+                        k,
+                        t,
+                        r,
+                        sigma,
+                        option_type: "put".to_string(),
+                        market_price: black_scholes_put(s, k, t, r, sigma),
+                    },
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_implied_variance_recovers_flat_volatility() {
+        let variance = implied_variance(&chain(0.25, 0.2)).unwrap();
+        assert!((variance - 0.04).abs() < 1e-3);
+
+        let strike = variance_swap_strike(&chain(0.25, 0.2)).unwrap();
+        assert!((strike - 20.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_vix_index() {
+        let vix = vix_index(&chain(20.0 / 365.0, 0.3), &chain(40.0 / 365.0, 0.3)).unwrap();
+        assert!((vix - 30.0).abs() < 0.5);
+
+        assert!(vix_index(&chain(0.2, 0.3), &chain(0.1, 0.3)).is_err());
+    }
+
+    #[test]
+    fn test_empty_chain() {
+        assert!(implied_variance(&[]).is_err());
+    }
+}
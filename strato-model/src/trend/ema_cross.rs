@@ -1,5 +1,6 @@
 /// Enum representing trading signals
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Signal {
     Buy,
     Sell,
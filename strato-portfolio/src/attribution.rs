@@ -0,0 +1,202 @@
+//! PnL attribution for multi-strategy runs.
+//!
+//! Every cash flow is tagged with the strategy (sleeve) and instrument that
+//! produced it, so the total PnL can be broken down into a strategy by
+//! instrument matrix, either over the whole run or cumulatively over time.
+
+use std::collections::BTreeSet;
+
+/// A cash flow tagged with its origin.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CashFlow {
+    /// Time of the cash flow in Unix milliseconds.
+    pub timestamp: i64,
+    pub strategy: String,
+    pub instrument: String,
+    /// Signed amount: positive for gains/receipts, negative for losses/fees.
+    pub amount: f64,
+}
+
+/// PnL broken down by strategy (rows) and instrument (columns).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttributionMatrix {
+    pub strategies: Vec<String>,
+    pub instruments: Vec<String>,
+    /// `pnl[i][j]` is the PnL of `strategies[i]` on `instruments[j]`.
+    pub pnl: Vec<Vec<f64>>,
+}
+
+impl AttributionMatrix {
+    /// PnL of a strategy on an instrument, or `0.0` if either is unknown.
+    pub fn get(&self, strategy: &str, instrument: &str) -> f64 {
+        let row = self.strategies.iter().position(|s| s == strategy);
+        let col = self.instruments.iter().position(|i| i == instrument);
+        match (row, col) {
+            (Some(row), Some(col)) => self.pnl[row][col],
+            _ => 0.0,
+        }
+    }
+
+    /// Total PnL of each strategy across instruments.
+    pub fn strategy_totals(&self) -> Vec<f64> {
+        self.pnl.iter().map(|row| row.iter().sum()).collect()
+    }
+
+    /// Total PnL of each instrument across strategies.
+    pub fn instrument_totals(&self) -> Vec<f64> {
+        (0..self.instruments.len())
+            .map(|col| self.pnl.iter().map(|row| row[col]).sum())
+            .collect()
+    }
+
+    /// Total PnL.
+    pub fn total(&self) -> f64 {
+        self.pnl.iter().flatten().sum()
+    }
+}
+
+/// A ledger of tagged cash flows.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PnlLedger {
+    pub flows: Vec<CashFlow>,
+}
+
+impl PnlLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a cash flow.
+    pub fn record(&mut self, timestamp: i64, strategy: &str, instrument: &str, amount: f64) {
+        self.flows.push(CashFlow {
+            timestamp,
+            strategy: strategy.to_string(),
+            instrument: instrument.to_string(),
+            amount,
+        });
+    }
+
+    /// Builds the attribution matrix over all recorded cash flows.
+    pub fn attribution(&self) -> AttributionMatrix {
+        self.attribution_until(i64::MAX)
+    }
+
+    /// Builds the cumulative attribution matrix at the end of each period.
+    ///
+    /// # Arguments
+    ///
+    /// * `period` - Period length in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// A vector of (period end timestamp, cumulative matrix) pairs covering
+    /// all cash flows, or an error if `period` is not positive. Every matrix
+    /// has the same rows and columns.
+    pub fn attribution_over_time(
+        &self,
+        period: i64,
+    ) -> Result<Vec<(i64, AttributionMatrix)>, String> {
+        if period <= 0 {
+            return Err(format!(
+                "Cannot attribute PnL over {} ms periods: the period must be positive.",
+                period
+            ));
+        }
+        let (Some(first), Some(last)) = (
+            self.flows.iter().map(|f| f.timestamp).min(),
+            self.flows.iter().map(|f| f.timestamp).max(),
+        ) else {
+            return Ok(Vec::new());
+        };
+
+        let mut snapshots = Vec::new();
+        let mut end = first - first.rem_euclid(period) + period;
+        loop {
+            snapshots.push((end, self.attribution_until(end)));
+            if end > last {
+                break;
+            }
+            end += period;
+        }
+        Ok(snapshots)
+    }
+
+    /// Builds the attribution matrix of the cash flows strictly before `end`.
+    fn attribution_until(&self, end: i64) -> AttributionMatrix {
+        let strategies: Vec<String> = self
+            .flows
+            .iter()
+            .map(|f| f.strategy.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let instruments: Vec<String> = self
+            .flows
+            .iter()
+            .map(|f| f.instrument.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let mut pnl = vec![vec![0.0; instruments.len()]; strategies.len()];
+        for flow in self.flows.iter().filter(|f| f.timestamp < end) {
+            let row = strategies.binary_search(&flow.strategy).unwrap();
+            let col = instruments.binary_search(&flow.instrument).unwrap();
+            pnl[row][col] += flow.amount;
+        }
+
+        AttributionMatrix {
+            strategies,
+            instruments,
+            pnl,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger() -> PnlLedger {
+        let mut ledger = PnlLedger::new();
+        ledger.record(0, "grid", "BTCUSDT", 10.0);
+        ledger.record(500, "grid", "ETHUSDT", -4.0);
+        ledger.record(1_200, "oir", "BTCUSDT", 3.0);
+        ledger.record(2_500, "grid", "BTCUSDT", -1.0);
+        ledger
+    }
+
+    #[test]
+    fn test_attribution() {
+        let matrix = ledger().attribution();
+
+        assert_eq!(matrix.strategies, vec!["grid", "oir"]);
+        assert_eq!(matrix.instruments, vec!["BTCUSDT", "ETHUSDT"]);
+        assert_eq!(matrix.get("grid", "BTCUSDT"), 9.0);
+        assert_eq!(matrix.get("oir", "ETHUSDT"), 0.0);
+        assert_eq!(matrix.strategy_totals(), vec![5.0, 3.0]);
+        assert_eq!(matrix.instrument_totals(), vec![12.0, -4.0]);
+        assert_eq!(matrix.total(), 8.0);
+    }
+
+    #[test]
+    fn test_attribution_over_time() {
+        let snapshots = ledger().attribution_over_time(1_000).unwrap();
+
+        let ends: Vec<i64> = snapshots.iter().map(|(end, _)| *end).collect();
+        assert_eq!(ends, vec![1_000, 2_000, 3_000]);
+        assert_eq!(snapshots[0].1.total(), 6.0);
+        assert_eq!(snapshots[1].1.total(), 9.0);
+        assert_eq!(snapshots[2].1.total(), 8.0);
+
+        assert!(ledger().attribution_over_time(0).is_err());
+        assert!(ledger().attribution_over_time(-1_000).is_err());
+        assert_eq!(
+            PnlLedger::new().attribution_over_time(1_000),
+            Ok(Vec::new())
+        );
+    }
+}
@@ -0,0 +1,317 @@
+/*!
+Trading sessions and perpetual funding times.
+
+Crypto markets trade around the clock, while strategies hedging or arbitraging
+against traditional venues need to know when those are open. A
+`MarketCalendar` answers `is_open`, `next_open` and `next_close` for:
+
+* `Session::Crypto` - always open.
+* `Session::Nyse` - regular NYSE hours, 09:30 to 16:00 New York time, Monday
+  to Friday.
+* `Session::Cme` - CME Globex, Sunday 17:00 to Friday 16:00 Chicago time with
+  a daily maintenance break from 16:00 to 17:00.
+
+US daylight saving time is applied (second Sunday of March to first Sunday of
+November). Exchange holidays are not built in: add them with
+`MarketCalendar::with_holiday`, which closes every session starting on that
+local day. Early closes are not modelled.
+
+Perpetuals settle funding at fixed UTC times, every 8 hours from midnight on
+most exchanges. `next_funding`, `previous_funding` and `funding_times` locate
+those settlements.
+*/
+
+use crate::vars::csv::civil_from_days;
+use crate::vars::csv::days_from_civil;
+
+const MINUTE_MS: i64 = 60_000;
+const HOUR_MS: i64 = 60 * MINUTE_MS;
+const DAY_MS: i64 = 24 * HOUR_MS;
+
+/// The default funding interval of perpetuals, settling at 00:00, 08:00 and
+/// 16:00 UTC.
+pub const FUNDING_INTERVAL: i64 = 8 * HOUR_MS;
+
+/// The number of days scanned for the next session, so that a calendar
+/// closed for good does not loop forever.
+const MAX_SCAN_DAYS: i64 = 400;
+
+/// A market whose trading hours a `MarketCalendar` follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Session {
+    /// Open around the clock.
+    #[default]
+    Crypto,
+    /// CME Globex, in Chicago time.
+    Cme,
+    /// NYSE regular trading hours, in New York time.
+    Nyse,
+}
+
+impl Session {
+    /// Offset of local standard time from UTC.
+    fn standard_offset(&self) -> i64 {
+        match self {
+            Session::Crypto => 0,
+            Session::Cme => -6 * HOUR_MS,
+            Session::Nyse => -5 * HOUR_MS,
+        }
+    }
+
+    /// Trading hours of a local weekday (Monday is 0) in minutes since local
+    /// midnight.
+    fn hours(&self, weekday: i64) -> &'static [(i64, i64)] {
+        match (self, weekday) {
+            (Session::Crypto, _) => &[(0, 24 * 60)],
+            (Session::Cme, 0..=3) => &[(0, 16 * 60), (17 * 60, 24 * 60)],
+            (Session::Cme, 4) => &[(0, 16 * 60)],
+            (Session::Cme, 6) => &[(17 * 60, 24 * 60)],
+            (Session::Nyse, 0..=4) => &[(9 * 60 + 30, 16 * 60)],
+            _ => &[],
+        }
+    }
+}
+
+/// Trading hours of a market.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarketCalendar {
+    session: Session,
+    /// Closed local days, in days since 1970-01-01.
+    holidays: Vec<i64>,
+}
+
+impl MarketCalendar {
+    pub fn new(session: Session) -> Self {
+        Self {
+            session,
+            holidays: Vec::new(),
+        }
+    }
+
+    /// Closes the sessions starting on a local date, e.g. `(2024, 12, 25)`.
+    pub fn with_holiday(mut self, year: i64, month: i64, day: i64) -> Self {
+        self.holidays.push(days_from_civil(year, month, day));
+        self
+    }
+
+    pub fn session(&self) -> Session {
+        self.session
+    }
+
+    /// Returns whether the market is open at a Unix millisecond timestamp.
+    pub fn is_open(&self, timestamp: i64) -> bool {
+        self.sessions(timestamp)
+            .take_while(|&(start, _)| start <= timestamp)
+            .any(|(_, end)| timestamp < end)
+    }
+
+    /// Returns the first time at or after `timestamp` at which the market is
+    /// open, i.e. `timestamp` itself if it is open, or `None` if no session
+    /// starts within a year.
+    pub fn next_open(&self, timestamp: i64) -> Option<i64> {
+        self.sessions(timestamp)
+            .find(|&(_, end)| end > timestamp)
+            .map(|(start, _)| start.max(timestamp))
+    }
+
+    /// Returns the end of the session open at `timestamp`, or of the next
+    /// session if the market is closed. Returns `None` for a market that never
+    /// closes.
+    pub fn next_close(&self, timestamp: i64) -> Option<i64> {
+        if self.session == Session::Crypto && self.holidays.is_empty() {
+            return None;
+        }
+        self.sessions(timestamp)
+            .find(|&(_, end)| end > timestamp)
+            .map(|(_, end)| end)
+    }
+
+    /// Sessions ending after the start of the local day before `from`, as
+    /// merged `[start, end)` UTC ranges in increasing order.
+    fn sessions(&self, from: i64) -> impl Iterator<Item = (i64, i64)> + '_ {
+        let first = (from + self.session.standard_offset()).div_euclid(DAY_MS) - 1;
+        let mut ranges = (first..first + MAX_SCAN_DAYS)
+            .flat_map(move |day| self.day_sessions(day))
+            .peekable();
+
+        // Sessions running past midnight are split per local day
+        std::iter::from_fn(move || {
+            let (start, mut end) = ranges.next()?;
+            while let Some(&(next_start, next_end)) = ranges.peek() {
+                if next_start > end {
+                    break;
+                }
+                end = end.max(next_end);
+                ranges.next();
+            }
+            Some((start, end))
+        })
+    }
+
+    /// Sessions starting on a local day, as UTC ranges.
+    fn day_sessions(&self, day: i64) -> Vec<(i64, i64)> {
+        if self.holidays.contains(&day) {
+            return Vec::new();
+        }
+        let midnight = day * DAY_MS - self.utc_offset(day);
+        self.session
+            .hours(weekday(day))
+            .iter()
+            .map(|&(open, close)| (midnight + open * MINUTE_MS, midnight + close * MINUTE_MS))
+            .collect()
+    }
+
+    /// Offset of local time from UTC on a local day. Sessions never open or
+    /// close during the 2 a.m. switch, so the offset is taken per day.
+    fn utc_offset(&self, day: i64) -> i64 {
+        let offset = self.session.standard_offset();
+        if self.session != Session::Crypto && is_us_dst(day) {
+            offset + HOUR_MS
+        } else {
+            offset
+        }
+    }
+}
+
+/// Day of the week of a number of days since 1970-01-01, Monday being 0.
+fn weekday(day: i64) -> i64 {
+    // 1970-01-01 was a Thursday
+    (day + 3).rem_euclid(7)
+}
+
+/// Whether US daylight saving time is in effect on a day, from the second
+/// Sunday of March to the first Sunday of November.
+fn is_us_dst(day: i64) -> bool {
+    let (year, _, _) = civil_from_days(day);
+    let first_sunday = |month| {
+        let first = days_from_civil(year, month, 1);
+        first + (6 - weekday(first))
+    };
+    (first_sunday(3) + 7..first_sunday(11)).contains(&day)
+}
+
+/// Returns the first funding settlement strictly after `timestamp`, with the
+/// default 8 hour interval.
+pub fn next_funding(timestamp: i64) -> i64 {
+    next_funding_every(timestamp, FUNDING_INTERVAL)
+}
+
+/// Returns the first funding settlement strictly after `timestamp`, for
+/// settlements every `interval` milliseconds from midnight UTC.
+pub fn next_funding_every(timestamp: i64, interval: i64) -> i64 {
+    (timestamp.div_euclid(interval) + 1) * interval
+}
+
+/// Returns the last funding settlement at or before `timestamp`, with the
+/// default 8 hour interval.
+pub fn previous_funding(timestamp: i64) -> i64 {
+    timestamp.div_euclid(FUNDING_INTERVAL) * FUNDING_INTERVAL
+}
+
+/// Returns the funding settlements in `[start, end)`, every `interval`
+/// milliseconds from midnight UTC.
+pub fn funding_times(start: i64, end: i64, interval: i64) -> Vec<i64> {
+    let first = (start + interval - 1).div_euclid(interval) * interval;
+    (0..)
+        .map(|i| first + i * interval)
+        .take_while(|&t| t < end)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vars::csv::parse_timestamp;
+    use crate::vars::csv::TimestampFormat;
+
+    fn ts(value: &str) -> i64 {
+        parse_timestamp(value, TimestampFormat::Iso8601).unwrap()
+    }
+
+    #[test]
+    fn test_nyse_hours() {
+        let nyse = MarketCalendar::new(Session::Nyse).with_holiday(2024, 7, 4);
+
+        // 09:30 EDT in summer, 09:30 EST in winter
+        assert!(!nyse.is_open(ts("2024-07-01T13:29:59Z")));
+        assert!(nyse.is_open(ts("2024-07-01T13:30:00Z")));
+        assert!(nyse.is_open(ts("2024-01-02T14:30:00Z")));
+        assert!(!nyse.is_open(ts("2024-01-02T14:00:00Z")));
+        assert_eq!(
+            nyse.next_close(ts("2024-07-01T15:00:00Z")),
+            Some(ts("2024-07-01T20:00:00Z"))
+        );
+
+        // Weekend and holiday
+        assert_eq!(
+            nyse.next_open(ts("2024-07-06T12:00:00Z")),
+            Some(ts("2024-07-08T13:30:00Z"))
+        );
+        assert!(!nyse.is_open(ts("2024-07-04T15:00:00Z")));
+        assert_eq!(
+            nyse.next_open(ts("2024-07-04T15:00:00Z")),
+            Some(ts("2024-07-05T13:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn test_cme_and_crypto_hours() {
+        let cme = MarketCalendar::new(Session::Cme);
+
+        assert!(cme.is_open(ts("2024-07-05T20:59:00Z")));
+        assert!(!cme.is_open(ts("2024-07-05T21:00:00Z")));
+        assert_eq!(
+            cme.next_open(ts("2024-07-06T12:00:00Z")),
+            Some(ts("2024-07-07T22:00:00Z"))
+        );
+        // The Sunday session runs through midnight into Monday
+        assert_eq!(
+            cme.next_close(ts("2024-07-07T22:30:00Z")),
+            Some(ts("2024-07-08T21:00:00Z"))
+        );
+        assert!(!cme.is_open(ts("2024-07-08T21:30:00Z")));
+        assert!(cme.is_open(ts("2024-07-08T22:00:00Z")));
+        // Standard time in winter
+        assert!(!cme.is_open(ts("2024-01-08T22:30:00Z")));
+        assert!(cme.is_open(ts("2024-01-08T23:00:00Z")));
+
+        let crypto = MarketCalendar::new(Session::Crypto);
+        assert!(crypto.is_open(ts("2024-07-06T12:00:00Z")));
+        assert_eq!(crypto.next_open(42), Some(42));
+        assert_eq!(crypto.next_close(42), None);
+    }
+
+    #[test]
+    fn test_funding_times() {
+        assert_eq!(
+            next_funding(ts("2024-01-01T07:59:00Z")),
+            ts("2024-01-01T08:00:00Z")
+        );
+        assert_eq!(
+            next_funding(ts("2024-01-01T08:00:00Z")),
+            ts("2024-01-01T16:00:00Z")
+        );
+        assert_eq!(
+            previous_funding(ts("2024-01-01T08:00:00Z")),
+            ts("2024-01-01T08:00:00Z")
+        );
+        assert_eq!(
+            next_funding_every(ts("2024-01-01T08:30:00Z"), HOUR_MS),
+            ts("2024-01-01T09:00:00Z")
+        );
+        assert_eq!(
+            funding_times(
+                ts("2024-01-01T00:00:00Z"),
+                ts("2024-01-02T00:00:00Z"),
+                FUNDING_INTERVAL
+            ),
+            vec![
+                ts("2024-01-01T00:00:00Z"),
+                ts("2024-01-01T08:00:00Z"),
+                ts("2024-01-01T16:00:00Z")
+            ]
+        );
+    }
+}
@@ -0,0 +1,447 @@
+/*!
+Self-exciting order arrivals.
+
+A Hawkes process with an exponential kernel has the intensity
+
+```text
+λ(t) = mu + Σ alpha * e^(-beta * (t - tᵢ))
+```
+
+over the past events `tᵢ`: every arrival raises the intensity by `alpha`,
+which decays back to the baseline `mu` at the rate `beta`. Orders arrive in
+such bursts, one trade drawing the next. The branching ratio `alpha / beta`
+is the number of events an event triggers on average; below one the process
+is stationary.
+
+- `Hawkes` - The parameters, with `simulate` drawing arrival times and `fit`
+  estimating the parameters from observed ones.
+- `HawkesIntensity` - The intensity of a stream of events and the expected
+  number of events over a coming horizon, a short-term activity forecast.
+- `simulate_trades` - Synthetic ticks arriving as a Hawkes process, priced
+  by a `PriceProcess`.
+
+Times are in seconds. The simulations require a stationary process, a
+branching ratio below one, as the number of events of an explosive one grows
+without bound.
+
+# Example
+
+```
+use strato_utils::hawkes::Hawkes;
+
+let hawkes = Hawkes::new(1.0, 1.0, 2.0).unwrap();
+let times = hawkes.simulate(2_000.0, 7).unwrap();
+let fitted = Hawkes::fit(&times, 2_000.0).unwrap();
+assert!((fitted.branching_ratio() - 0.5).abs() < 0.1);
+```
+*/
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use rand_distr::Exp1;
+
+use crate::sim::PriceProcess;
+use crate::sim::MS_PER_YEAR;
+use crate::vars::trade::Side;
+use crate::vars::trade::Trade;
+
+/// Largest number of expectation-maximization steps of `Hawkes::fit`.
+const MAX_ITERATIONS: usize = 1_000;
+
+/// Relative change of the parameters under which `Hawkes::fit` stops.
+const TOLERANCE: f64 = 1e-9;
+
+/// Parameters of a Hawkes process with an exponential kernel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hawkes {
+    /// Baseline intensity, events per second.
+    pub mu: f64,
+    /// Jump of the intensity at each event.
+    pub alpha: f64,
+    /// Decay rate of the jumps, per second.
+    pub beta: f64,
+}
+
+impl Hawkes {
+    /// Creates a stationary process, with `mu` and `beta` positive and
+    /// `alpha` in `[0, beta)`.
+    pub fn new(mu: f64, alpha: f64, beta: f64) -> Result<Self, String> {
+        let hawkes = Self { mu, alpha, beta };
+        hawkes.check()?;
+        Ok(hawkes)
+    }
+
+    /// Number of events an event triggers on average.
+    pub fn branching_ratio(&self) -> f64 {
+        self.alpha / self.beta
+    }
+
+    /// Long-run intensity of the stationary process, infinite when the
+    /// branching ratio is one or more.
+    pub fn mean_intensity(&self) -> f64 {
+        let ratio = self.branching_ratio();
+        if ratio < 1.0 {
+            self.mu / (1.0 - ratio)
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    /// Draws arrival times over `[0, horizon)` by Ogata's thinning.
+    ///
+    /// # Arguments
+    ///
+    /// * `horizon` - Length of the simulation in seconds.
+    /// * `seed` - Seed of the random number generator.
+    ///
+    /// # Returns
+    ///
+    /// The arrival times in increasing order, or an error if the parameters
+    /// are not those of a stationary process.
+    pub fn simulate(&self, horizon: f64, seed: u64) -> Result<Vec<f64>, String> {
+        self.check()?;
+        Ok(self.arrivals(horizon, &mut StdRng::seed_from_u64(seed)))
+    }
+
+    /// Checks the parameters the way `new` does, as the fields are public.
+    fn check(&self) -> Result<(), String> {
+        if !(self.mu > 0.0 && self.mu.is_finite()) {
+            return Err(format!(
+                "Invalid baseline intensity {}: it must be positive",
+                self.mu
+            ));
+        }
+        if !(self.beta > 0.0 && self.beta.is_finite()) {
+            return Err(format!(
+                "Invalid decay rate {}: it must be positive",
+                self.beta
+            ));
+        }
+        if !(self.alpha >= 0.0 && self.alpha < self.beta) {
+            return Err(format!(
+                "Invalid jump {}: it must be in [0, {}) for the process to be stationary",
+                self.alpha, self.beta
+            ));
+        }
+        Ok(())
+    }
+
+    fn arrivals(&self, horizon: f64, rng: &mut StdRng) -> Vec<f64> {
+        let mut times = Vec::new();
+        let mut time = 0.0;
+        // Excess of the intensity over `mu` at `time`
+        let mut excitation = 0.0;
+        loop {
+            // The intensity only decays until the next event, so its current
+            // value bounds it
+            let bound = self.mu + excitation;
+            let wait = rng.sample::<f64, _>(Exp1) / bound;
+            time += wait;
+            if time >= horizon || !time.is_finite() {
+                return times;
+            }
+            excitation *= (-self.beta * wait).exp();
+            if rng.gen::<f64>() * bound <= self.mu + excitation {
+                times.push(time);
+                excitation += self.alpha;
+            }
+        }
+    }
+
+    /// Log-likelihood of arrival times observed over `[0, horizon)`.
+    pub fn log_likelihood(&self, times: &[f64], horizon: f64) -> f64 {
+        let mut excitation = 0.0;
+        let mut last = 0.0;
+        let mut log_likelihood = 0.0;
+        for &time in times {
+            excitation *= (-self.beta * (time - last)).exp();
+            log_likelihood += (self.mu + excitation).ln();
+            excitation += self.alpha;
+            last = time;
+        }
+        let compensator = self.mu * horizon
+            + self.branching_ratio()
+                * times
+                    .iter()
+                    .map(|time| 1.0 - (-self.beta * (horizon - time)).exp())
+                    .sum::<f64>();
+        log_likelihood - compensator
+    }
+
+    /// Estimates the parameters from arrival times observed over
+    /// `[0, horizon)` by expectation-maximization.
+    ///
+    /// Each step weighs every event by the probabilities of it being
+    /// exogenous or triggered by each earlier event, and refits the baseline,
+    /// the branching ratio and the decay to these weights. The sums over the
+    /// pairs of events are carried recursively, so that a step is linear in
+    /// the number of events.
+    ///
+    /// # Arguments
+    ///
+    /// * `times` - Arrival times in increasing order, in seconds.
+    /// * `horizon` - Length of the observation in seconds.
+    ///
+    /// # Returns
+    ///
+    /// The fitted parameters, or `None` with fewer than two events.
+    pub fn fit(times: &[f64], horizon: f64) -> Option<Self> {
+        if times.len() < 2 || horizon <= 0.0 {
+            return None;
+        }
+        let count = times.len() as f64;
+        // Half of the events exogenous, the other half triggered within a
+        // mean inter-arrival time
+        let mut mu = 0.5 * count / horizon;
+        let mut ratio = 0.5;
+        let mut beta = count / horizon;
+
+        for _ in 0..MAX_ITERATIONS {
+            let alpha = ratio * beta;
+            let (mut background, mut triggered, mut triggered_lag) = (0.0, 0.0, 0.0);
+            // Σ e^(-beta * lag) and Σ lag * e^(-beta * lag) over the earlier
+            // events
+            let (mut decayed, mut decayed_lag) = (0.0, 0.0);
+            for (i, &time) in times.iter().enumerate() {
+                if i > 0 {
+                    let lag = time - times[i - 1];
+                    let decay = (-beta * lag).exp();
+                    decayed_lag = decay * (decayed_lag + lag * (decayed + 1.0));
+                    decayed = decay * (decayed + 1.0);
+                }
+                let intensity = mu + alpha * decayed;
+                background += mu / intensity;
+                triggered += alpha * decayed / intensity;
+                triggered_lag += alpha * decayed_lag / intensity;
+            }
+            let exposure: f64 = times
+                .iter()
+                .map(|time| 1.0 - (-beta * (horizon - time)).exp())
+                .sum();
+
+            let next = (
+                background / horizon,
+                triggered / exposure,
+                if triggered_lag > 0.0 {
+                    triggered / triggered_lag
+                } else {
+                    beta
+                },
+            );
+            let change = [(mu, next.0), (ratio, next.1), (beta, next.2)]
+                .iter()
+                .map(|(old, new)| (new - old).abs() / old.abs().max(f64::MIN_POSITIVE))
+                .fold(0.0, f64::max);
+            (mu, ratio, beta) = next;
+            if change < TOLERANCE || ratio <= 0.0 {
+                break;
+            }
+        }
+        Some(Self {
+            mu,
+            alpha: ratio * beta,
+            beta,
+        })
+    }
+}
+
+/// Intensity of a stream of events under a `Hawkes` process.
+#[derive(Debug, Clone)]
+pub struct HawkesIntensity {
+    pub hawkes: Hawkes,
+    /// Excess of the intensity over `mu` right after the last event.
+    excitation: f64,
+    last: Option<f64>,
+}
+
+impl HawkesIntensity {
+    pub fn new(hawkes: Hawkes) -> Self {
+        Self {
+            hawkes,
+            excitation: 0.0,
+            last: None,
+        }
+    }
+
+    /// Adds an event, not earlier than the previous one.
+    ///
+    /// Returns the intensity right after the event.
+    pub fn update(&mut self, time: f64) -> f64 {
+        self.excitation = self.intensity(time) - self.hawkes.mu + self.hawkes.alpha;
+        self.last = Some(time);
+        self.hawkes.mu + self.excitation
+    }
+
+    /// Intensity at `time`, not earlier than the last event.
+    pub fn intensity(&self, time: f64) -> f64 {
+        let decay = match self.last {
+            Some(last) => (-self.hawkes.beta * (time - last)).exp(),
+            None => 0.0,
+        };
+        self.hawkes.mu + self.excitation * decay
+    }
+
+    /// Expected number of events within `horizon` seconds after `time`,
+    /// events triggered in the meantime included.
+    ///
+    /// # Mathematical Formulation
+    ///
+    /// The expected intensity `m(s)` after `s` seconds follows
+    /// `m' = beta * mu - (beta - alpha) * m`, so that with `k = beta - alpha`
+    /// and `m̄ = beta * mu / k`:
+    ///
+    /// ```text
+    /// N = m̄ * h + (λ(t) - m̄) * (1 - e^(-k * h)) / k
+    /// ```
+    pub fn forecast(&self, time: f64, horizon: f64) -> f64 {
+        let Hawkes { mu, alpha, beta } = self.hawkes;
+        let intensity = self.intensity(time);
+        let k = beta - alpha;
+        if k.abs() < 1e-12 {
+            return intensity * horizon + 0.5 * beta * mu * horizon.powi(2);
+        }
+        let mean = beta * mu / k;
+        mean * horizon + (intensity - mean) * (1.0 - (-k * horizon).exp()) / k
+    }
+}
+
+/// Simulates trades arriving as a Hawkes process.
+///
+/// The price follows `process` between the arrivals, the side of each trade
+/// is given by the tick rule, an unchanged price repeating the previous side,
+/// and the quantities are exponentially distributed around `mean_qty`.
+///
+/// # Arguments
+///
+/// * `hawkes` - The arrival process.
+/// * `process` - The price process.
+/// * `initial_price` - Price at the start.
+/// * `horizon` - Length of the simulation in seconds.
+/// * `mean_qty` - Mean quantity of the trades.
+/// * `seed` - Seed of the random number generator.
+///
+/// # Returns
+///
+/// The trades in time order, timestamped in milliseconds from `0`, or an
+/// error if `hawkes` is not a stationary process.
+pub fn simulate_trades<P: PriceProcess>(
+    hawkes: &Hawkes,
+    mut process: P,
+    initial_price: f64,
+    horizon: f64,
+    mean_qty: f64,
+    seed: u64,
+) -> Result<Vec<Trade>, String> {
+    hawkes.check()?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let times = hawkes.arrivals(horizon, &mut rng);
+    let mut price = initial_price;
+    let mut side = Side::Buy;
+    let mut last = 0.0;
+    Ok(times
+        .into_iter()
+        .map(|time| {
+            let next = process.step(price, (time - last) * 1_000.0 / MS_PER_YEAR, &mut rng);
+            if next > price {
+                side = Side::Buy;
+            } else if next < price {
+                side = Side::Sell;
+            }
+            price = next;
+            last = time;
+            Trade {
+                timestamp: (time * 1_000.0) as i64,
+                price,
+                qty: mean_qty * rng.sample::<f64, _>(Exp1),
+                side,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::Gbm;
+
+    #[test]
+    fn test_simulate_and_fit() {
+        let hawkes = Hawkes::new(1.0, 1.0, 2.0).unwrap();
+        assert_eq!(hawkes.mean_intensity(), 2.0);
+
+        let times = hawkes.simulate(5_000.0, 3).unwrap();
+        assert_eq!(times, hawkes.simulate(5_000.0, 3).unwrap());
+        assert!(times.windows(2).all(|pair| pair[0] < pair[1]));
+        let rate = times.len() as f64 / 5_000.0;
+        assert!((rate - 2.0).abs() < 0.2, "{}", rate);
+
+        let fitted = Hawkes::fit(&times, 5_000.0).unwrap();
+        assert!((fitted.mu - 1.0).abs() < 0.15, "{:?}", fitted);
+        assert!(
+            (fitted.branching_ratio() - 0.5).abs() < 0.05,
+            "{:?}",
+            fitted
+        );
+        assert!((fitted.beta - 2.0).abs() < 0.4, "{:?}", fitted);
+        assert!(fitted.log_likelihood(&times, 5_000.0) >= hawkes.log_likelihood(&times, 5_000.0));
+
+        assert_eq!(Hawkes::fit(&times[..1], 5_000.0), None);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(Hawkes::new(0.0, 1.0, 2.0).is_err());
+        assert!(Hawkes::new(1.0, 1.0, 0.0).is_err());
+        assert!(Hawkes::new(1.0, -1.0, 2.0).is_err());
+        assert!(Hawkes::new(f64::NAN, 1.0, 2.0).is_err());
+        // An explosive process would never stop simulating
+        assert!(Hawkes::new(1.0, 2.0, 2.0).is_err());
+        let explosive = Hawkes {
+            mu: 1.0,
+            alpha: 3.0,
+            beta: 2.0,
+        };
+        assert!(explosive.simulate(1_000.0, 3).is_err());
+        assert!(simulate_trades(&explosive, Gbm::new(0.0, 0.5), 100.0, 600.0, 0.1, 11).is_err());
+    }
+
+    #[test]
+    fn test_intensity_and_forecast() {
+        let mut intensity = HawkesIntensity::new(Hawkes::new(1.0, 1.0, 2.0).unwrap());
+        assert_eq!(intensity.intensity(0.0), 1.0);
+
+        // Without events the expected intensity rises to its mean of 2
+        let forecast = intensity.forecast(0.0, 1_000.0);
+        assert!((forecast - (2_000.0 - 1.0)).abs() < 1e-9);
+
+        assert_eq!(intensity.update(1.0), 2.0);
+        assert_eq!(intensity.update(1.0), 3.0);
+        let decayed = intensity.intensity(1.5);
+        assert!((decayed - (1.0 + 2.0 * (-1.0f64).exp())).abs() < 1e-12);
+        // A burst forecasts more activity than the calm
+        let calm = HawkesIntensity::new(intensity.hawkes).forecast(1.5, 1.0);
+        assert!(intensity.forecast(1.5, 1.0) > calm);
+    }
+
+    #[test]
+    fn test_simulate_trades() {
+        let hawkes = Hawkes::new(2.0, 1.0, 2.0).unwrap();
+        let trades = simulate_trades(&hawkes, Gbm::new(0.0, 0.5), 100.0, 600.0, 0.1, 11).unwrap();
+
+        assert!(!trades.is_empty());
+        assert!(trades
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        assert!(trades.iter().all(|trade| trade.timestamp < 600_000));
+        assert!(trades.iter().all(|trade| trade.qty > 0.0));
+        for pair in trades.windows(2) {
+            if pair[1].price > pair[0].price {
+                assert_eq!(pair[1].side, Side::Buy);
+            } else if pair[1].price < pair[0].price {
+                assert_eq!(pair[1].side, Side::Sell);
+            }
+        }
+    }
+}
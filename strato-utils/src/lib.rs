@@ -1,13 +1,97 @@
+pub mod calendar;
+pub mod hawkes;
+pub mod progress;
 pub mod relative_depths;
+pub mod scenarios;
+pub mod sim;
 pub mod ta;
+pub mod validation;
 pub mod vars;
 
 #[cfg(test)]
 mod tests {
+    use crate::ta::accdist::accdist;
+    use crate::ta::aroon::aroon;
     use crate::ta::atr::atr;
+    use crate::ta::atr::atr_nan;
+    use crate::ta::atr::atr_percentile;
+    use crate::ta::atr::natr;
+    use crate::ta::cmf::cmf;
+    use crate::ta::correlation::rolling_corr;
+    use crate::ta::correlation::rolling_cov;
+    use crate::ta::cross::cross;
+    use crate::ta::cross::crossover;
+    use crate::ta::cross::crossover_indices;
+    use crate::ta::cross::crossunder;
+    use crate::ta::cross::crossunder_indices;
+    use crate::ta::dema::dema;
+    use crate::ta::dema::dema_nan;
+    use crate::ta::dema::tema;
+    use crate::ta::dema::tema_nan;
+    use crate::ta::dmi::dmi;
+    use crate::ta::donchian::donchian;
+    use crate::ta::drawdown::drawdown;
+    use crate::ta::drawdown::max_drawdown;
+    use crate::ta::drawdown::rolling_drawdown;
+    use crate::ta::ema::ema;
+    use crate::ta::ema::ema_nan;
+    use crate::ta::fractals::fractals;
+    use crate::ta::heikin_ashi::heikin_ashi;
+    use crate::ta::hma::hma;
+    use crate::ta::hma::hma_nan;
+    use crate::ta::indicator::Atr;
+    use crate::ta::indicator::Ema;
+    use crate::ta::indicator::HeikinAshi;
+    use crate::ta::indicator::Indicator;
+    use crate::ta::indicator::Resample;
+    use crate::ta::indicator::Sma;
+    use crate::ta::macd::macd;
+    use crate::ta::mfi::mfi;
+    use crate::ta::obv::obv;
+    use crate::ta::percentrank::percentrank;
+    use crate::ta::pivots::pivot_points;
+    use crate::ta::pivots::PivotKind;
+    use crate::ta::renko::range_bars;
+    use crate::ta::renko::RangeBars;
+    use crate::ta::renko::Renko;
+    use crate::ta::resample::fill_gaps;
+    use crate::ta::resample::find_gaps;
+    use crate::ta::resample::resample;
+    use crate::ta::resample::resample_filled;
+    use crate::ta::resample::FillPolicy;
+    use crate::ta::resample::Gap;
     use crate::ta::rma::rma;
+    use crate::ta::rma::rma_nan;
+    use crate::ta::roc::momentum;
+    use crate::ta::roc::roc;
+    use crate::ta::sar::sar;
     use crate::ta::sma::sma;
+    use crate::ta::sma::sma_chunk;
+    use crate::ta::sma::sma_nan;
+    use crate::ta::sma::SMA_CHUNK_LEN;
+    use crate::ta::stdev::stdev;
+    use crate::ta::stdev::variance;
+    use crate::ta::trade_bars::dollar_bars;
+    use crate::ta::trade_bars::tick_imbalance_bars;
+    use crate::ta::trade_bars::time_bars;
+    use crate::ta::trade_bars::volume_bars;
+    use crate::ta::trade_bars::TimeBars;
+    use crate::ta::ulcer::ulcer_index;
+    use crate::ta::vpin::vpin;
+    use crate::ta::vpin::Vpin;
+    use crate::ta::vwap::vwap;
+    use crate::ta::vwap::VwapAnchor;
+    use crate::ta::vwap::DAY_MS;
+    use crate::ta::vwma::vwma;
+    use crate::ta::vwma::vwma_nan;
+    use crate::ta::warmup::to_options;
+    use crate::ta::wma::wma;
+    use crate::ta::zigzag::zigzag;
+    use crate::ta::zigzag::ZigZagThreshold;
+    use crate::ta::zscore::zscore;
     use crate::vars::ohlc::Ohlc;
+    use crate::vars::trade::Side;
+    use crate::vars::trade::Trade;
 
     #[test]
     fn test_sma() {
@@ -16,6 +100,32 @@ mod tests {
         let expected_sma = vec![0.0, 0.0, 2.0, 3.0, 4.0];
         let sma_values = sma(&src, length);
         assert_eq!(sma_values, expected_sma);
+        assert_eq!(sma(&src, 0), vec![0.0; 5]);
+        assert_eq!(sma(&src, 6), vec![0.0; 5]);
+    }
+
+    #[test]
+    fn test_sma_chunks() {
+        // Several chunks of rolling sums
+        let src: Vec<f64> = (0..3 * SMA_CHUNK_LEN)
+            .map(|i| 100.0 + (i as f64 * 0.37).sin() * 10.0)
+            .collect();
+
+        for length in [1, 7, 300] {
+            let sma_values = sma(&src, length);
+            for i in length - 1..src.len() {
+                let window_sum: f64 = src[i + 1 - length..=i].iter().sum();
+                assert!((sma_values[i] - window_sum / length as f64).abs() < 1e-9);
+            }
+
+            // As `par_sma` does, the chunks may be computed in any order
+            let mut chunked = vec![0.0; src.len()];
+            let chunks: Vec<&mut [f64]> = chunked[length - 1..].chunks_mut(SMA_CHUNK_LEN).collect();
+            for (chunk_no, chunk) in chunks.into_iter().enumerate().rev() {
+                sma_chunk(&src, length, length - 1 + chunk_no * SMA_CHUNK_LEN, chunk);
+            }
+            assert_eq!(chunked, sma_values);
+        }
     }
 
     #[test]
@@ -36,6 +146,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_wma() {
+        let src = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let wma_values = wma(&src, 3);
+        // (1 * 1 + 2 * 2 + 3 * 3) / 6
+        let expected_wma = [0.0, 0.0, 14.0 / 6.0, 20.0 / 6.0, 26.0 / 6.0];
+        for (value, expected) in wma_values.iter().zip(expected_wma) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_hma() {
+        // The HMA tracks a linear series without lag
+        let src: Vec<f64> = (0..20).map(|i| i as f64).collect();
+        let hma_values = hma(&src, 9);
+
+        assert!(hma_values[..10].iter().all(|&v| v == 0.0));
+        for (value, expected) in hma_values[10..].iter().zip(&src[10..]) {
+            assert!((value - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_dema_and_tema() {
+        // Both remove the lag of the EMA on a linear series once converged
+        let src: Vec<f64> = (0..200).map(|i| i as f64).collect();
+        let ema_values = ema(src.clone(), 10);
+        let dema_values = dema(&src, 10);
+        let tema_values = tema(&src, 10);
+
+        assert_eq!(dema_values[0], 0.0);
+        assert!((src[199] - ema_values[199] - 4.5).abs() < 1e-6);
+        assert!((dema_values[199] - src[199]).abs() < 1e-6);
+        assert!((tema_values[199] - src[199]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_dema_and_tema_nan() {
+        let src: Vec<f64> = (0..200).map(|i| i as f64).collect();
+        let dema_values = dema_nan(&src, 10);
+        let tema_values = tema_nan(&src, 10);
+
+        // Each EMA warms up over its own first 10 defined inputs
+        assert!(dema_values[..18].iter().all(|v| v.is_nan()));
+        assert!((dema_values[18] - 18.0).abs() < 1e-9);
+        assert!(tema_values[..27].iter().all(|v| v.is_nan()));
+        assert!((tema_values[27] - 27.0).abs() < 1e-9);
+        assert!((dema_values[199] - src[199]).abs() < 1e-9);
+        assert!((tema_values[199] - src[199]).abs() < 1e-9);
+
+        assert!(dema_nan(&src[..5], 10).iter().all(|v| v.is_nan()));
+        assert!(tema_nan(&src, 0).iter().all(|v| v.is_nan()));
+    }
+
     #[test]
     fn test_atr() {
         let candles = vec![
@@ -70,4 +235,860 @@ mod tests {
             assert!((value - expected_atr[i]).abs() < 1e-6);
         }
     }
+
+    #[test]
+    fn test_macd() {
+        let src = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let (macd_line, signal_line, histogram) = macd(&src, 1, 3, 1);
+
+        // Fast EMA of length 1 is the source, slow EMA (alpha 0.5) lags behind
+        let expected_macd = [0.0, 0.5, 0.75, 0.875, 0.9375];
+        assert_eq!(macd_line, expected_macd);
+        assert_eq!(signal_line, expected_macd);
+        assert_eq!(histogram, vec![0.0; 5]);
+
+        let (macd_line, signal_line, histogram) = macd(&src, 2, 3, 2);
+        for ((h, m), s) in histogram.iter().zip(&macd_line).zip(&signal_line) {
+            assert!((h - (m - s)).abs() < 1e-12);
+        }
+        assert!(macd_line[4] > 0.0);
+    }
+
+    #[test]
+    fn test_vpin() {
+        // Sides follow the tick rule; buckets close with imbalances 1.0, 0.2, 1.0
+        let prices = vec![100.0, 101.0, 100.0, 101.0, 102.0, 101.0];
+        let volumes = vec![4.0, 2.0, 2.0, 4.0, 4.0, 2.0];
+        let expected_vpin = [0.0, 0.0, 0.0, 0.6, 0.6, 0.6];
+        let vpin_values = vpin(&prices, &volumes, 5.0, 2).unwrap();
+        assert_eq!(vpin_values.len(), expected_vpin.len());
+        for (i, &value) in vpin_values.iter().enumerate() {
+            assert!((value - expected_vpin[i]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_vpin_splits_large_trades() {
+        let mut estimator = Vpin::new(10.0, 2).unwrap();
+        assert_eq!(estimator.update_with_side(15.0, true), None);
+        assert_eq!(estimator.update_with_side(5.0, false), Some(0.5));
+    }
+
+    #[test]
+    fn test_vpin_rejects_invalid_parameters() {
+        assert!(Vpin::new(0.0, 2).is_err());
+        assert!(Vpin::new(-1.0, 2).is_err());
+        assert!(Vpin::new(f64::NAN, 2).is_err());
+        assert!(Vpin::new(10.0, 0).is_err());
+        assert!(vpin(&[100.0], &[1.0], 0.0, 2).is_err());
+    }
+
+    fn vwap_candles() -> Vec<Ohlc> {
+        // Typical prices 10, 20, 30, 40 with volumes 1, 3, 1, 1
+        [(10.0, 1.0), (20.0, 3.0), (30.0, 1.0), (40.0, 1.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, &(price, volume))| Ohlc {
+                timestamp: i as i64 * DAY_MS / 2,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_vwap_session() {
+        let values = vwap(&vwap_candles(), VwapAnchor::session(DAY_MS).unwrap());
+        assert_eq!(values, vec![10.0, 17.5, 30.0, 35.0]);
+
+        assert!(VwapAnchor::session(0).is_err());
+        assert!(VwapAnchor::session(-DAY_MS).is_err());
+        // Built directly, an empty session is a single one instead of a panic
+        let values = vwap(&vwap_candles(), VwapAnchor::Session(0));
+        assert_eq!(values[..3], [10.0, 17.5, 20.0]);
+        assert!((values[3] - 140.0 / 6.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_vwap_rolling() {
+        let values = vwap(&vwap_candles(), VwapAnchor::rolling(2).unwrap());
+        assert_eq!(values, vec![0.0, 17.5, 22.5, 35.0]);
+        assert!(VwapAnchor::rolling(0).is_err());
+    }
+
+    #[test]
+    fn test_vwap_anchored_to_timestamp() {
+        let values = vwap(&vwap_candles(), VwapAnchor::Timestamp(DAY_MS / 2));
+        assert_eq!(values, vec![0.0, 20.0, 22.5, 26.0]);
+    }
+
+    #[test]
+    fn test_donchian() {
+        let candles: Vec<Ohlc> = [(3.0, 1.0), (5.0, 2.0), (4.0, 0.0), (6.0, 3.0)]
+            .iter()
+            .map(|&(high, low)| Ohlc {
+                high,
+                low,
+                ..Default::default()
+            })
+            .collect();
+
+        let (upper, basis, lower) = donchian(&candles, 2);
+        assert_eq!(upper, vec![0.0, 5.0, 5.0, 6.0]);
+        assert_eq!(lower, vec![0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(basis, vec![0.0, 3.0, 2.5, 3.0]);
+    }
+
+    #[test]
+    fn test_dmi() {
+        // A steady uptrend has only positive directional movement
+        let candles: Vec<Ohlc> = (0..30)
+            .map(|i| {
+                let price = 100.0 + i as f64;
+                Ohlc {
+                    open: price,
+                    high: price + 1.0,
+                    low: price - 1.0,
+                    close: price,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        let (plus_di, minus_di, adx) = dmi(&candles, 14, 14);
+
+        assert!(minus_di.iter().all(|&v| v == 0.0));
+        assert!(plus_di[29] > 40.0);
+        assert!(adx.windows(2).skip(1).all(|w| w[1] >= w[0]));
+        assert!(adx[29] > 50.0 && adx[29] <= 100.0);
+    }
+
+    #[test]
+    fn test_sar() {
+        let candles: Vec<Ohlc> = [
+            (10.0, 8.0, 9.0),
+            (11.0, 9.0, 10.0),
+            (12.0, 10.0, 11.0),
+            (13.0, 11.0, 12.0),
+            (10.0, 7.0, 8.0),
+        ]
+        .iter()
+        .map(|&(high, low, close)| Ohlc {
+            high,
+            low,
+            close,
+            ..Default::default()
+        })
+        .collect();
+
+        let (sar_values, direction) = sar(&candles, 0.02, 0.02, 0.2);
+
+        assert_eq!(direction, vec![0.0, 1.0, 1.0, 1.0, -1.0]);
+        // First trend bar: the prior low, advanced towards the high of 11
+        assert!((sar_values[1] - 8.0).abs() < 1e-12);
+        assert!((sar_values[2] - 8.0).abs() < 1e-12);
+        assert!((sar_values[3] - 8.16).abs() < 1e-12);
+        // The reversal starts at the extreme high of the uptrend
+        assert_eq!(sar_values[4], 13.0);
+    }
+
+    fn volume_candles() -> Vec<Ohlc> {
+        [
+            (12.0, 8.0, 10.0, 100.0),
+            (12.0, 10.0, 12.0, 200.0),
+            (12.0, 10.0, 11.0, 300.0),
+            (11.0, 11.0, 11.0, 400.0),
+        ]
+        .iter()
+        .map(|&(high, low, close, volume)| Ohlc {
+            high,
+            low,
+            close,
+            volume,
+            ..Default::default()
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_vwma() {
+        let candles = volume_candles();
+        let closes: Vec<f64> = candles.iter().map(|c| c.close).collect();
+        let volumes: Vec<f64> = candles.iter().map(|c| c.volume).collect();
+
+        let vwma_values = vwma(&closes, &volumes, 2);
+        let expected_vwma = [0.0, 3400.0 / 300.0, 5700.0 / 500.0, 11.0];
+        for (value, expected) in vwma_values.iter().zip(expected_vwma) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        assert!(vwma_nan(&closes, &volumes, 2)[0].is_nan());
+        // Without volume it is the simple average
+        assert_eq!(vwma(&closes, &[0.0; 4], 2)[1], 11.0);
+    }
+
+    #[test]
+    fn test_obv() {
+        assert_eq!(obv(&volume_candles()), vec![0.0, 200.0, -100.0, -100.0]);
+    }
+
+    #[test]
+    fn test_accdist() {
+        // Close location values 0, 1, 0 and a candle with no range
+        assert_eq!(accdist(&volume_candles()), vec![0.0, 200.0, 200.0, 200.0]);
+    }
+
+    #[test]
+    fn test_crossover_and_crossunder() {
+        let a = [1.0, 3.0, 3.0, 1.0, 2.0, 3.0];
+        let b = [2.0, 2.0, 2.0, 2.0, 2.0, 2.0];
+
+        // Touching the level does not cross it, leaving it after does
+        assert_eq!(
+            crossover(&a, &b),
+            vec![false, true, false, false, false, true]
+        );
+        assert_eq!(
+            crossunder(&a, &b),
+            vec![false, false, false, true, false, false]
+        );
+        assert_eq!(cross(&a, &b), vec![false, true, false, true, false, true]);
+        assert_eq!(crossover_indices(&a, &b), vec![1, 5]);
+        assert_eq!(crossunder_indices(&a, &b), vec![3]);
+    }
+
+    #[test]
+    fn test_drawdown() {
+        let src = [100.0, 110.0, 99.0, 88.0, 110.0];
+        let drawdowns = drawdown(&src);
+        let expected_drawdowns = [0.0, 0.0, 0.1, 0.2, 0.0];
+        for (value, expected) in drawdowns.iter().zip(expected_drawdowns) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        assert!((max_drawdown(&src) - 0.2).abs() < 1e-12);
+
+        // The peak of the last two values at index 3 is 99
+        let rolling = rolling_drawdown(&src, 2);
+        let expected_rolling = [0.0, 0.0, 0.1, 11.0 / 99.0, 0.0];
+        for (value, expected) in rolling.iter().zip(expected_rolling) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_ulcer_index() {
+        let src = [100.0, 110.0, 99.0, 88.0, 110.0];
+        let ulcer_values = ulcer_index(&src, 2);
+
+        assert_eq!(ulcer_values[..2], [0.0, 0.0]);
+        assert!((ulcer_values[2] - 50.0_f64.sqrt()).abs() < 1e-9);
+        let expected = ((100.0 + (1100.0_f64 / 99.0).powi(2)) / 2.0).sqrt();
+        assert!((ulcer_values[3] - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_aroon() {
+        let candles: Vec<Ohlc> = [(1.0, 0.0), (3.0, 2.0), (2.0, 1.0), (4.0, 3.0), (3.0, 0.0)]
+            .iter()
+            .map(|&(high, low)| Ohlc {
+                high,
+                low,
+                ..Default::default()
+            })
+            .collect();
+        let (up, down, oscillator) = aroon(&candles, 2);
+
+        assert_eq!(up, vec![0.0, 0.0, 50.0, 100.0, 50.0]);
+        assert_eq!(down, vec![0.0, 0.0, 0.0, 50.0, 100.0]);
+        assert_eq!(oscillator, vec![0.0, 0.0, 50.0, 50.0, -50.0]);
+    }
+
+    #[test]
+    fn test_natr_and_atr_percentile() {
+        // True ranges 0, 2, 2, 0 over closes 10, 12, 11, 11
+        let candles = volume_candles();
+        assert_eq!(
+            natr(&candles, 1),
+            vec![0.0, 200.0 / 12.0, 200.0 / 11.0, 0.0]
+        );
+        assert_eq!(atr_percentile(&candles, 1, 2), vec![0.0, 0.0, 100.0, 0.0]);
+    }
+
+    #[test]
+    fn test_percentrank() {
+        let src = [1.0, 3.0, 2.0, 5.0, 4.0];
+        assert_eq!(percentrank(&src, 2), vec![0.0, 0.0, 50.0, 100.0, 50.0]);
+    }
+
+    #[test]
+    fn test_cmf() {
+        // Money flow volumes 0, 200, 0, 0
+        let cmf_values = cmf(&volume_candles(), 2);
+        assert_eq!(cmf_values, vec![0.0, 200.0 / 300.0, 200.0 / 500.0, 0.0]);
+    }
+
+    #[test]
+    fn test_mfi() {
+        // Typical prices 10, 34 / 3, 11, 11 with flows +6800 / 3 and -3300
+        let mfi_values = mfi(&volume_candles(), 2);
+        let expected = 100.0 - 100.0 / (1.0 + 6800.0 / 3.0 / 3300.0);
+
+        assert_eq!(mfi_values[..2], [0.0, 0.0]);
+        assert!((mfi_values[2] - expected).abs() < 1e-9);
+        assert_eq!(mfi_values[3], 0.0);
+    }
+
+    #[test]
+    fn test_roc_and_momentum() {
+        let src = [10.0, 20.0, 15.0, 30.0];
+
+        assert_eq!(roc(&src, 2), vec![0.0, 0.0, 50.0, 50.0]);
+        assert_eq!(momentum(&src, 2), vec![0.0, 0.0, 5.0, 10.0]);
+        assert_eq!(roc(&[0.0, 1.0], 1), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_variance_and_stdev() {
+        let src = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+
+        let variance_values = variance(&src, 8);
+        assert_eq!(variance_values[..7], [0.0; 7]);
+        assert_eq!(variance_values[7], 4.0);
+        assert_eq!(stdev(&src, 8)[7], 2.0);
+        assert_eq!(stdev(&src, 2)[1..4], [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_zscore() {
+        let src = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+
+        assert_eq!(zscore(&src, 8)[7], 2.0);
+        // No dispersion in the window
+        assert_eq!(zscore(&src, 2)[2], 0.0);
+        assert_eq!(zscore(&src, 2)[4], 1.0);
+    }
+
+    #[test]
+    fn test_rolling_cov_and_corr() {
+        let a = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let b = [2.0, 4.0, 6.0, 8.0, 10.0];
+        let c = [5.0, 4.0, 3.0, 2.0, 1.0, 0.0];
+
+        assert_eq!(
+            rolling_cov(&a, &b, 3),
+            vec![0.0, 0.0, 4.0 / 3.0, 4.0 / 3.0, 4.0 / 3.0]
+        );
+        for (ab, ac) in rolling_corr(&a, &b, 3)[2..]
+            .iter()
+            .zip(&rolling_corr(&a, &c, 3)[2..])
+        {
+            assert!((ab - 1.0).abs() < 1e-12);
+            assert!((ac + 1.0).abs() < 1e-12);
+        }
+        assert_eq!(rolling_corr(&a, &[1.0; 5], 3), vec![0.0; 5]);
+    }
+
+    #[test]
+    fn test_pivot_points() {
+        let candles = [
+            Ohlc {
+                high: 110.0,
+                low: 90.0,
+                close: 103.0,
+                ..Default::default()
+            },
+            Ohlc::default(),
+        ];
+
+        let classic = pivot_points(&candles, PivotKind::Classic);
+        assert_eq!(classic.len(), 2);
+        assert_eq!(classic[0].resistance, Vec::<f64>::new());
+        assert_eq!(classic[1].pivot, 101.0);
+        assert_eq!(classic[1].resistance, vec![112.0, 121.0, 132.0]);
+        assert_eq!(classic[1].support, vec![92.0, 81.0, 72.0]);
+
+        let fibonacci = &pivot_points(&candles, PivotKind::Fibonacci)[1];
+        assert!((fibonacci.resistance[0] - 108.64).abs() < 1e-9);
+        assert_eq!(fibonacci.support[2], 81.0);
+
+        let camarilla = &pivot_points(&candles, PivotKind::Camarilla)[1];
+        assert_eq!(camarilla.resistance.len(), 4);
+        assert!((camarilla.resistance[3] - 114.0).abs() < 1e-9);
+        assert!((camarilla.support[0] - (103.0 - 22.0 / 12.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_heikin_ashi() {
+        let candles = [
+            Ohlc {
+                timestamp: 1,
+                open: 10.0,
+                high: 14.0,
+                low: 8.0,
+                close: 12.0,
+                volume: 5.0,
+            },
+            Ohlc {
+                timestamp: 2,
+                open: 12.0,
+                high: 13.0,
+                low: 11.0,
+                close: 12.0,
+                volume: 6.0,
+            },
+        ];
+
+        let ha = heikin_ashi(&candles);
+
+        assert_eq!((ha[0].open, ha[0].close), (11.0, 11.0));
+        assert_eq!((ha[0].high, ha[0].low), (14.0, 8.0));
+        assert_eq!((ha[1].open, ha[1].close), (11.0, 12.0));
+        assert_eq!((ha[1].high, ha[1].low), (13.0, 11.0));
+        assert_eq!((ha[1].timestamp, ha[1].volume), (2, 6.0));
+    }
+
+    #[test]
+    fn test_renko() {
+        let mut renko = Renko::new(10.0).unwrap();
+        assert!(renko.update(0, 100.0, 1.0).is_empty());
+        assert!(renko.update(1, 109.0, 1.0).is_empty());
+
+        let bricks = renko.update(2, 125.0, 1.0);
+        let closes: Vec<f64> = bricks.iter().map(|b| b.close).collect();
+        assert_eq!(closes, vec![110.0, 120.0]);
+        assert_eq!(bricks[0].volume, 3.0);
+        assert_eq!(bricks[1].volume, 0.0);
+
+        // A one-brick pullback is not a reversal
+        assert!(renko.update(3, 105.0, 1.0).is_empty());
+        let bricks = renko.update(4, 100.0, 1.0);
+        assert_eq!(bricks.len(), 1);
+        assert_eq!((bricks[0].open, bricks[0].close), (110.0, 100.0));
+
+        assert!(Renko::new(0.0).is_err());
+        assert!(Renko::new(-10.0).is_err());
+        assert!(Renko::new(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_range_bars() {
+        let mut bars = RangeBars::new(5.0).unwrap();
+        assert!(bars.update(0, 100.0, 1.0).is_empty());
+        assert!(bars.update(1, 103.0, 1.0).is_empty());
+
+        let completed = bars.update(2, 111.0, 1.0);
+        assert_eq!(completed.len(), 2);
+        assert_eq!((completed[0].low, completed[0].high), (100.0, 105.0));
+        assert_eq!((completed[1].open, completed[1].close), (105.0, 110.0));
+        assert_eq!(completed[0].volume, 2.0);
+
+        let last = bars.finish().unwrap();
+        assert_eq!((last.open, last.close, last.volume), (110.0, 111.0, 1.0));
+
+        let candles = [Ohlc {
+            open: 100.0,
+            high: 112.0,
+            low: 99.0,
+            close: 110.0,
+            ..Default::default()
+        }];
+        assert_eq!(range_bars(&candles, 5.0).unwrap().len(), 2);
+        assert!(range_bars(&candles, 0.0).is_err());
+        assert!(RangeBars::new(-5.0).is_err());
+    }
+
+    fn swing_candles() -> Vec<Ohlc> {
+        [
+            100.0, 104.0, 110.0, 106.0, 98.0, 101.0, 97.0, 103.0, 108.0, 105.0,
+        ]
+        .iter()
+        .map(|&price| Ohlc {
+            open: price,
+            high: price + 1.0,
+            low: price - 1.0,
+            close: price,
+            ..Default::default()
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_zigzag_percent() {
+        let swings = zigzag(&swing_candles(), ZigZagThreshold::Percent(8.0));
+
+        let points: Vec<(usize, f64, bool, usize)> = swings
+            .iter()
+            .map(|s| (s.index, s.price, s.is_high, s.confirmed_at))
+            .collect();
+        assert_eq!(
+            points,
+            vec![
+                (0, 99.0, false, 2),
+                (2, 111.0, true, 4),
+                (6, 96.0, false, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zigzag_atr() {
+        let swings = zigzag(
+            &swing_candles(),
+            ZigZagThreshold::Atr {
+                length: 2,
+                mult: 100.0,
+            },
+        );
+        assert!(swings.is_empty());
+    }
+
+    #[test]
+    fn test_fractals() {
+        let swings = fractals(&swing_candles(), 2);
+
+        let points: Vec<(usize, f64, bool, usize)> = swings
+            .iter()
+            .map(|s| (s.index, s.price, s.is_high, s.confirmed_at))
+            .collect();
+        assert_eq!(points, vec![(2, 111.0, true, 4), (6, 96.0, false, 8)]);
+        assert!(fractals(&swing_candles(), 5).is_empty());
+    }
+
+    #[test]
+    fn test_nan_warmup() {
+        let src = [1.0, 2.0, 3.0, 4.0, 5.0];
+
+        assert_eq!(
+            to_options(&sma_nan(&src, 3)),
+            vec![None, None, Some(2.0), Some(3.0), Some(4.0)]
+        );
+        let rma_values = rma_nan(&src, 3);
+        assert!(rma_values[..2].iter().all(|v| v.is_nan()));
+        for (value, expected) in rma_values[2..].iter().zip([2.0, 8.0 / 3.0, 31.0 / 9.0]) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        assert!(rma_nan(&src, 6).iter().all(|v| v.is_nan()));
+        // Pine seeds the EMA with the SMA: 2, then 2 + (4 - 2) / 2 and so on
+        let ema_values = ema_nan(&src, 3);
+        assert!(ema_values[..2].iter().all(|v| v.is_nan()));
+        assert_eq!(ema_values[2..], [2.0, 3.0, 4.0]);
+        assert!(ema_nan(&src, 6).iter().all(|v| v.is_nan()));
+
+        let candles: Vec<Ohlc> = (0..3)
+            .map(|i| Ohlc {
+                high: 3.0 + i as f64,
+                low: 1.0 + i as f64,
+                close: 2.0 + i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let atr_values = atr_nan(&candles, 2);
+        assert!(atr_values[0].is_nan());
+        assert_eq!(atr_values[1..], [2.0, 2.0]);
+
+        let hma_values = hma_nan(&(0..20).map(|i| i as f64).collect::<Vec<_>>(), 9);
+        assert_eq!(hma_values.iter().filter(|v| v.is_nan()).count(), 10);
+    }
+
+    fn minute_candles() -> Vec<Ohlc> {
+        (0..60)
+            .map(|i| {
+                let price = 100.0 + (i as f64 * 0.7).sin() * 5.0;
+                Ohlc {
+                    timestamp: i * 60_000,
+                    open: price,
+                    high: price + 1.0 + (i % 3) as f64,
+                    low: price - 1.0,
+                    close: price + 0.5,
+                    volume: 1.0,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resample() {
+        // 1-minute candles with the minutes 2 to 5 missing
+        let candles: Vec<Ohlc> = [0, 1, 6, 7]
+            .iter()
+            .map(|&minute| Ohlc {
+                timestamp: minute * 60_000,
+                open: minute as f64,
+                high: minute as f64 + 1.0,
+                low: minute as f64 - 1.0,
+                close: minute as f64 + 0.5,
+                volume: 1.0,
+            })
+            .collect();
+
+        let resampled = resample(&candles, 60_000, 120_000).unwrap();
+        assert_eq!(resampled.len(), 2);
+        assert_eq!(resampled[0].timestamp, 0);
+        assert_eq!(
+            (
+                resampled[0].open,
+                resampled[0].high,
+                resampled[0].low,
+                resampled[0].close
+            ),
+            (0.0, 2.0, -1.0, 1.5)
+        );
+        assert_eq!(resampled[0].volume, 2.0);
+        assert_eq!(resampled[1].timestamp, 360_000);
+
+        let filled = resample_filled(&candles, 60_000, 120_000).unwrap();
+        let timestamps: Vec<i64> = filled.iter().map(|c| c.timestamp).collect();
+        assert_eq!(timestamps, vec![0, 120_000, 240_000, 360_000]);
+        assert_eq!(filled[1].close, 1.5);
+        assert_eq!(filled[1].volume, 0.0);
+
+        assert!(resample(&candles, 60_000, 90_000).is_err());
+    }
+
+    fn trades() -> Vec<Trade> {
+        [
+            (0, 10.0, 1.0),
+            (20_000, 12.0, 2.0),
+            (70_000, 11.0, 1.0),
+            (80_000, 9.0, 3.0),
+            (200_000, 10.0, 1.0),
+        ]
+        .iter()
+        .map(|&(timestamp, price, qty)| Trade {
+            timestamp,
+            price,
+            qty,
+            side: Side::Buy,
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_time_bars() {
+        let bars = time_bars(&trades(), 60_000).unwrap();
+        let timestamps: Vec<i64> = bars.iter().map(|b| b.timestamp).collect();
+
+        // The empty minute at 120_000 produces no bar
+        assert_eq!(timestamps, vec![0, 60_000, 180_000]);
+        assert_eq!(
+            (bars[1].open, bars[1].high, bars[1].low, bars[1].close),
+            (11.0, 11.0, 9.0, 9.0)
+        );
+        assert_eq!(bars[0].volume, 3.0);
+
+        assert!(time_bars(&trades(), 0).is_err());
+        assert!(TimeBars::new(-60_000).is_err());
+    }
+
+    #[test]
+    fn test_volume_and_dollar_bars() {
+        // Volumes 1, 2 | 1, 3 | 1 (incomplete)
+        let bars = volume_bars(&trades(), 3.0);
+        assert_eq!(bars.len(), 2);
+        assert_eq!(
+            (bars[0].timestamp, bars[0].close, bars[0].volume),
+            (0, 12.0, 3.0)
+        );
+        assert_eq!(
+            (bars[1].timestamp, bars[1].close, bars[1].volume),
+            (70_000, 9.0, 4.0)
+        );
+
+        // Notionals 10, 24 | 11, 27 | 10 (incomplete)
+        let bars = dollar_bars(&trades(), 30.0);
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].volume, 3.0);
+        assert_eq!(bars[1].low, 9.0);
+    }
+
+    #[test]
+    fn test_fill_gaps() {
+        // 1-minute candles with the minutes 2 to 4 and 6 missing
+        let candles: Vec<Ohlc> = [0, 1, 5, 7]
+            .iter()
+            .map(|&minute| Ohlc {
+                timestamp: minute * 60_000,
+                open: minute as f64,
+                high: minute as f64 + 1.0,
+                low: minute as f64 - 1.0,
+                close: minute as f64 + 0.5,
+                volume: 1.0,
+            })
+            .collect();
+
+        let gaps = find_gaps(&candles, 60_000).unwrap();
+        assert_eq!(
+            gaps,
+            vec![
+                Gap {
+                    start: 120_000,
+                    missing: 3
+                },
+                Gap {
+                    start: 360_000,
+                    missing: 1
+                }
+            ]
+        );
+        assert_eq!(gaps[0].end(60_000), 300_000);
+
+        let (filled, report) = fill_gaps(&candles, 60_000, FillPolicy::ForwardFill).unwrap();
+        assert_eq!(report, gaps);
+        let timestamps: Vec<i64> = filled.iter().map(|c| c.timestamp).collect();
+        assert_eq!(
+            timestamps,
+            (0..8).map(|minute| minute * 60_000).collect::<Vec<i64>>()
+        );
+        assert_eq!(
+            (filled[4].open, filled[4].close, filled[4].volume),
+            (1.5, 1.5, 0.0)
+        );
+        assert_eq!(filled[6].close, 5.5);
+
+        let (filled, _) = fill_gaps(&candles, 60_000, FillPolicy::Nan).unwrap();
+        assert_eq!(filled.len(), 8);
+        assert!(filled[2].close.is_nan() && filled[2].volume.is_nan());
+        assert_eq!(filled[5], candles[2]);
+
+        let (skipped, report) = fill_gaps(&candles, 60_000, FillPolicy::Skip).unwrap();
+        assert_eq!(skipped, candles);
+        assert_eq!(report.len(), 2);
+
+        assert!(find_gaps(&candles, 0).is_err());
+    }
+
+    #[test]
+    fn test_tick_imbalance_bars() {
+        let trade = |timestamp, side| Trade {
+            timestamp,
+            price: 10.0 + timestamp as f64,
+            qty: 1.0,
+            side,
+        };
+        // 4 trades netting +2, then a run of sells
+        let sides = [
+            Side::Buy,
+            Side::Buy,
+            Side::Sell,
+            Side::Buy,
+            Side::Sell,
+            Side::Sell,
+            Side::Buy,
+            Side::Sell,
+            Side::Sell,
+        ];
+        let trades: Vec<Trade> = sides
+            .iter()
+            .enumerate()
+            .map(|(i, &side)| trade(i as i64, side))
+            .collect();
+
+        let bars = tick_imbalance_bars(&trades, 4.0, 0.5);
+
+        // First bar: 4 trades, E[b] = 0.5. The next closes at |theta| >= 4 * 0.5
+        assert_eq!((bars[0].timestamp, bars[0].volume), (0, 4.0));
+        assert_eq!((bars[1].timestamp, bars[1].close), (4, 15.0));
+        assert_eq!(bars[1].volume, 2.0);
+        // Then E[T] = 3 and E[b] = -0.25: every trade exceeds 0.75
+        assert_eq!(bars.len(), 5);
+        assert!(bars[2..].iter().all(|b| b.volume == 1.0));
+    }
+
+    #[test]
+    fn test_streaming_matches_batch() {
+        let src: Vec<f64> = minute_candles().iter().map(|c| c.close).collect();
+
+        let streamed: Vec<f64> = Sma::new(5)
+            .batch(src.iter().copied())
+            .into_iter()
+            .map(|v| v.unwrap_or(f64::NAN))
+            .collect();
+        let batch = sma_nan(&src, 5);
+        for (a, b) in streamed.iter().zip(&batch) {
+            assert!(a.is_nan() && b.is_nan() || (a - b).abs() < 1e-9);
+        }
+
+        let streamed: Vec<f64> = Ema::new(5)
+            .batch(src.clone())
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(streamed, ema(src, 5));
+    }
+
+    #[test]
+    fn test_indicator_pipeline() {
+        let candles = minute_candles();
+
+        // ATR of Heikin-Ashi of 5-minute bars
+        let mut pipeline = Resample::new(5 * 60_000)
+            .unwrap()
+            .chain(HeikinAshi::new())
+            .chain(Atr::new(3));
+        let streamed: Vec<f64> = pipeline
+            .batch(candles.clone())
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let mut resample = Resample::new(5 * 60_000).unwrap();
+        let bars: Vec<Ohlc> = resample.batch(candles).into_iter().flatten().collect();
+        assert_eq!(bars.len(), 11);
+        assert_eq!(bars[1].timestamp, 5 * 60_000);
+        assert_eq!(bars[1].volume, 5.0);
+        let expected: Vec<f64> = atr_nan(&heikin_ashi(&bars), 3)
+            .into_iter()
+            .filter(|v| !v.is_nan())
+            .collect();
+
+        assert_eq!(streamed.len(), expected.len());
+        for (a, b) in streamed.iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-9);
+        }
+
+        assert!(Resample::new(0).is_err());
+        assert!(Resample::new(-5 * 60_000).is_err());
+    }
+
+    #[test]
+    fn test_indicator_combinators() {
+        let mut spread = Sma::new(2)
+            .zip(Ema::new(3))
+            .map(|(sma, ema): (f64, f64)| sma - ema);
+
+        assert_eq!(spread.next(1.0), None);
+        assert_eq!(spread.next(3.0), Some(2.0 - 2.0));
+        assert_eq!(spread.next(5.0), Some(4.0 - 3.5));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel_matches_sequential() {
+        use crate::ta::atr::true_range;
+        use crate::ta::parallel::par_atr;
+        use crate::ta::parallel::par_atr_sweep;
+        use crate::ta::parallel::par_rma_sweep;
+        use crate::ta::parallel::par_sma;
+        use crate::ta::parallel::par_true_range;
+
+        let candles = minute_candles();
+        let src: Vec<f64> = candles.iter().map(|c| c.close).collect();
+
+        assert_eq!(par_true_range(&candles), true_range(&candles));
+        assert_eq!(par_sma(&src, 7), sma(&src, 7));
+        assert_eq!(par_sma(&src, 0), sma(&src, 0));
+        let long: Vec<f64> = (0..3 * SMA_CHUNK_LEN).map(|i| (i as f64).sqrt()).collect();
+        assert_eq!(par_sma(&long, 50), sma(&long, 50));
+        assert_eq!(par_atr(&candles, 14), atr(&candles, 14));
+
+        let lengths = [2, 5, 14];
+        let atrs = par_atr_sweep(&candles, &lengths);
+        let rmas = par_rma_sweep(&src, &lengths);
+        for (i, &length) in lengths.iter().enumerate() {
+            assert_eq!(atrs[i], atr(&candles, length));
+            assert_eq!(rmas[i], rma(&src, length));
+        }
+    }
 }
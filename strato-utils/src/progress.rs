@@ -0,0 +1,238 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+/// A snapshot of the progress of a long-running job such as a backtest or a
+/// parameter sweep.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Progress {
+    /// Number of completed steps.
+    pub completed: usize,
+    /// Total number of steps, if known in advance.
+    pub total: Option<usize>,
+    /// Description of the current step (e.g., the parameter set being run).
+    pub label: String,
+    /// Time elapsed since the job started.
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Percentage of the job completed, if the total is known.
+    pub fn percent(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(100.0),
+            Some(total) => Some(self.completed as f64 / total as f64 * 100.0),
+            None => None,
+        }
+    }
+
+    /// Estimated time remaining, extrapolated from the average step duration.
+    pub fn eta(&self) -> Option<Duration> {
+        let total = self.total?;
+        if self.completed == 0 {
+            return None;
+        }
+        let remaining = total.saturating_sub(self.completed) as f64;
+        Some(self.elapsed.mul_f64(remaining / self.completed as f64))
+    }
+}
+
+/// Receives progress updates.
+pub trait ProgressReporter {
+    fn report(&mut self, progress: &Progress);
+}
+
+/// Any closure taking a `&Progress` can be used as a reporter.
+impl<F: FnMut(&Progress)> ProgressReporter for F {
+    fn report(&mut self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+/// Sends progress updates over a channel, e.g. to a UI thread. Updates are
+/// dropped once the receiver hangs up.
+impl ProgressReporter for Sender<Progress> {
+    fn report(&mut self, progress: &Progress) {
+        let _ = self.send(progress.clone());
+    }
+}
+
+/// A reporter that ignores all updates.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoProgress;
+
+impl ProgressReporter for NoProgress {
+    fn report(&mut self, _progress: &Progress) {}
+}
+
+/// A cloneable flag used to ask a running job to stop.
+///
+/// Cancellation is cooperative: the job checks the token between steps and
+/// returns early with whatever it has computed so far.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of every job holding a clone of this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Tracks the progress of a job, forwarding updates to a reporter and
+/// exposing the cancellation state.
+pub struct ProgressTracker<'a> {
+    reporter: &'a mut dyn ProgressReporter,
+    cancel: CancellationToken,
+    total: Option<usize>,
+    completed: usize,
+    report_every: usize,
+    started: Instant,
+}
+
+impl<'a> ProgressTracker<'a> {
+    /// Creates a tracker.
+    ///
+    /// # Arguments
+    ///
+    /// * `reporter` - Receiver of progress updates.
+    /// * `cancel` - Token checked by the job between steps.
+    /// * `total` - Total number of steps, if known.
+    /// * `report_every` - Report once every `report_every` steps (at least 1).
+    pub fn new(
+        reporter: &'a mut dyn ProgressReporter,
+        cancel: CancellationToken,
+        total: Option<usize>,
+        report_every: usize,
+    ) -> Self {
+        Self {
+            reporter,
+            cancel,
+            total,
+            completed: 0,
+            report_every: report_every.max(1),
+            started: Instant::now(),
+        }
+    }
+
+    /// Records a completed step and reports progress if due.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the job has been cancelled and should stop.
+    pub fn step(&mut self, label: &str) -> bool {
+        self.step_with(|| label.to_string())
+    }
+
+    /// Records a completed step like `step`, building the label only when
+    /// progress is reported, e.g. from a timestamp in a tight loop.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the job has been cancelled and should stop.
+    pub fn step_with(&mut self, label: impl FnOnce() -> String) -> bool {
+        self.completed += 1;
+        if self.completed.is_multiple_of(self.report_every) || Some(self.completed) == self.total {
+            let progress = self.progress(label());
+            self.reporter.report(&progress);
+        }
+        !self.is_cancelled()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Returns the current progress.
+    pub fn snapshot(&self, label: &str) -> Progress {
+        self.progress(label.to_string())
+    }
+
+    fn progress(&self, label: String) -> Progress {
+        Progress {
+            completed: self.completed,
+            total: self.total,
+            label,
+            elapsed: self.started.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use super::*;
+
+    #[test]
+    fn test_percent_and_eta() {
+        let progress = Progress {
+            completed: 25,
+            total: Some(100),
+            label: "ma_len=100".to_string(),
+            elapsed: Duration::from_secs(10),
+        };
+
+        assert_eq!(progress.percent(), Some(25.0));
+        assert_eq!(progress.eta(), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_tracker_reports_and_cancels() {
+        let mut reports = Vec::new();
+        let mut reporter = |p: &Progress| reports.push(p.completed);
+        let cancel = CancellationToken::new();
+        let mut tracker = ProgressTracker::new(&mut reporter, cancel.clone(), Some(5), 2);
+
+        assert!(tracker.step("a"));
+        assert!(tracker.step("b"));
+        cancel.cancel();
+        assert!(!tracker.step("c"));
+        drop(tracker);
+
+        assert_eq!(reports, vec![2]);
+    }
+
+    #[test]
+    fn test_label_built_only_when_reported() {
+        let mut labels = Vec::new();
+        let mut reporter = |p: &Progress| labels.push(p.label.clone());
+        let mut tracker = ProgressTracker::new(&mut reporter, CancellationToken::new(), None, 3);
+
+        let mut built = 0;
+        for step in 0..7 {
+            assert!(tracker.step_with(|| {
+                built += 1;
+                step.to_string()
+            }));
+        }
+        drop(tracker);
+
+        assert_eq!(built, 2);
+        assert_eq!(labels, vec!["2", "5"]);
+    }
+
+    #[test]
+    fn test_channel_reporter() {
+        let (mut sender, receiver) = channel();
+        let mut tracker = ProgressTracker::new(&mut sender, CancellationToken::new(), Some(1), 10);
+
+        tracker.step("only");
+
+        let progress = receiver.recv().unwrap();
+        assert_eq!(progress.label, "only");
+        assert_eq!(progress.percent(), Some(100.0));
+    }
+}
@@ -58,6 +58,114 @@ pub fn calculate_relative_depths(
     (relative_bid_depth, relative_ask_depth)
 }
 
+/// Calculates the reservation price of the Avellaneda-Stoikov model, the
+/// price at which a market maker holding `position` is indifferent to
+/// trading.
+///
+/// A long position lowers the reservation price below the mid price and a
+/// short position raises it, by the risk of holding the position over the
+/// remaining time.
+///
+/// # Parameters
+///
+/// - `mid_price`: The middle price of the book.
+/// - `volatility`: Standard deviation of the mid price per unit of time.
+/// - `time_horizon`: Time left to the horizon, in the unit of `volatility`.
+/// - `position`: How much of the asset you currently own. Positive for owning
+///   more, negative for owing more.
+/// - `risk_aversion`: The risk aversion `γ` of the market maker.
+///
+/// # Returns
+///
+/// The reservation price `s - q γ σ² τ`.
+pub fn calculate_reservation_price(
+    mid_price: f64,
+    volatility: f64,
+    time_horizon: f64,
+    position: f64,
+    risk_aversion: f64,
+) -> f64 {
+    mid_price - position * risk_aversion * volatility.powi(2) * time_horizon
+}
+
+/// Calculates the optimal half-spread of the Avellaneda-Stoikov model around
+/// the reservation price.
+///
+/// # Parameters
+///
+/// - `volatility`: Standard deviation of the mid price per unit of time.
+/// - `time_horizon`: Time left to the horizon, in the unit of `volatility`.
+/// - `risk_aversion`: The risk aversion `γ` of the market maker.
+/// - `liquidity`: The decay `κ` of the arrival rate of market orders with the
+///   distance of the quotes from the mid price, per unit of price.
+///
+/// # Returns
+///
+/// Half of the optimal spread `γ σ² τ + 2 / γ ln(1 + γ / κ)`, in price units.
+pub fn calculate_optimal_half_spread(
+    volatility: f64,
+    time_horizon: f64,
+    risk_aversion: f64,
+    liquidity: f64,
+) -> f64 {
+    (risk_aversion * volatility.powi(2) * time_horizon
+        + 2.0 / risk_aversion * (1.0 + risk_aversion / liquidity).ln())
+        / 2.0
+}
+
+/// Calculates the bid and ask prices of the Avellaneda-Stoikov model.
+///
+/// The optimal half-spread and the shift of the reservation price per
+/// `order_qty` of inventory are expressed relative to the mid price and fed to
+/// `calculate_relative_depths`, so that the quotes are the optimal
+/// half-spread away from the reservation price.
+///
+/// # Parameters
+///
+/// - `mid_price`: The middle price of the book.
+/// - `volatility`: Standard deviation of the mid price per unit of time.
+/// - `time_horizon`: Time left to the horizon, in the unit of `volatility`.
+/// - `position`: How much of the asset you currently own. Positive for owning
+///   more, negative for owing more.
+/// - `order_qty`: The size of each order you want to place.
+/// - `risk_aversion`: The risk aversion `γ` of the market maker.
+/// - `liquidity`: The decay `κ` of the arrival rate of market orders.
+///
+/// # Returns
+///
+/// A tuple containing the bid and the ask price.
+///
+/// # Example
+///
+/// ```
+/// use strato_utils::relative_depths::calculate_reservation_quotes;
+///
+/// let (bid, ask) = calculate_reservation_quotes(100.0, 0.2, 60.0, 0.0, 1.0, 0.1, 1.5);
+///
+/// assert!(bid < 100.0 && ask > 100.0);
+/// assert!((100.0 - bid - (ask - 100.0)).abs() < 1e-9);
+/// ```
+pub fn calculate_reservation_quotes(
+    mid_price: f64,
+    volatility: f64,
+    time_horizon: f64,
+    position: f64,
+    order_qty: f64,
+    risk_aversion: f64,
+    liquidity: f64,
+) -> (f64, f64) {
+    let half_spread =
+        calculate_optimal_half_spread(volatility, time_horizon, risk_aversion, liquidity);
+    let skew = risk_aversion * volatility.powi(2) * time_horizon * order_qty;
+    let (bid_depth, ask_depth) = calculate_relative_depths(
+        half_spread / mid_price,
+        skew / mid_price,
+        position,
+        order_qty,
+    );
+    (mid_price * (1.0 - bid_depth), mid_price * (1.0 + ask_depth))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,4 +211,28 @@ mod tests {
         assert_eq!(relative_bid_depth, 0.0); // 0.01 + 0.01 * (-10 / 10)
         assert_eq!(relative_ask_depth, 0.02); // 0.01 - 0.01 * (-10 / 10)
     }
+
+    #[test]
+    fn test_calculate_reservation_quotes() {
+        let (volatility, time_horizon, risk_aversion, liquidity) = (0.2, 60.0, 0.1, 1.5);
+        let half_spread =
+            calculate_optimal_half_spread(volatility, time_horizon, risk_aversion, liquidity);
+        assert!((half_spread - (0.24 + 20.0 * (1.0_f64 + 0.1 / 1.5).ln()) / 2.0).abs() < 1e-12);
+
+        // Long 3 units: quotes around a reservation price 0.72 lower
+        let reservation =
+            calculate_reservation_price(100.0, volatility, time_horizon, 3.0, risk_aversion);
+        assert!((reservation - 99.28).abs() < 1e-12);
+        let (bid, ask) = calculate_reservation_quotes(
+            100.0,
+            volatility,
+            time_horizon,
+            3.0,
+            2.0,
+            risk_aversion,
+            liquidity,
+        );
+        assert!((bid - (reservation - half_spread)).abs() < 1e-9);
+        assert!((ask - (reservation + half_spread)).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,125 @@
+use std::ops::Range;
+
+/// A historical market stress window described by its UTC date range.
+///
+/// Timestamps are Unix epoch milliseconds, with `start` inclusive and `end`
+/// exclusive, so a scenario can be used directly to query candle or trade
+/// data covering the event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StressScenario {
+    /// Short identifier used to look the scenario up.
+    pub name: &'static str,
+    /// Human readable description of the event.
+    pub description: &'static str,
+    /// Start of the window (inclusive), in Unix milliseconds.
+    pub start: i64,
+    /// End of the window (exclusive), in Unix milliseconds.
+    pub end: i64,
+}
+
+/// Covid crash of March 2020, including "Black Thursday" (2020-03-12) when
+/// BTC fell roughly 50% in two days.
+pub const COVID_CRASH_2020: StressScenario = StressScenario {
+    name: "covid_crash_2020",
+    description: "2020-03-08 to 2020-03-20: Covid liquidity crash and Black Thursday",
+    start: 1_583_625_600_000,
+    end: 1_584_662_400_000,
+};
+
+/// May 2021 crash, culminating in the 2021-05-19 liquidation cascade.
+pub const MAY_2021_CRASH: StressScenario = StressScenario {
+    name: "may_2021_crash",
+    description: "2021-05-10 to 2021-05-24: China mining ban and 19 May liquidation cascade",
+    start: 1_620_604_800_000,
+    end: 1_621_814_400_000,
+};
+
+/// Collapse of the Terra/LUNA ecosystem and the UST depeg.
+pub const LUNA_COLLAPSE_2022: StressScenario = StressScenario {
+    name: "luna_collapse_2022",
+    description: "2022-05-07 to 2022-05-14: UST depeg and LUNA collapse",
+    start: 1_651_881_600_000,
+    end: 1_652_486_400_000,
+};
+
+/// Week of the FTX insolvency and exchange halt.
+pub const FTX_WEEK_2022: StressScenario = StressScenario {
+    name: "ftx_week_2022",
+    description: "2022-11-06 to 2022-11-14: FTX insolvency and withdrawal halt",
+    start: 1_667_692_800_000,
+    end: 1_668_384_000_000,
+};
+
+/// The curated set of historical crypto stress windows.
+pub const HISTORICAL_SCENARIOS: [StressScenario; 4] = [
+    COVID_CRASH_2020,
+    MAY_2021_CRASH,
+    LUNA_COLLAPSE_2022,
+    FTX_WEEK_2022,
+];
+
+impl StressScenario {
+    /// Returns `true` if the timestamp falls inside the scenario window.
+    pub fn contains(&self, timestamp: i64) -> bool {
+        timestamp >= self.start && timestamp < self.end
+    }
+
+    /// Returns the index range of `timestamps` that falls inside the scenario
+    /// window.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamps` - Timestamps in Unix milliseconds, sorted ascending.
+    ///
+    /// # Returns
+    ///
+    /// A range that can be used to slice any series aligned with
+    /// `timestamps`. The range is empty if the data does not cover the event.
+    pub fn window(&self, timestamps: &[i64]) -> Range<usize> {
+        let from = timestamps.partition_point(|&ts| ts < self.start);
+        let to = timestamps.partition_point(|&ts| ts < self.end);
+        from..to
+    }
+}
+
+/// Looks up a historical scenario by name.
+pub fn find_scenario(name: &str) -> Option<StressScenario> {
+    HISTORICAL_SCENARIOS
+        .iter()
+        .find(|s| s.name == name)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_scenario() {
+        assert_eq!(find_scenario("ftx_week_2022"), Some(FTX_WEEK_2022));
+        assert_eq!(find_scenario("unknown"), None);
+    }
+
+    #[test]
+    fn test_scenarios_are_ordered_and_disjoint() {
+        for pair in HISTORICAL_SCENARIOS.windows(2) {
+            assert!(pair[0].start < pair[0].end);
+            assert!(pair[0].end <= pair[1].start);
+        }
+    }
+
+    #[test]
+    fn test_window() {
+        let day = 86_400_000;
+        let timestamps: Vec<i64> = (0..10)
+            .map(|i| COVID_CRASH_2020.start - 2 * day + i * 5 * day)
+            .collect();
+
+        let window = COVID_CRASH_2020.window(&timestamps);
+
+        assert_eq!(window, 1..3);
+        assert!(timestamps[window]
+            .iter()
+            .all(|&ts| COVID_CRASH_2020.contains(ts)));
+    }
+}
@@ -0,0 +1,449 @@
+/*!
+Synthetic market data for tests, examples and Monte Carlo studies.
+
+Prices follow a `PriceProcess`, advanced in steps of `dt` years:
+
+- `Gbm` - Geometric Brownian motion, the Black-Scholes world.
+- `OrnsteinUhlenbeck` - Mean reversion around a fixed level, e.g. a spread
+  or a pegged asset.
+- `JumpDiffusion` - Merton's GBM with lognormal jumps, for crash scenarios.
+- `RegimeSwitching` - A Markov chain switching between other processes, e.g.
+  calm and volatile markets.
+
+Every generator takes a seed, so the same seed always produces the same data.
+
+# Example
+
+```
+use strato_utils::sim::CandleGenerator;
+use strato_utils::sim::Gbm;
+
+let candles: Vec<_> = CandleGenerator::new(Gbm::new(0.0, 0.8), 100.0, 60_000, 42)
+    .take(1_000)
+    .collect();
+assert_eq!(candles.len(), 1_000);
+```
+*/
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use rand_distr::Distribution;
+use rand_distr::Exp1;
+use rand_distr::Poisson;
+use rand_distr::StandardNormal;
+
+use crate::vars::ohlc::Ohlc;
+
+/// Milliseconds in a year of 365 days, to convert candle intervals to `dt`.
+pub const MS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0 * 1_000.0;
+
+/// A stochastic price process.
+pub trait PriceProcess {
+    /// Advances `price` by `dt` years.
+    fn step(&mut self, price: f64, dt: f64, rng: &mut StdRng) -> f64;
+}
+
+impl PriceProcess for Box<dyn PriceProcess> {
+    fn step(&mut self, price: f64, dt: f64, rng: &mut StdRng) -> f64 {
+        (**self).step(price, dt, rng)
+    }
+}
+
+/// Geometric Brownian motion.
+///
+/// # Mathematical Formulation
+///
+/// ```text
+/// S(t + dt) = S(t) * exp((mu - sigma^2 / 2) * dt + sigma * sqrt(dt) * Z)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gbm {
+    /// Annualized drift.
+    pub mu: f64,
+    /// Annualized volatility.
+    pub sigma: f64,
+}
+
+impl Gbm {
+    pub fn new(mu: f64, sigma: f64) -> Self {
+        Self { mu, sigma }
+    }
+}
+
+impl PriceProcess for Gbm {
+    fn step(&mut self, price: f64, dt: f64, rng: &mut StdRng) -> f64 {
+        let z: f64 = rng.sample(StandardNormal);
+        price * ((self.mu - 0.5 * self.sigma.powi(2)) * dt + self.sigma * dt.sqrt() * z).exp()
+    }
+}
+
+/// Ornstein-Uhlenbeck process, reverting to `mean` at speed `theta`.
+///
+/// Prices are not bounded below and may turn negative if `sigma` is large
+/// relative to `mean`.
+///
+/// # Mathematical Formulation
+///
+/// Exact discretization of `dX = theta * (mean - X) dt + sigma dW`:
+///
+/// ```text
+/// X(t + dt) = mean + (X(t) - mean) * e^(-theta * dt)
+///           + sigma * sqrt((1 - e^(-2 * theta * dt)) / (2 * theta)) * Z
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrnsteinUhlenbeck {
+    /// Speed of mean reversion per year; the half-life is `ln(2) / theta`.
+    pub theta: f64,
+    /// Long-run mean.
+    pub mean: f64,
+    /// Annualized volatility, in price units.
+    pub sigma: f64,
+}
+
+impl OrnsteinUhlenbeck {
+    pub fn new(theta: f64, mean: f64, sigma: f64) -> Self {
+        Self { theta, mean, sigma }
+    }
+}
+
+impl PriceProcess for OrnsteinUhlenbeck {
+    fn step(&mut self, price: f64, dt: f64, rng: &mut StdRng) -> f64 {
+        let z: f64 = rng.sample(StandardNormal);
+        let decay = (-self.theta * dt).exp();
+        let std = if self.theta > 0.0 {
+            self.sigma * ((1.0 - decay.powi(2)) / (2.0 * self.theta)).sqrt()
+        } else {
+            self.sigma * dt.sqrt()
+        };
+        self.mean + (price - self.mean) * decay + std * z
+    }
+}
+
+/// Merton jump-diffusion: a GBM with Poisson arrivals of lognormal jumps.
+///
+/// The drift is compensated for the jumps, so the expected return is `mu`
+/// regardless of the jump parameters.
+///
+/// # Mathematical Formulation
+///
+/// ```text
+/// k = exp(jump_mean + jump_std^2 / 2) - 1
+/// S(t + dt) = S(t) * exp((mu - sigma^2 / 2 - lambda * k) * dt + sigma * sqrt(dt) * Z + J)
+/// ```
+///
+/// where `J` is the sum of `N ~ Poisson(lambda * dt)` jumps drawn from
+/// `Normal(jump_mean, jump_std^2)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JumpDiffusion {
+    /// Annualized drift.
+    pub mu: f64,
+    /// Annualized volatility of the diffusion.
+    pub sigma: f64,
+    /// Expected number of jumps per year (`lambda`).
+    pub jump_intensity: f64,
+    /// Mean of the log jump size; negative for crashes.
+    pub jump_mean: f64,
+    /// Standard deviation of the log jump size.
+    pub jump_std: f64,
+}
+
+impl PriceProcess for JumpDiffusion {
+    fn step(&mut self, price: f64, dt: f64, rng: &mut StdRng) -> f64 {
+        let z: f64 = rng.sample(StandardNormal);
+        let lambda = self.jump_intensity * dt;
+        let jumps = match Poisson::new(lambda) {
+            Ok(poisson) => poisson.sample(rng),
+            // Zero intensity
+            Err(_) => 0.0,
+        };
+        let jump = if jumps > 0.0 {
+            let z: f64 = rng.sample(StandardNormal);
+            jumps * self.jump_mean + jumps.sqrt() * self.jump_std * z
+        } else {
+            0.0
+        };
+        let k = (self.jump_mean + 0.5 * self.jump_std.powi(2)).exp() - 1.0;
+        let drift = (self.mu - 0.5 * self.sigma.powi(2) - self.jump_intensity * k) * dt;
+
+        price * (drift + self.sigma * dt.sqrt() * z + jump).exp()
+    }
+}
+
+/// Switches between processes following a Markov chain evaluated at every
+/// step.
+pub struct RegimeSwitching {
+    regimes: Vec<Box<dyn PriceProcess>>,
+    transitions: Vec<Vec<f64>>,
+    current: usize,
+}
+
+impl RegimeSwitching {
+    /// Creates a process starting in the first regime.
+    ///
+    /// # Arguments
+    ///
+    /// * `regimes` - The process of each regime.
+    /// * `transitions` - `transitions[i][j]` is the probability of moving from
+    ///   regime `i` to regime `j` at each step. Each row must sum to one.
+    ///
+    /// # Returns
+    ///
+    /// The process, or an error if `transitions` is not a square matrix of
+    /// probabilities matching the regimes.
+    pub fn new(
+        regimes: Vec<Box<dyn PriceProcess>>,
+        transitions: Vec<Vec<f64>>,
+    ) -> Result<Self, String> {
+        if regimes.is_empty() {
+            return Err("At least one regime is required.".to_string());
+        }
+        if transitions.len() != regimes.len() {
+            return Err(format!(
+                "Expected {} rows of transition probabilities, got {}.",
+                regimes.len(),
+                transitions.len()
+            ));
+        }
+        for (i, row) in transitions.iter().enumerate() {
+            let sum: f64 = row.iter().sum();
+            if row.len() != regimes.len()
+                || row.iter().any(|p| !(0.0..=1.0).contains(p))
+                || (sum - 1.0).abs() > 1e-9
+            {
+                return Err(format!(
+                    "Row {} of the transition matrix is not a probability distribution over {} regimes.",
+                    i,
+                    regimes.len()
+                ));
+            }
+        }
+
+        Ok(Self {
+            regimes,
+            transitions,
+            current: 0,
+        })
+    }
+
+    /// Index of the current regime.
+    pub fn regime(&self) -> usize {
+        self.current
+    }
+}
+
+impl PriceProcess for RegimeSwitching {
+    fn step(&mut self, price: f64, dt: f64, rng: &mut StdRng) -> f64 {
+        let price = self.regimes[self.current].step(price, dt, rng);
+
+        let u: f64 = rng.gen();
+        let mut cumulative = 0.0;
+        for (next, p) in self.transitions[self.current].iter().enumerate() {
+            cumulative += p;
+            if u < cumulative {
+                self.current = next;
+                break;
+            }
+        }
+
+        price
+    }
+}
+
+/// Simulates a price path.
+///
+/// # Arguments
+///
+/// * `process` - The price process.
+/// * `initial_price` - Price at the start of the path.
+/// * `steps` - Number of steps to simulate.
+/// * `dt` - Length of a step in years.
+/// * `seed` - Seed of the random number generator.
+///
+/// # Returns
+///
+/// `steps + 1` prices, starting with `initial_price`.
+pub fn simulate_path<P: PriceProcess>(
+    mut process: P,
+    initial_price: f64,
+    steps: usize,
+    dt: f64,
+    seed: u64,
+) -> Vec<f64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut path = Vec::with_capacity(steps + 1);
+    let mut price = initial_price;
+    path.push(price);
+    for _ in 0..steps {
+        price = process.step(price, dt, &mut rng);
+        path.push(price);
+    }
+    path
+}
+
+/// An endless iterator of candles built from a simulated price path.
+///
+/// Each candle is sampled from `substeps` steps of the process, so its high
+/// and low cover the intra-candle path, and opens at the close of the
+/// previous one. Volumes are exponentially distributed around
+/// `mean_volume`.
+pub struct CandleGenerator<P: PriceProcess> {
+    process: P,
+    rng: StdRng,
+    price: f64,
+    timestamp: i64,
+    interval: i64,
+    substeps: usize,
+    mean_volume: f64,
+}
+
+impl<P: PriceProcess> CandleGenerator<P> {
+    /// Creates a generator of `interval` millisecond candles starting at
+    /// timestamp `0`, with 10 substeps per candle and a mean volume of `1.0`.
+    pub fn new(process: P, initial_price: f64, interval: i64, seed: u64) -> Self {
+        Self {
+            process,
+            rng: StdRng::seed_from_u64(seed),
+            price: initial_price,
+            timestamp: 0,
+            interval,
+            substeps: 10,
+            mean_volume: 1.0,
+        }
+    }
+
+    /// Sets the timestamp of the first candle, in Unix milliseconds.
+    pub fn with_start(mut self, timestamp: i64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Sets the number of process steps per candle.
+    pub fn with_substeps(mut self, substeps: usize) -> Self {
+        self.substeps = substeps.max(1);
+        self
+    }
+
+    pub fn with_mean_volume(mut self, mean_volume: f64) -> Self {
+        self.mean_volume = mean_volume;
+        self
+    }
+
+    /// The wrapped process, e.g. to inspect the current regime.
+    pub fn process(&self) -> &P {
+        &self.process
+    }
+}
+
+impl<P: PriceProcess> Iterator for CandleGenerator<P> {
+    type Item = Ohlc;
+
+    fn next(&mut self) -> Option<Ohlc> {
+        let dt = self.interval as f64 / MS_PER_YEAR / self.substeps as f64;
+        let mut candle = Ohlc {
+            timestamp: self.timestamp,
+            open: self.price,
+            high: self.price,
+            low: self.price,
+            close: self.price,
+            volume: self.mean_volume * self.rng.sample::<f64, _>(Exp1),
+        };
+        for _ in 0..self.substeps {
+            self.price = self.process.step(self.price, dt, &mut self.rng);
+            candle.high = candle.high.max(self.price);
+            candle.low = candle.low.min(self.price);
+        }
+        candle.close = self.price;
+        self.timestamp += self.interval;
+
+        Some(candle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::validate_candles;
+
+    #[test]
+    fn test_seeded_paths_are_reproducible() {
+        let a = simulate_path(Gbm::new(0.1, 0.5), 100.0, 100, 1.0 / 365.0, 7);
+        let b = simulate_path(Gbm::new(0.1, 0.5), 100.0, 100, 1.0 / 365.0, 7);
+        let c = simulate_path(Gbm::new(0.1, 0.5), 100.0, 100, 1.0 / 365.0, 8);
+
+        assert_eq!(a.len(), 101);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_processes() {
+        // Without volatility a GBM compounds its drift
+        let path = simulate_path(Gbm::new(0.1, 0.0), 100.0, 10, 0.1, 1);
+        assert!((path[10] - 100.0 * 0.1f64.exp()).abs() < 1e-9);
+
+        // A fast mean reversion pulls the price to its mean
+        let path = simulate_path(OrnsteinUhlenbeck::new(50.0, 10.0, 0.1), 20.0, 100, 0.1, 1);
+        assert!((path[100] - 10.0).abs() < 0.1);
+
+        // Without jumps the jump-diffusion is a GBM
+        let no_jumps = JumpDiffusion {
+            mu: 0.1,
+            sigma: 0.5,
+            jump_intensity: 0.0,
+            jump_mean: -0.1,
+            jump_std: 0.05,
+        };
+        assert_eq!(
+            simulate_path(no_jumps, 100.0, 50, 0.01, 5),
+            simulate_path(Gbm::new(0.1, 0.5), 100.0, 50, 0.01, 5)
+        );
+
+        // One -10% jump per step on average, compensated by the drift
+        let jumps = JumpDiffusion {
+            jump_intensity: 100.0,
+            sigma: 0.0,
+            ..no_jumps
+        };
+        let mean = (0..4_000)
+            .map(|seed| simulate_path(jumps, 100.0, 1, 0.01, seed)[1])
+            .sum::<f64>()
+            / 4_000.0;
+        assert!((mean - 100.0 * 0.001f64.exp()).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_regime_switching() {
+        let regimes: Vec<Box<dyn PriceProcess>> =
+            vec![Box::new(Gbm::new(0.0, 0.0)), Box::new(Gbm::new(0.0, 2.0))];
+        assert!(RegimeSwitching::new(regimes, vec![vec![0.5, 0.6], vec![0.0, 1.0]]).is_err());
+
+        // Always switch to the volatile regime and stay there
+        let regimes: Vec<Box<dyn PriceProcess>> =
+            vec![Box::new(Gbm::new(0.0, 0.0)), Box::new(Gbm::new(0.0, 2.0))];
+        let process = RegimeSwitching::new(regimes, vec![vec![0.0, 1.0], vec![0.0, 1.0]]).unwrap();
+        let mut candles = CandleGenerator::new(process, 100.0, 60_000, 3).with_substeps(1);
+
+        let calm = candles.next().unwrap();
+        assert_eq!(candles.process().regime(), 1);
+        let volatile = candles.next().unwrap();
+        assert_eq!(calm.high, calm.low);
+        assert!(volatile.high > volatile.low);
+    }
+
+    #[test]
+    fn test_candles_are_valid() {
+        let candles: Vec<Ohlc> = CandleGenerator::new(Gbm::new(0.0, 0.8), 100.0, 60_000, 42)
+            .with_start(1_704_067_200_000)
+            .with_mean_volume(5.0)
+            .take(500)
+            .collect();
+
+        assert!(validate_candles(&candles).is_empty());
+        assert_eq!(candles[1].timestamp, 1_704_067_260_000);
+        assert_eq!(candles[1].open, candles[0].close);
+    }
+}
@@ -1,4 +1,38 @@
+pub mod accdist;
+pub mod aroon;
 pub mod atr;
+pub mod cmf;
+pub mod correlation;
+pub mod cross;
+pub mod dema;
+pub mod dmi;
+pub mod donchian;
+pub mod drawdown;
 pub mod ema;
+pub mod fractals;
+pub mod heikin_ashi;
+pub mod hma;
+pub mod indicator;
+pub mod macd;
+pub mod mfi;
+pub mod obv;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod percentrank;
+pub mod pivots;
+pub mod renko;
+pub mod resample;
 pub mod rma;
+pub mod roc;
+pub mod sar;
 pub mod sma;
+pub mod stdev;
+pub mod trade_bars;
+pub mod ulcer;
+pub mod vpin;
+pub mod vwap;
+pub mod vwma;
+pub mod warmup;
+pub mod wma;
+pub mod zigzag;
+pub mod zscore;
@@ -0,0 +1,21 @@
+use crate::vars::ohlc::Ohlc;
+
+/// https://www.tradingview.com/pine-script-reference/v5/#var_ta.accdist
+///
+/// Calculates the Accumulation/Distribution line, the cumulative volume
+/// weighted by the close location value `((close - low) - (high - close)) /
+/// (high - low)`. Candles with no range contribute nothing.
+pub fn accdist(candles: &[Ohlc]) -> Vec<f64> {
+    let mut ad_values = Vec::with_capacity(candles.len());
+    let mut ad = 0.0;
+
+    for c in candles {
+        let range = c.high - c.low;
+        if range > 0.0 {
+            ad += (2.0 * c.close - c.low - c.high) / range * c.volume;
+        }
+        ad_values.push(ad);
+    }
+
+    ad_values
+}
@@ -0,0 +1,53 @@
+use crate::vars::ohlc::Ohlc;
+
+/// Calculates the Aroon indicator of the candles.
+///
+/// # Arguments
+///
+/// * `candles` - A slice of `Ohlc` candles.
+/// * `length` - Number of candles in the lookback window, excluding the current
+///   one.
+///
+/// # Returns
+///
+/// A tuple containing three vectors, each `0.0` for the first `length`
+/// candles:
+/// - `up`: Aroon Up, from 0 to 100.
+/// - `down`: Aroon Down, from 0 to 100.
+/// - `oscillator`: Aroon Up minus Aroon Down, from -100 to 100.
+///
+/// # Mathematical Formulation
+///
+/// - `Aroon Up = 100 * (length - bars since the highest high) / length`
+/// - `Aroon Down = 100 * (length - bars since the lowest low) / length`
+///
+/// over the last `length + 1` candles. On ties the most recent extreme is
+/// used.
+pub fn aroon(candles: &[Ohlc], length: usize) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let mut up = vec![0.0; candles.len()];
+    let mut down = vec![0.0; candles.len()];
+    let mut oscillator = vec![0.0; candles.len()];
+
+    if length == 0 {
+        return (up, down, oscillator);
+    }
+
+    for i in length..candles.len() {
+        let mut highest = i - length;
+        let mut lowest = i - length;
+        for j in i - length..=i {
+            if candles[j].high >= candles[highest].high {
+                highest = j;
+            }
+            if candles[j].low <= candles[lowest].low {
+                lowest = j;
+            }
+        }
+
+        up[i] = 100.0 * (length - (i - highest)) as f64 / length as f64;
+        down[i] = 100.0 * (length - (i - lowest)) as f64 / length as f64;
+        oscillator[i] = up[i] - down[i];
+    }
+
+    (up, down, oscillator)
+}
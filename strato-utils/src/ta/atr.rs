@@ -1,4 +1,6 @@
+use crate::ta::percentrank::percentrank;
 use crate::ta::rma::rma;
+use crate::ta::rma::rma_nan;
 use crate::vars::ohlc::Ohlc;
 
 /// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.atr
@@ -27,3 +29,49 @@ pub fn atr(candles: &[Ohlc], length: usize) -> Vec<f64> {
 
     rma(&tr, length)
 }
+
+/// Like `atr`, but the first `length - 1` values are `f64::NAN`. As in Pine
+/// Script, the true range of the first candle is its high-low range.
+pub fn atr_nan(candles: &[Ohlc], length: usize) -> Vec<f64> {
+    let mut tr = true_range(candles);
+    if let Some(first) = candles.first() {
+        tr[0] = first.high - first.low;
+    }
+
+    rma_nan(&tr, length)
+}
+
+/// Calculates the normalized ATR, the ATR as a percentage of the close, so
+/// that volatility is comparable across assets of different price levels.
+///
+/// Values are `0.0` where the close is not positive.
+pub fn natr(candles: &[Ohlc], length: usize) -> Vec<f64> {
+    atr(candles, length)
+        .iter()
+        .zip(candles)
+        .map(|(atr, c)| {
+            if c.close > 0.0 {
+                100.0 * atr / c.close
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Calculates the percentile rank of the ATR among its previous `lookback`
+/// values, from 0 (the calmest) to 100 (the most volatile).
+///
+/// # Arguments
+///
+/// * `candles` - A slice of `Ohlc` candles.
+/// * `length` - ATR length.
+/// * `lookback` - Number of previous ATR values the current one is ranked
+///   against.
+///
+/// # Returns
+///
+/// A vector of ranks, `0.0` for the first `lookback` candles.
+pub fn atr_percentile(candles: &[Ohlc], length: usize, lookback: usize) -> Vec<f64> {
+    percentrank(&atr(candles, length), lookback)
+}
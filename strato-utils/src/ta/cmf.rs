@@ -0,0 +1,48 @@
+use crate::vars::ohlc::Ohlc;
+
+/// The standard CMF lookback length.
+pub const DEFAULT_CMF_LEN: usize = 20;
+
+/// Calculates the Chaikin Money Flow, the share of the volume in the window
+/// that was accumulated (positive) or distributed (negative).
+///
+/// # Arguments
+///
+/// * `candles` - A slice of `Ohlc` candles with volumes.
+/// * `length` - Number of candles in the window, typically `DEFAULT_CMF_LEN`.
+///
+/// # Returns
+///
+/// A vector of CMF values from -1 to 1, `0.0` for the first `length - 1`
+/// candles and for windows without volume.
+///
+/// # Mathematical Formulation
+///
+/// `CMF = Σ (CLV * volume) / Σ volume`
+///
+/// where `CLV = ((close - low) - (high - close)) / (high - low)` is the close
+/// location value, `0` for candles with no range.
+pub fn cmf(candles: &[Ohlc], length: usize) -> Vec<f64> {
+    let money_flow_volume: Vec<f64> = candles
+        .iter()
+        .map(|c| {
+            let range = c.high - c.low;
+            if range > 0.0 {
+                (2.0 * c.close - c.low - c.high) / range * c.volume
+            } else {
+                0.0
+            }
+        })
+        .collect();
+
+    let mut cmf_values = vec![0.0; candles.len()];
+    for i in length.saturating_sub(1)..candles.len() {
+        let flow: f64 = money_flow_volume[i + 1 - length..=i].iter().sum();
+        let volume: f64 = candles[i + 1 - length..=i].iter().map(|c| c.volume).sum();
+        if volume > 0.0 {
+            cmf_values[i] = flow / volume;
+        }
+    }
+
+    cmf_values
+}
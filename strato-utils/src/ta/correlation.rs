@@ -0,0 +1,50 @@
+use crate::ta::sma::sma;
+use crate::ta::stdev::stdev;
+
+/// Calculates the rolling population covariance of two series.
+///
+/// The series are compared up to the length of the shorter one. Values are
+/// `0.0` for the first `length - 1` values.
+pub fn rolling_cov(a: &[f64], b: &[f64], length: usize) -> Vec<f64> {
+    let n = a.len().min(b.len());
+    let (a, b) = (&a[..n], &b[..n]);
+    let mean_a = sma(a, length);
+    let mean_b = sma(b, length);
+    let mut cov_values = vec![0.0; n];
+
+    for i in length.saturating_sub(1)..n {
+        let sum: f64 = a[i + 1 - length..=i]
+            .iter()
+            .zip(&b[i + 1 - length..=i])
+            .map(|(x, y)| (x - mean_a[i]) * (y - mean_b[i]))
+            .sum();
+        cov_values[i] = sum / length as f64;
+    }
+
+    cov_values
+}
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.correlation
+///
+/// Calculates the rolling Pearson correlation of two series, from -1 to 1.
+///
+/// The series are compared up to the length of the shorter one. Values are
+/// `0.0` for the first `length - 1` values and wherever either window has no
+/// dispersion.
+pub fn rolling_corr(a: &[f64], b: &[f64], length: usize) -> Vec<f64> {
+    let n = a.len().min(b.len());
+    let cov = rolling_cov(a, b, length);
+    let stdev_a = stdev(&a[..n], length);
+    let stdev_b = stdev(&b[..n], length);
+
+    (0..n)
+        .map(|i| {
+            let denom = stdev_a[i] * stdev_b[i];
+            if denom > 0.0 {
+                (cov[i] / denom).clamp(-1.0, 1.0)
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
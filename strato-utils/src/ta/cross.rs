@@ -0,0 +1,59 @@
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.crossover
+///
+/// Returns `true` where `a` crosses over `b`: `a` is above `b` on the current
+/// value and was at or below it on the previous one. The series are compared
+/// over the length of the shorter one, and the first value is never a cross.
+pub fn crossover(a: &[f64], b: &[f64]) -> Vec<bool> {
+    crossings(a, b, |prev_a, prev_b, a, b| a > b && prev_a <= prev_b)
+}
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.crossunder
+///
+/// Returns `true` where `a` crosses under `b`: `a` is below `b` on the
+/// current value and was at or above it on the previous one.
+pub fn crossunder(a: &[f64], b: &[f64]) -> Vec<bool> {
+    crossings(a, b, |prev_a, prev_b, a, b| a < b && prev_a >= prev_b)
+}
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.cross
+///
+/// Returns `true` where `a` crosses `b` in either direction.
+pub fn cross(a: &[f64], b: &[f64]) -> Vec<bool> {
+    crossover(a, b)
+        .into_iter()
+        .zip(crossunder(a, b))
+        .map(|(over, under)| over || under)
+        .collect()
+}
+
+/// Returns the indices at which `a` crosses over `b`.
+pub fn crossover_indices(a: &[f64], b: &[f64]) -> Vec<usize> {
+    indices(&crossover(a, b))
+}
+
+/// Returns the indices at which `a` crosses under `b`.
+pub fn crossunder_indices(a: &[f64], b: &[f64]) -> Vec<usize> {
+    indices(&crossunder(a, b))
+}
+
+fn crossings<F>(a: &[f64], b: &[f64], crossed: F) -> Vec<bool>
+where
+    F: Fn(f64, f64, f64, f64) -> bool,
+{
+    let len = a.len().min(b.len());
+    let mut cross_values = vec![false; len];
+
+    for i in 1..len {
+        cross_values[i] = crossed(a[i - 1], b[i - 1], a[i], b[i]);
+    }
+
+    cross_values
+}
+
+fn indices(events: &[bool]) -> Vec<usize> {
+    events
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &event)| event.then_some(i))
+        .collect()
+}
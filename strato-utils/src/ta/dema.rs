@@ -0,0 +1,65 @@
+use crate::ta::ema::ema;
+use crate::ta::ema::ema_nan;
+
+/// Calculates the Double Exponential Moving Average, `2 * EMA - EMA(EMA)`.
+pub fn dema(src: &[f64], length: usize) -> Vec<f64> {
+    let ema1 = ema(src.to_vec(), length);
+    let ema2 = ema(ema1.clone(), length);
+
+    ema1.iter()
+        .zip(ema2.iter())
+        .map(|(e1, e2)| 2.0 * e1 - e2)
+        .collect()
+}
+
+/// Calculates the Triple Exponential Moving Average,
+/// `3 * EMA - 3 * EMA(EMA) + EMA(EMA(EMA))`.
+pub fn tema(src: &[f64], length: usize) -> Vec<f64> {
+    let ema1 = ema(src.to_vec(), length);
+    let ema2 = ema(ema1.clone(), length);
+    let ema3 = ema(ema2.clone(), length);
+
+    ema1.iter()
+        .zip(ema2.iter())
+        .zip(ema3.iter())
+        .map(|((e1, e2), e3)| 3.0 * e1 - 3.0 * e2 + e3)
+        .collect()
+}
+
+/// Like `dema`, but built on `ema_nan`: each EMA is seeded with the SMA of its
+/// first `length` defined inputs, so the first `2 * (length - 1)` values are
+/// `f64::NAN`.
+pub fn dema_nan(src: &[f64], length: usize) -> Vec<f64> {
+    let ema1 = ema_nan(src, length);
+    let ema2 = chained_ema_nan(&ema1, length);
+
+    ema1.iter()
+        .zip(ema2.iter())
+        .map(|(e1, e2)| 2.0 * e1 - e2)
+        .collect()
+}
+
+/// Like `tema`, but built on `ema_nan`, so the first `3 * (length - 1)` values
+/// are `f64::NAN`.
+pub fn tema_nan(src: &[f64], length: usize) -> Vec<f64> {
+    let ema1 = ema_nan(src, length);
+    let ema2 = chained_ema_nan(&ema1, length);
+    let ema3 = chained_ema_nan(&ema2, length);
+
+    ema1.iter()
+        .zip(ema2.iter())
+        .zip(ema3.iter())
+        .map(|((e1, e2), e3)| 3.0 * e1 - 3.0 * e2 + e3)
+        .collect()
+}
+
+/// `ema_nan` of an EMA still warming up, starting at its first defined value.
+fn chained_ema_nan(values: &[f64], length: usize) -> Vec<f64> {
+    let start = values
+        .iter()
+        .position(|value| !value.is_nan())
+        .unwrap_or(values.len());
+    let mut ema_values = vec![f64::NAN; start];
+    ema_values.extend(ema_nan(&values[start..], length));
+    ema_values
+}
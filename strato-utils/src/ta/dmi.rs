@@ -0,0 +1,60 @@
+use crate::ta::atr::true_range;
+use crate::ta::rma::rma;
+use crate::vars::ohlc::Ohlc;
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.dmi
+///
+/// Calculates the Directional Movement Index (DMI) and the Average
+/// Directional Index (ADX).
+///
+/// # Arguments
+///
+/// * `candles` - A slice of `Ohlc` candles.
+/// * `di_length` - Smoothing length of the directional indicators.
+/// * `adx_smoothing` - Smoothing length of the ADX.
+///
+/// # Returns
+///
+/// A tuple containing three vectors:
+/// - `plus_di`: The +DI, from 0 to 100.
+/// - `minus_di`: The -DI, from 0 to 100.
+/// - `adx`: The ADX, from 0 to 100.
+pub fn dmi(
+    candles: &[Ohlc],
+    di_length: usize,
+    adx_smoothing: usize,
+) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let mut plus_dm = vec![0.0; candles.len()];
+    let mut minus_dm = vec![0.0; candles.len()];
+
+    for i in 1..candles.len() {
+        let up = candles[i].high - candles[i - 1].high;
+        let down = candles[i - 1].low - candles[i].low;
+        if up > down && up > 0.0 {
+            plus_dm[i] = up;
+        }
+        if down > up && down > 0.0 {
+            minus_dm[i] = down;
+        }
+    }
+
+    let tr = rma(&true_range(candles), di_length);
+    let plus_dm = rma(&plus_dm, di_length);
+    let minus_dm = rma(&minus_dm, di_length);
+
+    let mut plus_di = vec![0.0; candles.len()];
+    let mut minus_di = vec![0.0; candles.len()];
+    let mut dx = vec![0.0; candles.len()];
+    for i in 0..candles.len() {
+        if tr[i] > 0.0 {
+            plus_di[i] = 100.0 * plus_dm[i] / tr[i];
+            minus_di[i] = 100.0 * minus_dm[i] / tr[i];
+        }
+        let sum = plus_di[i] + minus_di[i];
+        dx[i] = (plus_di[i] - minus_di[i]).abs() / if sum == 0.0 { 1.0 } else { sum };
+    }
+
+    let adx = rma(&dx, adx_smoothing).iter().map(|v| v * 100.0).collect();
+
+    (plus_di, minus_di, adx)
+}
@@ -0,0 +1,53 @@
+use crate::vars::ohlc::Ohlc;
+use crate::vars::window::Windows;
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.highest
+pub fn highest(src: &[f64], length: usize) -> Vec<f64> {
+    rolling(src, length, f64::max)
+}
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.lowest
+pub fn lowest(src: &[f64], length: usize) -> Vec<f64> {
+    rolling(src, length, f64::min)
+}
+
+/// Calculates the Donchian Channels of the candles.
+///
+/// # Arguments
+///
+/// * `candles` - A slice of `Ohlc` candles.
+/// * `length` - Number of candles in the lookback window, including the current
+///   one.
+///
+/// # Returns
+///
+/// A tuple containing three vectors, each `0.0` for the first `length - 1`
+/// candles:
+/// - `upper`: The highest high over the window.
+/// - `basis`: The midpoint of the upper and lower bands.
+/// - `lower`: The lowest low over the window.
+pub fn donchian(candles: &[Ohlc], length: usize) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let highs: Vec<f64> = candles.iter().map(|c| c.high).collect();
+    let lows: Vec<f64> = candles.iter().map(|c| c.low).collect();
+
+    let upper = highest(&highs, length);
+    let lower = lowest(&lows, length);
+    let basis = upper
+        .iter()
+        .zip(lower.iter())
+        .map(|(u, l)| (u + l) / 2.0)
+        .collect();
+
+    (upper, basis, lower)
+}
+
+fn rolling(src: &[f64], length: usize, f: fn(f64, f64) -> f64) -> Vec<f64> {
+    let mut values = Vec::with_capacity(src.len());
+    values.resize((length - 1).min(src.len()), 0.0);
+    values.extend(
+        src.windows_of(length)
+            .map(|window| window[1..].iter().copied().fold(window[0], f)),
+    );
+
+    values
+}
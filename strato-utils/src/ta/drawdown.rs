@@ -0,0 +1,42 @@
+/// Calculates the drawdown of every value from the running peak, as a
+/// fraction of the peak (e.g., `0.25` is 25% below the peak).
+///
+/// Works on any positive series, e.g. prices or an equity curve.
+pub fn drawdown(src: &[f64]) -> Vec<f64> {
+    let mut peak = f64::MIN;
+
+    src.iter()
+        .map(|&value| {
+            peak = peak.max(value);
+            if peak > 0.0 {
+                1.0 - value / peak
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Calculates the drawdown of every value from the peak of the last `length`
+/// values, including the current one, as a fraction of the peak.
+///
+/// While fewer than `length` values are available, the peak is taken over
+/// all values so far.
+pub fn rolling_drawdown(src: &[f64], length: usize) -> Vec<f64> {
+    (0..src.len())
+        .map(|i| {
+            let start = (i + 1).saturating_sub(length.max(1));
+            let peak = src[start..=i].iter().copied().fold(f64::MIN, f64::max);
+            if peak > 0.0 {
+                1.0 - src[i] / peak
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Returns the maximum drawdown of the series, as a fraction of the peak.
+pub fn max_drawdown(src: &[f64]) -> f64 {
+    drawdown(src).into_iter().fold(0.0, f64::max)
+}
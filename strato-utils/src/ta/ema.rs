@@ -12,3 +12,24 @@ pub fn ema(src: Vec<f64>, length: usize) -> Vec<f64> {
 
     ema
 }
+
+/// Like `ema`, but seeded as in Pine Script: the first `length - 1` values
+/// are `f64::NAN` and the SMA of the first `length` values is placed at index
+/// `length - 1`.
+///
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.ema
+pub fn ema_nan(src: &[f64], length: usize) -> Vec<f64> {
+    let alpha = 2.0 / (length as f64 + 1.0);
+    let mut ema_values = vec![f64::NAN; src.len()];
+
+    if length == 0 || src.len() < length {
+        return ema_values;
+    }
+
+    ema_values[length - 1] = src[..length].iter().sum::<f64>() / length as f64;
+    for i in length..src.len() {
+        ema_values[i] = alpha * src[i] + (1.0 - alpha) * ema_values[i - 1];
+    }
+
+    ema_values
+}
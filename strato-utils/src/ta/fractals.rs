@@ -0,0 +1,48 @@
+use crate::ta::zigzag::SwingPoint;
+use crate::vars::ohlc::Ohlc;
+
+/// Detects Williams fractals: candles whose high (low) is strictly above
+/// (below) the highs (lows) of the `lookback` candles on each side.
+///
+/// # Arguments
+///
+/// * `candles` - A slice of `Ohlc` candles.
+/// * `lookback` - Number of candles on each side, `2` in the original
+///   definition.
+///
+/// # Returns
+///
+/// The swing points in index order, highs before lows on the same candle. A
+/// fractal is confirmed `lookback` candles after it forms.
+pub fn fractals(candles: &[Ohlc], lookback: usize) -> Vec<SwingPoint> {
+    let mut swings = Vec::new();
+    if lookback == 0 {
+        return swings;
+    }
+
+    for i in lookback..candles.len().saturating_sub(lookback) {
+        let neighbours = || {
+            candles[i - lookback..i]
+                .iter()
+                .chain(&candles[i + 1..=i + lookback])
+        };
+        if neighbours().all(|c| c.high < candles[i].high) {
+            swings.push(SwingPoint {
+                index: i,
+                price: candles[i].high,
+                is_high: true,
+                confirmed_at: i + lookback,
+            });
+        }
+        if neighbours().all(|c| c.low > candles[i].low) {
+            swings.push(SwingPoint {
+                index: i,
+                price: candles[i].low,
+                is_high: false,
+                confirmed_at: i + lookback,
+            });
+        }
+    }
+
+    swings
+}
@@ -0,0 +1,34 @@
+use crate::vars::ohlc::Ohlc;
+
+/// Converts candles into Heikin-Ashi candles.
+///
+/// Timestamps and volumes are kept.
+///
+/// # Mathematical Formulation
+///
+/// - `HA close = (open + high + low + close) / 4`
+/// - `HA open = (prev HA open + prev HA close) / 2`, and `(open + close) / 2`
+///   for the first candle
+/// - `HA high = max(high, HA open, HA close)`
+/// - `HA low = min(low, HA open, HA close)`
+pub fn heikin_ashi(candles: &[Ohlc]) -> Vec<Ohlc> {
+    let mut ha_candles: Vec<Ohlc> = Vec::with_capacity(candles.len());
+
+    for c in candles {
+        let close = (c.open + c.high + c.low + c.close) / 4.0;
+        let open = match ha_candles.last() {
+            Some(prev) => (prev.open + prev.close) / 2.0,
+            None => (c.open + c.close) / 2.0,
+        };
+        ha_candles.push(Ohlc {
+            timestamp: c.timestamp,
+            open,
+            high: c.high.max(open).max(close),
+            low: c.low.min(open).min(close),
+            close,
+            volume: c.volume,
+        });
+    }
+
+    ha_candles
+}
@@ -0,0 +1,34 @@
+use crate::ta::warmup::with_nan_warmup;
+use crate::ta::wma::wma;
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.hma
+///
+/// The Hull Moving Average `wma(2 * wma(src, n / 2) - wma(src, n), sqrt(n))`.
+/// Values are `0.0` until both inner averages and the outer average are
+/// warmed up, i.e. for the first `n + floor(sqrt(n)) - 2` values.
+pub fn hma(src: &[f64], length: usize) -> Vec<f64> {
+    let half_wma = wma(src, (length / 2).max(1));
+    let full_wma = wma(src, length);
+    let sqrt_length = ((length as f64).sqrt().floor() as usize).max(1);
+
+    let mut hma_values = vec![0.0; src.len()];
+    if src.len() < length {
+        return hma_values;
+    }
+
+    let diff: Vec<f64> = (length - 1..src.len())
+        .map(|i| 2.0 * half_wma[i] - full_wma[i])
+        .collect();
+    for (i, value) in wma(&diff, sqrt_length).into_iter().enumerate() {
+        hma_values[length - 1 + i] = value;
+    }
+
+    hma_values
+}
+
+/// Like `hma`, but the first `n + floor(sqrt(n)) - 2` values are
+/// `f64::NAN`.
+pub fn hma_nan(src: &[f64], length: usize) -> Vec<f64> {
+    let sqrt_length = ((length as f64).sqrt().floor() as usize).max(1);
+    with_nan_warmup(hma(src, length), (length + sqrt_length).saturating_sub(2))
+}
@@ -0,0 +1,366 @@
+/*!
+A streaming `Indicator` trait and combinators to compose indicators into
+pipelines.
+
+An indicator consumes one input at a time and returns `None` while it has no
+output for it, either because it is warming up or because it aggregates
+several inputs into one output (e.g., `Resample`). Pipelines are declared once
+with `chain`, `map` and `zip` and then driven either value by value from a
+live feed or over a whole series with `batch`:
+
+```
+use strato_utils::ta::indicator::Atr;
+use strato_utils::ta::indicator::HeikinAshi;
+use strato_utils::ta::indicator::Indicator;
+use strato_utils::ta::indicator::Resample;
+
+// ATR of Heikin-Ashi of 5-minute bars
+let mut pipeline = Resample::new(5 * 60 * 1000)
+    .unwrap()
+    .chain(HeikinAshi::new())
+    .chain(Atr::new(14));
+```
+*/
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use crate::vars::ohlc::Ohlc;
+
+/// A stateful indicator producing at most one output per input.
+pub trait Indicator<In, Out> {
+    /// Feeds the next input and returns the output for it, if any.
+    fn next(&mut self, input: In) -> Option<Out>;
+
+    /// Feeds a whole series.
+    ///
+    /// # Returns
+    ///
+    /// One entry per input, aligned with the inputs.
+    fn batch<T>(&mut self, inputs: T) -> Vec<Option<Out>>
+    where
+        T: IntoIterator<Item = In>,
+        Self: Sized,
+    {
+        inputs.into_iter().map(|input| self.next(input)).collect()
+    }
+
+    /// Transforms every output with `f`.
+    fn map<F, O>(self, f: F) -> Map<Self, F, Out>
+    where
+        F: FnMut(Out) -> O,
+        Self: Sized,
+    {
+        Map {
+            inner: self,
+            f,
+            _mid: PhantomData,
+        }
+    }
+
+    /// Feeds every output into `next`.
+    fn chain<N, O>(self, next: N) -> Chain<Self, N, Out>
+    where
+        N: Indicator<Out, O>,
+        Self: Sized,
+    {
+        Chain {
+            first: self,
+            second: next,
+            _mid: PhantomData,
+        }
+    }
+
+    /// Feeds every input to both indicators and pairs their outputs. The pair
+    /// is `None` unless both have an output.
+    fn zip<B, O>(self, other: B) -> Zip<Self, B>
+    where
+        B: Indicator<In, O>,
+        In: Clone,
+        Self: Sized,
+    {
+        Zip {
+            first: self,
+            second: other,
+        }
+    }
+}
+
+/// See `Indicator::map`.
+pub struct Map<I, F, Mid> {
+    inner: I,
+    f: F,
+    _mid: PhantomData<fn() -> Mid>,
+}
+
+impl<In, Mid, Out, I, F> Indicator<In, Out> for Map<I, F, Mid>
+where
+    I: Indicator<In, Mid>,
+    F: FnMut(Mid) -> Out,
+{
+    fn next(&mut self, input: In) -> Option<Out> {
+        self.inner.next(input).map(&mut self.f)
+    }
+}
+
+/// See `Indicator::chain`.
+pub struct Chain<A, B, Mid> {
+    first: A,
+    second: B,
+    _mid: PhantomData<fn() -> Mid>,
+}
+
+impl<In, Mid, Out, A, B> Indicator<In, Out> for Chain<A, B, Mid>
+where
+    A: Indicator<In, Mid>,
+    B: Indicator<Mid, Out>,
+{
+    fn next(&mut self, input: In) -> Option<Out> {
+        self.first.next(input).and_then(|mid| self.second.next(mid))
+    }
+}
+
+/// See `Indicator::zip`.
+pub struct Zip<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<In, OA, OB, A, B> Indicator<In, (OA, OB)> for Zip<A, B>
+where
+    In: Clone,
+    A: Indicator<In, OA>,
+    B: Indicator<In, OB>,
+{
+    fn next(&mut self, input: In) -> Option<(OA, OB)> {
+        let a = self.first.next(input.clone());
+        let b = self.second.next(input);
+        a.zip(b)
+    }
+}
+
+/// Streaming simple moving average, matching `sma_nan`.
+#[derive(Debug, Clone)]
+pub struct Sma {
+    length: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Sma {
+    pub fn new(length: usize) -> Self {
+        Self {
+            length,
+            window: VecDeque::with_capacity(length + 1),
+            sum: 0.0,
+        }
+    }
+}
+
+impl Indicator<f64, f64> for Sma {
+    fn next(&mut self, input: f64) -> Option<f64> {
+        self.window.push_back(input);
+        self.sum += input;
+        if self.window.len() > self.length {
+            self.sum -= self.window.pop_front().unwrap_or_default();
+        }
+        (self.window.len() == self.length).then(|| self.sum / self.length as f64)
+    }
+}
+
+/// Streaming exponential moving average, matching `ema`: seeded with the
+/// first input, so it has an output from the start.
+#[derive(Debug, Clone)]
+pub struct Ema {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl Ema {
+    pub fn new(length: usize) -> Self {
+        Self {
+            alpha: 2.0 / (length as f64 + 1.0),
+            value: None,
+        }
+    }
+}
+
+impl Indicator<f64, f64> for Ema {
+    fn next(&mut self, input: f64) -> Option<f64> {
+        let value = match self.value {
+            Some(prev) => self.alpha * input + (1.0 - self.alpha) * prev,
+            None => input,
+        };
+        self.value = Some(value);
+        self.value
+    }
+}
+
+/// Streaming RMA, matching `rma_nan`: seeded with the SMA of the first
+/// `length` inputs.
+#[derive(Debug, Clone)]
+pub struct Rma {
+    length: usize,
+    seed: Sma,
+    value: Option<f64>,
+}
+
+impl Rma {
+    pub fn new(length: usize) -> Self {
+        Self {
+            length,
+            seed: Sma::new(length),
+            value: None,
+        }
+    }
+}
+
+impl Indicator<f64, f64> for Rma {
+    fn next(&mut self, input: f64) -> Option<f64> {
+        self.value = match self.value {
+            Some(prev) => {
+                let alpha = 1.0 / self.length as f64;
+                Some(alpha * input + (1.0 - alpha) * prev)
+            }
+            None => self.seed.next(input),
+        };
+        self.value
+    }
+}
+
+/// Streaming average true range, matching `atr_nan`.
+#[derive(Debug, Clone)]
+pub struct Atr {
+    rma: Rma,
+    prev_close: Option<f64>,
+}
+
+impl Atr {
+    pub fn new(length: usize) -> Self {
+        Self {
+            rma: Rma::new(length),
+            prev_close: None,
+        }
+    }
+}
+
+impl Indicator<Ohlc, f64> for Atr {
+    fn next(&mut self, input: Ohlc) -> Option<f64> {
+        let high_low = input.high - input.low;
+        let tr = match self.prev_close {
+            Some(prev_close) => high_low
+                .max((input.high - prev_close).abs())
+                .max((input.low - prev_close).abs()),
+            None => high_low,
+        };
+        self.prev_close = Some(input.close);
+        self.rma.next(tr)
+    }
+}
+
+/// Streaming Heikin-Ashi transform, matching `heikin_ashi`.
+#[derive(Debug, Clone, Default)]
+pub struct HeikinAshi {
+    prev: Option<Ohlc>,
+}
+
+impl HeikinAshi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Indicator<Ohlc, Ohlc> for HeikinAshi {
+    fn next(&mut self, input: Ohlc) -> Option<Ohlc> {
+        let close = (input.open + input.high + input.low + input.close) / 4.0;
+        let open = match self.prev {
+            Some(prev) => (prev.open + prev.close) / 2.0,
+            None => (input.open + input.close) / 2.0,
+        };
+        let candle = Ohlc {
+            timestamp: input.timestamp,
+            open,
+            high: input.high.max(open).max(close),
+            low: input.low.min(open).min(close),
+            close,
+            volume: input.volume,
+        };
+        self.prev = Some(candle);
+        self.prev
+    }
+}
+
+/// Aggregates candles into candles of a longer interval.
+///
+/// A candle is emitted once the first candle of the next interval arrives,
+/// so the last interval of a series is only available through `finish`.
+#[derive(Debug, Clone)]
+pub struct Resample {
+    interval: i64,
+    current: Option<Ohlc>,
+}
+
+impl Resample {
+    /// Creates a resampler to `interval` milliseconds, or returns an error if
+    /// `interval` is not positive.
+    pub fn new(interval: i64) -> Result<Self, String> {
+        if interval <= 0 {
+            return Err(format!(
+                "Cannot resample to {} ms: the interval must be positive.",
+                interval
+            ));
+        }
+        Ok(Self {
+            interval,
+            current: None,
+        })
+    }
+
+    /// Returns the candle in progress, if any.
+    pub fn finish(&mut self) -> Option<Ohlc> {
+        self.current.take()
+    }
+}
+
+impl Indicator<Ohlc, Ohlc> for Resample {
+    fn next(&mut self, input: Ohlc) -> Option<Ohlc> {
+        let start = input.timestamp - input.timestamp.rem_euclid(self.interval);
+        match self.current.as_mut() {
+            Some(current) if current.timestamp == start => {
+                current.high = current.high.max(input.high);
+                current.low = current.low.min(input.low);
+                current.close = input.close;
+                current.volume += input.volume;
+                None
+            }
+            _ => self.current.replace(Ohlc {
+                timestamp: start,
+                ..input
+            }),
+        }
+    }
+}
+
+/// Wraps a closure as an indicator.
+pub struct FnIndicator<F, In> {
+    f: F,
+    _in: PhantomData<fn(In)>,
+}
+
+impl<F, In> FnIndicator<F, In> {
+    pub fn new(f: F) -> Self {
+        Self {
+            f,
+            _in: PhantomData,
+        }
+    }
+}
+
+impl<F, In, Out> Indicator<In, Out> for FnIndicator<F, In>
+where
+    F: FnMut(In) -> Option<Out>,
+{
+    fn next(&mut self, input: In) -> Option<Out> {
+        (self.f)(input)
+    }
+}
@@ -0,0 +1,42 @@
+use crate::ta::ema::ema;
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.macd
+///
+/// Calculates the Moving Average Convergence Divergence (MACD).
+///
+/// # Arguments
+///
+/// * `src` - Source series, typically close prices.
+/// * `fast` - Length of the fast EMA.
+/// * `slow` - Length of the slow EMA.
+/// * `signal` - Length of the EMA of the MACD line.
+///
+/// # Returns
+///
+/// A tuple containing three vectors:
+/// - `macd_line`: Fast EMA minus slow EMA.
+/// - `signal_line`: EMA of the MACD line.
+/// - `histogram`: MACD line minus signal line.
+pub fn macd(
+    src: &[f64],
+    fast: usize,
+    slow: usize,
+    signal: usize,
+) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let fast_ema = ema(src.to_vec(), fast);
+    let slow_ema = ema(src.to_vec(), slow);
+
+    let macd_line: Vec<f64> = fast_ema
+        .iter()
+        .zip(slow_ema.iter())
+        .map(|(f, s)| f - s)
+        .collect();
+    let signal_line = ema(macd_line.clone(), signal);
+    let histogram = macd_line
+        .iter()
+        .zip(signal_line.iter())
+        .map(|(m, s)| m - s)
+        .collect();
+
+    (macd_line, signal_line, histogram)
+}
@@ -0,0 +1,56 @@
+use crate::vars::ohlc::Ohlc;
+
+/// The standard MFI lookback length.
+pub const DEFAULT_MFI_LEN: usize = 14;
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.mfi
+///
+/// Calculates the Money Flow Index, a volume-weighted analogue of the RSI
+/// over the typical price `(high + low + close) / 3`.
+///
+/// # Arguments
+///
+/// * `candles` - A slice of `Ohlc` candles with volumes.
+/// * `length` - Number of price changes in the lookback window, typically
+///   `DEFAULT_MFI_LEN`.
+///
+/// # Returns
+///
+/// A vector of MFI values from 0 to 100, `0.0` for the first `length`
+/// candles.
+///
+/// # Mathematical Formulation
+///
+/// `MFI = 100 - 100 / (1 + Σ positive flow / Σ negative flow)`
+///
+/// where the money flow `typical price * volume` of a candle is positive when
+/// the typical price rose and negative when it fell.
+pub fn mfi(candles: &[Ohlc], length: usize) -> Vec<f64> {
+    let src: Vec<f64> = candles
+        .iter()
+        .map(|c| (c.high + c.low + c.close) / 3.0)
+        .collect();
+    let mut positive_flow = vec![0.0; candles.len()];
+    let mut negative_flow = vec![0.0; candles.len()];
+    for i in 1..candles.len() {
+        let flow = src[i] * candles[i].volume;
+        if src[i] > src[i - 1] {
+            positive_flow[i] = flow;
+        } else if src[i] < src[i - 1] {
+            negative_flow[i] = flow;
+        }
+    }
+
+    let mut mfi_values = vec![0.0; candles.len()];
+    for i in length..candles.len() {
+        let upper: f64 = positive_flow[i + 1 - length..=i].iter().sum();
+        let lower: f64 = negative_flow[i + 1 - length..=i].iter().sum();
+        mfi_values[i] = if lower == 0.0 {
+            100.0
+        } else {
+            100.0 - 100.0 / (1.0 + upper / lower)
+        };
+    }
+
+    mfi_values
+}
@@ -0,0 +1,23 @@
+use crate::vars::ohlc::Ohlc;
+
+/// https://www.tradingview.com/pine-script-reference/v5/#var_ta.obv
+///
+/// Calculates the On-Balance Volume, the cumulative volume signed by the
+/// direction of the close-to-close change. Starts at `0.0`.
+pub fn obv(candles: &[Ohlc]) -> Vec<f64> {
+    let mut obv_values = vec![0.0; candles.len()];
+
+    for i in 1..candles.len() {
+        let change = candles[i].close - candles[i - 1].close;
+        let signed_volume = if change > 0.0 {
+            candles[i].volume
+        } else if change < 0.0 {
+            -candles[i].volume
+        } else {
+            0.0
+        };
+        obv_values[i] = obv_values[i - 1] + signed_volume;
+    }
+
+    obv_values
+}
@@ -0,0 +1,86 @@
+/*!
+Parallel versions of the batch indicators, behind the `rayon` feature.
+
+Recursive indicators such as `rma` cannot be split across threads within a
+single series, so the speed-up comes from two places:
+- element-wise work (true range, rolling sums) is computed in parallel, and
+- parameter sweeps compute one series per parameter on separate threads,
+  sharing the inputs that do not depend on the parameter.
+
+Every function returns exactly what its sequential counterpart returns.
+*/
+
+use rayon::prelude::*;
+
+use crate::ta::rma::rma;
+use crate::ta::sma::sma_chunk;
+use crate::ta::sma::SMA_CHUNK_LEN;
+use crate::vars::ohlc::Ohlc;
+
+/// Parallel `true_range`.
+pub fn par_true_range(candles: &[Ohlc]) -> Vec<f64> {
+    (0..candles.len())
+        .into_par_iter()
+        .map(|i| {
+            if i == 0 {
+                return 0.0;
+            }
+            let high_low = candles[i].high - candles[i].low;
+            let high_close = (candles[i].high - candles[i - 1].close).abs();
+            let low_close = (candles[i].low - candles[i - 1].close).abs();
+            high_low.max(high_close).max(low_close)
+        })
+        .collect()
+}
+
+/// Parallel `sma`, computing its chunks of rolling sums on separate threads.
+pub fn par_sma(src: &[f64], length: usize) -> Vec<f64> {
+    let mut sma_values = vec![0.0; src.len()];
+    if length == 0 || src.len() < length {
+        return sma_values;
+    }
+
+    sma_values[length - 1..]
+        .par_chunks_mut(SMA_CHUNK_LEN)
+        .enumerate()
+        .for_each(|(chunk_no, chunk)| {
+            sma_chunk(src, length, length - 1 + chunk_no * SMA_CHUNK_LEN, chunk)
+        });
+
+    sma_values
+}
+
+/// Parallel `atr`.
+pub fn par_atr(candles: &[Ohlc], length: usize) -> Vec<f64> {
+    rma(&par_true_range(candles), length)
+}
+
+/// Runs an indicator once per parameter on separate threads.
+///
+/// # Arguments
+///
+/// * `params` - Parameters of the sweep, e.g. lengths.
+/// * `f` - Computes the indicator for one parameter.
+///
+/// # Returns
+///
+/// One series per parameter, in the order of `params`.
+pub fn par_sweep<P, F>(params: &[P], f: F) -> Vec<Vec<f64>>
+where
+    P: Sync,
+    F: Fn(&P) -> Vec<f64> + Sync + Send,
+{
+    params.par_iter().map(f).collect()
+}
+
+/// Computes `rma` for every length in parallel.
+pub fn par_rma_sweep(src: &[f64], lengths: &[usize]) -> Vec<Vec<f64>> {
+    par_sweep(lengths, |&length| rma(src, length))
+}
+
+/// Computes `atr` for every length in parallel, computing the true range
+/// only once.
+pub fn par_atr_sweep(candles: &[Ohlc], lengths: &[usize]) -> Vec<Vec<f64>> {
+    let tr = par_true_range(candles);
+    par_sweep(lengths, |&length| rma(&tr, length))
+}
@@ -0,0 +1,20 @@
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.percentrank
+///
+/// Calculates the percentage of the previous `length` values that are less
+/// than or equal to the current value.
+///
+/// Values are `0.0` for the first `length` values.
+pub fn percentrank(src: &[f64], length: usize) -> Vec<f64> {
+    let mut rank_values = vec![0.0; src.len()];
+
+    if length == 0 {
+        return rank_values;
+    }
+
+    for i in length..src.len() {
+        let count = src[i - length..i].iter().filter(|&&v| v <= src[i]).count();
+        rank_values[i] = 100.0 * count as f64 / length as f64;
+    }
+
+    rank_values
+}
@@ -0,0 +1,99 @@
+use crate::vars::ohlc::Ohlc;
+
+/// The formula used to derive the pivot levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PivotKind {
+    /// Floor trader pivots with three support and resistance levels.
+    Classic,
+    /// Levels at the 38.2%, 61.8% and 100% Fibonacci ratios of the range.
+    Fibonacci,
+    /// Four tight levels around the close, scaled by `1.1 / 12`, `1.1 / 6`,
+    /// `1.1 / 4` and `1.1 / 2` of the range.
+    Camarilla,
+}
+
+/// The pivot and its support/resistance levels, ordered from the nearest to
+/// the pivot outwards.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PivotLevels {
+    pub pivot: f64,
+    pub resistance: Vec<f64>,
+    pub support: Vec<f64>,
+}
+
+/// Calculates the pivot levels for the next period from a completed one.
+///
+/// # Arguments
+///
+/// * `prev` - The `Ohlc` of the prior period, e.g. the previous day.
+/// * `kind` - The pivot formula.
+///
+/// # Returns
+///
+/// The `PivotLevels` of the next period.
+///
+/// # Mathematical Formulation
+///
+/// `P = (H + L + C) / 3` and `R = H - L`
+/// - Classic: `R1 = 2P - L`, `R2 = P + R`, `R3 = H + 2(P - L)` and `S1 = 2P -
+///   H`, `S2 = P - R`, `S3 = L - 2(H - P)`
+/// - Fibonacci: `Rn = P + f * R` and `Sn = P - f * R` for `f` in `0.382, 0.618,
+///   1.0`
+/// - Camarilla: `Rn = C + R * 1.1 / d` and `Sn = C - R * 1.1 / d` for `d` in
+///   `12, 6, 4, 2`
+pub fn pivot_levels(prev: &Ohlc, kind: PivotKind) -> PivotLevels {
+    let (high, low, close) = (prev.high, prev.low, prev.close);
+    let pivot = (high + low + close) / 3.0;
+    let range = high - low;
+
+    let (resistance, support) = match kind {
+        PivotKind::Classic => (
+            vec![2.0 * pivot - low, pivot + range, high + 2.0 * (pivot - low)],
+            vec![
+                2.0 * pivot - high,
+                pivot - range,
+                low - 2.0 * (high - pivot),
+            ],
+        ),
+        PivotKind::Fibonacci => {
+            let ratios = [0.382, 0.618, 1.0];
+            (
+                ratios.iter().map(|f| pivot + f * range).collect(),
+                ratios.iter().map(|f| pivot - f * range).collect(),
+            )
+        }
+        PivotKind::Camarilla => {
+            let divisors = [12.0, 6.0, 4.0, 2.0];
+            (
+                divisors.iter().map(|d| close + range * 1.1 / d).collect(),
+                divisors.iter().map(|d| close - range * 1.1 / d).collect(),
+            )
+        }
+    };
+
+    PivotLevels {
+        pivot,
+        resistance,
+        support,
+    }
+}
+
+/// Calculates the pivot levels of every candle from the candle before it.
+///
+/// The candles should be of the pivot period (e.g., daily candles for daily
+/// pivots). The first candle has no prior period and gets default levels.
+pub fn pivot_points(candles: &[Ohlc], kind: PivotKind) -> Vec<PivotLevels> {
+    let mut levels = Vec::with_capacity(candles.len());
+
+    for i in 0..candles.len() {
+        if i == 0 {
+            levels.push(PivotLevels::default());
+        } else {
+            levels.push(pivot_levels(&candles[i - 1], kind));
+        }
+    }
+
+    levels
+}
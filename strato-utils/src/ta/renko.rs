@@ -0,0 +1,217 @@
+use crate::vars::ohlc::Ohlc;
+
+/// Builds Renko bricks of a fixed size from a stream of prices.
+///
+/// A brick is added when the price moves a full brick beyond the close of the
+/// last brick in the trend direction, or two bricks against it (a reversal).
+#[derive(Debug, Clone)]
+pub struct Renko {
+    brick_size: f64,
+    /// Close of the last brick, or the first price before any brick.
+    close: Option<f64>,
+    /// `1.0` after an up brick, `-1.0` after a down brick, `0.0` before any.
+    direction: f64,
+    volume: f64,
+}
+
+impl Renko {
+    /// Creates a builder, or an error if `brick_size` is not a positive
+    /// number.
+    pub fn new(brick_size: f64) -> Result<Self, String> {
+        check_size("Renko brick size", brick_size)?;
+        Ok(Self {
+            brick_size,
+            close: None,
+            direction: 0.0,
+            volume: 0.0,
+        })
+    }
+
+    /// Adds a price and returns the bricks it completes.
+    ///
+    /// The volume traded since the last brick is assigned to the first
+    /// completed brick.
+    pub fn update(&mut self, timestamp: i64, price: f64, volume: f64) -> Vec<Ohlc> {
+        self.volume += volume;
+        let Some(mut close) = self.close else {
+            self.close = Some(price);
+            return Vec::new();
+        };
+
+        let size = self.brick_size;
+        let mut bricks = Vec::new();
+        loop {
+            let up = if self.direction < 0.0 {
+                2.0 * size
+            } else {
+                size
+            };
+            let down = if self.direction > 0.0 {
+                2.0 * size
+            } else {
+                size
+            };
+
+            let direction = if price >= close + up {
+                1.0
+            } else if price <= close - down {
+                -1.0
+            } else {
+                break;
+            };
+            // A reversal brick starts from the open of the last brick
+            let open = if self.direction == -direction {
+                close + direction * size
+            } else {
+                close
+            };
+            close = open + direction * size;
+            self.direction = direction;
+
+            bricks.push(Ohlc {
+                timestamp,
+                open,
+                high: open.max(close),
+                low: open.min(close),
+                close,
+                volume: std::mem::take(&mut self.volume),
+            });
+        }
+
+        self.close = Some(close);
+        bricks
+    }
+}
+
+/// Builds Renko bricks from candles, following the path
+/// open → low → high → close for bullish candles and open → high → low →
+/// close for bearish ones. Fails on the brick sizes `Renko::new` rejects.
+pub fn renko(candles: &[Ohlc], brick_size: f64) -> Result<Vec<Ohlc>, String> {
+    let mut builder = Renko::new(brick_size)?;
+    Ok(candles
+        .iter()
+        .flat_map(|c| {
+            candle_path(c)
+                .into_iter()
+                .flat_map(|(price, volume)| builder.update(c.timestamp, price, volume))
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+/// Builds bars that each span a fixed price range from a stream of prices.
+///
+/// A bar completes as soon as its high-low range reaches `range`. A price
+/// jumping further completes several bars, each opening where the previous
+/// one closed.
+#[derive(Debug, Clone)]
+pub struct RangeBars {
+    range: f64,
+    current: Option<Ohlc>,
+}
+
+impl RangeBars {
+    /// Creates a builder, or an error if `range` is not a positive number.
+    pub fn new(range: f64) -> Result<Self, String> {
+        check_size("Range bar range", range)?;
+        Ok(Self {
+            range,
+            current: None,
+        })
+    }
+
+    /// Adds a price and returns the bars it completes.
+    ///
+    /// The volume is assigned to the bar the price ends up in.
+    pub fn update(&mut self, timestamp: i64, price: f64, volume: f64) -> Vec<Ohlc> {
+        let range = self.range;
+        let mut bars = Vec::new();
+        let mut bar = self.current.take().unwrap_or(Ohlc {
+            timestamp,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: 0.0,
+        });
+
+        loop {
+            let limit = if price >= bar.low + range {
+                bar.low + range
+            } else if price <= bar.high - range {
+                bar.high - range
+            } else {
+                break;
+            };
+            bar.high = bar.high.max(limit);
+            bar.low = bar.low.min(limit);
+            bar.close = limit;
+            bars.push(bar);
+            bar = Ohlc {
+                timestamp,
+                open: limit,
+                high: limit,
+                low: limit,
+                close: limit,
+                volume: 0.0,
+            };
+        }
+
+        bar.high = bar.high.max(price);
+        bar.low = bar.low.min(price);
+        bar.close = price;
+        bar.volume += volume;
+        self.current = Some(bar);
+        bars
+    }
+
+    /// Returns the incomplete bar in progress, if any.
+    pub fn finish(&mut self) -> Option<Ohlc> {
+        self.current.take()
+    }
+}
+
+/// Builds fixed-range bars from candles, following the same intrabar path as
+/// `renko`. The incomplete last bar is not included. Fails on the ranges
+/// `RangeBars::new` rejects.
+pub fn range_bars(candles: &[Ohlc], range: f64) -> Result<Vec<Ohlc>, String> {
+    let mut builder = RangeBars::new(range)?;
+    Ok(candles
+        .iter()
+        .flat_map(|c| {
+            candle_path(c)
+                .into_iter()
+                .flat_map(|(price, volume)| builder.update(c.timestamp, price, volume))
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+/// Checks that a brick size or range is a positive number; anything else
+/// would complete bars forever.
+fn check_size(name: &str, size: f64) -> Result<(), String> {
+    if size.is_finite() && size > 0.0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid {} {}: it must be a positive number.",
+            name, size
+        ))
+    }
+}
+
+/// The assumed intrabar path of a candle as (price, volume) pairs, with the
+/// volume on the close.
+fn candle_path(c: &Ohlc) -> [(f64, f64); 4] {
+    let (first, second) = if c.close >= c.open {
+        (c.low, c.high)
+    } else {
+        (c.high, c.low)
+    };
+    [
+        (c.open, 0.0),
+        (first, 0.0),
+        (second, 0.0),
+        (c.close, c.volume),
+    ]
+}
@@ -0,0 +1,160 @@
+use crate::ta::indicator::Indicator;
+use crate::ta::indicator::Resample;
+use crate::vars::ohlc::Ohlc;
+
+/// Resamples candles to a longer interval, e.g. 1-minute bars into 5-minute
+/// or 1-hour bars.
+///
+/// Candles are grouped by the `to` interval their open time falls in, with
+/// intervals aligned to the Unix epoch. Each group becomes one candle with the
+/// first open, the highest high, the lowest low, the last close and the summed
+/// volume, timestamped at the start of the interval. Missing source candles
+/// are tolerated, and intervals without any candle are skipped; use
+/// `resample_filled` to keep them.
+///
+/// # Arguments
+///
+/// * `candles` - Candles sorted by timestamp.
+/// * `from` - Interval of the candles in milliseconds.
+/// * `to` - Target interval in milliseconds, a multiple of `from`.
+///
+/// # Returns
+///
+/// The resampled candles, or an error if `to` is not a positive multiple of
+/// `from`.
+pub fn resample(candles: &[Ohlc], from: i64, to: i64) -> Result<Vec<Ohlc>, String> {
+    if from <= 0 || to < from || to % from != 0 {
+        return Err(format!(
+            "Cannot resample from {} ms to {} ms: the target interval must be a multiple of the source interval.",
+            from, to
+        ));
+    }
+
+    let mut resample = Resample::new(to)?;
+    let mut resampled: Vec<Ohlc> = candles.iter().filter_map(|&c| resample.next(c)).collect();
+    resampled.extend(resample.finish());
+
+    Ok(resampled)
+}
+
+/// Like `resample`, but intervals without any candle are filled with a flat
+/// candle at the previous close and with zero volume, so the output has one
+/// candle per `to` interval between the first and the last candle.
+pub fn resample_filled(candles: &[Ohlc], from: i64, to: i64) -> Result<Vec<Ohlc>, String> {
+    let resampled = resample(candles, from, to)?;
+    let (filled, _) = fill_gaps(&resampled, to, FillPolicy::ForwardFill)?;
+    Ok(filled)
+}
+
+/// How `fill_gaps` fills the candles missing from a series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FillPolicy {
+    /// A flat candle at the previous close, with zero volume.
+    ForwardFill,
+    /// A candle whose prices and volume are NaN.
+    Nan,
+    /// No candle: the series is left as is and the gaps are only reported.
+    Skip,
+}
+
+/// A run of consecutive candles missing from a series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gap {
+    /// Open time of the first missing candle in Unix milliseconds.
+    pub start: i64,
+    /// Number of missing candles.
+    pub missing: usize,
+}
+
+impl Gap {
+    /// Open time of the candle following the gap.
+    pub fn end(&self, interval: i64) -> i64 {
+        self.start + self.missing as i64 * interval
+    }
+}
+
+/// Finds the candles missing from a series of `interval` candles.
+///
+/// A gap is reported wherever two consecutive candles are more than one
+/// interval apart, starting one interval after the earlier candle.
+///
+/// # Arguments
+///
+/// * `candles` - Candles sorted by timestamp.
+/// * `interval` - Interval of the candles in milliseconds.
+///
+/// # Returns
+///
+/// The gaps in chronological order, or an error if `interval` is not
+/// positive.
+pub fn find_gaps(candles: &[Ohlc], interval: i64) -> Result<Vec<Gap>, String> {
+    if interval <= 0 {
+        return Err(format!(
+            "Cannot find gaps in {} ms candles: the interval must be positive.",
+            interval
+        ));
+    }
+
+    Ok(candles
+        .windows(2)
+        .filter_map(|pair| {
+            let missing = (pair[1].timestamp - pair[0].timestamp) / interval - 1;
+            (missing > 0).then(|| Gap {
+                start: pair[0].timestamp + interval,
+                missing: missing as usize,
+            })
+        })
+        .collect())
+}
+
+/// Fills the candles missing from a series of `interval` candles according to
+/// `policy`.
+///
+/// # Arguments
+///
+/// * `candles` - Candles sorted by timestamp.
+/// * `interval` - Interval of the candles in milliseconds.
+/// * `policy` - How the missing candles are filled.
+///
+/// # Returns
+///
+/// The filled series and the gaps found by `find_gaps`, or an error if
+/// `interval` is not positive.
+pub fn fill_gaps(
+    candles: &[Ohlc],
+    interval: i64,
+    policy: FillPolicy,
+) -> Result<(Vec<Ohlc>, Vec<Gap>), String> {
+    let gaps = find_gaps(candles, interval)?;
+    if policy == FillPolicy::Skip || gaps.is_empty() {
+        return Ok((candles.to_vec(), gaps));
+    }
+
+    let missing: usize = gaps.iter().map(|gap| gap.missing).sum();
+    let mut filled: Vec<Ohlc> = Vec::with_capacity(candles.len() + missing);
+    for &candle in candles {
+        if let Some(&prev) = filled.last() {
+            let (price, volume) = match policy {
+                FillPolicy::Nan => (f64::NAN, f64::NAN),
+                _ => (prev.close, 0.0),
+            };
+            let mut timestamp = prev.timestamp + interval;
+            while timestamp + interval <= candle.timestamp {
+                filled.push(Ohlc {
+                    timestamp,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume,
+                });
+                timestamp += interval;
+            }
+        }
+        filled.push(candle);
+    }
+
+    Ok((filled, gaps))
+}
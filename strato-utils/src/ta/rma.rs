@@ -18,3 +18,22 @@ pub fn rma(src: &[f64], length: usize) -> Vec<f64> {
 
     rma_values
 }
+
+/// Like `rma`, but the first `length - 1` values are `f64::NAN` and the SMA
+/// seed is placed at index `length - 1`, as in Pine Script, rather than at
+/// index 0.
+pub fn rma_nan(src: &[f64], length: usize) -> Vec<f64> {
+    let alpha = 1.0 / length as f64;
+    let mut rma_values = vec![f64::NAN; src.len()];
+
+    if length == 0 || src.len() < length {
+        return rma_values;
+    }
+
+    rma_values[length - 1] = src[..length].iter().sum::<f64>() / length as f64;
+    for i in length..src.len() {
+        rma_values[i] = alpha * src[i] + (1.0 - alpha) * rma_values[i - 1];
+    }
+
+    rma_values
+}
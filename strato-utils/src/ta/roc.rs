@@ -0,0 +1,35 @@
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.roc
+///
+/// Calculates the Rate of Change, the percentage change of `src` over
+/// `length` values: `100 * (src - src[length]) / src[length]`.
+///
+/// Values are `0.0` for the first `length` values and wherever the earlier
+/// value is zero.
+pub fn roc(src: &[f64], length: usize) -> Vec<f64> {
+    let mut roc_values = vec![0.0; src.len()];
+
+    for i in length..src.len() {
+        let prev = src[i - length];
+        if prev != 0.0 {
+            roc_values[i] = 100.0 * (src[i] - prev) / prev;
+        }
+    }
+
+    roc_values
+}
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.mom
+///
+/// Calculates the momentum, the absolute change of `src` over `length`
+/// values: `src - src[length]`.
+///
+/// Values are `0.0` for the first `length` values.
+pub fn momentum(src: &[f64], length: usize) -> Vec<f64> {
+    let mut momentum_values = vec![0.0; src.len()];
+
+    for i in length..src.len() {
+        momentum_values[i] = src[i] - src[i - length];
+    }
+
+    momentum_values
+}
@@ -0,0 +1,85 @@
+use crate::vars::ohlc::Ohlc;
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.sar
+///
+/// Calculates the Parabolic SAR (Stop And Reverse).
+///
+/// # Arguments
+///
+/// * `candles` - A slice of `Ohlc` candles.
+/// * `start` - Initial acceleration factor, e.g. `0.02`.
+/// * `increment` - Increment of the acceleration factor on every new extreme,
+///   e.g. `0.02`.
+/// * `max` - Maximum acceleration factor, e.g. `0.2`.
+///
+/// # Returns
+///
+/// A tuple containing two vectors:
+/// - `sar`: The SAR values, usable as a trailing stop.
+/// - `direction`: `1.0` while the SAR is below the price (uptrend), `-1.0`
+///   while it is above (downtrend).
+///
+/// Both are `0.0` for the first candle.
+pub fn sar(candles: &[Ohlc], start: f64, increment: f64, max: f64) -> (Vec<f64>, Vec<f64>) {
+    let mut sar_values = vec![0.0; candles.len()];
+    let mut direction = vec![0.0; candles.len()];
+    if candles.len() < 2 {
+        return (sar_values, direction);
+    }
+
+    let mut is_below = candles[1].close > candles[0].close;
+    let (mut result, mut extreme) = if is_below {
+        (candles[0].low, candles[1].high)
+    } else {
+        (candles[0].high, candles[1].low)
+    };
+    let mut acceleration = start;
+
+    for i in 1..candles.len() {
+        let candle = &candles[i];
+        let mut is_first_trend_bar = i == 1;
+
+        result += acceleration * (extreme - result);
+
+        if is_below && result > candle.low {
+            is_first_trend_bar = true;
+            is_below = false;
+            result = candle.high.max(extreme);
+            extreme = candle.low;
+            acceleration = start;
+        } else if !is_below && result < candle.high {
+            is_first_trend_bar = true;
+            is_below = true;
+            result = candle.low.min(extreme);
+            extreme = candle.high;
+            acceleration = start;
+        }
+
+        if !is_first_trend_bar {
+            if is_below && candle.high > extreme {
+                extreme = candle.high;
+                acceleration = (acceleration + increment).min(max);
+            } else if !is_below && candle.low < extreme {
+                extreme = candle.low;
+                acceleration = (acceleration + increment).min(max);
+            }
+        }
+
+        if is_below {
+            result = result.min(candles[i - 1].low);
+            if i > 1 {
+                result = result.min(candles[i - 2].low);
+            }
+        } else {
+            result = result.max(candles[i - 1].high);
+            if i > 1 {
+                result = result.max(candles[i - 2].high);
+            }
+        }
+
+        sar_values[i] = result;
+        direction[i] = if is_below { 1.0 } else { -1.0 };
+    }
+
+    (sar_values, direction)
+}
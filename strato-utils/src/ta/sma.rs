@@ -1,14 +1,44 @@
+use crate::ta::warmup::with_nan_warmup;
+
+/// Number of values computed from a rolling sum before its window is summed
+/// again from scratch, bounding the rounding errors the updates build up.
+pub(crate) const SMA_CHUNK_LEN: usize = 1024;
+
+/// The values before the first full window are `0.0`, and all of them with a
+/// `length` of zero.
 pub fn sma(src: &[f64], length: usize) -> Vec<f64> {
-    let mut sma_values = Vec::with_capacity(src.len());
-
-    for i in 0..src.len() {
-        if i < length - 1 {
-            sma_values.push(0.0);
-        } else {
-            let sum: f64 = src[i + 1 - length..=i].iter().sum();
-            sma_values.push(sum / length as f64);
-        }
+    let mut sma_values = vec![0.0; src.len()];
+    if length == 0 || src.len() < length {
+        return sma_values;
+    }
+
+    for (chunk_no, chunk) in sma_values[length - 1..]
+        .chunks_mut(SMA_CHUNK_LEN)
+        .enumerate()
+    {
+        sma_chunk(src, length, length - 1 + chunk_no * SMA_CHUNK_LEN, chunk);
     }
 
     sma_values
 }
+
+/// Computes the SMA values at the indices `first..first + out.len()` of
+/// `src` into `out`, summing the first window and rolling the sum over the
+/// next ones. `sma` and `par_sma` split the values into the same chunks, so
+/// that they agree exactly whatever order the chunks are computed in.
+///
+/// `length` must be positive and `first` at least `length - 1`.
+pub(crate) fn sma_chunk(src: &[f64], length: usize, first: usize, out: &mut [f64]) {
+    let mut sum: f64 = src[first + 1 - length..=first].iter().sum();
+    for (i, value) in (first..).zip(out.iter_mut()) {
+        if i > first {
+            sum += src[i] - src[i - length];
+        }
+        *value = sum / length as f64;
+    }
+}
+
+/// Like `sma`, but the first `length - 1` values are `f64::NAN`.
+pub fn sma_nan(src: &[f64], length: usize) -> Vec<f64> {
+    with_nan_warmup(sma(src, length), length.saturating_sub(1))
+}
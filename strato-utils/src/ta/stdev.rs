@@ -0,0 +1,28 @@
+use crate::ta::sma::sma;
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.variance
+///
+/// Calculates the rolling population (biased) variance. Values are `0.0` for
+/// the first `length - 1` values.
+pub fn variance(src: &[f64], length: usize) -> Vec<f64> {
+    let mean = sma(src, length);
+    let mut variance_values = vec![0.0; src.len()];
+
+    for i in length.saturating_sub(1)..src.len() {
+        let sum_sq: f64 = src[i + 1 - length..=i]
+            .iter()
+            .map(|v| (v - mean[i]).powi(2))
+            .sum();
+        variance_values[i] = sum_sq / length as f64;
+    }
+
+    variance_values
+}
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.stdev
+///
+/// Calculates the rolling population (biased) standard deviation. Values are
+/// `0.0` for the first `length - 1` values.
+pub fn stdev(src: &[f64], length: usize) -> Vec<f64> {
+    variance(src, length).iter().map(|v| v.sqrt()).collect()
+}
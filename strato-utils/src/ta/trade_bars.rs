@@ -0,0 +1,252 @@
+/*!
+Aggregation of trades into bars.
+
+- Time bars close every fixed interval, like exchange candles.
+- Volume bars close once a fixed base volume has traded.
+- Dollar bars close once a fixed quote notional has traded.
+- Tick imbalance bars close once buyers or sellers dominate the trade flow
+  more than expected.
+
+Volume and dollar bars sample more often when the market is active, and
+imbalance bars when informed traders are, which makes their returns closer to
+i.i.d. than those of time bars (López de Prado, "Advances in Financial
+Machine Learning", ch. 2). All bars are emitted as
+`Ohlc` so candle strategies can run on them unchanged.
+*/
+
+use crate::vars::ohlc::Ohlc;
+use crate::vars::trade::Trade;
+
+/// Builds time bars of a fixed interval from a stream of trades.
+#[derive(Debug, Clone)]
+pub struct TimeBars {
+    interval: i64,
+    current: Option<Ohlc>,
+}
+
+impl TimeBars {
+    /// Creates a builder of `interval` millisecond bars aligned to the Unix
+    /// epoch, or returns an error if `interval` is not positive.
+    pub fn new(interval: i64) -> Result<Self, String> {
+        if interval <= 0 {
+            return Err(format!(
+                "Cannot build {} ms time bars: the interval must be positive.",
+                interval
+            ));
+        }
+        Ok(Self {
+            interval,
+            current: None,
+        })
+    }
+
+    /// Adds a trade and returns the previous bar once a trade of a later
+    /// interval arrives. Intervals without trades produce no bar.
+    pub fn update(&mut self, trade: &Trade) -> Option<Ohlc> {
+        let start = trade.timestamp - trade.timestamp.rem_euclid(self.interval);
+        match self.current.as_mut() {
+            Some(current) if current.timestamp == start => {
+                extend(current, trade);
+                None
+            }
+            _ => self.current.replace(open(start, trade)),
+        }
+    }
+
+    /// Returns the bar in progress, if any.
+    pub fn finish(&mut self) -> Option<Ohlc> {
+        self.current.take()
+    }
+}
+
+/// Builds volume or dollar bars from a stream of trades.
+///
+/// A bar closes with the trade that brings its volume (or notional) to the
+/// threshold; trades are not split across bars, so a bar may exceed it.
+#[derive(Debug, Clone)]
+pub struct VolumeBars {
+    threshold: f64,
+    dollar: bool,
+    current: Option<Ohlc>,
+    filled: f64,
+}
+
+impl VolumeBars {
+    /// Creates a builder of bars of `threshold` base volume.
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            dollar: false,
+            current: None,
+            filled: 0.0,
+        }
+    }
+
+    /// Creates a builder of bars of `threshold` quote notional
+    /// (`price * qty`).
+    pub fn dollar(threshold: f64) -> Self {
+        Self {
+            dollar: true,
+            ..Self::new(threshold)
+        }
+    }
+
+    /// Adds a trade and returns the bar it completes, if any. The bar is
+    /// timestamped with its first trade.
+    pub fn update(&mut self, trade: &Trade) -> Option<Ohlc> {
+        match self.current.as_mut() {
+            Some(current) => extend(current, trade),
+            None => self.current = Some(open(trade.timestamp, trade)),
+        }
+        self.filled += if self.dollar {
+            trade.price * trade.qty
+        } else {
+            trade.qty
+        };
+
+        if self.filled >= self.threshold {
+            self.filled = 0.0;
+            self.current.take()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the incomplete bar in progress, if any.
+    pub fn finish(&mut self) -> Option<Ohlc> {
+        self.filled = 0.0;
+        self.current.take()
+    }
+}
+
+/// Builds tick imbalance bars from a stream of trades.
+///
+/// Each trade counts as `+1` if the aggressor bought and `-1` if it sold. A
+/// bar closes once the absolute sum of these signs reaches its expected
+/// value, estimated from the previous bars.
+///
+/// # Mathematical Formulation
+///
+/// ```text
+/// theta_T = Σ b_t                       (b_t = +1 buy, -1 sell)
+/// close when |theta_T| >= E[T] * |E[b]|
+/// ```
+///
+/// `E[T]`, the number of trades per bar, and `E[b]`, the mean sign of a
+/// trade, are exponentially weighted averages over the completed bars. The
+/// first bar, with no history, closes after `expected_ticks` trades.
+#[derive(Debug, Clone)]
+pub struct TickImbalanceBars {
+    alpha: f64,
+    expected_ticks: f64,
+    expected_imbalance: Option<f64>,
+    current: Option<Ohlc>,
+    theta: f64,
+    ticks: usize,
+}
+
+impl TickImbalanceBars {
+    /// Creates a builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected_ticks` - Initial estimate of the number of trades per bar.
+    /// * `alpha` - Weight of the last bar in the expectations, in `(0, 1]`.
+    pub fn new(expected_ticks: f64, alpha: f64) -> Self {
+        Self {
+            alpha,
+            expected_ticks,
+            expected_imbalance: None,
+            current: None,
+            theta: 0.0,
+            ticks: 0,
+        }
+    }
+
+    /// Adds a trade and returns the bar it completes, if any. The bar is
+    /// timestamped with its first trade.
+    pub fn update(&mut self, trade: &Trade) -> Option<Ohlc> {
+        match self.current.as_mut() {
+            Some(current) => extend(current, trade),
+            None => self.current = Some(open(trade.timestamp, trade)),
+        }
+        self.theta += trade.side.sign();
+        self.ticks += 1;
+
+        let complete = match self.expected_imbalance {
+            Some(imbalance) => self.theta.abs() >= self.expected_ticks * imbalance.abs(),
+            None => self.ticks as f64 >= self.expected_ticks,
+        };
+        if !complete {
+            return None;
+        }
+
+        let ticks = self.ticks as f64;
+        let imbalance = self.theta / ticks;
+        self.expected_ticks += self.alpha * (ticks - self.expected_ticks);
+        self.expected_imbalance = Some(match self.expected_imbalance {
+            Some(expected) => expected + self.alpha * (imbalance - expected),
+            None => imbalance,
+        });
+        self.theta = 0.0;
+        self.ticks = 0;
+        self.current.take()
+    }
+
+    /// Returns the incomplete bar in progress, if any.
+    pub fn finish(&mut self) -> Option<Ohlc> {
+        self.theta = 0.0;
+        self.ticks = 0;
+        self.current.take()
+    }
+}
+
+/// Aggregates trades into time bars of `interval` milliseconds, including the
+/// last, possibly incomplete, bar.
+///
+/// Returns an error if `interval` is not positive.
+pub fn time_bars(trades: &[Trade], interval: i64) -> Result<Vec<Ohlc>, String> {
+    let mut builder = TimeBars::new(interval)?;
+    let mut bars: Vec<Ohlc> = trades.iter().filter_map(|t| builder.update(t)).collect();
+    bars.extend(builder.finish());
+    Ok(bars)
+}
+
+/// Aggregates trades into bars of `threshold` base volume. A trailing
+/// incomplete bar is dropped.
+pub fn volume_bars(trades: &[Trade], threshold: f64) -> Vec<Ohlc> {
+    let mut builder = VolumeBars::new(threshold);
+    trades.iter().filter_map(|t| builder.update(t)).collect()
+}
+
+/// Aggregates trades into bars of `threshold` quote notional. A trailing
+/// incomplete bar is dropped.
+pub fn dollar_bars(trades: &[Trade], threshold: f64) -> Vec<Ohlc> {
+    let mut builder = VolumeBars::dollar(threshold);
+    trades.iter().filter_map(|t| builder.update(t)).collect()
+}
+
+/// Aggregates trades into tick imbalance bars. A trailing incomplete bar is
+/// dropped. See `TickImbalanceBars`.
+pub fn tick_imbalance_bars(trades: &[Trade], expected_ticks: f64, alpha: f64) -> Vec<Ohlc> {
+    let mut builder = TickImbalanceBars::new(expected_ticks, alpha);
+    trades.iter().filter_map(|t| builder.update(t)).collect()
+}
+
+fn open(timestamp: i64, trade: &Trade) -> Ohlc {
+    Ohlc {
+        timestamp,
+        open: trade.price,
+        high: trade.price,
+        low: trade.price,
+        close: trade.price,
+        volume: trade.qty,
+    }
+}
+
+fn extend(bar: &mut Ohlc, trade: &Trade) {
+    bar.high = bar.high.max(trade.price);
+    bar.low = bar.low.min(trade.price);
+    bar.close = trade.price;
+    bar.volume += trade.qty;
+}
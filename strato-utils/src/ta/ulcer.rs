@@ -0,0 +1,35 @@
+use crate::ta::drawdown::rolling_drawdown;
+
+/// Calculates the Ulcer Index, the quadratic mean of the percentage
+/// drawdowns over the window, which penalizes deep and long drawdowns while
+/// ignoring upside volatility.
+///
+/// # Arguments
+///
+/// * `src` - A positive series, e.g. closes or an equity curve.
+/// * `length` - Number of values in the window; drawdowns are measured from the
+///   peak of the same window.
+///
+/// # Returns
+///
+/// A vector of Ulcer Index values in percent, `0.0` for the first
+/// `length - 1` values.
+///
+/// # Mathematical Formulation
+///
+/// `UI = sqrt(Σ R_i² / length)`, where `R_i = 100 * (src_i - max) / max` is
+/// the percentage drawdown from the highest value of the last `length` values.
+pub fn ulcer_index(src: &[f64], length: usize) -> Vec<f64> {
+    let drawdowns: Vec<f64> = rolling_drawdown(src, length)
+        .iter()
+        .map(|dd| (100.0 * dd).powi(2))
+        .collect();
+    let mut ulcer_values = vec![0.0; src.len()];
+
+    for i in length.saturating_sub(1)..src.len() {
+        let sum_sq: f64 = drawdowns[i + 1 - length..=i].iter().sum();
+        ulcer_values[i] = (sum_sq / length as f64).sqrt();
+    }
+
+    ulcer_values
+}
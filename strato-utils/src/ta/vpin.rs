@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+
+/// Volume-synchronized probability of informed trading (VPIN).
+///
+/// Trades are accumulated into buckets of equal volume. Once a bucket is full,
+/// its order flow imbalance `|buy - sell| / bucket_volume` is recorded, and
+/// VPIN is the average imbalance over the last `window` buckets.
+///
+/// Easley, López de Prado and O'Hara, "Flow Toxicity and Liquidity in a
+/// High-frequency World" (2012).
+#[derive(Debug, Clone)]
+pub struct Vpin {
+    bucket_volume: f64,
+    window: usize,
+    buy_volume: f64,
+    sell_volume: f64,
+    last_price: Option<f64>,
+    last_side_is_buy: bool,
+    imbalances: VecDeque<f64>,
+    imbalance_sum: f64,
+}
+
+impl Vpin {
+    /// Creates a new estimator.
+    ///
+    /// # Arguments
+    ///
+    /// * `bucket_volume` - Volume contained in each bucket.
+    /// * `window` - Number of buckets averaged into the VPIN value.
+    ///
+    /// # Returns
+    ///
+    /// The estimator, or an error if `bucket_volume` is not a positive number
+    /// or `window` is zero.
+    pub fn new(bucket_volume: f64, window: usize) -> Result<Self, String> {
+        if !(bucket_volume.is_finite() && bucket_volume > 0.0) {
+            return Err(format!(
+                "Invalid VPIN bucket volume {}: it must be a positive number.",
+                bucket_volume
+            ));
+        }
+        if window == 0 {
+            return Err("Invalid VPIN window: at least one bucket must be averaged.".to_string());
+        }
+
+        Ok(Self {
+            bucket_volume,
+            window,
+            buy_volume: 0.0,
+            sell_volume: 0.0,
+            last_price: None,
+            last_side_is_buy: true,
+            imbalances: VecDeque::with_capacity(window + 1),
+            imbalance_sum: 0.0,
+        })
+    }
+
+    /// Adds a trade whose aggressor side is inferred with the tick rule: an
+    /// uptick is a buy, a downtick a sell, and an unchanged price repeats the
+    /// previous side.
+    ///
+    /// Returns the current VPIN value once `window` buckets have completed.
+    pub fn update(&mut self, price: f64, volume: f64) -> Option<f64> {
+        let is_buy = match self.last_price {
+            Some(last) if price > last => true,
+            Some(last) if price < last => false,
+            _ => self.last_side_is_buy,
+        };
+        self.last_price = Some(price);
+        self.update_with_side(volume, is_buy)
+    }
+
+    /// Adds a trade with a known aggressor side.
+    ///
+    /// Returns the current VPIN value once `window` buckets have completed.
+    pub fn update_with_side(&mut self, volume: f64, is_buy: bool) -> Option<f64> {
+        self.last_side_is_buy = is_buy;
+        let mut remaining = volume;
+
+        // A large trade may fill the current bucket and spill into new ones
+        while remaining > 0.0 {
+            let room = self.bucket_volume - (self.buy_volume + self.sell_volume);
+            let filled = remaining.min(room);
+            if is_buy {
+                self.buy_volume += filled;
+            } else {
+                self.sell_volume += filled;
+            }
+            remaining -= filled;
+
+            if filled == room {
+                self.close_bucket();
+            }
+        }
+
+        self.value()
+    }
+
+    /// Returns the current VPIN value, or `None` until `window` buckets have
+    /// completed.
+    pub fn value(&self) -> Option<f64> {
+        if self.imbalances.len() < self.window {
+            return None;
+        }
+        Some(self.imbalance_sum / self.window as f64)
+    }
+
+    fn close_bucket(&mut self) {
+        let imbalance = (self.buy_volume - self.sell_volume).abs() / self.bucket_volume;
+        self.imbalances.push_back(imbalance);
+        self.imbalance_sum += imbalance;
+        if self.imbalances.len() > self.window {
+            if let Some(oldest) = self.imbalances.pop_front() {
+                self.imbalance_sum -= oldest;
+            }
+        }
+        self.buy_volume = 0.0;
+        self.sell_volume = 0.0;
+    }
+}
+
+/// Computes the VPIN series for a stream of trades using the tick rule.
+///
+/// Values before `window` buckets have completed are `0.0`. Fails on the
+/// parameters `Vpin::new` rejects.
+pub fn vpin(
+    prices: &[f64],
+    volumes: &[f64],
+    bucket_volume: f64,
+    window: usize,
+) -> Result<Vec<f64>, String> {
+    let mut estimator = Vpin::new(bucket_volume, window)?;
+    Ok(prices
+        .iter()
+        .zip(volumes)
+        .map(|(&price, &volume)| estimator.update(price, volume).unwrap_or(0.0))
+        .collect())
+}
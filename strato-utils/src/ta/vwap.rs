@@ -0,0 +1,105 @@
+use crate::vars::ohlc::Ohlc;
+
+/// Length of a daily session in milliseconds.
+pub const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// The point from which the VWAP accumulates.
+///
+/// `VwapAnchor::session` and `VwapAnchor::rolling` check their length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VwapAnchor {
+    /// Resets at the start of every session of the given length in
+    /// milliseconds, e.g. `DAY_MS` for the UTC day.
+    Session(i64),
+    /// Over the last N candles.
+    Rolling(usize),
+    /// From the first candle at or after the given Unix ms timestamp.
+    Timestamp(i64),
+}
+
+impl VwapAnchor {
+    /// Creates a session anchor, or an error if `length` is not positive.
+    pub fn session(length: i64) -> Result<Self, String> {
+        if length <= 0 {
+            return Err(format!(
+                "Invalid VWAP session length {} ms: it must be positive.",
+                length
+            ));
+        }
+        Ok(Self::Session(length))
+    }
+
+    /// Creates a rolling anchor, or an error if `length` is zero.
+    pub fn rolling(length: usize) -> Result<Self, String> {
+        if length == 0 {
+            return Err("Invalid VWAP rolling length: at least one candle is needed.".to_string());
+        }
+        Ok(Self::Rolling(length))
+    }
+}
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.vwap
+///
+/// Calculates the Volume-Weighted Average Price (VWAP) of the typical price
+/// `(high + low + close) / 3`.
+///
+/// # Arguments
+///
+/// * `candles` - A slice of `Ohlc` candles with timestamps and volumes.
+/// * `anchor` - Where the accumulation starts.
+///
+/// # Returns
+///
+/// A vector of VWAP values. Candles before a timestamp anchor, or before the
+/// first full window of a rolling anchor, are `0.0`. If no volume has traded
+/// since the anchor, the typical price is used. A session anchor of zero
+/// length never resets.
+pub fn vwap(candles: &[Ohlc], anchor: VwapAnchor) -> Vec<f64> {
+    let src: Vec<f64> = candles
+        .iter()
+        .map(|c| (c.high + c.low + c.close) / 3.0)
+        .collect();
+    let mut vwap_values = vec![0.0; candles.len()];
+
+    let mut pv_sum = 0.0;
+    let mut volume_sum = 0.0;
+    for i in 0..candles.len() {
+        match anchor {
+            VwapAnchor::Session(length) => {
+                let session = candles[i].timestamp.checked_div_euclid(length);
+                if i > 0 && session != candles[i - 1].timestamp.checked_div_euclid(length) {
+                    pv_sum = 0.0;
+                    volume_sum = 0.0;
+                }
+            }
+            VwapAnchor::Rolling(length) => {
+                if i >= length {
+                    pv_sum -= src[i - length] * candles[i - length].volume;
+                    volume_sum -= candles[i - length].volume;
+                }
+            }
+            VwapAnchor::Timestamp(start) => {
+                if candles[i].timestamp < start {
+                    continue;
+                }
+            }
+        }
+
+        pv_sum += src[i] * candles[i].volume;
+        volume_sum += candles[i].volume;
+
+        if let VwapAnchor::Rolling(length) = anchor {
+            if i + 1 < length {
+                continue;
+            }
+        }
+        vwap_values[i] = if volume_sum > 0.0 {
+            pv_sum / volume_sum
+        } else {
+            src[i]
+        };
+    }
+
+    vwap_values
+}
@@ -0,0 +1,40 @@
+use crate::ta::sma::sma;
+use crate::ta::warmup::with_nan_warmup;
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.vwma
+///
+/// # Arguments
+///
+/// * `src` - Source series, e.g. closes.
+/// * `volume` - Volume of each bar, aligned with `src`.
+/// * `length` - Number of bars in the window.
+///
+/// # Returns
+///
+/// The VWMA, `0.0` for the first `length - 1` values. Windows without volume
+/// fall back to the simple average.
+///
+/// # Mathematical Formulation
+///
+/// `VWMA = SMA(src * volume, length) / SMA(volume, length)`
+pub fn vwma(src: &[f64], volume: &[f64], length: usize) -> Vec<f64> {
+    let weighted: Vec<f64> = src.iter().zip(volume).map(|(s, v)| s * v).collect();
+    let weighted_sma = sma(&weighted, length);
+    let volume_sma = sma(volume, length);
+    let src_sma = sma(src, length);
+
+    (0..weighted_sma.len())
+        .map(|i| {
+            if volume_sma[i] > 0.0 {
+                weighted_sma[i] / volume_sma[i]
+            } else {
+                src_sma[i]
+            }
+        })
+        .collect()
+}
+
+/// Like `vwma`, but the first `length - 1` values are `f64::NAN`.
+pub fn vwma_nan(src: &[f64], volume: &[f64], length: usize) -> Vec<f64> {
+    with_nan_warmup(vwma(src, volume, length), length.saturating_sub(1))
+}
@@ -0,0 +1,26 @@
+/*!
+Warm-up helpers for the batch indicators.
+
+Most indicators in `ta` fill the values before they have enough history with
+`0.0`, which is indistinguishable from a real value and, e.g., turns into
+absurd grid levels on short histories. The `*_nan` variants of the
+indicators (`sma_nan`, `rma_nan`, `ema_nan`, `atr_nan`, `wma_nan`, `hma_nan`,
+`dema_nan`, `tema_nan`, `vwma_nan`) return `f64::NAN` instead: comparisons against NaN are always
+false and arithmetic propagates it, so signals simply do not fire until the
+indicator is warm.
+*/
+
+/// Replaces the first `period` values with `f64::NAN`.
+pub fn with_nan_warmup(mut values: Vec<f64>, period: usize) -> Vec<f64> {
+    let period = period.min(values.len());
+    values[..period].fill(f64::NAN);
+    values
+}
+
+/// Converts NaN values to `None`, for callers preferring explicit options.
+pub fn to_options(values: &[f64]) -> Vec<Option<f64>> {
+    values
+        .iter()
+        .map(|&v| if v.is_nan() { None } else { Some(v) })
+        .collect()
+}
@@ -0,0 +1,27 @@
+use crate::ta::warmup::with_nan_warmup;
+
+/// https://www.tradingview.com/pine-script-reference/v5/#fun_ta.wma
+pub fn wma(src: &[f64], length: usize) -> Vec<f64> {
+    let norm = (length * (length + 1)) as f64 / 2.0;
+    let mut wma_values = Vec::with_capacity(src.len());
+
+    for i in 0..src.len() {
+        if i < length - 1 {
+            wma_values.push(0.0);
+        } else {
+            let sum: f64 = src[i + 1 - length..=i]
+                .iter()
+                .enumerate()
+                .map(|(j, &value)| value * (j + 1) as f64)
+                .sum();
+            wma_values.push(sum / norm);
+        }
+    }
+
+    wma_values
+}
+
+/// Like `wma`, but the first `length - 1` values are `f64::NAN`.
+pub fn wma_nan(src: &[f64], length: usize) -> Vec<f64> {
+    with_nan_warmup(wma(src, length), length.saturating_sub(1))
+}
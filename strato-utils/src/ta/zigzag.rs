@@ -0,0 +1,90 @@
+use crate::ta::atr::atr;
+use crate::vars::ohlc::Ohlc;
+
+/// The price move against the trend needed to confirm a swing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ZigZagThreshold {
+    /// A percentage of the swing price, e.g. `5.0` for 5%.
+    Percent(f64),
+    /// A multiple of the ATR at the confirming candle.
+    Atr { length: usize, mult: f64 },
+}
+
+/// A confirmed swing high or low.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwingPoint {
+    /// Index of the candle making the swing.
+    pub index: usize,
+    /// High of a swing high, or low of a swing low.
+    pub price: f64,
+    pub is_high: bool,
+    /// Index of the candle whose move confirmed the swing. Use this rather
+    /// than `index` in backtests to avoid look-ahead.
+    pub confirmed_at: usize,
+}
+
+/// Detects ZigZag swings: alternating highs and lows separated by moves of at
+/// least the reversal threshold.
+///
+/// # Arguments
+///
+/// * `candles` - A slice of `Ohlc` candles.
+/// * `threshold` - The reversal threshold.
+///
+/// # Returns
+///
+/// The confirmed swing points in order. The swing in progress at the end of
+/// the series is not confirmed and is not included.
+pub fn zigzag(candles: &[Ohlc], threshold: ZigZagThreshold) -> Vec<SwingPoint> {
+    let atr_values = match threshold {
+        ZigZagThreshold::Atr { length, .. } => atr(candles, length),
+        ZigZagThreshold::Percent(_) => Vec::new(),
+    };
+    let reversal = |i: usize, price: f64| match threshold {
+        ZigZagThreshold::Percent(percent) => price * percent / 100.0,
+        ZigZagThreshold::Atr { mult, .. } => atr_values[i] * mult,
+    };
+
+    let mut swings = Vec::new();
+    let Some(first) = candles.first() else {
+        return swings;
+    };
+    // 1.0 while tracking a high, -1.0 while tracking a low, 0.0 until the
+    // first swing
+    let mut direction = 0.0;
+    let mut high = (0, first.high);
+    let mut low = (0, first.low);
+
+    for (i, c) in candles.iter().enumerate().skip(1) {
+        if direction >= 0.0 && c.high > high.1 {
+            high = (i, c.high);
+        }
+        if direction <= 0.0 && c.low < low.1 {
+            low = (i, c.low);
+        }
+
+        if direction >= 0.0 && c.low <= high.1 - reversal(i, high.1) {
+            swings.push(SwingPoint {
+                index: high.0,
+                price: high.1,
+                is_high: true,
+                confirmed_at: i,
+            });
+            direction = -1.0;
+            low = (i, c.low);
+        } else if direction <= 0.0 && c.high >= low.1 + reversal(i, low.1) {
+            swings.push(SwingPoint {
+                index: low.0,
+                price: low.1,
+                is_high: false,
+                confirmed_at: i,
+            });
+            direction = 1.0;
+            high = (i, c.high);
+        }
+    }
+
+    swings
+}
@@ -0,0 +1,21 @@
+use crate::ta::sma::sma;
+use crate::ta::stdev::stdev;
+
+/// Calculates the rolling z-score, the number of standard deviations `src` is
+/// away from its rolling mean: `(src - sma) / stdev`.
+///
+/// Values are `0.0` for the first `length - 1` values and wherever the
+/// window has no dispersion.
+pub fn zscore(src: &[f64], length: usize) -> Vec<f64> {
+    let mean = sma(src, length);
+    let stdev = stdev(src, length);
+    let mut zscore_values = vec![0.0; src.len()];
+
+    for i in length.saturating_sub(1)..src.len() {
+        if stdev[i] > 0.0 {
+            zscore_values[i] = (src[i] - mean[i]) / stdev[i];
+        }
+    }
+
+    zscore_values
+}
@@ -0,0 +1,203 @@
+/*!
+Validation of market data before it is used in a backtest.
+
+Exchange dumps and resampled files regularly contain broken candles: highs
+below lows, wicks that do not cover the body, duplicated or out of order
+rows. Strategies do not fail on them, they silently produce wrong signals, so
+`validate_candles` reports every such candle and `check_candles` turns them
+into an error to bail out early.
+*/
+
+use std::fmt;
+
+use crate::vars::ohlc::Ohlc;
+
+/// A broken invariant of a single candle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ViolationKind {
+    /// A price or the volume is NaN or infinite.
+    NonFinite,
+    /// The high is below the low.
+    HighBelowLow,
+    /// The high is below the open or the close.
+    HighBelowBody,
+    /// The low is above the open or the close.
+    LowAboveBody,
+    /// The volume is negative.
+    NegativeVolume,
+    /// The timestamp equals the one of the previous candle.
+    DuplicateTimestamp,
+    /// The timestamp is before the one of the previous candle.
+    TimestampDecreasing { previous: i64 },
+}
+
+/// A violation found by `validate_candles`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Violation {
+    /// Index of the offending candle.
+    pub index: usize,
+    /// Timestamp of the offending candle.
+    pub timestamp: i64,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "candle {} at {}: ", self.index, self.timestamp)?;
+        match self.kind {
+            ViolationKind::NonFinite => write!(f, "non-finite price or volume"),
+            ViolationKind::HighBelowLow => write!(f, "high is below low"),
+            ViolationKind::HighBelowBody => write!(f, "high is below open or close"),
+            ViolationKind::LowAboveBody => write!(f, "low is above open or close"),
+            ViolationKind::NegativeVolume => write!(f, "negative volume"),
+            ViolationKind::DuplicateTimestamp => write!(f, "duplicate timestamp"),
+            ViolationKind::TimestampDecreasing { previous } => {
+                write!(f, "timestamp is before the previous one ({})", previous)
+            }
+        }
+    }
+}
+
+/// Checks the invariants of every candle.
+///
+/// A candle may break several invariants and is reported once for each.
+/// Price invariants are not checked on candles with non-finite values.
+///
+/// # Returns
+///
+/// The violations in candle order, empty if the data is valid.
+pub fn validate_candles(candles: &[Ohlc]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    for (index, candle) in candles.iter().enumerate() {
+        let mut push = |kind| {
+            violations.push(Violation {
+                index,
+                timestamp: candle.timestamp,
+                kind,
+            })
+        };
+
+        if let Some(previous) = index.checked_sub(1).map(|i| candles[i].timestamp) {
+            if candle.timestamp == previous {
+                push(ViolationKind::DuplicateTimestamp);
+            } else if candle.timestamp < previous {
+                push(ViolationKind::TimestampDecreasing { previous });
+            }
+        }
+
+        let values = [
+            candle.open,
+            candle.high,
+            candle.low,
+            candle.close,
+            candle.volume,
+        ];
+        if values.iter().any(|v| !v.is_finite()) {
+            push(ViolationKind::NonFinite);
+            continue;
+        }
+        if candle.high < candle.low {
+            push(ViolationKind::HighBelowLow);
+        }
+        if candle.high < candle.open.max(candle.close) {
+            push(ViolationKind::HighBelowBody);
+        }
+        if candle.low > candle.open.min(candle.close) {
+            push(ViolationKind::LowAboveBody);
+        }
+        if candle.volume < 0.0 {
+            push(ViolationKind::NegativeVolume);
+        }
+    }
+
+    violations
+}
+
+/// Validates the candles, failing on the first violations found.
+///
+/// # Returns
+///
+/// An error listing up to ten violations and the total count.
+pub fn check_candles(candles: &[Ohlc]) -> Result<(), String> {
+    const MAX_REPORTED: usize = 10;
+
+    let violations = validate_candles(candles);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let listed: Vec<String> = violations
+        .iter()
+        .take(MAX_REPORTED)
+        .map(|v| v.to_string())
+        .collect();
+    Err(format!(
+        "Invalid market data, {} violation(s): {}",
+        violations.len(),
+        listed.join("; ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(timestamp: i64, open: f64, high: f64, low: f64, close: f64) -> Ohlc {
+        Ohlc {
+            timestamp,
+            open,
+            high,
+            low,
+            close,
+            volume: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_valid_candles() {
+        let candles = vec![
+            candle(0, 1.0, 2.0, 0.5, 1.5),
+            candle(60_000, 1.5, 1.5, 1.5, 1.5),
+        ];
+        assert!(validate_candles(&candles).is_empty());
+        assert!(check_candles(&candles).is_ok());
+        assert!(check_candles(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_violations() {
+        let mut negative_volume = candle(180_000, 1.0, 2.0, 0.5, 1.5);
+        negative_volume.volume = -1.0;
+        let candles = vec![
+            candle(60_000, 1.0, 2.0, 0.5, 1.5),
+            // High below low, and therefore below the body
+            candle(60_000, 1.0, 0.5, 2.0, 1.5),
+            candle(0, 1.0, 1.2, 0.9, 1.5),
+            negative_volume,
+            candle(240_000, 1.0, f64::NAN, 0.5, 1.5),
+        ];
+
+        let kinds: Vec<(usize, ViolationKind)> = validate_candles(&candles)
+            .iter()
+            .map(|v| (v.index, v.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (1, ViolationKind::DuplicateTimestamp),
+                (1, ViolationKind::HighBelowLow),
+                (1, ViolationKind::HighBelowBody),
+                (1, ViolationKind::LowAboveBody),
+                (2, ViolationKind::TimestampDecreasing { previous: 60_000 }),
+                (2, ViolationKind::HighBelowBody),
+                (3, ViolationKind::NegativeVolume),
+                (4, ViolationKind::NonFinite),
+            ]
+        );
+
+        let error = check_candles(&candles).unwrap_err();
+        assert!(error.starts_with("Invalid market data, 8 violation(s): candle 1 at 60000"));
+    }
+}
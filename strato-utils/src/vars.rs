@@ -1 +1,11 @@
+#[cfg(feature = "parquet")]
+pub mod columnar;
+pub mod csv;
+pub mod funding;
+pub mod instrument;
 pub mod ohlc;
+pub mod order_book;
+pub mod panel;
+pub mod time_series;
+pub mod trade;
+pub mod window;
@@ -0,0 +1,395 @@
+/*!
+Arrow and Parquet interop for candles and trades, behind the `parquet`
+feature.
+
+Candles are stored with the columns `timestamp, open, high, low, close,
+volume` and trades with `timestamp, price, qty, side`. Timestamps are written
+as `timestamp[ms, UTC]` and `side` as the strings `"buy"` and `"sell"`, which
+pandas and polars read natively.
+
+The readers are lenient so that files produced by other tools can be loaded:
+timestamps may be of any unit or plain Unix milliseconds, numeric columns of
+any numeric type, `side` either a string or a signed number, and `volume` may
+be missing. Null values are read as zero.
+
+Multi-year tick files do not fit in memory; `read_candles_parquet_chunks` and
+`read_trades_parquet_chunks` stream them in chunks instead.
+*/
+
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::Array;
+use arrow::array::ArrayRef;
+use arrow::array::AsArray;
+use arrow::array::Float64Array;
+use arrow::array::StringArray;
+use arrow::array::TimestampMillisecondArray;
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use arrow::datatypes::Field;
+use arrow::datatypes::Float64Type;
+use arrow::datatypes::Schema;
+use arrow::datatypes::SchemaRef;
+use arrow::datatypes::TimeUnit;
+use arrow::datatypes::TimestampMillisecondType;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+
+use crate::vars::ohlc::Ohlc;
+use crate::vars::trade::Side;
+use crate::vars::trade::Trade;
+
+/// Schema of candle record batches.
+pub fn candle_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        timestamp_field(),
+        Field::new("open", DataType::Float64, false),
+        Field::new("high", DataType::Float64, false),
+        Field::new("low", DataType::Float64, false),
+        Field::new("close", DataType::Float64, false),
+        Field::new("volume", DataType::Float64, false),
+    ]))
+}
+
+/// Schema of trade record batches.
+pub fn trade_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        timestamp_field(),
+        Field::new("price", DataType::Float64, false),
+        Field::new("qty", DataType::Float64, false),
+        Field::new("side", DataType::Utf8, false),
+    ]))
+}
+
+/// Converts candles into a record batch.
+pub fn candles_to_batch(candles: &[Ohlc]) -> Result<RecordBatch, String> {
+    let values = |f: fn(&Ohlc) -> f64| -> ArrayRef {
+        Arc::new(Float64Array::from_iter_values(candles.iter().map(f)))
+    };
+    let columns = vec![
+        timestamp_column(candles.iter().map(|c| c.timestamp)),
+        values(|c| c.open),
+        values(|c| c.high),
+        values(|c| c.low),
+        values(|c| c.close),
+        values(|c| c.volume),
+    ];
+
+    RecordBatch::try_new(candle_schema(), columns).map_err(|e| e.to_string())
+}
+
+/// Converts a record batch into candles.
+pub fn candles_from_batch(batch: &RecordBatch) -> Result<Vec<Ohlc>, String> {
+    let timestamp = timestamp_values(batch)?;
+    let open = f64_values(batch, "open")?;
+    let high = f64_values(batch, "high")?;
+    let low = f64_values(batch, "low")?;
+    let close = f64_values(batch, "close")?;
+    let volume = match batch.column_by_name("volume") {
+        Some(_) => f64_values(batch, "volume")?,
+        None => vec![0.0; batch.num_rows()],
+    };
+
+    Ok((0..batch.num_rows())
+        .map(|i| Ohlc {
+            timestamp: timestamp[i],
+            open: open[i],
+            high: high[i],
+            low: low[i],
+            close: close[i],
+            volume: volume[i],
+        })
+        .collect())
+}
+
+/// Converts trades into a record batch.
+pub fn trades_to_batch(trades: &[Trade]) -> Result<RecordBatch, String> {
+    let columns: Vec<ArrayRef> = vec![
+        timestamp_column(trades.iter().map(|t| t.timestamp)),
+        Arc::new(Float64Array::from_iter_values(
+            trades.iter().map(|t| t.price),
+        )),
+        Arc::new(Float64Array::from_iter_values(trades.iter().map(|t| t.qty))),
+        Arc::new(StringArray::from_iter_values(trades.iter().map(
+            |t| match t.side {
+                Side::Buy => "buy",
+                Side::Sell => "sell",
+            },
+        ))),
+    ];
+
+    RecordBatch::try_new(trade_schema(), columns).map_err(|e| e.to_string())
+}
+
+/// Converts a record batch into trades.
+pub fn trades_from_batch(batch: &RecordBatch) -> Result<Vec<Trade>, String> {
+    let timestamp = timestamp_values(batch)?;
+    let price = f64_values(batch, "price")?;
+    let qty = f64_values(batch, "qty")?;
+    let side = side_values(batch)?;
+
+    Ok((0..batch.num_rows())
+        .map(|i| Trade {
+            timestamp: timestamp[i],
+            price: price[i],
+            qty: qty[i],
+            side: side[i],
+        })
+        .collect())
+}
+
+/// Writes candles to a Parquet file.
+pub fn write_candles_parquet<P: AsRef<Path>>(path: P, candles: &[Ohlc]) -> Result<(), String> {
+    write_parquet(path.as_ref(), candles_to_batch(candles)?)
+}
+
+/// Reads candles from a Parquet file.
+pub fn read_candles_parquet<P: AsRef<Path>>(path: P) -> Result<Vec<Ohlc>, String> {
+    let mut candles = Vec::new();
+    for batch in read_parquet(path.as_ref())? {
+        candles.extend(candles_from_batch(&batch)?);
+    }
+    Ok(candles)
+}
+
+/// Streams candles from a Parquet file in chunks of at most `chunk_size`
+/// candles. Only the row group being decoded is held in memory.
+pub fn read_candles_parquet_chunks<P: AsRef<Path>>(
+    path: P,
+    chunk_size: usize,
+) -> Result<impl Iterator<Item = Result<Vec<Ohlc>, String>>, String> {
+    Ok(parquet_batches(path.as_ref(), chunk_size)?.map(|batch| candles_from_batch(&batch?)))
+}
+
+/// Writes trades to a Parquet file.
+pub fn write_trades_parquet<P: AsRef<Path>>(path: P, trades: &[Trade]) -> Result<(), String> {
+    write_parquet(path.as_ref(), trades_to_batch(trades)?)
+}
+
+/// Reads trades from a Parquet file.
+pub fn read_trades_parquet<P: AsRef<Path>>(path: P) -> Result<Vec<Trade>, String> {
+    let mut trades = Vec::new();
+    for batch in read_parquet(path.as_ref())? {
+        trades.extend(trades_from_batch(&batch)?);
+    }
+    Ok(trades)
+}
+
+/// Streams trades from a Parquet file in chunks of at most `chunk_size`
+/// trades. Only the row group being decoded is held in memory.
+pub fn read_trades_parquet_chunks<P: AsRef<Path>>(
+    path: P,
+    chunk_size: usize,
+) -> Result<impl Iterator<Item = Result<Vec<Trade>, String>>, String> {
+    Ok(parquet_batches(path.as_ref(), chunk_size)?.map(|batch| trades_from_batch(&batch?)))
+}
+
+fn write_parquet(path: &Path, batch: RecordBatch) -> Result<(), String> {
+    let file =
+        File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None).map_err(|e| e.to_string())?;
+    writer.write(&batch).map_err(|e| e.to_string())?;
+    writer.close().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn read_parquet(path: &Path) -> Result<Vec<RecordBatch>, String> {
+    parquet_batches(path, DEFAULT_BATCH_SIZE)?.collect()
+}
+
+/// Rows decoded per record batch when reading a whole file.
+const DEFAULT_BATCH_SIZE: usize = 8192;
+
+fn parquet_batches(
+    path: &Path,
+    batch_size: usize,
+) -> Result<impl Iterator<Item = Result<RecordBatch, String>>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+        .and_then(|builder| builder.with_batch_size(batch_size).build())
+        .map_err(|e| e.to_string())?;
+    Ok(reader.map(|batch| batch.map_err(|e| e.to_string())))
+}
+
+fn timestamp_field() -> Field {
+    Field::new(
+        "timestamp",
+        DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+        false,
+    )
+}
+
+fn timestamp_column(timestamps: impl Iterator<Item = i64>) -> ArrayRef {
+    Arc::new(TimestampMillisecondArray::from_iter_values(timestamps).with_timezone("UTC"))
+}
+
+fn column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a ArrayRef, String> {
+    batch
+        .column_by_name(name)
+        .ok_or_else(|| format!("Column '{}' not found.", name))
+}
+
+/// Reads the `timestamp` column as Unix milliseconds, converting from any
+/// timestamp unit. Integer columns are taken to be milliseconds already.
+fn timestamp_values(batch: &RecordBatch) -> Result<Vec<i64>, String> {
+    let array = column(batch, "timestamp")?;
+    let array = match array.data_type() {
+        DataType::Timestamp(_, _) => cast(array, &DataType::Timestamp(TimeUnit::Millisecond, None)),
+        _ => cast(array, &DataType::Int64)
+            .and_then(|a| cast(&a, &DataType::Timestamp(TimeUnit::Millisecond, None))),
+    }
+    .map_err(|e| format!("Column 'timestamp': {}", e))?;
+
+    Ok(array
+        .as_primitive::<TimestampMillisecondType>()
+        .iter()
+        .map(Option::unwrap_or_default)
+        .collect())
+}
+
+fn f64_values(batch: &RecordBatch, name: &str) -> Result<Vec<f64>, String> {
+    let array = cast(column(batch, name)?, &DataType::Float64)
+        .map_err(|e| format!("Column '{}': {}", name, e))?;
+
+    Ok(array
+        .as_primitive::<Float64Type>()
+        .iter()
+        .map(Option::unwrap_or_default)
+        .collect())
+}
+
+/// Reads the `side` column, either strings (`buy`/`b`/`bid`, case
+/// insensitive, and anything else as a sell) or signed numbers.
+fn side_values(batch: &RecordBatch) -> Result<Vec<Side>, String> {
+    let array = column(batch, "side")?;
+
+    if matches!(array.data_type(), DataType::Utf8 | DataType::LargeUtf8) {
+        let array = cast(array, &DataType::Utf8).map_err(|e| e.to_string())?;
+        Ok(array
+            .as_string::<i32>()
+            .iter()
+            .map(|side| match side.map(str::to_ascii_lowercase).as_deref() {
+                Some("buy" | "b" | "bid") => Side::Buy,
+                _ => Side::Sell,
+            })
+            .collect())
+    } else {
+        Ok(f64_values(batch, "side")?
+            .into_iter()
+            .map(|sign| if sign > 0.0 { Side::Buy } else { Side::Sell })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let candles = vec![Ohlc {
+            timestamp: 1_704_067_200_000,
+            open: 1.0,
+            high: 2.0,
+            low: 0.5,
+            close: 1.5,
+            volume: 10.0,
+        }];
+        let trades = vec![
+            Trade {
+                timestamp: 1_704_067_200_001,
+                price: 1.5,
+                qty: 2.0,
+                side: Side::Buy,
+            },
+            Trade {
+                timestamp: 1_704_067_200_002,
+                price: 1.4,
+                qty: 1.0,
+                side: Side::Sell,
+            },
+        ];
+
+        let dir = std::env::temp_dir();
+        let candle_path = dir.join(format!("strato-candles-{}.parquet", std::process::id()));
+        let trade_path = dir.join(format!("strato-trades-{}.parquet", std::process::id()));
+        write_candles_parquet(&candle_path, &candles).unwrap();
+        write_trades_parquet(&trade_path, &trades).unwrap();
+
+        let read_candles = read_candles_parquet(&candle_path).unwrap();
+        let read_trades = read_trades_parquet(&trade_path).unwrap();
+        std::fs::remove_file(candle_path).unwrap();
+        std::fs::remove_file(trade_path).unwrap();
+
+        assert_eq!(read_candles[0].timestamp, candles[0].timestamp);
+        assert_eq!(read_candles[0].close, 1.5);
+        assert_eq!(read_candles[0].volume, 10.0);
+        assert_eq!(read_trades, trades);
+    }
+
+    #[test]
+    fn test_parquet_chunks() {
+        let candles: Vec<Ohlc> = (0..5)
+            .map(|i| Ohlc {
+                timestamp: i * 60_000,
+                close: i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let path =
+            std::env::temp_dir().join(format!("strato-chunks-{}.parquet", std::process::id()));
+        write_candles_parquet(&path, &candles).unwrap();
+
+        let chunks: Vec<Vec<Ohlc>> = read_candles_parquet_chunks(&path, 2)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let sizes: Vec<usize> = chunks.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        let closes: Vec<f64> = chunks.concat().iter().map(|c| c.close).collect();
+        assert_eq!(closes, vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_lenient_batch() {
+        // Nanosecond timestamps, integer prices, numeric sides
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            Field::new("price", DataType::Int64, false),
+            Field::new("qty", DataType::Float32, false),
+            Field::new("side", DataType::Int8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(arrow::array::TimestampNanosecondArray::from(vec![
+                    1_704_067_200_000_000_000,
+                ])),
+                Arc::new(arrow::array::Int64Array::from(vec![100])),
+                Arc::new(arrow::array::Float32Array::from(vec![0.5])),
+                Arc::new(arrow::array::Int8Array::from(vec![-1])),
+            ],
+        )
+        .unwrap();
+
+        let trades = trades_from_batch(&batch).unwrap();
+        assert_eq!(
+            trades,
+            vec![Trade {
+                timestamp: 1_704_067_200_000,
+                price: 100.0,
+                qty: 0.5,
+                side: Side::Sell,
+            }]
+        );
+    }
+}
@@ -0,0 +1,609 @@
+/*!
+CSV import and export of candles.
+
+Exchange exports differ in column order, header names, delimiters and
+timestamp units, so the layout of a file is described by a `CsvFormat`. The
+defaults read and write the crate's own layout:
+
+```text
+timestamp,open,high,low,close,volume
+1704067200000,42283.6,42554.6,42261.0,42475.2,1271.7
+```
+
+Fields are split on the delimiter without quoting support, which covers the
+numeric exports of the major exchanges.
+
+`CsvCandleReader` streams a file line by line, for files too large to load
+with `Ohlc::from_csv`.
+*/
+
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Lines;
+use std::io::Write;
+use std::iter::Enumerate;
+use std::path::Path;
+
+use crate::vars::ohlc::Ohlc;
+
+/// A column of a CSV file, by header name or by zero-based position.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CsvColumn {
+    Name(String),
+    Index(usize),
+}
+
+impl From<&str> for CsvColumn {
+    fn from(name: &str) -> Self {
+        CsvColumn::Name(name.to_string())
+    }
+}
+
+impl From<usize> for CsvColumn {
+    fn from(index: usize) -> Self {
+        CsvColumn::Index(index)
+    }
+}
+
+/// How timestamps are encoded in a CSV file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimestampFormat {
+    /// Unix seconds, possibly fractional.
+    UnixSeconds,
+    /// Unix milliseconds.
+    UnixMillis,
+    /// Unix microseconds.
+    UnixMicros,
+    /// Unix nanoseconds.
+    UnixNanos,
+    /// ISO 8601 / RFC 3339 date-times such as `2024-01-01T00:00:00Z` or
+    /// `2024-01-01 00:00:00`; times without an offset are read as UTC.
+    Iso8601,
+    /// Detects the unit of numeric timestamps from their magnitude and falls
+    /// back to ISO 8601 otherwise. Written as Unix milliseconds.
+    #[default]
+    Auto,
+}
+
+/// Describes the layout of a candle CSV file.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CsvFormat {
+    /// Field delimiter.
+    pub delimiter: char,
+    /// Whether the first line is a header.
+    pub has_header: bool,
+    pub timestamp: CsvColumn,
+    pub open: CsvColumn,
+    pub high: CsvColumn,
+    pub low: CsvColumn,
+    pub close: CsvColumn,
+    /// Volume column; volumes are `0.0` when it is `None`.
+    pub volume: Option<CsvColumn>,
+    pub timestamp_format: TimestampFormat,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            has_header: true,
+            timestamp: "timestamp".into(),
+            open: "open".into(),
+            high: "high".into(),
+            low: "low".into(),
+            close: "close".into(),
+            volume: Some("volume".into()),
+            timestamp_format: TimestampFormat::Auto,
+        }
+    }
+}
+
+impl CsvFormat {
+    /// Layout of the Binance kline dumps (data.binance.vision): no header,
+    /// open time in milliseconds followed by OHLCV.
+    pub fn binance() -> Self {
+        Self {
+            has_header: false,
+            timestamp: 0.into(),
+            open: 1.into(),
+            high: 2.into(),
+            low: 3.into(),
+            close: 4.into(),
+            volume: Some(5.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Streams candles from CSV one line at a time, so files larger than memory
+/// can be processed.
+///
+/// Yields the candles in file order; an error names the offending line and
+/// empty lines are skipped.
+pub struct CsvCandleReader<R: BufRead> {
+    lines: Enumerate<Lines<R>>,
+    delimiter: char,
+    timestamp_format: TimestampFormat,
+    columns: [usize; 5],
+    volume: Option<usize>,
+}
+
+impl<R: BufRead> CsvCandleReader<R> {
+    /// Reads the header, if any, and resolves the columns of `format`.
+    pub fn new(reader: R, format: &CsvFormat) -> Result<Self, String> {
+        let mut lines = reader.lines().enumerate();
+        let header: Vec<String> = if format.has_header {
+            match lines.next() {
+                Some((_, line)) => split(&line.map_err(|e| e.to_string())?, format.delimiter)
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+                None => Vec::new(),
+            }
+        } else {
+            Vec::new()
+        };
+        // A file without even its header yields no candles, so the columns
+        // need not resolve
+        let is_empty = format.has_header && header.is_empty();
+
+        let resolve = |column: &CsvColumn| match column {
+            CsvColumn::Name(_) if is_empty => Ok(0),
+            _ => resolve_column(column, &header, format.has_header),
+        };
+        Ok(Self {
+            lines,
+            delimiter: format.delimiter,
+            timestamp_format: format.timestamp_format,
+            columns: [
+                resolve(&format.timestamp)?,
+                resolve(&format.open)?,
+                resolve(&format.high)?,
+                resolve(&format.low)?,
+                resolve(&format.close)?,
+            ],
+            volume: format.volume.as_ref().map(resolve).transpose()?,
+        })
+    }
+
+    /// Groups the candles into chunks of at most `size` candles. An error is
+    /// yielded after the candles preceding it.
+    pub fn chunks(self, size: usize) -> impl Iterator<Item = Result<Vec<Ohlc>, String>> {
+        let mut candles = self;
+        let mut error = None;
+        std::iter::from_fn(move || {
+            if let Some(e) = error.take() {
+                return Some(Err(e));
+            }
+            let mut chunk = Vec::with_capacity(size);
+            for candle in candles.by_ref() {
+                match candle {
+                    Ok(candle) => chunk.push(candle),
+                    Err(e) if chunk.is_empty() => return Some(Err(e)),
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                }
+                if chunk.len() >= size {
+                    break;
+                }
+            }
+            (!chunk.is_empty()).then_some(Ok(chunk))
+        })
+    }
+
+    fn parse(&self, line_no: usize, line: &str) -> Result<Ohlc, String> {
+        let fields = split(line, self.delimiter);
+        let field = |index: usize| {
+            fields
+                .get(index)
+                .copied()
+                .ok_or_else(|| format!("Line {}: missing column {}.", line_no + 1, index))
+        };
+        let number = |index: usize| {
+            let value = field(index)?;
+            value
+                .parse::<f64>()
+                .map_err(|_| format!("Line {}: invalid number '{}'.", line_no + 1, value))
+        };
+        let [timestamp, open, high, low, close] = self.columns;
+
+        Ok(Ohlc {
+            timestamp: parse_timestamp(field(timestamp)?, self.timestamp_format)
+                .map_err(|e| format!("Line {}: {}", line_no + 1, e))?,
+            open: number(open)?,
+            high: number(high)?,
+            low: number(low)?,
+            close: number(close)?,
+            volume: match self.volume {
+                Some(index) => number(index)?,
+                None => 0.0,
+            },
+        })
+    }
+}
+
+impl CsvCandleReader<BufReader<File>> {
+    /// Opens a CSV file for streaming.
+    pub fn open<P: AsRef<Path>>(path: P, format: &CsvFormat) -> Result<Self, String> {
+        let file = File::open(path.as_ref())
+            .map_err(|e| format!("Failed to open {}: {}", path.as_ref().display(), e))?;
+        Self::new(BufReader::new(file), format)
+    }
+}
+
+impl<R: BufRead> Iterator for CsvCandleReader<R> {
+    type Item = Result<Ohlc, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (line_no, line) = self.lines.next()?;
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.to_string())),
+            };
+            if !line.trim().is_empty() {
+                return Some(self.parse(line_no, &line));
+            }
+        }
+    }
+}
+
+impl Ohlc {
+    /// Reads candles from CSV.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the CSV data.
+    /// * `format` - Layout of the data.
+    ///
+    /// # Returns
+    ///
+    /// The candles in file order, or an error naming the offending line.
+    /// Empty lines are skipped. Use `CsvCandleReader` to stream large files.
+    pub fn from_csv<R: BufRead>(reader: R, format: &CsvFormat) -> Result<Vec<Ohlc>, String> {
+        CsvCandleReader::new(reader, format)?.collect()
+    }
+
+    /// Reads candles from a CSV file. See `from_csv`.
+    pub fn from_csv_file<P: AsRef<Path>>(path: P, format: &CsvFormat) -> Result<Vec<Ohlc>, String> {
+        let file = File::open(path.as_ref())
+            .map_err(|e| format!("Failed to open {}: {}", path.as_ref().display(), e))?;
+        Self::from_csv(BufReader::new(file), format)
+    }
+
+    /// Writes candles as CSV.
+    ///
+    /// Columns are always written in the order timestamp, open, high, low,
+    /// close, volume, with the delimiter and timestamp format of `format` and
+    /// a `timestamp,open,high,low,close,volume` header if `has_header` is set.
+    /// The volume is omitted if `format.volume` is `None`.
+    pub fn to_csv<W: Write>(
+        candles: &[Ohlc],
+        mut writer: W,
+        format: &CsvFormat,
+    ) -> Result<(), String> {
+        let d = format.delimiter;
+        let with_volume = format.volume.is_some();
+
+        if format.has_header {
+            let mut header = format!("timestamp{d}open{d}high{d}low{d}close");
+            if with_volume {
+                header.push_str(&format!("{d}volume"));
+            }
+            writeln!(writer, "{}", header).map_err(|e| e.to_string())?;
+        }
+        for c in candles {
+            let mut line = format!(
+                "{}{d}{}{d}{}{d}{}{d}{}",
+                format_timestamp(c.timestamp, format.timestamp_format),
+                c.open,
+                c.high,
+                c.low,
+                c.close
+            );
+            if with_volume {
+                line.push_str(&format!("{d}{}", c.volume));
+            }
+            writeln!(writer, "{}", line).map_err(|e| e.to_string())?;
+        }
+
+        writer.flush().map_err(|e| e.to_string())
+    }
+
+    /// Writes candles to a CSV file. See `to_csv`.
+    pub fn to_csv_file<P: AsRef<Path>>(
+        candles: &[Ohlc],
+        path: P,
+        format: &CsvFormat,
+    ) -> Result<(), String> {
+        let file = File::create(path.as_ref())
+            .map_err(|e| format!("Failed to create {}: {}", path.as_ref().display(), e))?;
+        Self::to_csv(candles, BufWriter::new(file), format)
+    }
+}
+
+/// Parses a timestamp into Unix milliseconds.
+pub fn parse_timestamp(value: &str, format: TimestampFormat) -> Result<i64, String> {
+    let numeric = || {
+        value
+            .parse::<f64>()
+            .map_err(|_| format!("invalid timestamp '{}'.", value))
+    };
+
+    match format {
+        TimestampFormat::UnixSeconds => Ok((numeric()? * 1_000.0).round() as i64),
+        TimestampFormat::UnixMillis => Ok(numeric()?.round() as i64),
+        TimestampFormat::UnixMicros => Ok((numeric()? / 1_000.0).round() as i64),
+        TimestampFormat::UnixNanos => Ok((numeric()? / 1_000_000.0).round() as i64),
+        TimestampFormat::Iso8601 => parse_iso8601(value),
+        TimestampFormat::Auto => match value.parse::<f64>() {
+            // Seconds until the year 5138, milliseconds until then, etc.
+            Ok(v) if v.abs() < 1e11 => parse_timestamp(value, TimestampFormat::UnixSeconds),
+            Ok(v) if v.abs() < 1e14 => parse_timestamp(value, TimestampFormat::UnixMillis),
+            Ok(v) if v.abs() < 1e17 => parse_timestamp(value, TimestampFormat::UnixMicros),
+            Ok(_) => parse_timestamp(value, TimestampFormat::UnixNanos),
+            Err(_) => parse_iso8601(value),
+        },
+    }
+}
+
+/// Formats Unix milliseconds as a timestamp.
+pub fn format_timestamp(timestamp: i64, format: TimestampFormat) -> String {
+    match format {
+        TimestampFormat::UnixSeconds => {
+            if timestamp % 1_000 == 0 {
+                (timestamp / 1_000).to_string()
+            } else {
+                (timestamp as f64 / 1_000.0).to_string()
+            }
+        }
+        TimestampFormat::UnixMillis | TimestampFormat::Auto => timestamp.to_string(),
+        TimestampFormat::UnixMicros => (timestamp * 1_000).to_string(),
+        TimestampFormat::UnixNanos => (timestamp * 1_000_000).to_string(),
+        TimestampFormat::Iso8601 => {
+            let days = timestamp.div_euclid(86_400_000);
+            let ms = timestamp.rem_euclid(86_400_000);
+            let (year, month, day) = civil_from_days(days);
+            format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+                year,
+                month,
+                day,
+                ms / 3_600_000,
+                ms / 60_000 % 60,
+                ms / 1_000 % 60,
+                ms % 1_000
+            )
+        }
+    }
+}
+
+/// Parses `YYYY-MM-DD[(T| )HH:MM[:SS[.fff]]][Z|±HH:MM]` into Unix
+/// milliseconds.
+fn parse_iso8601(value: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid timestamp '{}'.", value);
+    let number = |s: &str| s.parse::<i64>().map_err(|_| invalid());
+
+    let (date, time) = match value.find(['T', ' ']) {
+        Some(i) => (&value[..i], &value[i + 1..]),
+        None => (value, ""),
+    };
+    let mut date_parts = date.splitn(3, '-');
+    let year = number(date_parts.next().ok_or_else(invalid)?)?;
+    let month = number(date_parts.next().ok_or_else(invalid)?)?;
+    let day = number(date_parts.next().ok_or_else(invalid)?)?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+
+    // Split the UTC offset off the time of day
+    let (time, offset_ms) = if let Some(time) = time.strip_suffix('Z') {
+        (time, 0)
+    } else if let Some(i) = time.rfind(['+', '-']) {
+        let sign = if time[i..].starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = time[i + 1..]
+            .split_once(':')
+            .unwrap_or((&time[i + 1..], "0"));
+        (
+            &time[..i],
+            sign * (number(hours)? * 3_600_000 + number(minutes)? * 60_000),
+        )
+    } else {
+        (time, 0)
+    };
+
+    let mut time_ms = 0;
+    if !time.is_empty() {
+        let mut time_parts = time.splitn(3, ':');
+        let hours = number(time_parts.next().ok_or_else(invalid)?)?;
+        let minutes = number(time_parts.next().ok_or_else(invalid)?)?;
+        let seconds = match time_parts.next() {
+            Some(s) => s.parse::<f64>().map_err(|_| invalid())?,
+            None => 0.0,
+        };
+        time_ms = hours * 3_600_000 + minutes * 60_000 + (seconds * 1_000.0).round() as i64;
+    }
+
+    Ok(days_from_civil(year, month, day) * 86_400_000 + time_ms - offset_ms)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date of a number of days since 1970-01-01.
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn split(line: &str, delimiter: char) -> Vec<&str> {
+    line.split(delimiter)
+        .map(|field| field.trim().trim_matches('"'))
+        .collect()
+}
+
+fn resolve_column(
+    column: &CsvColumn,
+    header: &[String],
+    has_header: bool,
+) -> Result<usize, String> {
+    match column {
+        CsvColumn::Index(index) => Ok(*index),
+        CsvColumn::Name(name) if has_header => header
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Column '{}' not found in the header.", name)),
+        CsvColumn::Name(name) => Err(format!(
+            "Column '{}' is referenced by name but the file has no header.",
+            name
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_round_trip() {
+        let candles = vec![
+            Ohlc {
+                timestamp: 1_704_067_200_000,
+                open: 1.0,
+                high: 2.0,
+                low: 0.5,
+                close: 1.5,
+                volume: 10.0,
+            },
+            Ohlc {
+                timestamp: 1_704_067_260_000,
+                open: 1.5,
+                high: 3.0,
+                low: 1.0,
+                close: 2.5,
+                volume: 20.0,
+            },
+        ];
+
+        for timestamp_format in [TimestampFormat::Auto, TimestampFormat::Iso8601] {
+            let format = CsvFormat {
+                timestamp_format,
+                ..Default::default()
+            };
+            let mut buffer = Vec::new();
+            Ohlc::to_csv(&candles, &mut buffer, &format).unwrap();
+            let parsed = Ohlc::from_csv(buffer.as_slice(), &format).unwrap();
+
+            assert_eq!(parsed.len(), 2);
+            assert_eq!(parsed[1].timestamp, candles[1].timestamp);
+            assert_eq!(parsed[1].close, 2.5);
+            assert_eq!(parsed[1].volume, 20.0);
+        }
+    }
+
+    #[test]
+    fn test_column_mapping() {
+        let data = "Date;Close;High;Low;Open\n2024-01-01 00:01:00;2;3;1;1.5\n\n";
+        let format = CsvFormat {
+            delimiter: ';',
+            timestamp: "date".into(),
+            open: "Open".into(),
+            high: "High".into(),
+            low: "Low".into(),
+            close: "Close".into(),
+            volume: None,
+            ..Default::default()
+        };
+        let candles = Ohlc::from_csv(data.as_bytes(), &format).unwrap();
+
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].timestamp, 1_704_067_260_000);
+        assert_eq!(candles[0].open, 1.5);
+        assert_eq!(candles[0].volume, 0.0);
+
+        let binance = "1704067200000,1,2,0.5,1.5,10,1704067259999\n";
+        let candles = Ohlc::from_csv(binance.as_bytes(), &CsvFormat::binance()).unwrap();
+        assert_eq!(candles[0].timestamp, 1_704_067_200_000);
+        assert_eq!(candles[0].volume, 10.0);
+
+        let error = Ohlc::from_csv("timestamp,open\n".as_bytes(), &CsvFormat::default());
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn test_streaming_chunks() {
+        let data = "timestamp,open,high,low,close,volume\n\
+                    0,1,1,1,1,1\n\
+                    60000,2,2,2,2,2\n\
+                    \n\
+                    120000,3,3,3,3,3\n\
+                    180000,x,4,4,4,4\n";
+
+        let mut chunks = CsvCandleReader::new(data.as_bytes(), &CsvFormat::default())
+            .unwrap()
+            .chunks(2);
+        let sizes: Vec<usize> = chunks.by_ref().take(2).map(|c| c.unwrap().len()).collect();
+        assert_eq!(sizes, vec![2, 1]);
+        assert_eq!(
+            chunks.next().unwrap().unwrap_err(),
+            "Line 6: invalid number 'x'."
+        );
+        assert!(chunks.next().is_none());
+
+        let empty = CsvCandleReader::new("".as_bytes(), &CsvFormat::default()).unwrap();
+        assert_eq!(empty.count(), 0);
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let ms = 1_704_067_200_123;
+        assert_eq!(
+            parse_timestamp("1704067200.123", TimestampFormat::Auto),
+            Ok(ms)
+        );
+        assert_eq!(
+            parse_timestamp("1704067200123", TimestampFormat::Auto),
+            Ok(ms)
+        );
+        assert_eq!(
+            parse_timestamp("1704067200123000", TimestampFormat::Auto),
+            Ok(ms)
+        );
+        assert_eq!(
+            parse_timestamp("2024-01-01T02:00:00.123+02:00", TimestampFormat::Auto),
+            Ok(ms)
+        );
+        assert_eq!(
+            format_timestamp(ms, TimestampFormat::Iso8601),
+            "2024-01-01T00:00:00.123Z"
+        );
+        assert_eq!(
+            parse_timestamp("2024-01-01", TimestampFormat::Iso8601),
+            Ok(ms - 123)
+        );
+        assert!(parse_timestamp("yesterday", TimestampFormat::Auto).is_err());
+    }
+}
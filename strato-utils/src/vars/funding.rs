@@ -0,0 +1,17 @@
+/// A funding rate settlement of a perpetual.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FundingRate {
+    /// Settlement time in Unix milliseconds.
+    pub timestamp: i64,
+    /// Rate paid by longs to shorts for the period, e.g. `0.0001` for 0.01%.
+    pub rate: f64,
+}
+
+impl FundingRate {
+    /// Funding paid by a position of `qty` base units (negative for a short)
+    /// marked at `price`; negative when the position receives funding.
+    pub fn payment(&self, qty: f64, price: f64) -> f64 {
+        qty * price * self.rate
+    }
+}
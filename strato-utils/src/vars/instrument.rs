@@ -0,0 +1,112 @@
+/// Trading constraints and contract specification of an instrument.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Instrument {
+    pub symbol: String,
+    /// Minimum price increment.
+    pub tick_size: f64,
+    /// Minimum quantity increment, in contracts.
+    pub lot_size: f64,
+    /// Units of the underlying per contract, `1.0` for spot and linear perps.
+    pub multiplier: f64,
+    /// Currency prices are quoted in.
+    pub quote_currency: String,
+    /// Currency PnL and margin are settled in.
+    pub settlement_currency: String,
+}
+
+impl Instrument {
+    /// Creates an instrument with a multiplier of `1.0` and no currencies.
+    pub fn new(symbol: &str, tick_size: f64, lot_size: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            tick_size,
+            lot_size,
+            multiplier: 1.0,
+            quote_currency: String::new(),
+            settlement_currency: String::new(),
+        }
+    }
+
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn with_currencies(mut self, quote: &str, settlement: &str) -> Self {
+        self.quote_currency = quote.to_string();
+        self.settlement_currency = settlement.to_string();
+        self
+    }
+
+    /// Rounds a price to the nearest tick.
+    pub fn round_price(&self, price: f64) -> f64 {
+        snap(price, self.tick_size, f64::round)
+    }
+
+    /// Rounds a signed quantity towards zero to a whole number of lots, so an
+    /// order is never larger than requested.
+    pub fn round_qty(&self, qty: f64) -> f64 {
+        snap(qty, self.lot_size, f64::trunc)
+    }
+
+    /// Number of contracts, rounded to lots, for a quantity of the underlying.
+    pub fn contracts(&self, underlying_qty: f64) -> f64 {
+        self.round_qty(underlying_qty / self.multiplier)
+    }
+
+    /// Notional value in quote currency of `qty` contracts at `price`.
+    pub fn notional(&self, price: f64, qty: f64) -> f64 {
+        price * qty * self.multiplier
+    }
+}
+
+/// Rounds `value` to a multiple of `step` with `f`, absorbing the binary
+/// floating point error of the division and of the result. A non-positive
+/// step leaves the value unchanged.
+fn snap(value: f64, step: f64, f: fn(f64) -> f64) -> f64 {
+    if step <= 0.0 {
+        return value;
+    }
+    let steps = value / step;
+    // 0.3 / 0.1 is 2.9999999999999996, which must not truncate to 2
+    let steps = if (steps - steps.round()).abs() < 1e-9 {
+        steps.round()
+    } else {
+        f(steps)
+    };
+    // Drop the noise of the multiplication, e.g. 3.0 * 0.1
+    let decimals = (-step.log10()).ceil().clamp(0.0, 15.0) as i32 + 1;
+    let scale = 10f64.powi(decimals);
+    (steps * step * scale).round() / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounding() {
+        let instrument = Instrument::new("BTCUSDT", 0.1, 0.001);
+
+        assert_eq!(instrument.round_price(100.04), 100.0);
+        assert_eq!(instrument.round_price(100.06), 100.1);
+        assert_eq!(instrument.round_qty(0.0129), 0.012);
+        assert_eq!(instrument.round_qty(-0.0129), -0.012);
+        assert_eq!(instrument.round_qty(0.003), 0.003);
+        assert_eq!(Instrument::new("X", 0.1, 0.1).round_qty(0.3), 0.3);
+        assert_eq!(Instrument::new("X", 0.0, 0.0).round_qty(0.123), 0.123);
+    }
+
+    #[test]
+    fn test_contracts() {
+        // Contract worth 100 units of the underlying, settled in BTC
+        let instrument = Instrument::new("BTCUSD", 0.5, 1.0)
+            .with_multiplier(100.0)
+            .with_currencies("USD", "BTC");
+
+        assert_eq!(instrument.contracts(2_550.0), 25.0);
+        assert_eq!(instrument.notional(10.0, 25.0), 25_000.0);
+        assert_eq!(instrument.settlement_currency, "BTC");
+    }
+}
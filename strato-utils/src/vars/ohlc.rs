@@ -1,7 +1,12 @@
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ohlc {
+    /// Open time of the candle in Unix milliseconds.
+    pub timestamp: i64,
     pub open: f64,
     pub high: f64,
     pub low: f64,
     pub close: f64,
+    /// Traded volume in base units.
+    pub volume: f64,
 }
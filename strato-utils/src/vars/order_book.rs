@@ -0,0 +1,216 @@
+/*!
+Level 2 order book snapshots.
+
+`OrderBook` holds the aggregated bid and ask ladders of a single instrument,
+independently of where they come from (hftbacktest depth, an exchange
+websocket, a recorded file), so book features are computed the same way in
+backtests and live trading.
+*/
+
+use crate::vars::trade::Side;
+
+/// An aggregated price level of the book.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Level {
+    pub price: f64,
+    pub qty: f64,
+}
+
+/// A snapshot of the bid and ask ladders.
+///
+/// Bids are kept sorted by decreasing price and asks by increasing price, so
+/// the best quotes are always at index 0. Levels never have a zero quantity.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderBook {
+    /// Time of the snapshot in Unix milliseconds.
+    pub timestamp: i64,
+    bids: Vec<Level>,
+    asks: Vec<Level>,
+}
+
+impl OrderBook {
+    /// Creates a book from unordered levels. Levels with a non-positive
+    /// quantity are dropped.
+    pub fn new(timestamp: i64, mut bids: Vec<Level>, mut asks: Vec<Level>) -> Self {
+        bids.retain(|l| l.qty > 0.0);
+        asks.retain(|l| l.qty > 0.0);
+        bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+        asks.sort_by(|a, b| a.price.total_cmp(&b.price));
+        Self {
+            timestamp,
+            bids,
+            asks,
+        }
+    }
+
+    /// Bid levels, best first.
+    pub fn bids(&self) -> &[Level] {
+        &self.bids
+    }
+
+    /// Ask levels, best first.
+    pub fn asks(&self) -> &[Level] {
+        &self.asks
+    }
+
+    pub fn best_bid(&self) -> Option<Level> {
+        self.bids.first().copied()
+    }
+
+    pub fn best_ask(&self) -> Option<Level> {
+        self.asks.first().copied()
+    }
+
+    /// Sets the quantity of a level from an incremental update, removing the
+    /// level if `qty` is zero. `Side::Buy` updates the bids.
+    pub fn update(&mut self, side: Side, price: f64, qty: f64) {
+        let (levels, is_before): (_, fn(f64, f64) -> bool) = match side {
+            Side::Buy => (&mut self.bids, |level, price| level > price),
+            Side::Sell => (&mut self.asks, |level, price| level < price),
+        };
+        let index = levels.partition_point(|l| is_before(l.price, price));
+
+        match levels.get_mut(index) {
+            Some(level) if level.price == price => {
+                if qty > 0.0 {
+                    level.qty = qty;
+                } else {
+                    levels.remove(index);
+                }
+            }
+            _ if qty > 0.0 => levels.insert(index, Level { price, qty }),
+            _ => {}
+        }
+    }
+
+    /// Mid-price of the best bid and ask.
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / 2.0)
+    }
+
+    /// Absolute bid-ask spread.
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    /// Bid-ask spread as a percentage of the best bid.
+    pub fn spread_pct(&self) -> Option<f64> {
+        let bid = self.best_bid()?.price;
+        Some((self.best_ask()?.price - bid) / bid * 100.0)
+    }
+
+    /// Mid-price weighted by the opposite top-of-book quantities.
+    ///
+    /// # Mathematical Formulation
+    ///
+    /// ```text
+    /// microprice = (bid * ask_qty + ask * bid_qty) / (bid_qty + ask_qty)
+    /// ```
+    ///
+    /// It leans towards the ask when bids are heavier, i.e. towards the side
+    /// the price is more likely to move to.
+    pub fn microprice(&self) -> Option<f64> {
+        let bid = self.best_bid()?;
+        let ask = self.best_ask()?;
+        Some((bid.price * ask.qty + ask.price * bid.qty) / (bid.qty + ask.qty))
+    }
+
+    /// Sums the bid and ask quantities of the top `levels` levels.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of (bid quantity, ask quantity).
+    pub fn depth(&self, levels: usize) -> (f64, f64) {
+        let sum = |ladder: &[Level]| ladder.iter().take(levels).map(|l| l.qty).sum();
+        (sum(&self.bids), sum(&self.asks))
+    }
+
+    /// Order Imbalance Ratio (OIR) of the top `levels` levels, in `[-1, 1]`.
+    ///
+    /// Level `i` (0 being the best) is weighted by `decay^i`, so a `decay` of
+    /// `1.0` weights all levels equally and smaller values focus on the top
+    /// of the book.
+    ///
+    /// # Mathematical Formulation
+    ///
+    /// ```text
+    /// OIR = (Σ w_i * bid_qty_i - Σ w_i * ask_qty_i) / (Σ w_i * bid_qty_i + Σ w_i * ask_qty_i)
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// The imbalance, or `0.0` for an empty book.
+    pub fn imbalance(&self, levels: usize, decay: f64) -> f64 {
+        let weighted = |ladder: &[Level]| -> f64 {
+            ladder
+                .iter()
+                .take(levels)
+                .zip(std::iter::successors(Some(1.0), |w| Some(w * decay)))
+                .map(|(l, w)| w * l.qty)
+                .sum()
+        };
+        let bid = weighted(&self.bids);
+        let ask = weighted(&self.asks);
+        if bid + ask > 0.0 {
+            (bid - ask) / (bid + ask)
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book() -> OrderBook {
+        let level = |price, qty| Level { price, qty };
+        OrderBook::new(
+            0,
+            vec![level(99.0, 2.0), level(100.0, 3.0), level(98.0, 0.0)],
+            vec![level(102.0, 4.0), level(101.0, 1.0)],
+        )
+    }
+
+    #[test]
+    fn test_features() {
+        let book = book();
+
+        assert_eq!(
+            book.best_bid(),
+            Some(Level {
+                price: 100.0,
+                qty: 3.0
+            })
+        );
+        assert_eq!(book.bids().len(), 2);
+        assert_eq!(book.mid(), Some(100.5));
+        assert_eq!(book.spread(), Some(1.0));
+        assert_eq!(book.spread_pct(), Some(1.0));
+        assert_eq!(book.microprice(), Some(100.75));
+        assert_eq!(book.depth(2), (5.0, 5.0));
+        assert_eq!(book.imbalance(1, 1.0), 0.5);
+        assert_eq!(book.imbalance(2, 1.0), 0.0);
+        // Weights 1, 0.5: bids 3 + 1, asks 1 + 2
+        assert!((book.imbalance(2, 0.5) - 1.0 / 7.0).abs() < 1e-12);
+        assert_eq!(OrderBook::default().mid(), None);
+        assert_eq!(OrderBook::default().imbalance(5, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut book = book();
+
+        book.update(Side::Buy, 99.5, 1.0);
+        book.update(Side::Buy, 100.0, 0.0);
+        book.update(Side::Sell, 101.0, 5.0);
+        book.update(Side::Sell, 103.0, 0.0);
+
+        let prices = |ladder: &[Level]| ladder.iter().map(|l| l.price).collect::<Vec<_>>();
+        assert_eq!(prices(book.bids()), vec![99.5, 99.0]);
+        assert_eq!(prices(book.asks()), vec![101.0, 102.0]);
+        assert_eq!(book.best_ask().unwrap().qty, 5.0);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::vars::time_series::TimeSeries;
+
+/// Which timestamps a `Panel` keeps when its series are sampled at different
+/// times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Join {
+    /// Every timestamp of any series.
+    #[default]
+    Outer,
+    /// Only the timestamps present in every series.
+    Inner,
+}
+
+/// How a `Panel` fills the values a series has no row for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fill {
+    /// Leave the values as `f64::NAN`.
+    #[default]
+    Nan,
+    /// Carry the last known value forward. Values before the first row of a
+    /// series stay `f64::NAN`.
+    Forward,
+    /// Carry the last known value forward for at most this many rows, e.g.
+    /// to bridge short gaps without hiding a delisting.
+    ForwardLimit(usize),
+}
+
+/// Time series of several symbols aligned on a common timestamp index.
+///
+/// Every series of the panel has the same timestamps, so values at the same
+/// index are simultaneous across symbols, as required by pairs trading,
+/// cross-asset features and portfolio backtests.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Panel {
+    timestamps: Vec<i64>,
+    /// Series by symbol, in insertion order.
+    series: Vec<(String, TimeSeries)>,
+}
+
+impl Panel {
+    /// Aligns series onto a common timestamp index.
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - Series by symbol.
+    /// * `join` - Which timestamps to keep.
+    /// * `fill` - How to fill the rows a series is missing.
+    ///
+    /// # Returns
+    ///
+    /// The panel, or an error if a symbol appears twice.
+    pub fn align(
+        series: Vec<(String, TimeSeries)>,
+        join: Join,
+        fill: Fill,
+    ) -> Result<Self, String> {
+        for (i, (symbol, _)) in series.iter().enumerate() {
+            if series[..i].iter().any(|(s, _)| s == symbol) {
+                return Err(format!("Symbol '{}' appears more than once.", symbol));
+            }
+        }
+
+        let mut timestamps: Vec<i64> = match join {
+            Join::Outer => series
+                .iter()
+                .flat_map(|(_, s)| s.timestamps().iter().copied())
+                .collect(),
+            Join::Inner => match series.split_first() {
+                Some(((_, first), rest)) => first
+                    .timestamps()
+                    .iter()
+                    .copied()
+                    .filter(|&t| rest.iter().all(|(_, s)| s.index_of(t).is_some()))
+                    .collect(),
+                None => Vec::new(),
+            },
+        };
+        timestamps.sort_unstable();
+        timestamps.dedup();
+
+        let series = series
+            .into_iter()
+            .map(|(symbol, s)| Ok((symbol, reindex(&s, &timestamps, fill)?)))
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { timestamps, series })
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    pub fn timestamps(&self) -> &[i64] {
+        &self.timestamps
+    }
+
+    /// Symbols of the panel, in insertion order.
+    pub fn symbols(&self) -> impl Iterator<Item = &str> {
+        self.series.iter().map(|(symbol, _)| symbol.as_str())
+    }
+
+    /// Returns the aligned series of a symbol.
+    pub fn get(&self, symbol: &str) -> Option<&TimeSeries> {
+        self.series
+            .iter()
+            .find(|(s, _)| s == symbol)
+            .map(|(_, series)| series)
+    }
+
+    /// Returns the values of a column of a symbol.
+    pub fn column(&self, symbol: &str, name: &str) -> Option<&[f64]> {
+        self.get(symbol)?.column(name)
+    }
+
+    /// Returns the values of a column for every symbol that has it, e.g. the
+    /// `close` of every asset for a correlation matrix.
+    pub fn cross_section(&self, name: &str) -> Vec<(&str, &[f64])> {
+        self.series
+            .iter()
+            .filter_map(|(symbol, series)| Some((symbol.as_str(), series.column(name)?)))
+            .collect()
+    }
+}
+
+/// Reindexes a series onto `timestamps`, a sorted superset or subset of its
+/// own timestamps.
+fn reindex(series: &TimeSeries, timestamps: &[i64], fill: Fill) -> Result<TimeSeries, String> {
+    let limit = match fill {
+        Fill::Nan => 0,
+        Fill::Forward => usize::MAX,
+        Fill::ForwardLimit(limit) => limit,
+    };
+    // Source row of each timestamp, exact or within the fill limit
+    let mut rows = Vec::with_capacity(timestamps.len());
+    let mut last: Option<(usize, usize)> = None;
+    for &timestamp in timestamps {
+        match series.index_of(timestamp) {
+            Some(row) => {
+                last = Some((row, 0));
+                rows.push(Some(row));
+            }
+            None => {
+                last = last.map(|(row, age)| (row, age + 1));
+                rows.push(last.filter(|&(_, age)| age <= limit).map(|(row, _)| row));
+            }
+        }
+    }
+
+    let mut aligned = TimeSeries::new(timestamps.to_vec())?;
+    for name in series.names() {
+        let values = series.column(name).unwrap_or_default();
+        let column = rows
+            .iter()
+            .map(|row| row.map_or(f64::NAN, |row| values[row]))
+            .collect();
+        aligned.insert(name, column)?;
+    }
+    Ok(aligned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(timestamps: Vec<i64>, close: Vec<f64>) -> TimeSeries {
+        TimeSeries::new(timestamps)
+            .unwrap()
+            .with_column("close", close)
+            .unwrap()
+    }
+
+    fn symbols() -> Vec<(String, TimeSeries)> {
+        vec![
+            (
+                "BTC".to_string(),
+                series(vec![1, 2, 3, 4, 5], vec![10.0, 11.0, 12.0, 13.0, 14.0]),
+            ),
+            ("ETH".to_string(), series(vec![2, 5], vec![1.0, 2.0])),
+        ]
+    }
+
+    fn assert_values(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(
+                a == e || (a.is_nan() && e.is_nan()),
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_outer_join_fills() {
+        let nan = f64::NAN;
+
+        let panel = Panel::align(symbols(), Join::Outer, Fill::Nan).unwrap();
+        assert_eq!(panel.timestamps(), &[1, 2, 3, 4, 5]);
+        assert_values(
+            panel.column("ETH", "close").unwrap(),
+            &[nan, 1.0, nan, nan, 2.0],
+        );
+
+        let panel = Panel::align(symbols(), Join::Outer, Fill::Forward).unwrap();
+        assert_values(
+            panel.column("ETH", "close").unwrap(),
+            &[nan, 1.0, 1.0, 1.0, 2.0],
+        );
+
+        let panel = Panel::align(symbols(), Join::Outer, Fill::ForwardLimit(1)).unwrap();
+        assert_values(
+            panel.column("ETH", "close").unwrap(),
+            &[nan, 1.0, 1.0, nan, 2.0],
+        );
+        assert_eq!(panel.column("BTC", "close").unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_inner_join() {
+        let panel = Panel::align(symbols(), Join::Inner, Fill::Nan).unwrap();
+
+        assert_eq!(panel.timestamps(), &[2, 5]);
+        assert_eq!(panel.symbols().collect::<Vec<_>>(), vec!["BTC", "ETH"]);
+        let closes = panel.cross_section("close");
+        assert_eq!(closes[0], ("BTC", &[11.0, 14.0][..]));
+        assert_eq!(closes[1], ("ETH", &[1.0, 2.0][..]));
+
+        let mut duplicated = symbols();
+        duplicated.push(duplicated[0].clone());
+        assert!(Panel::align(duplicated, Join::Inner, Fill::Nan).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use std::ops::Range;
+
+use crate::vars::ohlc::Ohlc;
+
+/// Timestamps with named `f64` columns aligned to them, e.g. prices,
+/// indicators and signals.
+///
+/// Every column has exactly one value per timestamp and timestamps are
+/// strictly increasing; both are checked whenever the series is built or a
+/// column is added, so values at the same index always belong together.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeSeries {
+    timestamps: Vec<i64>,
+    /// Columns in insertion order.
+    columns: Vec<(String, Vec<f64>)>,
+}
+
+impl TimeSeries {
+    /// Creates a series without columns.
+    ///
+    /// # Returns
+    ///
+    /// The series, or an error if the timestamps are not strictly increasing.
+    pub fn new(timestamps: Vec<i64>) -> Result<Self, String> {
+        if let Some(i) = timestamps.windows(2).position(|w| w[1] <= w[0]) {
+            return Err(format!(
+                "Timestamps must be strictly increasing, but {} follows {}.",
+                timestamps[i + 1],
+                timestamps[i]
+            ));
+        }
+        Ok(Self {
+            timestamps,
+            columns: Vec::new(),
+        })
+    }
+
+    /// Creates a series with the `open`, `high`, `low`, `close` and `volume`
+    /// columns of the candles.
+    pub fn from_candles(candles: &[Ohlc]) -> Result<Self, String> {
+        let column = |f: fn(&Ohlc) -> f64| candles.iter().map(f).collect::<Vec<f64>>();
+        Self::new(candles.iter().map(|c| c.timestamp).collect())?
+            .with_column("open", column(|c| c.open))?
+            .with_column("high", column(|c| c.high))?
+            .with_column("low", column(|c| c.low))?
+            .with_column("close", column(|c| c.close))?
+            .with_column("volume", column(|c| c.volume))
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    pub fn timestamps(&self) -> &[i64] {
+        &self.timestamps
+    }
+
+    /// Names of the columns, in insertion order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.columns.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the values of a column.
+    pub fn column(&self, name: &str) -> Option<&[f64]> {
+        self.columns
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    /// Adds a column, replacing any column of the same name.
+    ///
+    /// # Returns
+    ///
+    /// An error if the column does not have one value per timestamp.
+    pub fn insert(&mut self, name: &str, values: Vec<f64>) -> Result<(), String> {
+        if values.len() != self.len() {
+            return Err(format!(
+                "Column '{}' has {} values but the series has {} timestamps.",
+                name,
+                values.len(),
+                self.len()
+            ));
+        }
+        match self.columns.iter_mut().find(|(n, _)| n == name) {
+            Some((_, column)) => *column = values,
+            None => self.columns.push((name.to_string(), values)),
+        }
+        Ok(())
+    }
+
+    /// Builder-style `insert`.
+    pub fn with_column(mut self, name: &str, values: Vec<f64>) -> Result<Self, String> {
+        self.insert(name, values)?;
+        Ok(self)
+    }
+
+    /// Computes a column from another one, e.g. an indicator from `close`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use strato_utils::ta::sma::sma;
+    /// use strato_utils::vars::time_series::TimeSeries;
+    ///
+    /// let mut series = TimeSeries::new(vec![1, 2, 3])
+    ///     .unwrap()
+    ///     .with_column("close", vec![1.0, 2.0, 3.0])
+    ///     .unwrap();
+    /// series.derive("sma_2", "close", |close| sma(close, 2)).unwrap();
+    /// assert_eq!(series.column("sma_2"), Some(&[0.0, 1.5, 2.5][..]));
+    /// ```
+    pub fn derive<F>(&mut self, name: &str, source: &str, f: F) -> Result<(), String>
+    where
+        F: FnOnce(&[f64]) -> Vec<f64>,
+    {
+        let values = f(self
+            .column(source)
+            .ok_or_else(|| format!("Column '{}' not found.", source))?);
+        self.insert(name, values)
+    }
+
+    /// Removes a column and returns its values.
+    pub fn remove(&mut self, name: &str) -> Option<Vec<f64>> {
+        let index = self.columns.iter().position(|(n, _)| n == name)?;
+        Some(self.columns.remove(index).1)
+    }
+
+    /// Index of a timestamp, if present.
+    pub fn index_of(&self, timestamp: i64) -> Option<usize> {
+        self.timestamps.binary_search(&timestamp).ok()
+    }
+
+    /// Returns the rows in `range`, with every column.
+    ///
+    /// # Panics
+    ///
+    /// If the range is out of bounds.
+    pub fn slice(&self, range: Range<usize>) -> TimeSeries {
+        TimeSeries {
+            timestamps: self.timestamps[range.clone()].to_vec(),
+            columns: self
+                .columns
+                .iter()
+                .map(|(name, values)| (name.clone(), values[range.clone()].to_vec()))
+                .collect(),
+        }
+    }
+
+    /// Returns the rows with `start <= timestamp < end`.
+    pub fn between(&self, start: i64, end: i64) -> TimeSeries {
+        let from = self.timestamps.partition_point(|&t| t < start);
+        let to = self.timestamps.partition_point(|&t| t < end).max(from);
+        self.slice(from..to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alignment_is_enforced() {
+        assert!(TimeSeries::new(vec![1, 3, 2]).is_err());
+        assert!(TimeSeries::new(vec![1, 1]).is_err());
+
+        let mut series = TimeSeries::new(vec![10, 20, 30]).unwrap();
+        assert!(series.insert("close", vec![1.0, 2.0]).is_err());
+        series.insert("close", vec![1.0, 2.0, 3.0]).unwrap();
+        series.insert("close", vec![4.0, 5.0, 6.0]).unwrap();
+
+        assert_eq!(series.names().collect::<Vec<_>>(), vec!["close"]);
+        assert_eq!(series.column("close"), Some(&[4.0, 5.0, 6.0][..]));
+        assert!(series.derive("x", "missing", |v| v.to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_slicing() {
+        let candles: Vec<Ohlc> = (0..5)
+            .map(|i| Ohlc {
+                timestamp: i * 60_000,
+                close: i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let series = TimeSeries::from_candles(&candles).unwrap();
+
+        let window = series.between(60_000, 180_000);
+        assert_eq!(window.timestamps(), &[60_000, 120_000]);
+        assert_eq!(window.column("close"), Some(&[1.0, 2.0][..]));
+        assert_eq!(window.names().count(), 5);
+        assert!(series.between(300_000, 0).is_empty());
+        assert_eq!(series.index_of(240_000), Some(4));
+        assert_eq!(series.index_of(1), None);
+    }
+}
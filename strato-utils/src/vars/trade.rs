@@ -0,0 +1,30 @@
+/// Side of the aggressor of a trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    /// `1.0` for buys and `-1.0` for sells.
+    pub fn sign(&self) -> f64 {
+        match self {
+            Side::Buy => 1.0,
+            Side::Sell => -1.0,
+        }
+    }
+}
+
+/// A single trade (tick) from an exchange feed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trade {
+    /// Time of the trade in Unix milliseconds.
+    pub timestamp: i64,
+    pub price: f64,
+    /// Traded quantity in base units.
+    pub qty: f64,
+    /// Side of the aggressor.
+    pub side: Side,
+}
@@ -0,0 +1,187 @@
+/*!
+Rolling and expanding window views over slices of candles or values.
+
+Indicator and feature code often needs the trailing window of each candle.
+`windows_of` and `expanding` yield those windows as borrowed sub-slices, so
+nothing is copied however long the windows are:
+
+```
+use strato_utils::vars::ohlc::Ohlc;
+use strato_utils::vars::window::Windows;
+
+let candles: Vec<Ohlc> = (0..5)
+    .map(|i| Ohlc {
+        high: i as f64,
+        ..Ohlc::default()
+    })
+    .collect();
+
+// Highest high of the last 3 candles, from the third candle on
+let highest: Vec<f64> = candles
+    .windows_of(3)
+    .map(|w| w.iter().map(|c| c.high).fold(f64::MIN, f64::max))
+    .collect();
+assert_eq!(highest, vec![2.0, 3.0, 4.0]);
+```
+
+Windows are aligned on their last element: the `k`-th window of
+`windows_of(len)` ends at index `k + len - 1` and the `k`-th window of
+`expanding()` at index `k`.
+*/
+
+use std::iter::FusedIterator;
+
+/// Window views over a slice.
+pub trait Windows<T> {
+    /// Returns the trailing windows of `len` elements, one per element from
+    /// index `len - 1` on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is 0.
+    fn windows_of(&self, len: usize) -> WindowsOf<'_, T>;
+
+    /// Returns the windows from the first element up to each element.
+    fn expanding(&self) -> Expanding<'_, T>;
+}
+
+impl<T> Windows<T> for [T] {
+    fn windows_of(&self, len: usize) -> WindowsOf<'_, T> {
+        assert!(len > 0, "window length must be positive");
+        WindowsOf {
+            src: self,
+            len,
+            end: len,
+            back: self.len(),
+        }
+    }
+
+    fn expanding(&self) -> Expanding<'_, T> {
+        Expanding {
+            src: self,
+            end: 1,
+            back: self.len(),
+        }
+    }
+}
+
+/// Iterator over the trailing windows of a slice, see `Windows::windows_of`.
+#[derive(Debug, Clone)]
+pub struct WindowsOf<'a, T> {
+    src: &'a [T],
+    len: usize,
+    /// End (exclusive) of the next window from the front.
+    end: usize,
+    /// End (exclusive) of the next window from the back.
+    back: usize,
+}
+
+impl<'a, T> Iterator for WindowsOf<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.end > self.back {
+            return None;
+        }
+        let window = &self.src[self.end - self.len..self.end];
+        self.end += 1;
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.back + 1).saturating_sub(self.end);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for WindowsOf<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.end > self.back {
+            return None;
+        }
+        let window = &self.src[self.back - self.len..self.back];
+        self.back -= 1;
+        Some(window)
+    }
+}
+
+impl<T> ExactSizeIterator for WindowsOf<'_, T> {}
+
+impl<T> FusedIterator for WindowsOf<'_, T> {}
+
+/// Iterator over the expanding windows of a slice, see
+/// `Windows::expanding`.
+#[derive(Debug, Clone)]
+pub struct Expanding<'a, T> {
+    src: &'a [T],
+    /// End (exclusive) of the next window from the front.
+    end: usize,
+    /// End (exclusive) of the next window from the back.
+    back: usize,
+}
+
+impl<'a, T> Iterator for Expanding<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.end > self.back {
+            return None;
+        }
+        let window = &self.src[..self.end];
+        self.end += 1;
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.back + 1).saturating_sub(self.end);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for Expanding<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.end > self.back {
+            return None;
+        }
+        let window = &self.src[..self.back];
+        self.back -= 1;
+        Some(window)
+    }
+}
+
+impl<T> ExactSizeIterator for Expanding<'_, T> {}
+
+impl<T> FusedIterator for Expanding<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_of() {
+        let src = [1.0, 2.0, 3.0, 4.0];
+
+        let windows: Vec<&[f64]> = src.windows_of(3).collect();
+        assert_eq!(windows, vec![&[1.0, 2.0, 3.0][..], &[2.0, 3.0, 4.0][..]]);
+        assert_eq!(src.windows_of(1).len(), 4);
+        assert_eq!(src.windows_of(5).count(), 0);
+        assert_eq!(src.windows_of(5).len(), 0);
+
+        let mut windows = src.windows_of(2);
+        assert_eq!(windows.next_back(), Some(&[3.0, 4.0][..]));
+        assert_eq!(windows.next(), Some(&[1.0, 2.0][..]));
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows.next_back(), Some(&[2.0, 3.0][..]));
+        assert_eq!(windows.next(), None);
+    }
+
+    #[test]
+    fn test_expanding() {
+        let src = [1, 2, 3];
+
+        let sums: Vec<i32> = src.expanding().map(|w| w.iter().sum()).collect();
+        assert_eq!(sums, vec![1, 3, 6]);
+        assert_eq!(src.expanding().next_back(), Some(&src[..]));
+        assert_eq!([0; 0].expanding().count(), 0);
+    }
+}